@@ -0,0 +1,15 @@
+//! Refactoring advisor modules
+//!
+//! This module contains analyses that go beyond raw metrics and suggest
+//! concrete refactorings:
+//! - Parameter struct suggestions for long parameter lists
+//! - Refactoring hints for functions over their limits, located from the AST
+
+pub mod parameter_struct;
+pub mod refactoring;
+
+// Re-export commonly used types and functions for convenience
+pub use parameter_struct::{
+    ParameterStructSuggestion, suggest_parameter_structs, suggest_parameter_structs_for_files,
+};
+pub use refactoring::{RefactoringHint, RefactoringSuggestion, suggest_refactorings};
@@ -0,0 +1,323 @@
+use crate::analyzer::parse_source;
+use crate::errors::AnalysisResult;
+use crate::normalize_path;
+use crate::source::read_source;
+use quote::ToTokens;
+use std::collections::{BTreeMap, BTreeSet};
+use syn::{FnArg, ImplItem, Item, Pat, Signature, TraitItem, Type};
+
+/// Minimum number of shared parameters required before a struct is suggested
+const MIN_CLUSTER_SIZE: usize = 3;
+
+/// A typed function parameter, compared by both name and type
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Parameter {
+    pub name: String,
+    pub ty: String,
+}
+
+/// The parameter list of a single function
+struct FunctionSignature {
+    name: String,
+    params: Vec<Parameter>,
+}
+
+/// A group of parameters that travel together across several long parameter lists
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParameterStructSuggestion {
+    pub struct_name: String,
+    pub parameters: Vec<Parameter>,
+    pub functions: Vec<String>,
+}
+
+impl ParameterStructSuggestion {
+    /// Renders the suggested struct definition as a single line of Rust source
+    pub fn struct_definition(&self) -> String {
+        let fields = self
+            .parameters
+            .iter()
+            .map(|param| format!("{}: {}", param.name, param.ty))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("struct {} {{ {} }}", self.struct_name, fields)
+    }
+}
+
+/// Suggests parameter structs for the long parameter lists found in a single source file
+pub fn suggest_parameter_structs(
+    source: &str,
+    max_params: usize,
+) -> AnalysisResult<Vec<ParameterStructSuggestion>> {
+    let signatures = collect_signatures(source)?;
+    Ok(find_parameter_clusters(&signatures, max_params))
+}
+
+/// Suggests parameter structs for long parameter lists shared across multiple files
+///
+/// Files that cannot be read or parsed are skipped, matching `analyze_all_files`.
+pub fn suggest_parameter_structs_for_files(
+    file_paths: &[String],
+    max_params: usize,
+) -> Vec<ParameterStructSuggestion> {
+    let mut signatures = Vec::new();
+
+    for path in file_paths {
+        let Ok(source) = read_source(path) else {
+            continue;
+        };
+        if let Ok(file_signatures) = collect_signatures(&source.text) {
+            let normalized_path = normalize_path(path);
+            signatures.extend(
+                file_signatures
+                    .into_iter()
+                    .map(|signature| FunctionSignature {
+                        name: format!("{}::{}", normalized_path, signature.name),
+                        params: signature.params,
+                    }),
+            );
+        }
+    }
+
+    find_parameter_clusters(&signatures, max_params)
+}
+
+/// Collects the typed parameter lists of all functions in the source
+///
+/// Besides top-level functions, these are the methods of `impl` and trait
+/// blocks, named after their type or trait, e.g. `Canvas::draw`, and the
+/// functions of inline modules, named after their module path.
+fn collect_signatures(source: &str) -> AnalysisResult<Vec<FunctionSignature>> {
+    let parsed = parse_source(source)?;
+    let mut signatures = Vec::new();
+    add_signatures(&parsed.items, "", &mut signatures);
+    Ok(signatures)
+}
+
+/// Adds the signatures of the functions among `items` and of the items nested in them
+fn add_signatures(items: &[Item], prefix: &str, signatures: &mut Vec<FunctionSignature>) {
+    let mut add = |owner: &str, sig: &Signature| {
+        signatures.push(FunctionSignature {
+            name: format!("{prefix}{owner}{}", sig.ident),
+            params: sig.inputs.iter().filter_map(to_parameter).collect(),
+        });
+    };
+    let mut modules = Vec::new();
+    for item in items {
+        match item {
+            Item::Fn(func) => add("", &func.sig),
+            Item::Impl(block) => {
+                let owner = format!("{}::", type_name(&block.self_ty));
+                for item in &block.items {
+                    if let ImplItem::Fn(method) = item {
+                        add(&owner, &method.sig);
+                    }
+                }
+            }
+            Item::Trait(block) => {
+                let owner = format!("{}::", block.ident);
+                for item in &block.items {
+                    if let TraitItem::Fn(method) = item {
+                        add(&owner, &method.sig);
+                    }
+                }
+            }
+            Item::Mod(module) => {
+                if let Some((_, items)) = &module.content {
+                    modules.push((format!("{prefix}{}::", module.ident), items));
+                }
+            }
+            _ => {}
+        }
+    }
+    for (prefix, items) in modules {
+        add_signatures(items, &prefix, signatures);
+    }
+}
+
+/// Returns the name of the type of an `impl` block, without generic arguments
+fn type_name(ty: &Type) -> String {
+    match ty {
+        Type::Path(path) => match path.path.segments.last() {
+            Some(segment) => segment.ident.to_string(),
+            None => ty.to_token_stream().to_string(),
+        },
+        _ => ty.to_token_stream().to_string(),
+    }
+}
+
+/// Converts a function argument into a comparable parameter, skipping `self` receivers
+fn to_parameter(arg: &FnArg) -> Option<Parameter> {
+    match arg {
+        FnArg::Receiver(_) => None,
+        FnArg::Typed(pat_type) => {
+            let name = match pat_type.pat.as_ref() {
+                Pat::Ident(pat_ident) => pat_ident.ident.to_string(),
+                other => other.to_token_stream().to_string(),
+            };
+            Some(Parameter {
+                name,
+                ty: pat_type.ty.to_token_stream().to_string(),
+            })
+        }
+    }
+}
+
+/// Finds parameter groups shared by at least two functions exceeding the parameter threshold
+fn find_parameter_clusters(
+    signatures: &[FunctionSignature],
+    max_params: usize,
+) -> Vec<ParameterStructSuggestion> {
+    let long_lists: Vec<(&str, BTreeSet<&Parameter>)> = signatures
+        .iter()
+        .filter(|signature| signature.params.len() > max_params)
+        .map(|signature| (signature.name.as_str(), signature.params.iter().collect()))
+        .collect();
+
+    // Every pairwise intersection that is large enough is a candidate cluster
+    let mut clusters: BTreeMap<BTreeSet<&Parameter>, BTreeSet<&str>> = BTreeMap::new();
+    for (i, (_, params_a)) in long_lists.iter().enumerate() {
+        for (_, params_b) in long_lists.iter().skip(i + 1) {
+            let shared: BTreeSet<&Parameter> = params_a.intersection(params_b).copied().collect();
+            if shared.len() >= MIN_CLUSTER_SIZE {
+                clusters.entry(shared).or_default();
+            }
+        }
+    }
+
+    // A cluster belongs to every long parameter list that contains all of its parameters
+    for (cluster, functions) in clusters.iter_mut() {
+        for (name, params) in &long_lists {
+            if cluster.is_subset(params) {
+                functions.insert(name);
+            }
+        }
+    }
+
+    let mut suggestions: Vec<ParameterStructSuggestion> = clusters
+        .into_iter()
+        .map(|(cluster, functions)| {
+            let parameters: Vec<Parameter> = cluster.into_iter().cloned().collect();
+            ParameterStructSuggestion {
+                struct_name: suggest_struct_name(&parameters),
+                parameters,
+                functions: functions.into_iter().map(str::to_string).collect(),
+            }
+        })
+        .collect();
+
+    suggestions.sort_by(|a, b| {
+        b.functions
+            .len()
+            .cmp(&a.functions.len())
+            .then_with(|| b.parameters.len().cmp(&a.parameters.len()))
+            .then_with(|| a.struct_name.cmp(&b.struct_name))
+    });
+    suggestions
+}
+
+/// Builds a PascalCase struct name from the first few parameter names
+fn suggest_struct_name(parameters: &[Parameter]) -> String {
+    let mut name: String = parameters
+        .iter()
+        .take(3)
+        .flat_map(|param| param.name.split('_'))
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect();
+    name.push_str("Params");
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shared_parameters_suggest_struct() {
+        let source = r#"
+            fn draw(x: u32, y: u32, width: u32, height: u32, color: u8, alpha: u8) {}
+            fn fill(x: u32, y: u32, width: u32, height: u32, pattern: u8, scale: f32) {}
+        "#;
+
+        let suggestions = suggest_parameter_structs(source, 5).unwrap();
+
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].parameters.len(), 4);
+        assert_eq!(suggestions[0].functions, vec!["draw", "fill"]);
+        assert_eq!(suggestions[0].struct_name, "HeightWidthXParams");
+    }
+
+    #[test]
+    fn test_methods_and_module_functions_are_inspected() {
+        let source = r#"
+            struct Canvas;
+            impl Canvas {
+                fn draw(&self, x: u32, y: u32, width: u32, height: u32, color: u8, alpha: u8) {}
+            }
+            trait Shape {
+                fn fill(&self, x: u32, y: u32, width: u32, height: u32, pattern: u8, scale: f32);
+            }
+            mod text {
+                fn print(x: u32, y: u32, width: u32, height: u32, font: u8, size: f32) {}
+            }
+        "#;
+
+        let suggestions = suggest_parameter_structs(source, 5).unwrap();
+
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(
+            suggestions[0].functions,
+            vec!["Canvas::draw", "Shape::fill", "text::print"]
+        );
+    }
+
+    #[test]
+    fn test_short_parameter_lists_are_ignored() {
+        let source = r#"
+            fn draw(x: u32, y: u32, width: u32) {}
+            fn fill(x: u32, y: u32, width: u32) {}
+        "#;
+
+        let suggestions = suggest_parameter_structs(source, 5).unwrap();
+        assert!(suggestions.is_empty());
+    }
+
+    #[test]
+    fn test_same_names_with_different_types_do_not_cluster() {
+        let source = r#"
+            fn a(x: u32, y: u32, z: u32, p: u8, q: u8, r: u8) {}
+            fn b(x: i64, y: i64, z: i64, s: u8, t: u8, u: u8) {}
+        "#;
+
+        let suggestions = suggest_parameter_structs(source, 5).unwrap();
+        assert!(suggestions.is_empty());
+    }
+
+    #[test]
+    fn test_struct_definition_rendering() {
+        let suggestion = ParameterStructSuggestion {
+            struct_name: "PointParams".to_string(),
+            parameters: vec![
+                Parameter {
+                    name: "x".to_string(),
+                    ty: "u32".to_string(),
+                },
+                Parameter {
+                    name: "y".to_string(),
+                    ty: "u32".to_string(),
+                },
+            ],
+            functions: vec!["draw".to_string()],
+        };
+
+        assert_eq!(
+            suggestion.struct_definition(),
+            "struct PointParams { x: u32, y: u32 }"
+        );
+    }
+}
@@ -0,0 +1,449 @@
+use crate::analyzer::{FunctionAnalysisResult, parse_source};
+use crate::config::Thresholds;
+use crate::source::read_source;
+use quote::ToTokens;
+use std::collections::BTreeMap;
+use syn::spanned::Spanned;
+use syn::visit::Visit;
+use syn::{Block, Expr, Item, ItemFn};
+
+/// Minimum number of lines of a construct worth extracting into its own function
+const MIN_EXTRACT_LINES: usize = 10;
+
+/// Minimum number of branches of a match or if/else chain worth pointing out
+const MIN_BRANCHES: usize = 4;
+
+/// Longest construct subject quoted in a hint, in characters
+const MAX_SUBJECT_CHARS: usize = 40;
+
+/// A concrete refactoring hint for part of a function
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RefactoringHint {
+    /// Metric whose limit the hint helps to meet
+    pub metric: &'static str,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub message: String,
+}
+
+/// Hints for a function exceeding one or more limits
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RefactoringSuggestion {
+    pub function: String,
+    pub start_line: usize,
+    pub hints: Vec<RefactoringHint>,
+}
+
+/// Suggests refactorings for the functions exceeding the limits
+///
+/// The source of each function is parsed again to locate the constructs that
+/// make it long, complex, or deeply nested. Functions whose file can no longer
+/// be read or parsed, and functions without a construct worth pointing out,
+/// are skipped.
+pub fn suggest_refactorings(
+    results: &[FunctionAnalysisResult],
+    thresholds: &Thresholds,
+) -> Vec<RefactoringSuggestion> {
+    let mut by_file: BTreeMap<&str, Vec<(&FunctionAnalysisResult, Vec<&'static str>)>> =
+        BTreeMap::new();
+    for result in results {
+        let metrics: Vec<&'static str> = thresholds
+            .violations(result)
+            .iter()
+            .map(|violation| violation.metric)
+            .collect();
+        if let Some(file) = result.source_file()
+            && !metrics.is_empty()
+        {
+            by_file.entry(file).or_default().push((result, metrics));
+        }
+    }
+
+    let mut suggestions = Vec::new();
+    for (file, functions) in by_file {
+        let Ok(source) = read_source(file) else {
+            continue;
+        };
+        let Ok(parsed) = parse_source(&source.text) else {
+            continue;
+        };
+        for (result, metrics) in functions {
+            let hints = parsed
+                .items
+                .iter()
+                .find_map(|item| match item {
+                    Item::Fn(func) if func.sig.ident == result.name => Some(func),
+                    _ => None,
+                })
+                .map(|func| hints_for_function(func, &metrics))
+                .unwrap_or_default();
+            if !hints.is_empty() {
+                suggestions.push(RefactoringSuggestion {
+                    function: result.qualified_name(),
+                    start_line: result.start_line,
+                    hints,
+                });
+            }
+        }
+    }
+    suggestions
+}
+
+/// Derives hints for the given exceeded metrics from the structure of a function
+pub fn hints_for_function(func: &ItemFn, metrics: &[&'static str]) -> Vec<RefactoringHint> {
+    let mut walker = ConstructWalker::default();
+    walker.visit_block(&func.block);
+    let constructs = walker.constructs;
+
+    let mut hints = Vec::new();
+    for &metric in metrics {
+        let hint = match metric {
+            "nesting" => nesting_hint(&constructs, walker.peak),
+            "complexity" => branching_hint(&constructs),
+            "code" | "total" => length_hint(&constructs),
+            _ => None,
+        };
+        // Several metrics may point at the same construct, which is named once
+        if let Some(hint) = hint
+            && !hints.iter().any(|known: &RefactoringHint| {
+                known.start_line == hint.start_line && known.end_line == hint.end_line
+            })
+        {
+            hints.push(RefactoringHint { metric, ..hint });
+        }
+    }
+    hints
+}
+
+/// Points at the outermost construct around the most deeply nested code
+fn nesting_hint(constructs: &[Construct], peak: Option<usize>) -> Option<RefactoringHint> {
+    let deepest = &constructs[peak?];
+    let mut outermost = deepest;
+    while let Some(parent) = outermost.parent {
+        outermost = &constructs[parent];
+    }
+    Some(RefactoringHint {
+        metric: "nesting",
+        start_line: outermost.start_line,
+        end_line: outermost.end_line,
+        message: format!(
+            "nesting peaks on line {} inside {} (lines {}–{}); consider {}",
+            deepest.start_line,
+            outermost.description(),
+            outermost.start_line,
+            outermost.end_line,
+            outermost.kind.extraction_advice()
+        ),
+    })
+}
+
+/// Points at the match or if/else chain with the most branches
+fn branching_hint(constructs: &[Construct]) -> Option<RefactoringHint> {
+    let widest = constructs
+        .iter()
+        .rev()
+        .filter(|construct| construct.branches >= MIN_BRANCHES)
+        .max_by_key(|construct| construct.branches)?;
+    let (branches, advice) = match widest.kind {
+        ConstructKind::Match => ("arms", "a lookup table or one function per arm"),
+        _ => ("branches", "a match or a lookup table"),
+    };
+    Some(RefactoringHint {
+        metric: "complexity",
+        start_line: widest.start_line,
+        end_line: widest.end_line,
+        message: format!(
+            "{} (lines {}–{}) has {} {branches}; consider {advice}",
+            widest.description(),
+            widest.start_line,
+            widest.end_line,
+            widest.branches
+        ),
+    })
+}
+
+/// Points at the longest construct, which is the largest candidate for extraction
+///
+/// Of constructs spanning the same lines, such as a closure and its body, the
+/// outermost is named.
+fn length_hint(constructs: &[Construct]) -> Option<RefactoringHint> {
+    let longest = constructs
+        .iter()
+        .rev()
+        .filter(|construct| construct.lines() >= MIN_EXTRACT_LINES)
+        .max_by_key(|construct| construct.lines())?;
+    Some(RefactoringHint {
+        metric: "code",
+        start_line: longest.start_line,
+        end_line: longest.end_line,
+        message: format!(
+            "{} spans {} lines ({}–{}); consider {}",
+            longest.description(),
+            longest.lines(),
+            longest.start_line,
+            longest.end_line,
+            longest.kind.extraction_advice()
+        ),
+    })
+}
+
+/// Kind of a construct that adds a nesting level
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConstructKind {
+    Match,
+    If,
+    Loop,
+    Closure,
+    Block,
+}
+
+impl ConstructKind {
+    fn extraction_advice(self) -> &'static str {
+        match self {
+            ConstructKind::Match => "extracting the arm bodies into functions",
+            ConstructKind::If => "early returns or extracting the branches into functions",
+            ConstructKind::Loop => "extracting the loop body into a function",
+            ConstructKind::Closure => "turning the closure into a named function",
+            ConstructKind::Block => "extracting the block into a function",
+        }
+    }
+}
+
+/// A construct of a function body, with its place in the nesting
+#[derive(Debug, Clone)]
+struct Construct {
+    kind: ConstructKind,
+    /// What the construct is about, e.g. the scrutinee of a match
+    subject: Option<String>,
+    start_line: usize,
+    end_line: usize,
+    /// Index of the construct this one is nested in
+    parent: Option<usize>,
+    /// Arms of a match, or branches of an if/else chain started by this `if`
+    branches: usize,
+}
+
+impl Construct {
+    fn lines(&self) -> usize {
+        self.end_line - self.start_line + 1
+    }
+
+    fn description(&self) -> String {
+        let kind = match self.kind {
+            ConstructKind::Match => "the match",
+            ConstructKind::If if self.branches > 2 => "the if/else chain",
+            ConstructKind::If => "the if",
+            ConstructKind::Loop => "the loop",
+            ConstructKind::Closure => "the closure",
+            ConstructKind::Block => "the block",
+        };
+        match &self.subject {
+            Some(subject) => format!("{kind} on `{subject}`"),
+            None => kind.to_string(),
+        }
+    }
+}
+
+/// Records the constructs of a function body and the most deeply nested one
+///
+/// Nesting levels are counted like the nesting depth metric: conditions and
+/// scrutinees belong to the enclosing level, and nested items are skipped.
+#[derive(Default)]
+struct ConstructWalker {
+    constructs: Vec<Construct>,
+    /// Constructs enclosing the visited code, innermost last
+    stack: Vec<usize>,
+    /// Most deeply nested construct, the first one found at that depth
+    peak: Option<usize>,
+    peak_depth: usize,
+    /// Set while visiting the `else` of an if, whose branches are counted by its chain
+    in_chain: bool,
+}
+
+impl ConstructWalker {
+    fn enter(&mut self, kind: ConstructKind, node: &impl Spanned, subject: Option<&Expr>) {
+        let span = node.span();
+        let index = self.constructs.len();
+        self.constructs.push(Construct {
+            kind,
+            subject: subject.map(subject_text),
+            start_line: span.start().line,
+            end_line: span.end().line,
+            parent: self.stack.last().copied(),
+            branches: 0,
+        });
+        self.stack.push(index);
+        if self.stack.len() > self.peak_depth {
+            self.peak_depth = self.stack.len();
+            self.peak = Some(index);
+        }
+    }
+
+    fn leave(&mut self) {
+        self.stack.pop();
+    }
+
+    fn nested_block(&mut self, kind: ConstructKind, node: &impl Spanned, block: &Block) {
+        self.enter(kind, node, None);
+        self.visit_block(block);
+        self.leave();
+    }
+}
+
+impl<'ast> Visit<'ast> for ConstructWalker {
+    fn visit_item(&mut self, _item: &'ast Item) {}
+
+    fn visit_expr_match(&mut self, node: &'ast syn::ExprMatch) {
+        self.visit_expr(&node.expr);
+        self.enter(ConstructKind::Match, node, Some(&node.expr));
+        if let Some(construct) = self.constructs.last_mut() {
+            construct.branches = node.arms.len();
+        }
+        for arm in &node.arms {
+            self.visit_arm(arm);
+        }
+        self.leave();
+    }
+
+    fn visit_expr_if(&mut self, node: &'ast syn::ExprIf) {
+        let chained = std::mem::take(&mut self.in_chain);
+        self.visit_expr(&node.cond);
+        self.enter(ConstructKind::If, node, Some(&node.cond));
+        if !chained && let Some(construct) = self.constructs.last_mut() {
+            let mut branches = 1;
+            let mut link = node;
+            while let Some((_, else_branch)) = &link.else_branch {
+                branches += 1;
+                match else_branch.as_ref() {
+                    Expr::If(next) => link = next,
+                    _ => break,
+                }
+            }
+            construct.branches = branches;
+        }
+        self.visit_block(&node.then_branch);
+        if let Some((_, else_branch)) = &node.else_branch {
+            self.in_chain = matches!(else_branch.as_ref(), Expr::If(_));
+            self.visit_expr(else_branch);
+        }
+        self.leave();
+    }
+
+    fn visit_expr_while(&mut self, node: &'ast syn::ExprWhile) {
+        self.visit_expr(&node.cond);
+        self.enter(ConstructKind::Loop, node, Some(&node.cond));
+        self.visit_block(&node.body);
+        self.leave();
+    }
+
+    fn visit_expr_for_loop(&mut self, node: &'ast syn::ExprForLoop) {
+        self.visit_expr(&node.expr);
+        self.enter(ConstructKind::Loop, node, Some(&node.expr));
+        self.visit_block(&node.body);
+        self.leave();
+    }
+
+    fn visit_expr_loop(&mut self, node: &'ast syn::ExprLoop) {
+        self.nested_block(ConstructKind::Loop, node, &node.body);
+    }
+
+    fn visit_expr_block(&mut self, node: &'ast syn::ExprBlock) {
+        self.nested_block(ConstructKind::Block, node, &node.block);
+    }
+
+    fn visit_expr_unsafe(&mut self, node: &'ast syn::ExprUnsafe) {
+        self.nested_block(ConstructKind::Block, node, &node.block);
+    }
+
+    fn visit_expr_async(&mut self, node: &'ast syn::ExprAsync) {
+        self.nested_block(ConstructKind::Block, node, &node.block);
+    }
+
+    fn visit_expr_closure(&mut self, node: &'ast syn::ExprClosure) {
+        self.enter(ConstructKind::Closure, node, None);
+        self.visit_expr(&node.body);
+        self.leave();
+    }
+}
+
+/// Returns the source of an expression as written, shortened for a hint
+fn subject_text(expr: &Expr) -> String {
+    let text = expr
+        .span()
+        .source_text()
+        .unwrap_or_else(|| expr.to_token_stream().to_string());
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() > MAX_SUBJECT_CHARS {
+        let shortened: String = text.chars().take(MAX_SUBJECT_CHARS - 1).collect();
+        format!("{shortened}…")
+    } else {
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hints(source: &str, metrics: &[&'static str]) -> Vec<RefactoringHint> {
+        let func: ItemFn = syn::parse_str(source).unwrap();
+        hints_for_function(&func, metrics)
+    }
+
+    #[test]
+    fn test_nesting_hint_names_the_outermost_construct() {
+        let source = "fn handle(token: Token) {
+    setup();
+    match token.kind {
+        Kind::Open => {
+            for child in token.children {
+                if child.valid {
+                    emit(child);
+                }
+            }
+        }
+        Kind::Close => close(),
+    }
+}";
+
+        let hints = hints(source, &["nesting"]);
+
+        assert_eq!(hints.len(), 1);
+        assert_eq!((hints[0].start_line, hints[0].end_line), (3, 12));
+        assert_eq!(
+            hints[0].message,
+            "nesting peaks on line 6 inside the match on `token.kind` (lines 3–12); \
+             consider extracting the arm bodies into functions"
+        );
+    }
+
+    #[test]
+    fn test_complexity_hint_counts_chain_branches() {
+        let source = "fn grade(score: u32) -> char {
+    if score > 90 {
+        'A'
+    } else if score > 80 {
+        'B'
+    } else if score > 70 {
+        'C'
+    } else {
+        'F'
+    }
+}";
+
+        let hints = hints(source, &["complexity", "nesting"]);
+
+        assert_eq!(
+            hints[0].message,
+            "the if/else chain on `score > 90` (lines 2–10) has 4 branches; \
+             consider a match or a lookup table"
+        );
+        // The nesting hint points at the same chain and is not repeated
+        assert_eq!(hints.len(), 1);
+    }
+
+    #[test]
+    fn test_short_functions_get_no_length_hint() {
+        assert!(hints("fn small() { loop { tick(); } }", &["code"]).is_empty());
+    }
+}
@@ -1,5 +1,63 @@
+use serde::Deserialize;
 use syn::{Arm, Block, Expr, Item, ItemFn, Stmt};
 
+/// Counting rules controlling which constructs add cyclomatic complexity
+///
+/// Branches (`if`, `match` arms) and loops always count. The remaining decision
+/// points are counted differently by different tools, so each can be toggled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ComplexityRules {
+    pub count_returns: bool,
+    pub count_break_continue: bool,
+    pub count_try: bool,
+    pub count_boolean_operators: bool,
+}
+
+impl Default for ComplexityRules {
+    fn default() -> Self {
+        ComplexityPreset::default().rules()
+    }
+}
+
+/// Named sets of complexity counting rules
+#[derive(clap::ValueEnum, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ComplexityPreset {
+    /// Classic McCabe: only branches and loops
+    Mccabe,
+    /// Sonar-like: branches, loops, and boolean operators
+    Sonar,
+    /// Every decision point, including early exits and `?` (default)
+    #[default]
+    Strict,
+}
+
+impl ComplexityPreset {
+    /// Returns the counting rules of this preset
+    pub fn rules(self) -> ComplexityRules {
+        match self {
+            ComplexityPreset::Mccabe => ComplexityRules {
+                count_returns: false,
+                count_break_continue: false,
+                count_try: false,
+                count_boolean_operators: false,
+            },
+            ComplexityPreset::Sonar => ComplexityRules {
+                count_returns: false,
+                count_break_continue: false,
+                count_try: false,
+                count_boolean_operators: true,
+            },
+            ComplexityPreset::Strict => ComplexityRules {
+                count_returns: true,
+                count_break_continue: true,
+                count_try: true,
+                count_boolean_operators: true,
+            },
+        }
+    }
+}
+
 /// Calculates the cyclomatic complexity of a function
 ///
 /// Cyclomatic complexity is a software metric that measures the number of linearly
@@ -16,53 +74,60 @@ use syn::{Arm, Block, Expr, Item, ItemFn, Stmt};
 /// - break/continue statements
 /// - && and || operators in boolean expressions
 /// - ? operator (try expressions)
+///
+/// This uses the strict preset; see [`ComplexityPreset`] for alternatives.
 pub fn calculate_cyclomatic_complexity(func: &ItemFn) -> usize {
+    calculate_cyclomatic_complexity_with_rules(func, ComplexityRules::default())
+}
+
+/// Calculates the cyclomatic complexity of a function using the given counting rules
+pub fn calculate_cyclomatic_complexity_with_rules(func: &ItemFn, rules: ComplexityRules) -> usize {
     let mut complexity = 1; // Base complexity
 
     // Analyze the function body
-    complexity += analyze_block(&func.block);
+    complexity += analyze_block(&func.block, rules);
 
     complexity
 }
 
 /// Analyzes a block of statements for complexity
-fn analyze_block(block: &Block) -> usize {
+fn analyze_block(block: &Block, rules: ComplexityRules) -> usize {
     let mut complexity = 0;
 
     for stmt in &block.stmts {
-        complexity += analyze_statement(stmt);
+        complexity += analyze_statement(stmt, rules);
     }
 
     complexity
 }
 
 /// Analyzes a single statement for complexity
-fn analyze_statement(stmt: &Stmt) -> usize {
+fn analyze_statement(stmt: &Stmt, rules: ComplexityRules) -> usize {
     match stmt {
-        Stmt::Expr(expr, _) => analyze_expression(expr),
+        Stmt::Expr(expr, _) => analyze_expression(expr, rules),
         Stmt::Local(local) => {
             let mut complexity = 0;
             if let Some(init) = &local.init {
-                complexity += analyze_expression(&init.expr);
+                complexity += analyze_expression(&init.expr, rules);
             }
             complexity
         }
-        Stmt::Item(item) => analyze_item(item),
+        Stmt::Item(item) => analyze_item(item, rules),
         Stmt::Macro(_) => 0, // Macros are not analyzed for complexity
     }
 }
 
 /// Analyzes an expression for complexity
-fn analyze_expression(expr: &Expr) -> usize {
+fn analyze_expression(expr: &Expr, rules: ComplexityRules) -> usize {
     match expr {
         // Conditional expressions add complexity
         Expr::If(expr_if) => {
             let mut complexity = 1; // if condition
-            complexity += analyze_expression(&expr_if.cond);
-            complexity += analyze_block(&expr_if.then_branch);
+            complexity += analyze_expression(&expr_if.cond, rules);
+            complexity += analyze_block(&expr_if.then_branch, rules);
 
             if let Some((_, else_branch)) = &expr_if.else_branch {
-                complexity += analyze_expression(else_branch);
+                complexity += analyze_expression(else_branch, rules);
             }
 
             complexity
@@ -71,10 +136,10 @@ fn analyze_expression(expr: &Expr) -> usize {
         // Match expressions: base complexity + each arm
         Expr::Match(expr_match) => {
             let mut complexity = 1; // Base for match
-            complexity += analyze_expression(&expr_match.expr);
+            complexity += analyze_expression(&expr_match.expr, rules);
 
             for arm in &expr_match.arms {
-                complexity += analyze_match_arm(arm);
+                complexity += analyze_match_arm(arm, rules);
             }
 
             complexity
@@ -83,20 +148,20 @@ fn analyze_expression(expr: &Expr) -> usize {
         // Loop expressions add complexity
         Expr::While(expr_while) => {
             let mut complexity = 1; // while condition
-            complexity += analyze_expression(&expr_while.cond);
-            complexity += analyze_block(&expr_while.body);
+            complexity += analyze_expression(&expr_while.cond, rules);
+            complexity += analyze_block(&expr_while.body, rules);
             complexity
         }
 
         Expr::ForLoop(expr_for) => {
             let mut complexity = 1; // for loop
-            complexity += analyze_expression(&expr_for.expr);
-            complexity += analyze_block(&expr_for.body);
+            complexity += analyze_expression(&expr_for.expr, rules);
+            complexity += analyze_block(&expr_for.body, rules);
             complexity
         }
 
         Expr::Loop(expr_loop) => {
-            1 + analyze_block(&expr_loop.body) // loop adds complexity
+            1 + analyze_block(&expr_loop.body, rules) // loop adds complexity
         }
 
         // Logical operators add complexity
@@ -105,65 +170,69 @@ fn analyze_expression(expr: &Expr) -> usize {
 
             // && and || operators add decision points
             match expr_binary.op {
-                syn::BinOp::And(_) | syn::BinOp::Or(_) => complexity += 1,
+                syn::BinOp::And(_) | syn::BinOp::Or(_) if rules.count_boolean_operators => {
+                    complexity += 1
+                }
                 _ => {}
             }
 
-            complexity += analyze_expression(&expr_binary.left);
-            complexity += analyze_expression(&expr_binary.right);
+            complexity += analyze_expression(&expr_binary.left, rules);
+            complexity += analyze_expression(&expr_binary.right, rules);
             complexity
         }
 
         // Try expressions (?) add complexity
-        Expr::Try(expr_try) => 1 + analyze_expression(&expr_try.expr),
+        Expr::Try(expr_try) => {
+            usize::from(rules.count_try) + analyze_expression(&expr_try.expr, rules)
+        }
 
         // Return statements add complexity (except final returns)
         Expr::Return(expr_return) => {
-            let mut complexity = 1; // return statement
+            let mut complexity = usize::from(rules.count_returns); // return statement
             if let Some(expr) = &expr_return.expr {
-                complexity += analyze_expression(expr);
+                complexity += analyze_expression(expr, rules);
             }
             complexity
         }
 
         // Break and continue add complexity
         Expr::Break(expr_break) => {
-            let mut complexity = 1; // break statement
+            let mut complexity = usize::from(rules.count_break_continue); // break statement
             if let Some(expr) = &expr_break.expr {
-                complexity += analyze_expression(expr);
+                complexity += analyze_expression(expr, rules);
             }
             complexity
         }
 
-        Expr::Continue(_) => 1, // continue statement
+        Expr::Continue(_) => usize::from(rules.count_break_continue), // continue statement
 
         // Block expressions
-        Expr::Block(expr_block) => analyze_block(&expr_block.block),
+        Expr::Block(expr_block) => analyze_block(&expr_block.block, rules),
 
         // Unsafe blocks
-        Expr::Unsafe(expr_unsafe) => analyze_block(&expr_unsafe.block),
+        Expr::Unsafe(expr_unsafe) => analyze_block(&expr_unsafe.block, rules),
 
         // Async blocks
-        Expr::Async(expr_async) => analyze_block(&expr_async.block),
+        Expr::Async(expr_async) => analyze_block(&expr_async.block, rules),
 
         // Closures
-        Expr::Closure(expr_closure) => analyze_expression(&expr_closure.body),
+        Expr::Closure(expr_closure) => analyze_expression(&expr_closure.body, rules),
 
         // Function calls and method calls
         Expr::Call(expr_call) => {
             let mut complexity = 0;
-            complexity += analyze_expression(&expr_call.func);
+            complexity += analyze_expression(&expr_call.func, rules);
             for arg in &expr_call.args {
-                complexity += analyze_expression(arg);
+                complexity += analyze_expression(arg, rules);
             }
             complexity
         }
 
         Expr::MethodCall(expr_method) => {
             let mut complexity = 0;
-            complexity += analyze_expression(&expr_method.receiver);
+            complexity += analyze_expression(&expr_method.receiver, rules);
             for arg in &expr_method.args {
-                complexity += analyze_expression(arg);
+                complexity += analyze_expression(arg, rules);
             }
             complexity
         }
@@ -172,7 +241,7 @@ fn analyze_expression(expr: &Expr) -> usize {
         Expr::Array(expr_array) => {
             let mut complexity = 0;
             for elem in &expr_array.elems {
-                complexity += analyze_expression(elem);
+                complexity += analyze_expression(elem, rules);
             }
             complexity
         }
@@ -180,37 +249,39 @@ fn analyze_expression(expr: &Expr) -> usize {
         Expr::Tuple(expr_tuple) => {
             let mut complexity = 0;
             for elem in &expr_tuple.elems {
-                complexity += analyze_expression(elem);
+                complexity += analyze_expression(elem, rules);
             }
             complexity
         }
 
         // Field access and indexing
-        Expr::Field(expr_field) => analyze_expression(&expr_field.base),
+        Expr::Field(expr_field) => analyze_expression(&expr_field.base, rules),
         Expr::Index(expr_index) => {
-            analyze_expression(&expr_index.expr) + analyze_expression(&expr_index.index)
+            analyze_expression(&expr_index.expr, rules)
+                + analyze_expression(&expr_index.index, rules)
         }
 
         // Assignment expressions
         Expr::Assign(expr_assign) => {
-            analyze_expression(&expr_assign.left) + analyze_expression(&expr_assign.right)
+            analyze_expression(&expr_assign.left, rules)
+                + analyze_expression(&expr_assign.right, rules)
         }
 
         // Reference and dereference
-        Expr::Reference(expr_ref) => analyze_expression(&expr_ref.expr),
-        Expr::Unary(expr_unary) => analyze_expression(&expr_unary.expr),
+        Expr::Reference(expr_ref) => analyze_expression(&expr_ref.expr, rules),
+        Expr::Unary(expr_unary) => analyze_expression(&expr_unary.expr, rules),
 
         // Cast expressions
-        Expr::Cast(expr_cast) => analyze_expression(&expr_cast.expr),
+        Expr::Cast(expr_cast) => analyze_expression(&expr_cast.expr, rules),
 
         // Range expressions
         Expr::Range(expr_range) => {
             let mut complexity = 0;
             if let Some(start) = &expr_range.start {
-                complexity += analyze_expression(start);
+                complexity += analyze_expression(start, rules);
             }
             if let Some(end) = &expr_range.end {
-                complexity += analyze_expression(end);
+                complexity += analyze_expression(end, rules);
             }
             complexity
         }
@@ -219,19 +290,19 @@ fn analyze_expression(expr: &Expr) -> usize {
         Expr::Struct(expr_struct) => {
             let mut complexity = 0;
             for field in &expr_struct.fields {
-                complexity += analyze_expression(&field.expr);
+                complexity += analyze_expression(&field.expr, rules);
             }
             if let Some(rest) = &expr_struct.rest {
-                complexity += analyze_expression(rest);
+                complexity += analyze_expression(rest, rules);
             }
             complexity
         }
 
         // Parenthesized expressions
-        Expr::Paren(expr_paren) => analyze_expression(&expr_paren.expr),
+        Expr::Paren(expr_paren) => analyze_expression(&expr_paren.expr, rules),
 
         // Group expressions
-        Expr::Group(expr_group) => analyze_expression(&expr_group.expr),
+        Expr::Group(expr_group) => analyze_expression(&expr_group.expr, rules),
 
         // All other expressions (literals, paths, etc.) don't add complexity
         _ => 0,
@@ -239,24 +310,24 @@ fn analyze_expression(expr: &Expr) -> usize {
 }
 
 /// Analyzes a match arm for complexity
-fn analyze_match_arm(arm: &Arm) -> usize {
+fn analyze_match_arm(arm: &Arm, rules: ComplexityRules) -> usize {
     let mut complexity = 1; // Each arm adds complexity
 
     // Analyze guard conditions
     if let Some((_, guard)) = &arm.guard {
-        complexity += analyze_expression(guard);
+        complexity += analyze_expression(guard, rules);
     }
 
     // Analyze the arm body
-    complexity += analyze_expression(&arm.body);
+    complexity += analyze_expression(&arm.body, rules);
 
     complexity
 }
 
 /// Analyzes an item (nested function, etc.) for complexity
-fn analyze_item(item: &Item) -> usize {
+fn analyze_item(item: &Item, rules: ComplexityRules) -> usize {
     match item {
-        Item::Fn(item_fn) => calculate_cyclomatic_complexity(item_fn),
+        Item::Fn(item_fn) => calculate_cyclomatic_complexity_with_rules(item_fn, rules),
         _ => 0, // Other items don't add to the current function's complexity
     }
 }
@@ -420,4 +491,35 @@ mod tests {
         // Base 1 + match 1 + 4 arms (each with guard or condition) = 6
         assert_eq!(calculate_cyclomatic_complexity(&func), 6);
     }
+
+    #[test]
+    fn test_presets_differ_on_early_exits() {
+        let func: ItemFn = parse_quote! {
+            fn with_exits(items: &[i32]) -> Result<i32, String> {
+                for item in items {
+                    if *item < 0 && *item > -10 {
+                        return Err("negative".to_string());
+                    }
+                    if *item == 0 {
+                        continue;
+                    }
+                }
+                let value = parse()?;
+                Ok(value)
+            }
+        };
+
+        // Base 1 + for 1 + if 1 + if 1 = 4
+        let mccabe = ComplexityPreset::Mccabe.rules();
+        assert_eq!(calculate_cyclomatic_complexity_with_rules(&func, mccabe), 4);
+
+        // McCabe + && 1 = 5
+        let sonar = ComplexityPreset::Sonar.rules();
+        assert_eq!(calculate_cyclomatic_complexity_with_rules(&func, sonar), 5);
+
+        // Sonar + return 1 + continue 1 + try 1 = 8
+        let strict = ComplexityPreset::Strict.rules();
+        assert_eq!(calculate_cyclomatic_complexity_with_rules(&func, strict), 8);
+        assert_eq!(calculate_cyclomatic_complexity(&func), 8);
+    }
 }
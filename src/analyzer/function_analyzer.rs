@@ -7,24 +7,30 @@ mod tests {
     use crate::analyze_all_files;
     use crate::analyzer::function_extractor::FunctionSpan;
     use crate::analyzer::{
-        FunctionAnalysisResult, analyze_function_lines,
-        calculate_cyclomatic_complexity_from_source, calculate_nesting_depth_from_source,
-        count_function_lines,
+        AnalysisOptions, FunctionAnalysisResult, analyze_function_lines, analyze_parsed,
+        analyze_source, calculate_cyclomatic_complexity, calculate_nesting_depth,
+        count_expressions, count_function_lines, count_statements, count_todo_markers,
+        extract_function_spans,
     };
+    use crate::errors::AnalysisError;
+    use std::path::PathBuf;
+    use syn::ItemFn;
 
     #[test]
     fn test_analyze_function_lines_basic() {
         let lines = vec![
-            "fn hello() {".to_string(),
-            "    println!(\"Hello\");".to_string(),
-            "    // This is a comment".to_string(),
-            "".to_string(),
-            "}".to_string(),
+            "fn hello() {",
+            "    println!(\"Hello\");",
+            "    // This is a comment",
+            "",
+            "}",
         ];
 
         let span = FunctionSpan {
             name: "hello".to_string(),
+            start_line: 1,
             lines,
+            is_test: false,
         };
 
         let source = "fn hello() {\n    println!(\"Hello\");\n    // This is a comment\n\n}";
@@ -41,15 +47,13 @@ mod tests {
 
     #[test]
     fn test_analyze_function_lines_only_code() {
-        let lines = vec![
-            "fn add(a: i32, b: i32) -> i32 {".to_string(),
-            "    a + b".to_string(),
-            "}".to_string(),
-        ];
+        let lines = vec!["fn add(a: i32, b: i32) -> i32 {", "    a + b", "}"];
 
         let span = FunctionSpan {
             name: "add".to_string(),
+            start_line: 1,
             lines,
+            is_test: false,
         };
 
         let source = "fn add(a: i32, b: i32) -> i32 {\n    a + b\n}";
@@ -67,15 +71,17 @@ mod tests {
     #[test]
     fn test_analyze_function_lines_only_comments() {
         let lines = vec![
-            "// Function documentation".to_string(),
-            "/* Multi-line comment".to_string(),
-            "   continues here */".to_string(),
-            "// Another comment".to_string(),
+            "// Function documentation",
+            "/* Multi-line comment",
+            "   continues here */",
+            "// Another comment",
         ];
 
         let span = FunctionSpan {
             name: "documented_function".to_string(),
+            start_line: 1,
             lines,
+            is_test: false,
         };
 
         // For this test, the source doesn't contain the actual function,
@@ -94,16 +100,13 @@ mod tests {
 
     #[test]
     fn test_analyze_function_lines_empty_function() {
-        let lines = vec![
-            "fn empty_function() {".to_string(),
-            "".to_string(),
-            "".to_string(),
-            "}".to_string(),
-        ];
+        let lines = vec!["fn empty_function() {", "", "", "}"];
 
         let span = FunctionSpan {
             name: "empty_function".to_string(),
+            start_line: 1,
             lines,
+            is_test: false,
         };
 
         let source = "fn empty_function() {\n\n\n}";
@@ -120,22 +123,24 @@ mod tests {
     #[test]
     fn test_analyze_function_lines_mixed_content() {
         let lines = vec![
-            "fn complex_function() {".to_string(),
-            "    // Initialize variables".to_string(),
-            "    let x = 10;".to_string(),
-            "".to_string(),
-            "    /* Calculate result".to_string(),
-            "       using complex logic */".to_string(),
-            "    let result = x * 2;".to_string(),
-            "".to_string(),
-            "    // Return the result".to_string(),
-            "    result".to_string(),
-            "}".to_string(),
+            "fn complex_function() {",
+            "    // Initialize variables",
+            "    let x = 10;",
+            "",
+            "    /* Calculate result",
+            "       using complex logic */",
+            "    let result = x * 2;",
+            "",
+            "    // Return the result",
+            "    result",
+            "}",
         ];
 
         let span = FunctionSpan {
             name: "complex_function".to_string(),
+            start_line: 1,
             lines,
+            is_test: false,
         };
 
         let source = "fn complex_function() {\n    // Initialize variables\n    let x = 10;\n\n    /* Calculate result\n       using complex logic */\n    let result = x * 2;\n\n    // Return the result\n    result\n}";
@@ -177,13 +182,14 @@ mod tests {
     #[test]
     fn test_function_analysis_result_creation() {
         let result = FunctionAnalysisResult {
-            name: "test_function".to_string(),
             total: 10,
-            code: 7,
             comment: 2,
             empty: 1,
-            cyclomatic_complexity: 3,
             nesting_depth: 2,
+            stmt_count: 4,
+            expr_count: 9,
+            end_line: 10,
+            ..FunctionAnalysisResult::sample("test_function", 7, 3)
         };
 
         assert_eq!(result.name, "test_function");
@@ -199,13 +205,14 @@ mod tests {
     #[test]
     fn test_function_analysis_result_clone() {
         let original = FunctionAnalysisResult {
-            name: "original".to_string(),
             total: 5,
-            code: 3,
             comment: 1,
             empty: 1,
-            cyclomatic_complexity: 2,
             nesting_depth: 1,
+            stmt_count: 2,
+            expr_count: 5,
+            end_line: 5,
+            ..FunctionAnalysisResult::sample("original", 3, 2)
         };
 
         let cloned = original.clone();
@@ -217,6 +224,8 @@ mod tests {
         assert_eq!(original.empty, cloned.empty);
         assert_eq!(original.cyclomatic_complexity, cloned.cyclomatic_complexity);
         assert_eq!(original.nesting_depth, cloned.nesting_depth);
+        assert_eq!(original.stmt_count, cloned.stmt_count);
+        assert_eq!(original.expr_count, cloned.expr_count);
     }
 
     #[test]
@@ -240,17 +249,19 @@ mod tests {
     fn test_line_classification_edge_cases() {
         // Test various edge cases for line classification
         let lines = vec![
-            "    // Comment with leading spaces".to_string(),
-            "\t/* Comment with tab */".to_string(),
-            "  ".to_string(),                              // Only spaces
-            "\t\t".to_string(),                            // Only tabs
-            "code_line();  // Inline comment".to_string(), // Mixed content - this is code, not comment
-            "/*".to_string(),                              // Comment start only
+            "    // Comment with leading spaces",
+            "\t/* Comment with tab */",
+            "  ",                              // Only spaces
+            "\t\t",                            // Only tabs
+            "code_line();  // Inline comment", // Mixed content - this is code, not comment
+            "/*",                              // Comment start only
         ];
 
         let span = FunctionSpan {
             name: "edge_case_function".to_string(),
+            start_line: 1,
             lines,
+            is_test: false,
         };
 
         let source = "fn edge_case_function() {\n    // Comment with leading spaces\n\t/* Comment with tab */\n  \n\t\t\n    code_line();  // Inline comment\n    /*\n}";
@@ -277,7 +288,9 @@ mod tests {
 
         let span = FunctionSpan {
             name: "zero_lines".to_string(),
+            start_line: 1,
             lines,
+            is_test: false,
         };
 
         let source = "fn zero_lines() {}";
@@ -293,15 +306,13 @@ mod tests {
 
     #[test]
     fn test_cyclomatic_complexity_simple_function() {
-        let lines = vec![
-            "fn simple() {".to_string(),
-            "    println!(\"Hello\");".to_string(),
-            "}".to_string(),
-        ];
+        let lines = vec!["fn simple() {", "    println!(\"Hello\");", "}"];
 
         let span = FunctionSpan {
             name: "simple".to_string(),
+            start_line: 1,
             lines,
+            is_test: false,
         };
 
         let source = "fn simple() {\n    println!(\"Hello\");\n}";
@@ -313,16 +324,18 @@ mod tests {
     #[test]
     fn test_cyclomatic_complexity_with_if() {
         let lines = vec![
-            "fn with_if(x: i32) {".to_string(),
-            "    if x > 0 {".to_string(),
-            "        println!(\"positive\");".to_string(),
-            "    }".to_string(),
-            "}".to_string(),
+            "fn with_if(x: i32) {",
+            "    if x > 0 {",
+            "        println!(\"positive\");",
+            "    }",
+            "}",
         ];
 
         let span = FunctionSpan {
             name: "with_if".to_string(),
+            start_line: 1,
             lines,
+            is_test: false,
         };
 
         let source =
@@ -335,17 +348,19 @@ mod tests {
     #[test]
     fn test_cyclomatic_complexity_with_match() {
         let lines = vec![
-            "fn with_match(x: Option<i32>) {".to_string(),
-            "    match x {".to_string(),
-            "        Some(val) => println!(\"{}\", val),".to_string(),
-            "        None => println!(\"nothing\"),".to_string(),
-            "    }".to_string(),
-            "}".to_string(),
+            "fn with_match(x: Option<i32>) {",
+            "    match x {",
+            "        Some(val) => println!(\"{}\", val),",
+            "        None => println!(\"nothing\"),",
+            "    }",
+            "}",
         ];
 
         let span = FunctionSpan {
             name: "with_match".to_string(),
+            start_line: 1,
             lines,
+            is_test: false,
         };
 
         let source = "fn with_match(x: Option<i32>) {\n    match x {\n        Some(val) => println!(\"{}\", val),\n        None => println!(\"nothing\"),\n    }\n}";
@@ -357,19 +372,21 @@ mod tests {
     #[test]
     fn test_cyclomatic_complexity_with_loops() {
         let lines = vec![
-            "fn with_loops() {".to_string(),
-            "    while true {".to_string(),
-            "        break;".to_string(),
-            "    }".to_string(),
-            "    for i in 0..10 {".to_string(),
-            "        continue;".to_string(),
-            "    }".to_string(),
-            "}".to_string(),
+            "fn with_loops() {",
+            "    while true {",
+            "        break;",
+            "    }",
+            "    for i in 0..10 {",
+            "        continue;",
+            "    }",
+            "}",
         ];
 
         let span = FunctionSpan {
             name: "with_loops".to_string(),
+            start_line: 1,
             lines,
+            is_test: false,
         };
 
         let source = "fn with_loops() {\n    while true {\n        break;\n    }\n    for i in 0..10 {\n        continue;\n    }\n}";
@@ -381,16 +398,18 @@ mod tests {
     #[test]
     fn test_cyclomatic_complexity_with_logical_operators() {
         let lines = vec![
-            "fn with_logical(a: bool, b: bool, c: bool) {".to_string(),
-            "    if a && b || c {".to_string(),
-            "        println!(\"complex condition\");".to_string(),
-            "    }".to_string(),
-            "}".to_string(),
+            "fn with_logical(a: bool, b: bool, c: bool) {",
+            "    if a && b || c {",
+            "        println!(\"complex condition\");",
+            "    }",
+            "}",
         ];
 
         let span = FunctionSpan {
             name: "with_logical".to_string(),
+            start_line: 1,
             lines,
+            is_test: false,
         };
 
         let source = "fn with_logical(a: bool, b: bool, c: bool) {\n    if a && b || c {\n        println!(\"complex condition\");\n    }\n}";
@@ -401,17 +420,13 @@ mod tests {
 
     #[test]
     fn test_count_function_lines_basic() {
-        let lines = vec![
-            "fn test() {".to_string(),
-            "    let x = 1;".to_string(),
-            "    // comment".to_string(),
-            "".to_string(),
-            "}".to_string(),
-        ];
+        let lines = vec!["fn test() {", "    let x = 1;", "    // comment", "", "}"];
 
         let span = FunctionSpan {
             name: "test".to_string(),
+            start_line: 1,
             lines,
+            is_test: false,
         };
 
         let (total, code, comment, empty) = count_function_lines(&span);
@@ -423,28 +438,216 @@ mod tests {
     }
 
     #[test]
-    fn test_calculate_cyclomatic_complexity_from_source() {
-        let source =
-            "fn with_if(x: i32) {\n    if x > 0 {\n        println!(\"positive\");\n    }\n}";
-        let complexity = calculate_cyclomatic_complexity_from_source(source, "with_if");
-        assert_eq!(complexity, 2); // Base 1 + if 1
+    fn test_calculate_cyclomatic_complexity() {
+        let func: ItemFn = syn::parse_quote! {
+            fn with_if(x: i32) {
+                if x > 0 {
+                    println!("positive");
+                }
+            }
+        };
+        assert_eq!(calculate_cyclomatic_complexity(&func), 2); // Base 1 + if 1
     }
 
     #[test]
-    fn test_calculate_nesting_depth_from_source() {
-        let source = "fn nested() {\n    if true {\n        for i in 0..10 {\n            println!(\"{}\", i);\n        }\n    }\n}";
-        let nesting = calculate_nesting_depth_from_source(source, "nested");
-        assert_eq!(nesting, 2); // if + for
+    fn test_calculate_nesting_depth() {
+        let func: ItemFn = syn::parse_quote! {
+            fn nested() {
+                if true {
+                    for i in 0..10 {
+                        println!("{}", i);
+                    }
+                }
+            }
+        };
+        assert_eq!(calculate_nesting_depth(&func), 2); // if + for
+    }
+
+    #[test]
+    fn test_calculate_statement_counts() {
+        let func: ItemFn = syn::parse_quote! {
+            fn counted() {
+                let x = 1;
+                println!("{}", x);
+            }
+        };
+        assert_eq!(count_statements(&func), 2); // let + macro statement
+        assert_eq!(count_expressions(&func), 1); // literal 1 (macro arguments are not parsed)
     }
 
     #[test]
     fn test_function_not_found_defaults() {
         let source = "fn other_function() {}";
+        let span = FunctionSpan {
+            name: "nonexistent".to_string(),
+            start_line: 1,
+            lines: vec!["fn other_function() {}"],
+            is_test: false,
+        };
 
-        let complexity = calculate_cyclomatic_complexity_from_source(source, "nonexistent");
-        assert_eq!(complexity, 1); // Default complexity
+        let result = analyze_function_lines(&span, source);
+        assert!(!result.ast_metrics);
+        assert_eq!(result.cyclomatic_complexity, 1); // Default complexity
+        assert_eq!(result.nesting_depth, 0); // Default nesting
+        assert_eq!((result.stmt_count, result.expr_count), (0, 0)); // Default counts
+    }
+
+    #[test]
+    fn test_comment_density() {
+        let result = FunctionAnalysisResult {
+            total: 10,
+            comment: 2,
+            empty: 2,
+            stmt_count: 3,
+            expr_count: 4,
+            end_line: 10,
+            ..FunctionAnalysisResult::sample("documented", 6, 1)
+        };
+
+        assert_eq!(result.comment_density(), 25.0); // 2 / (6 + 2)
+    }
 
-        let nesting = calculate_nesting_depth_from_source(source, "nonexistent");
-        assert_eq!(nesting, 0); // Default nesting
+    #[test]
+    fn test_comment_density_without_lines() {
+        let result = FunctionAnalysisResult {
+            total: 1,
+            empty: 1,
+            end_line: 1,
+            ..FunctionAnalysisResult::sample("blank", 0, 1)
+        };
+
+        assert_eq!(result.comment_density(), 0.0);
+    }
+
+    #[test]
+    fn test_count_todo_markers() {
+        let lines = vec![
+            "fn debt() {",
+            "    // TODO: handle errors",
+            "    let x = 1; // FIXME and HACK",
+            "    /* TODOS is not a marker */",
+            "    let todo = \"TODO in a string\";",
+            "}",
+        ];
+
+        let span = FunctionSpan {
+            name: "debt".to_string(),
+            start_line: 1,
+            lines,
+            is_test: false,
+        };
+
+        let markers = AnalysisOptions::default().todo_markers;
+        assert_eq!(count_todo_markers(&span, &markers), 3);
+    }
+
+    #[test]
+    fn test_count_custom_todo_markers() {
+        let lines = vec![
+            "fn debt() {",
+            "    // TODO: handle errors",
+            "    // XXX: revisit",
+            "}",
+        ];
+
+        let span = FunctionSpan {
+            name: "debt".to_string(),
+            start_line: 1,
+            lines,
+            is_test: false,
+        };
+
+        assert_eq!(count_todo_markers(&span, &["XXX".to_string()]), 1);
+    }
+
+    #[test]
+    fn test_qualified_name_and_module_path_from_file() {
+        let in_file = |file: &str| FunctionAnalysisResult {
+            file: PathBuf::from(file),
+            ..FunctionAnalysisResult::sample("run", 3, 1)
+        };
+
+        let json = in_file("src/output/json.rs");
+        assert_eq!(json.source_file(), Some("src/output/json.rs"));
+        assert_eq!(json.qualified_name(), "src/output/json.rs::run");
+        assert_eq!(json.module_path().as_deref(), Some("crate::output::json"));
+        assert_eq!(
+            in_file("crates/core/src/analyzer/mod.rs")
+                .module_path()
+                .as_deref(),
+            Some("crate::analyzer")
+        );
+        assert_eq!(
+            in_file("src/lib.rs").module_path().as_deref(),
+            Some("crate")
+        );
+        assert_eq!(in_file("").source_file(), None);
+        assert_eq!(in_file("").qualified_name(), "run");
+    }
+
+    #[test]
+    fn test_analyze_source_matches_per_function_analysis() {
+        let source = r#"
+async fn fetch<T: Clone>(url: &str) -> T {
+    // TODO: retry
+    if url.is_empty() {
+        for _ in 0..3 {
+            wait();
+        }
+    }
+    todo!()
+}
+
+fn simple() {
+    let x = 1;
+}
+"#;
+        let options = AnalysisOptions::default();
+        let results = analyze_source(source, &options).unwrap();
+        let expected: Vec<FunctionAnalysisResult> = extract_function_spans(source)
+            .unwrap()
+            .iter()
+            .map(|span| analyze_function_lines(span, source))
+            .collect();
+
+        assert_eq!(results, expected);
+        assert_eq!(results[0].cyclomatic_complexity, 3);
+        assert_eq!(results[0].nesting_depth, 2);
+        assert_eq!(results[0].todo_count, 1);
+        assert!(results[0].resilience.is_some());
+        assert!(analyze_source("fn broken(", &options).is_err());
+    }
+
+    #[test]
+    fn test_analyze_parsed_reuses_callers_syntax_tree() {
+        let source = "fn one() {\n    if true {}\n}\n\nfn two() {}\n";
+        let file = syn::parse_file(source).unwrap();
+
+        let results = analyze_parsed(&file, source);
+
+        assert_eq!(
+            results,
+            analyze_source(source, &AnalysisOptions::default()).unwrap()
+        );
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].cyclomatic_complexity, 2);
+        assert_eq!((results[1].start_line, results[1].end_line), (5, 5));
+    }
+
+    #[test]
+    fn test_parse_error_reports_its_location() {
+        let error = analyze_source(
+            "fn ok() {}\n\nfn broken() {\n    let = 1;\n}\n",
+            &AnalysisOptions::default(),
+        )
+        .unwrap_err()
+        .in_file("src/broken.rs");
+
+        match error {
+            AnalysisError::ParseError {
+                file, line, column, ..
+            } => assert_eq!((file.as_str(), line, column), ("src/broken.rs", 4, 9)),
+            error => panic!("expected a parse error, got {error}"),
+        }
     }
 }
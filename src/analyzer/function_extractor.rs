@@ -1,37 +1,245 @@
 use crate::errors::{AnalysisError, AnalysisResult};
-use syn::Item;
+use syn::punctuated::Punctuated;
+use syn::{Attribute, File, Item, ItemFn, Meta, Token};
 
 /// Represents a span of lines that contain a function
-pub struct FunctionSpan {
+///
+/// The lines borrow from the analyzed source, so extracting functions does not
+/// copy the file.
+pub struct FunctionSpan<'a> {
     pub name: String,
-    pub lines: Vec<String>,
+    /// 1-based line number of the first line of the function
+    pub start_line: usize,
+    pub lines: Vec<&'a str>,
+    /// True for test code: `#[test]`, `#[tokio::test]`, `#[bench]`, and
+    /// `#[cfg(test)]` functions, and the functions of `#[cfg(test)]` modules
+    pub is_test: bool,
+}
+
+impl FunctionSpan<'_> {
+    /// Returns the 1-based line number of the last line of the function
+    pub fn end_line(&self) -> usize {
+        self.start_line + self.lines.len().saturating_sub(1)
+    }
 }
 
 /// Extracts function spans from source code using syn parser
-pub fn extract_function_spans(source: &str) -> AnalysisResult<Vec<FunctionSpan>> {
-    let lines: Vec<&str> = source.lines().collect();
-    let parsed = syn::parse_file(source).map_err(|e| {
-        AnalysisError::Io(std::io::Error::other(format!(
-            "Failed to parse Rust source: {e}"
-        )))
-    })?;
+pub fn extract_function_spans(source: &str) -> AnalysisResult<Vec<FunctionSpan<'_>>> {
+    let parsed = parse_source(source)?;
+    Ok(extract_parsed_functions(&parsed, source)
+        .into_iter()
+        .map(|(span, _)| span)
+        .collect())
+}
 
-    let mut spans = Vec::new();
+/// Parses a Rust source file
+///
+/// A syntax error becomes [`AnalysisError::ParseError`] at the position syn
+/// reports, with an empty file; see [`AnalysisError::in_file`].
+pub fn parse_source(source: &str) -> AnalysisResult<File> {
+    syn::parse_file(source).map_err(|e| {
+        let start = e.span().start();
+        AnalysisError::ParseError {
+            file: String::new(),
+            line: start.line,
+            column: start.column + 1,
+            message: e.to_string(),
+        }
+    })
+}
+
+/// Extracts function spans from a file that has already been parsed from `source`
+///
+/// Top-level functions are extracted, as well as the functions of inline
+/// `#[cfg(test)]` modules, which are named after their module path, e.g.
+/// `tests::test_parse`. Other inline modules are not descended into.
+pub fn extract_parsed_functions<'a, 'f>(
+    file: &'f File,
+    source: &'a str,
+) -> Vec<(FunctionSpan<'a>, &'f ItemFn)> {
+    let lines: Vec<&str> = source.lines().collect();
+    let mut functions = Vec::new();
 
-    for item in parsed.items {
+    for item in &file.items {
         if let Item::Fn(f) = item {
             let name = f.sig.ident.to_string();
             // syn doesn't provide line numbers directly, so we'll use a simpler approach
             // We'll find the function by name in the source lines
             if let Some((start, end)) = find_function_bounds(&lines, &name) {
-                let slice: Vec<String> = lines[start..=end].iter().map(|s| s.to_string()).collect();
+                let span = FunctionSpan {
+                    name,
+                    start_line: start + 1,
+                    lines: lines[start..=end].to_vec(),
+                    is_test: is_test_function(&f.attrs),
+                };
+                functions.push((span, f));
+            }
+        } else if let Item::Mod(module) = item
+            && let Some((_, items)) = &module.content
+            && is_cfg_test(&module.attrs)
+        {
+            let prefix = format!("{}::", module.ident);
+            add_test_module_functions(items, &prefix, &lines, &mut functions);
+        }
+    }
+
+    functions
+}
 
-                spans.push(FunctionSpan { name, lines: slice });
+/// Adds the functions of a `#[cfg(test)]` module and of its inline submodules
+///
+/// Names may repeat those of top-level functions, so the lines are taken from
+/// the syntax tree instead of being searched by name.
+fn add_test_module_functions<'a, 'f>(
+    items: &'f [Item],
+    prefix: &str,
+    lines: &[&'a str],
+    functions: &mut Vec<(FunctionSpan<'a>, &'f ItemFn)>,
+) {
+    for item in items {
+        match item {
+            Item::Fn(f) => {
+                let start = f.sig.fn_token.span.start().line;
+                let end = f.block.brace_token.span.close().end().line;
+                if let Some(span_lines) = lines.get(start.saturating_sub(1)..end) {
+                    let span = FunctionSpan {
+                        name: format!("{prefix}{}", f.sig.ident),
+                        start_line: start,
+                        lines: span_lines.to_vec(),
+                        is_test: true,
+                    };
+                    functions.push((span, f));
+                }
+            }
+            Item::Mod(module) => {
+                if let Some((_, items)) = &module.content {
+                    let prefix = format!("{prefix}{}::", module.ident);
+                    add_test_module_functions(items, &prefix, lines, functions);
+                }
             }
+            _ => {}
         }
     }
+}
+
+/// Returns true if the attributes mark a test or benchmark function, or only
+/// compile it for tests
+fn is_test_function(attrs: &[Attribute]) -> bool {
+    is_cfg_test(attrs)
+        || attrs.iter().any(|attr| {
+            attr.path()
+                .segments
+                .last()
+                .is_some_and(|segment| segment.ident == "test" || segment.ident == "bench")
+        })
+}
 
-    Ok(spans)
+/// Returns true if a `#[cfg]` attribute requires the `test` configuration,
+/// e.g. `#[cfg(test)]` or `#[cfg(all(test, unix))]`
+fn is_cfg_test(attrs: &[Attribute]) -> bool {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("cfg"))
+        .filter_map(|attr| attr.parse_args::<Meta>().ok())
+        .any(|meta| requires_test(&meta))
+}
+
+fn requires_test(meta: &Meta) -> bool {
+    match meta {
+        Meta::Path(path) => path.is_ident("test"),
+        Meta::List(list) if list.path.is_ident("all") => list
+            .parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
+            .is_ok_and(|nested| nested.iter().any(requires_test)),
+        _ => false,
+    }
+}
+
+/// Finds top-level functions without parsing, from their `fn` keyword and brace balance
+///
+/// Used when syn cannot parse a file, e.g. because it uses syntax newer than
+/// the bundled syn. Braces in strings are counted too, so spans are approximate.
+pub fn extract_function_spans_heuristically(source: &str) -> Vec<FunctionSpan<'_>> {
+    let lines: Vec<&str> = source.lines().collect();
+    let mut functions = Vec::new();
+    let mut depth = 0;
+    let mut index = 0;
+
+    while index < lines.len() {
+        if depth == 0
+            && let Some(name) = function_name(lines[index])
+            && let Some(end) = body_end(&lines, index)
+        {
+            functions.push(FunctionSpan {
+                name,
+                start_line: index + 1,
+                lines: lines[index..=end].to_vec(),
+                is_test: false,
+            });
+            index = end + 1;
+            continue;
+        }
+        depth = brace_depth_after(lines[index], depth);
+        index += 1;
+    }
+
+    functions
+}
+
+/// Returns the name of the function declared on the line, if it starts a function
+fn function_name(line: &str) -> Option<String> {
+    let mut tokens = line.split_whitespace().skip_while(|token| {
+        matches!(
+            *token,
+            "pub" | "const" | "async" | "unsafe" | "extern" | "default"
+        ) || token.starts_with("pub(")
+            || token.starts_with('"')
+    });
+    if tokens.next()? != "fn" {
+        return None;
+    }
+    let name: String = tokens
+        .next()?
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || *c == '_' || *c == '#')
+        .collect();
+    (!name.is_empty()).then_some(name)
+}
+
+/// Returns the index of the line closing the body of the function starting at `start`
+///
+/// Returns None for declarations without a body and bodies that are never closed.
+fn body_end(lines: &[&str], start: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (index, line) in lines.iter().enumerate().skip(start) {
+        for ch in code_part(line).chars() {
+            match ch {
+                ';' if depth == 0 => return None,
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(index);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    None
+}
+
+/// Returns the brace depth after a line, starting from `depth`
+fn brace_depth_after(line: &str, depth: usize) -> usize {
+    code_part(line).chars().fold(depth, |depth, ch| match ch {
+        '{' => depth + 1,
+        '}' => depth.saturating_sub(1),
+        _ => depth,
+    })
+}
+
+/// Returns the line without its trailing `//` comment
+fn code_part(line: &str) -> &str {
+    line.split_once("//").map_or(line, |(code, _)| code)
 }
 
 /// Finds the start and end line indices of a function by name
@@ -64,3 +272,84 @@ fn find_function_bounds(lines: &[&str], fn_name: &str) -> Option<(usize, usize)>
 
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heuristic_extraction_finds_top_level_functions() {
+        let source = "\
+pub(crate) async fn fetch() -> u8 {
+    if yeet { 1 } else { 2 } // { unbalanced in a comment
+}
+
+extern \"C\" {
+    fn declared();
+}
+
+impl Foo {
+    fn method(&self) {}
+}
+
+const fn answer() -> u8 { 42 }
+";
+        let spans = extract_function_spans_heuristically(source);
+
+        let found: Vec<_> = spans
+            .iter()
+            .map(|span| (span.name.as_str(), span.start_line, span.end_line()))
+            .collect();
+        assert_eq!(found, [("fetch", 1, 3), ("answer", 13, 13)]);
+    }
+
+    #[test]
+    fn test_test_functions_are_marked() {
+        let source = "\
+fn helper() {}
+
+#[tokio::test]
+async fn fetches() {}
+
+#[cfg(all(test, unix))]
+mod tests {
+    fn helper() {
+        setup();
+    }
+
+    mod nested {
+        #[test]
+        fn works() {}
+    }
+}
+
+#[cfg(not(test))]
+mod production {
+    fn skipped() {}
+}
+";
+        let file = parse_source(source).unwrap();
+        let functions = extract_parsed_functions(&file, source);
+
+        let found: Vec<_> = functions
+            .iter()
+            .map(|(span, _)| {
+                (
+                    span.name.as_str(),
+                    span.start_line,
+                    span.end_line(),
+                    span.is_test,
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("helper", 1, 1, false),
+                ("fetches", 4, 4, true),
+                ("tests::helper", 8, 10, true),
+                ("tests::nested::works", 14, 14, true),
+            ]
+        );
+    }
+}
@@ -3,32 +3,233 @@
 //! This module contains all code analysis functionality including:
 //! - Cyclomatic complexity calculation
 //! - Nesting depth measurement
+//! - Statement and expression counting
+//! - Signature complexity (generics, lifetimes, where-clauses)
+//! - Timeout/retry detection for async functions
 //! - Function line analysis and aggregation
 //! - Function extraction from source code
+//! - Recovery of the parseable items of files with syntax errors
 
-use syn::{Item, parse_file};
+use crate::cancellation::CancellationToken;
+use crate::errors::AnalysisResult;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use syn::ItemFn;
 
 /// Result of analyzing a function's line composition, complexity, and nesting
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct FunctionAnalysisResult {
+    /// Function name, without its file
     pub name: String,
+    /// Source file of the function; empty if the function was not read from a file
+    pub file: PathBuf,
     pub total: usize,
     pub code: usize,
     pub comment: usize,
     pub empty: usize,
     pub cyclomatic_complexity: usize,
     pub nesting_depth: usize,
+    pub stmt_count: usize,
+    pub expr_count: usize,
+    pub todo_count: usize,
+    /// Generic parameters + lifetime parameters + where-clause predicates
+    pub signature_complexity: usize,
+    /// Timeout/retry protection; only present for async functions
+    pub resilience: Option<Resilience>,
+    /// 1-based line number where the function starts
+    pub start_line: usize,
+    /// 1-based line number where the function ends
+    pub end_line: usize,
+    /// False if the function could not be parsed and only its lines were counted;
+    /// complexity, nesting, and the other syntax-based metrics are then unavailable
+    pub ast_metrics: bool,
+    /// True for test code, see [`FunctionSpan::is_test`]
+    #[serde(default)]
+    pub is_test: bool,
+}
+
+/// Technical-debt markers counted in comments by default
+pub const DEFAULT_TODO_MARKERS: [&str; 3] = ["TODO", "FIXME", "HACK"];
+
+/// Options controlling how functions are analyzed
+#[derive(Debug, Clone)]
+pub struct AnalysisOptions {
+    /// Markers counted in the comments of each function (e.g. TODO, FIXME)
+    pub todo_markers: Vec<String>,
+    /// Rules deciding which constructs add cyclomatic complexity
+    pub complexity_rules: ComplexityRules,
+    /// Number of files analyzed in parallel; 0 uses one thread per CPU
+    pub jobs: usize,
+    /// Maximum number of files read at the same time; unbounded if None
+    pub max_open_files: Option<usize>,
+    /// Time after which the analysis of a single file is abandoned; unlimited if None
+    pub file_timeout: Option<Duration>,
+    /// Token that stops the analysis of further files when cancelled
+    pub cancellation: CancellationToken,
+    /// Analyze the top-level items that parse when a file has syntax errors
+    pub recover: bool,
+    /// Count the lines of functions that do not parse, found without syn
+    pub heuristic_fallback: bool,
+    /// Size in bytes above which a file is skipped instead of parsed; unlimited if None
+    pub max_file_size: Option<u64>,
+}
+
+impl Default for AnalysisOptions {
+    fn default() -> Self {
+        Self {
+            todo_markers: DEFAULT_TODO_MARKERS.iter().map(|m| m.to_string()).collect(),
+            complexity_rules: ComplexityRules::default(),
+            jobs: 0,
+            max_open_files: None,
+            file_timeout: None,
+            cancellation: CancellationToken::default(),
+            recover: false,
+            heuristic_fallback: false,
+            max_file_size: None,
+        }
+    }
+}
+
+/// Keys of the numeric metrics returned by [`FunctionAnalysisResult::metrics`], in order
+pub const METRIC_KEYS: [&str; 11] = [
+    "total",
+    "code",
+    "comment",
+    "empty",
+    "complexity",
+    "nesting",
+    "comment_density",
+    "todo_count",
+    "signature",
+    "stmts",
+    "exprs",
+];
+
+impl FunctionAnalysisResult {
+    /// Returns every numeric metric of the function keyed by its report name
+    /// The names match the keys used in JSON output and in test fixtures
+    pub fn metrics(&self) -> Vec<(&'static str, f64)> {
+        vec![
+            ("total", self.total as f64),
+            ("code", self.code as f64),
+            ("comment", self.comment as f64),
+            ("empty", self.empty as f64),
+            ("complexity", self.cyclomatic_complexity as f64),
+            ("nesting", self.nesting_depth as f64),
+            ("comment_density", self.comment_density()),
+            ("todo_count", self.todo_count as f64),
+            ("signature", self.signature_complexity as f64),
+            ("stmts", self.stmt_count as f64),
+            ("exprs", self.expr_count as f64),
+        ]
+    }
+
+    /// Returns a single metric by its key, or None for an unknown key
+    pub fn metric(&self, key: &str) -> Option<f64> {
+        self.metrics()
+            .into_iter()
+            .find(|&(metric, _)| metric == key)
+            .map(|(_, value)| value)
+    }
+
+    /// Returns the function name qualified with its file, e.g. `src/lib.rs::run`
+    ///
+    /// This is how functions are identified in reports, baselines, and diffs.
+    pub fn qualified_name(&self) -> String {
+        match self.source_file() {
+            Some(file) => format!("{file}::{}", self.name),
+            None => self.name.clone(),
+        }
+    }
+
+    /// Returns the source file with `/` separators, or None if the file is unknown
+    pub fn source_file(&self) -> Option<&str> {
+        self.file.to_str().filter(|file| !file.is_empty())
+    }
+
+    /// Returns the Rust module path of the function's source file, e.g. `crate::output::json`
+    ///
+    /// The path is taken relative to the last `src` directory; `lib.rs`, `main.rs`,
+    /// and `mod.rs` name their parent module. Inline `mod` blocks are not resolved.
+    pub fn module_path(&self) -> Option<String> {
+        let file = self.source_file()?.strip_suffix(".rs")?;
+        let relative = match file.rsplit_once("/src/") {
+            Some((_, relative)) => relative,
+            None => file.strip_prefix("src/").unwrap_or(file),
+        };
+        let mut segments = vec!["crate"];
+        segments.extend(relative.split('/'));
+        if matches!(segments.last(), Some(&("lib" | "main" | "mod"))) && segments.len() > 1 {
+            segments.pop();
+        }
+        Some(segments.join("::"))
+    }
+
+    /// Returns the comment density as a percentage: `comment / (code + comment) * 100`
+    /// Functions without any code or comment lines have a density of 0
+    pub fn comment_density(&self) -> f64 {
+        let counted = self.code + self.comment;
+        if counted == 0 {
+            0.0
+        } else {
+            self.comment as f64 * 100.0 / counted as f64
+        }
+    }
+}
+
+#[cfg(test)]
+impl FunctionAnalysisResult {
+    /// Test fixture: a function of `code` lines without comments or blank lines,
+    /// starting on line 1 of an unknown file, with every other metric at its minimum
+    ///
+    /// Tests set the fields they care about with struct update syntax.
+    pub(crate) fn sample(name: &str, code: usize, complexity: usize) -> Self {
+        Self {
+            name: name.to_string(),
+            file: PathBuf::new(),
+            total: code,
+            code,
+            comment: 0,
+            empty: 0,
+            cyclomatic_complexity: complexity,
+            nesting_depth: 0,
+            stmt_count: 0,
+            expr_count: 0,
+            todo_count: 0,
+            signature_complexity: 0,
+            resilience: None,
+            start_line: 1,
+            end_line: code,
+            ast_metrics: true,
+            is_test: false,
+        }
+    }
 }
 
 pub mod cyclomatic_complexity;
 pub mod function_analyzer;
 pub mod function_extractor;
 pub mod nesting_depth;
+pub mod recovery;
+pub mod resilience;
+pub mod signature_complexity;
+pub mod statement_count;
 
 // Re-export commonly used functions for convenience
-pub use cyclomatic_complexity::calculate_cyclomatic_complexity;
-pub use function_extractor::{FunctionSpan, extract_function_spans};
+pub use cyclomatic_complexity::{
+    ComplexityPreset, ComplexityRules, calculate_cyclomatic_complexity,
+    calculate_cyclomatic_complexity_with_rules,
+};
+pub use function_extractor::{
+    FunctionSpan, extract_function_spans, extract_function_spans_heuristically,
+    extract_parsed_functions, parse_source,
+};
 pub use nesting_depth::calculate_nesting_depth;
+pub use recovery::{UnparsedRegion, analyze_recovering};
+pub use resilience::{Resilience, detect_resilience};
+pub use signature_complexity::{SignatureComplexity, calculate_signature_complexity};
+pub use statement_count::{count_expressions, count_statements};
 
 // Main interface functions are now defined directly in this module
 
@@ -58,54 +259,175 @@ pub fn count_function_lines(func: &FunctionSpan) -> (usize, usize, usize, usize)
     (total, code, comment, empty)
 }
 
-/// Calculates cyclomatic complexity for a specific function by name from source code
-pub fn calculate_cyclomatic_complexity_from_source(source: &str, function_name: &str) -> usize {
-    if let Ok(parsed) = parse_file(source) {
-        for item in parsed.items {
-            if let Item::Fn(func) = item {
-                if func.sig.ident == function_name {
-                    return cyclomatic_complexity::calculate_cyclomatic_complexity(&func);
-                }
-            }
-        }
+/// Counts occurrences of technical-debt markers in the comments of a function span
+///
+/// Both full comment lines and trailing `//` comments are scanned. Markers only
+/// match as whole words, so `TODOS` or `todo_list` are not counted for `TODO`.
+pub fn count_todo_markers(func: &FunctionSpan, markers: &[String]) -> usize {
+    func.lines
+        .iter()
+        .filter_map(|line| comment_text(line))
+        .map(|comment| {
+            markers
+                .iter()
+                .map(|marker| count_word_occurrences(comment, marker))
+                .sum::<usize>()
+        })
+        .sum()
+}
+
+/// Returns the comment portion of a line, if any
+fn comment_text(line: &str) -> Option<&str> {
+    let trimmed = line.trim();
+    if trimmed.starts_with("//") || trimmed.starts_with("/*") || trimmed.starts_with('*') {
+        Some(trimmed)
+    } else {
+        trimmed.find("//").map(|pos| &trimmed[pos..])
     }
-    1 // Default complexity for simple functions
-}
-
-/// Calculates nesting depth for a specific function by name from source code
-pub fn calculate_nesting_depth_from_source(source: &str, function_name: &str) -> usize {
-    if let Ok(parsed) = parse_file(source) {
-        for item in parsed.items {
-            if let Item::Fn(func) = item {
-                if func.sig.ident == function_name {
-                    return nesting_depth::calculate_nesting_depth(&func);
-                }
-            }
-        }
+}
+
+/// Counts whole-word occurrences of a word in text
+fn count_word_occurrences(text: &str, word: &str) -> usize {
+    if word.is_empty() {
+        return 0;
     }
-    0 // Default nesting depth
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+    text.match_indices(word)
+        .filter(|(pos, _)| {
+            let before = text[..*pos].chars().next_back();
+            let after = text[pos + word.len()..].chars().next();
+            !before.is_some_and(is_word_char) && !after.is_some_and(is_word_char)
+        })
+        .count()
 }
 
-/// Analyzes the line composition, cyclomatic complexity, and nesting depth of a function span
-/// This is the main integration function that combines all metrics
-pub fn analyze_function_complete(func: &FunctionSpan, source: &str) -> FunctionAnalysisResult {
+/// Computes the line metrics of a function that could not be parsed
+///
+/// Without a syntax tree only the lines and TODO markers are counted; the
+/// result has `ast_metrics` unset and neutral values for the other metrics.
+pub fn analyze_unparsed_function(
+    func: &FunctionSpan,
+    options: &AnalysisOptions,
+) -> FunctionAnalysisResult {
+    let (total, code, comment, empty) = count_function_lines(func);
+    FunctionAnalysisResult {
+        name: func.name.clone(),
+        file: PathBuf::new(),
+        total,
+        code,
+        comment,
+        empty,
+        cyclomatic_complexity: 1,
+        nesting_depth: 0,
+        stmt_count: 0,
+        expr_count: 0,
+        todo_count: count_todo_markers(func, &options.todo_markers),
+        signature_complexity: 0,
+        resilience: None,
+        start_line: func.start_line,
+        end_line: func.end_line(),
+        ast_metrics: false,
+        is_test: func.is_test,
+    }
+}
+
+/// Time spent parsing a source file and computing the metrics of its functions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PhaseTimings {
+    pub parse: Duration,
+    pub analysis: Duration,
+}
+
+/// Analyzes every function of a source file, parsing it once
+pub fn analyze_source(
+    source: &str,
+    options: &AnalysisOptions,
+) -> AnalysisResult<Vec<FunctionAnalysisResult>> {
+    analyze_source_timed(source, options).map(|(results, _)| results)
+}
+
+/// Analyzes every function of a source file like [`analyze_source`], timing both phases
+pub fn analyze_source_timed(
+    source: &str,
+    options: &AnalysisOptions,
+) -> AnalysisResult<(Vec<FunctionAnalysisResult>, PhaseTimings)> {
+    let start = Instant::now();
+    let file = parse_source(source)?;
+    let parsed = Instant::now();
+    let results = analyze_parsed_with_options(&file, source, options);
+    let timings = PhaseTimings {
+        parse: parsed - start,
+        analysis: parsed.elapsed(),
+    };
+    Ok((results, timings))
+}
+
+/// Analyzes every function of a file that the caller has already parsed from `source`
+///
+/// Tools that parse with syn themselves can compute metrics without a second parse.
+pub fn analyze_parsed(file: &syn::File, source: &str) -> Vec<FunctionAnalysisResult> {
+    analyze_parsed_with_options(file, source, &AnalysisOptions::default())
+}
+
+/// Analyzes an already-parsed file like [`analyze_parsed`], with the given options
+pub fn analyze_parsed_with_options(
+    file: &syn::File,
+    source: &str,
+    options: &AnalysisOptions,
+) -> Vec<FunctionAnalysisResult> {
+    extract_parsed_functions(file, source)
+        .iter()
+        .map(|(span, item)| analyze_parsed_function(span, item, options))
+        .collect()
+}
+
+/// Computes all metrics of a function from its span and its parsed syntax tree
+pub fn analyze_parsed_function(
+    func: &FunctionSpan,
+    item: &ItemFn,
+    options: &AnalysisOptions,
+) -> FunctionAnalysisResult {
     let (total, code, comment, empty) = count_function_lines(func);
-    let cyclomatic_complexity = calculate_cyclomatic_complexity_from_source(source, &func.name);
-    let nesting_depth = calculate_nesting_depth_from_source(source, &func.name);
 
     FunctionAnalysisResult {
         name: func.name.clone(),
+        file: PathBuf::new(),
         total,
         code,
         comment,
         empty,
-        cyclomatic_complexity,
-        nesting_depth,
+        cyclomatic_complexity: cyclomatic_complexity::calculate_cyclomatic_complexity_with_rules(
+            item,
+            options.complexity_rules,
+        ),
+        nesting_depth: nesting_depth::calculate_nesting_depth(item),
+        stmt_count: statement_count::count_statements(item),
+        expr_count: statement_count::count_expressions(item),
+        todo_count: count_todo_markers(func, &options.todo_markers),
+        signature_complexity: signature_complexity::calculate_signature_complexity(item).score(),
+        resilience: resilience::detect_resilience(item),
+        start_line: func.start_line,
+        end_line: func.end_line(),
+        ast_metrics: true,
+        is_test: func.is_test,
     }
 }
 
-/// Backward compatibility alias for analyze_function_complete
-/// @deprecated Use analyze_function_complete instead
-pub fn analyze_function_lines(func: &FunctionSpan, source: &str) -> FunctionAnalysisResult {
-    analyze_function_complete(func, source)
+/// Analyzes a single function span, parsing `source` to find the function by name
+///
+/// Test helper only: the analysis parses each file once, see [`analyze_source`].
+#[cfg(test)]
+pub(crate) fn analyze_function_lines(func: &FunctionSpan, source: &str) -> FunctionAnalysisResult {
+    let options = AnalysisOptions::default();
+    let parsed = parse_source(source).ok();
+    let item = parsed.as_ref().and_then(|parsed| {
+        parsed.items.iter().find_map(|item| match item {
+            syn::Item::Fn(item) if item.sig.ident == func.name => Some(item),
+            _ => None,
+        })
+    });
+    match item {
+        Some(item) => analyze_parsed_function(func, item, &options),
+        None => analyze_unparsed_function(func, &options),
+    }
 }
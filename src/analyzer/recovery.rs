@@ -0,0 +1,239 @@
+//! Recovery from syntax errors
+//!
+//! A single syntax error makes syn reject the whole file. With recovery, the
+//! file is split at top-level item boundaries and every chunk is parsed on its
+//! own, so one broken item no longer discards the functions around it.
+//! Consecutive chunks that still fail to parse are reported as an
+//! [`UnparsedRegion`].
+//!
+//! Item boundaries are found from the layout alone: an item starts at a line
+//! that begins in the first column, together with the attributes and comments
+//! right above it. Rustfmt-formatted code always follows this layout.
+//!
+//! With the heuristic fallback, the functions of the regions that do not parse
+//! are still found by their `fn` keyword and brace balance and get line
+//! counts, without the syntax-based metrics.
+
+use super::function_extractor::{
+    extract_function_spans_heuristically, extract_parsed_functions, parse_source,
+};
+use super::{
+    AnalysisOptions, FunctionAnalysisResult, analyze_parsed_function, analyze_unparsed_function,
+};
+use crate::errors::AnalysisError;
+
+/// Lines of a source file that do not parse, even split into items
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnparsedRegion {
+    /// 1-based line number of the first line of the region
+    pub start_line: usize,
+    /// 1-based line number of the last line of the region
+    pub end_line: usize,
+    /// Syntax error reported for the first item of the region
+    pub message: String,
+}
+
+/// A top-level item of a source file, with the comments and attributes above it
+struct Chunk<'a> {
+    /// 1-based line number of the first line of the chunk
+    start_line: usize,
+    text: &'a str,
+}
+
+impl Chunk<'_> {
+    /// Returns the 1-based line number of the last line of the chunk
+    fn end_line(&self) -> usize {
+        self.start_line + self.text.lines().count().max(1) - 1
+    }
+}
+
+/// Analyzes the functions of a source file with syntax errors as far as possible
+///
+/// With `options.recover` every top-level item that parses is analyzed; with
+/// `options.heuristic_fallback` the lines of the functions that do not parse
+/// are counted. Returns the results in source order together with the regions
+/// that do not parse.
+pub fn analyze_recovering(
+    source: &str,
+    options: &AnalysisOptions,
+) -> (Vec<FunctionAnalysisResult>, Vec<UnparsedRegion>) {
+    let mut results = Vec::new();
+    let mut regions: Vec<UnparsedRegion> = Vec::new();
+    let chunks = if options.recover {
+        item_chunks(source)
+    } else {
+        vec![Chunk {
+            start_line: 1,
+            text: source,
+        }]
+    };
+
+    for chunk in chunks {
+        match parse_source(chunk.text) {
+            Ok(file) => {
+                for (mut span, item) in extract_parsed_functions(&file, chunk.text) {
+                    span.start_line += chunk.start_line - 1;
+                    results.push(analyze_parsed_function(&span, item, options));
+                }
+            }
+            Err(error) => {
+                if options.heuristic_fallback {
+                    for mut span in extract_function_spans_heuristically(chunk.text) {
+                        span.start_line += chunk.start_line - 1;
+                        results.push(analyze_unparsed_function(&span, options));
+                    }
+                }
+                add_region(&mut regions, &chunk, error);
+            }
+        }
+    }
+
+    (results, regions)
+}
+
+/// Adds a chunk that does not parse to the regions, extending the last one if adjacent
+fn add_region(regions: &mut Vec<UnparsedRegion>, chunk: &Chunk, error: AnalysisError) {
+    match regions.last_mut() {
+        Some(region) if region.end_line + 1 == chunk.start_line => {
+            region.end_line = chunk.end_line();
+        }
+        _ => regions.push(UnparsedRegion {
+            start_line: chunk.start_line,
+            end_line: chunk.end_line(),
+            message: match error {
+                AnalysisError::ParseError { message, .. } => message,
+                error => error.to_string(),
+            },
+        }),
+    }
+}
+
+/// Splits source code into chunks of one top-level item each
+fn item_chunks(source: &str) -> Vec<Chunk<'_>> {
+    let mut chunks = Vec::new();
+    let mut chunk_start = 0;
+    let mut chunk_line = 1;
+    let mut offset = 0;
+    let mut previous_attaches = false;
+
+    for (index, line) in source.split_inclusive('\n').enumerate() {
+        if starts_item(line) && !previous_attaches && offset > chunk_start {
+            chunks.push(Chunk {
+                start_line: chunk_line,
+                text: &source[chunk_start..offset],
+            });
+            chunk_start = offset;
+            chunk_line = index + 1;
+        }
+        if !line.trim().is_empty() {
+            previous_attaches = attaches_to_next(line);
+        }
+        offset += line.len();
+    }
+    if offset > chunk_start {
+        chunks.push(Chunk {
+            start_line: chunk_line,
+            text: &source[chunk_start..],
+        });
+    }
+
+    chunks
+}
+
+/// Returns true if the line may begin a top-level item: it starts in the first
+/// column and does not close a bracket
+fn starts_item(line: &str) -> bool {
+    line.chars()
+        .next()
+        .is_some_and(|c| !c.is_whitespace() && !matches!(c, '}' | ')' | ']'))
+}
+
+/// Returns true if the line belongs to the item below it: an attribute or a comment
+fn attaches_to_next(line: &str) -> bool {
+    line.starts_with("#[") || line.starts_with("//") || line.starts_with("/*")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_items_around_a_syntax_error_are_analyzed() {
+        let source = "\
+/// Adds one
+fn before(x: u32) -> u32 {
+    x + 1
+}
+
+fn broken( {
+    let = ;
+}
+
+#[inline]
+fn after() {
+    if true {}
+}
+";
+        let options = AnalysisOptions {
+            recover: true,
+            ..AnalysisOptions::default()
+        };
+        assert!(parse_source(source).is_err());
+
+        let (results, regions) = analyze_recovering(source, &options);
+
+        let names: Vec<_> = results.iter().map(|result| result.name.as_str()).collect();
+        assert_eq!(names, ["before", "after"]);
+        assert_eq!((results[1].start_line, results[1].end_line), (11, 13));
+        assert_eq!(results[1].cyclomatic_complexity, 2);
+        assert_eq!(regions.len(), 1);
+        assert_eq!((regions[0].start_line, regions[0].end_line), (6, 9));
+    }
+
+    #[test]
+    fn test_consecutive_broken_items_form_one_region() {
+        let source = "fn a( {\n}\nfn b( {\n}\nfn c() {}\n";
+
+        let options = AnalysisOptions {
+            recover: true,
+            ..AnalysisOptions::default()
+        };
+
+        let (results, regions) = analyze_recovering(source, &options);
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(
+            regions
+                .iter()
+                .map(|region| (region.start_line, region.end_line))
+                .collect::<Vec<_>>(),
+            [(1, 4)]
+        );
+    }
+
+    #[test]
+    fn test_heuristic_fallback_counts_lines_of_broken_functions() {
+        let source = "fn parsed() {}\n\nfn newer() {\n    let x = 1 ~~ 2;\n    x\n}\n";
+        let options = AnalysisOptions {
+            heuristic_fallback: true,
+            ..AnalysisOptions::default()
+        };
+
+        let (results, regions) = analyze_recovering(source, &options);
+
+        // Without --recover the whole file is one region, so both are estimated
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|result| !result.ast_metrics));
+        assert_eq!((results[1].name.as_str(), results[1].total), ("newer", 4));
+        assert_eq!((results[1].start_line, results[1].end_line), (3, 6));
+        assert_eq!(regions.len(), 1);
+
+        let recovering = AnalysisOptions {
+            recover: true,
+            ..options
+        };
+        let (results, _) = analyze_recovering(source, &recovering);
+        assert!(results[0].ast_metrics);
+        assert!(!results[1].ast_metrics);
+    }
+}
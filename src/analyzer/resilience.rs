@@ -0,0 +1,135 @@
+use serde::{Deserialize, Serialize};
+use syn::visit::{self, Visit};
+use syn::{Expr, ItemFn, Path};
+
+/// Timeout and retry protection detected in an async function
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Resilience {
+    /// No timeout or retry wrapper was found
+    Unprotected,
+    /// The function wraps work in a timeout (e.g. `tokio::time::timeout`)
+    Timeout,
+    /// The function wraps work in a retry helper (e.g. `tokio_retry::Retry`)
+    Retry,
+    /// Both timeout and retry wrappers were found
+    TimeoutAndRetry,
+}
+
+impl Resilience {
+    /// Returns the label used in reports
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Resilience::Unprotected => "unprotected",
+            Resilience::Timeout => "timeout",
+            Resilience::Retry => "retry",
+            Resilience::TimeoutAndRetry => "timeout+retry",
+        }
+    }
+}
+
+/// Detects timeout and retry wrappers used by an async function
+///
+/// Returns `None` for functions that are not `async`. Detection is name based:
+/// any called function, method, or path segment containing `timeout` or `retry`
+/// (case-insensitive) counts, which covers `tokio::time::timeout`,
+/// `future.timeout(..)`, `tokio_retry::Retry::spawn`, and `backoff::future::retry`.
+pub fn detect_resilience(func: &ItemFn) -> Option<Resilience> {
+    func.sig.asyncness?;
+
+    let mut detector = WrapperDetector::default();
+    detector.visit_block(&func.block);
+
+    let resilience = match (detector.timeout, detector.retry) {
+        (true, true) => Resilience::TimeoutAndRetry,
+        (true, false) => Resilience::Timeout,
+        (false, true) => Resilience::Retry,
+        (false, false) => Resilience::Unprotected,
+    };
+    Some(resilience)
+}
+
+/// AST visitor that looks for timeout and retry wrappers in calls
+#[derive(Default)]
+struct WrapperDetector {
+    timeout: bool,
+    retry: bool,
+}
+
+impl WrapperDetector {
+    fn record_name(&mut self, name: &str) {
+        let name = name.to_lowercase();
+        self.timeout |= name.contains("timeout");
+        self.retry |= name.contains("retry");
+    }
+
+    fn record_path(&mut self, path: &Path) {
+        for segment in &path.segments {
+            self.record_name(&segment.ident.to_string());
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for WrapperDetector {
+    fn visit_expr(&mut self, expr: &'ast Expr) {
+        match expr {
+            Expr::Call(call) => {
+                if let Expr::Path(path) = call.func.as_ref() {
+                    self.record_path(&path.path);
+                }
+            }
+            Expr::MethodCall(call) => self.record_name(&call.method.to_string()),
+            _ => {}
+        }
+        visit::visit_expr(self, expr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse_quote;
+
+    #[test]
+    fn test_sync_function_has_no_annotation() {
+        let func: ItemFn = parse_quote! {
+            fn sync() {
+                std::thread::sleep(timeout());
+            }
+        };
+
+        assert_eq!(detect_resilience(&func), None);
+    }
+
+    #[test]
+    fn test_unprotected_async_function() {
+        let func: ItemFn = parse_quote! {
+            async fn fetch(client: &Client) -> Response {
+                client.get(URL).send().await
+            }
+        };
+
+        assert_eq!(detect_resilience(&func), Some(Resilience::Unprotected));
+    }
+
+    #[test]
+    fn test_tokio_timeout() {
+        let func: ItemFn = parse_quote! {
+            async fn fetch(client: &Client) -> Result<Response, Elapsed> {
+                tokio::time::timeout(Duration::from_secs(5), client.get(URL).send()).await
+            }
+        };
+
+        assert_eq!(detect_resilience(&func), Some(Resilience::Timeout));
+    }
+
+    #[test]
+    fn test_timeout_and_retry() {
+        let func: ItemFn = parse_quote! {
+            async fn fetch(client: &Client) -> Result<Response, Error> {
+                Retry::spawn(strategy(), || client.get(URL).send().timeout(LIMIT)).await
+            }
+        };
+
+        assert_eq!(detect_resilience(&func), Some(Resilience::TimeoutAndRetry));
+    }
+}
@@ -0,0 +1,92 @@
+use syn::{GenericParam, ItemFn};
+
+/// Generic parameter counts of a function signature
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct SignatureComplexity {
+    /// Type and const generic parameters
+    pub generic_params: usize,
+    /// Lifetime parameters
+    pub lifetime_params: usize,
+    /// Predicates in the `where` clause
+    pub where_predicates: usize,
+}
+
+impl SignatureComplexity {
+    /// Returns the combined signature-complexity score
+    ///
+    /// Every generic parameter, lifetime parameter, and where-clause predicate
+    /// adds one, so a plain `fn foo(x: i32)` scores 0.
+    pub fn score(&self) -> usize {
+        self.generic_params + self.lifetime_params + self.where_predicates
+    }
+}
+
+/// Counts the generic parameters, lifetimes, and where-clause predicates of a function
+///
+/// Only the function's own signature is inspected; generics of an enclosing
+/// `impl` block and anonymous `impl Trait` arguments are not counted.
+pub fn calculate_signature_complexity(func: &ItemFn) -> SignatureComplexity {
+    let generics = &func.sig.generics;
+    let mut complexity = SignatureComplexity::default();
+
+    for param in &generics.params {
+        match param {
+            GenericParam::Lifetime(_) => complexity.lifetime_params += 1,
+            GenericParam::Type(_) | GenericParam::Const(_) => complexity.generic_params += 1,
+        }
+    }
+    complexity.where_predicates = generics
+        .where_clause
+        .as_ref()
+        .map_or(0, |where_clause| where_clause.predicates.len());
+
+    complexity
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse_quote;
+
+    #[test]
+    fn test_plain_function_scores_zero() {
+        let func: ItemFn = parse_quote! {
+            fn plain(x: i32) -> i32 { x }
+        };
+
+        let complexity = calculate_signature_complexity(&func);
+        assert_eq!(complexity, SignatureComplexity::default());
+        assert_eq!(complexity.score(), 0);
+    }
+
+    #[test]
+    fn test_generics_lifetimes_and_where_clause() {
+        let func: ItemFn = parse_quote! {
+            fn merge<'a, 'b, K, V, const N: usize>(left: &'a [K; N], right: &'b V) -> usize
+            where
+                K: Ord + Clone,
+                V: IntoIterator<Item = K>,
+                'b: 'a,
+            {
+                0
+            }
+        };
+
+        let complexity = calculate_signature_complexity(&func);
+        assert_eq!(complexity.generic_params, 3);
+        assert_eq!(complexity.lifetime_params, 2);
+        assert_eq!(complexity.where_predicates, 3);
+        assert_eq!(complexity.score(), 8);
+    }
+
+    #[test]
+    fn test_inline_bounds_are_not_where_predicates() {
+        let func: ItemFn = parse_quote! {
+            fn show<T: std::fmt::Display>(value: T) {}
+        };
+
+        let complexity = calculate_signature_complexity(&func);
+        assert_eq!(complexity.generic_params, 1);
+        assert_eq!(complexity.where_predicates, 0);
+    }
+}
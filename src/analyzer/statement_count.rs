@@ -0,0 +1,107 @@
+use syn::visit::{self, Visit};
+use syn::{Expr, ItemFn, Stmt};
+
+/// Counts the statements in a function body
+///
+/// Statement counts are derived from the AST rather than physical lines, so they
+/// stay stable when code is reformatted (e.g. a long call split over many lines).
+/// Statements inside nested blocks, closures, and nested functions are included.
+pub fn count_statements(func: &ItemFn) -> usize {
+    let mut counter = NodeCounter::default();
+    counter.visit_block(&func.block);
+    counter.statements
+}
+
+/// Counts the expressions in a function body
+///
+/// Every expression node is counted, including sub-expressions, so `a + b`
+/// contributes three expressions (the binary expression and both operands).
+pub fn count_expressions(func: &ItemFn) -> usize {
+    let mut counter = NodeCounter::default();
+    counter.visit_block(&func.block);
+    counter.expressions
+}
+
+/// AST visitor that tallies statement and expression nodes
+#[derive(Default)]
+struct NodeCounter {
+    statements: usize,
+    expressions: usize,
+}
+
+impl<'ast> Visit<'ast> for NodeCounter {
+    fn visit_stmt(&mut self, stmt: &'ast Stmt) {
+        self.statements += 1;
+        visit::visit_stmt(self, stmt);
+    }
+
+    fn visit_expr(&mut self, expr: &'ast Expr) {
+        self.expressions += 1;
+        visit::visit_expr(self, expr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse_quote;
+
+    #[test]
+    fn test_empty_function_counts() {
+        let func: ItemFn = parse_quote! {
+            fn empty() {}
+        };
+
+        assert_eq!(count_statements(&func), 0);
+        assert_eq!(count_expressions(&func), 0);
+    }
+
+    #[test]
+    fn test_simple_statements() {
+        let func: ItemFn = parse_quote! {
+            fn simple() -> i32 {
+                let x = 1;
+                let y = x + 2;
+                y
+            }
+        };
+
+        assert_eq!(count_statements(&func), 3);
+        // 1, x + 2, x, 2, y
+        assert_eq!(count_expressions(&func), 5);
+    }
+
+    #[test]
+    fn test_counts_are_independent_of_formatting() {
+        let compact: ItemFn = parse_quote! {
+            fn compact() { call(a, b, c); }
+        };
+        let spread: ItemFn = parse_quote! {
+            fn spread() {
+                call(
+                    a,
+                    b,
+                    c,
+                );
+            }
+        };
+
+        assert_eq!(count_statements(&compact), count_statements(&spread));
+        assert_eq!(count_expressions(&compact), count_expressions(&spread));
+    }
+
+    #[test]
+    fn test_nested_blocks_are_counted() {
+        let func: ItemFn = parse_quote! {
+            fn nested(flag: bool) {
+                if flag {
+                    let a = 1;
+                    println!("{}", a);
+                }
+            }
+        };
+
+        // if expression statement, let, macro statement
+        assert_eq!(count_statements(&func), 3);
+    }
+}
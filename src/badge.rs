@@ -0,0 +1,198 @@
+//! Code-health badges
+//!
+//! `fnloc badge` summarizes an analysis as a single badge — the average
+//! cyclomatic complexity, or the most complex function — rendered either as a
+//! standalone SVG or as a shields.io endpoint JSON document, so repositories can
+//! show badges generated from local analysis.
+
+use crate::analyzer::FunctionAnalysisResult;
+use serde::Serialize;
+
+/// Metric shown on a badge
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BadgeMetric {
+    /// Average cyclomatic complexity over all functions (default)
+    #[default]
+    AverageComplexity,
+    /// Cyclomatic complexity of the most complex function
+    WorstFunction,
+}
+
+/// Encoding of a badge
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BadgeStyle {
+    /// Standalone flat SVG image (default)
+    #[default]
+    Svg,
+    /// shields.io endpoint JSON, for https://img.shields.io/endpoint
+    Shields,
+}
+
+/// Label, message, and color of a badge
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Badge {
+    pub label: String,
+    pub message: String,
+    /// Hex color of the message side, without the leading `#`
+    pub color: &'static str,
+}
+
+/// shields.io endpoint schema
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ShieldsEndpoint<'a> {
+    schema_version: u8,
+    label: &'a str,
+    message: &'a str,
+    color: &'a str,
+}
+
+impl Badge {
+    /// Summarizes analysis results as a badge for the given metric
+    pub fn from_results(results: &[FunctionAnalysisResult], metric: BadgeMetric) -> Self {
+        match metric {
+            BadgeMetric::AverageComplexity => {
+                let total: usize = results.iter().map(|r| r.cyclomatic_complexity).sum();
+                let average = total as f64 / results.len().max(1) as f64;
+                Self {
+                    label: "avg complexity".to_string(),
+                    message: format!("{average:.1}"),
+                    color: complexity_color(average),
+                }
+            }
+            BadgeMetric::WorstFunction => {
+                let worst = results.iter().max_by_key(|r| r.cyclomatic_complexity);
+                let (message, complexity) = match worst {
+                    Some(worst) => (
+                        format!("{} ({})", worst.name, worst.cyclomatic_complexity),
+                        worst.cyclomatic_complexity as f64,
+                    ),
+                    None => ("none".to_string(), 0.0),
+                };
+                Self {
+                    label: "max complexity".to_string(),
+                    message,
+                    color: complexity_color(complexity),
+                }
+            }
+        }
+    }
+
+    /// Renders the badge in the given style
+    pub fn render(&self, style: BadgeStyle) -> String {
+        match style {
+            BadgeStyle::Svg => self.to_svg(),
+            BadgeStyle::Shields => self.to_shields_json(),
+        }
+    }
+
+    /// Renders a flat SVG badge
+    pub fn to_svg(&self) -> String {
+        let label_width = text_width(&self.label);
+        let message_width = text_width(&self.message);
+        let width = label_width + message_width;
+        let label = escape_xml(&self.label);
+        let message = escape_xml(&self.message);
+        let label_x = label_width / 2;
+        let message_x = label_width + message_width / 2;
+        format!(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{label}: {message}">
+  <title>{label}: {message}</title>
+  <rect width="{label_width}" height="20" fill="#555"/>
+  <rect x="{label_width}" width="{message_width}" height="20" fill="#{color}"/>
+  <g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11">
+    <text x="{label_x}" y="14">{label}</text>
+    <text x="{message_x}" y="14">{message}</text>
+  </g>
+</svg>"##,
+            color = self.color
+        )
+    }
+
+    /// Renders a shields.io endpoint JSON document
+    pub fn to_shields_json(&self) -> String {
+        let endpoint = ShieldsEndpoint {
+            schema_version: 1,
+            label: &self.label,
+            message: &self.message,
+            color: self.color,
+        };
+        // The endpoint only contains strings and numbers, so serialization cannot fail
+        serde_json::to_string(&endpoint).unwrap_or_default()
+    }
+}
+
+/// Picks a color from green to red for a cyclomatic complexity
+fn complexity_color(complexity: f64) -> &'static str {
+    if complexity <= 5.0 {
+        "4c1"
+    } else if complexity <= 10.0 {
+        "dfb317"
+    } else if complexity <= 20.0 {
+        "fe7d37"
+    } else {
+        "e05d44"
+    }
+}
+
+/// Approximates the rendered width of badge text, including padding
+fn text_width(text: &str) -> usize {
+    text.chars().count() * 7 + 10
+}
+
+/// Escapes text for use in SVG content and attributes
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn sample_result(name: &str, complexity: usize) -> FunctionAnalysisResult {
+        let (file, name) = name.rsplit_once("::").unwrap_or(("", name));
+        FunctionAnalysisResult {
+            file: PathBuf::from(file),
+            total: 10,
+            comment: 1,
+            empty: 1,
+            nesting_depth: 1,
+            stmt_count: 3,
+            expr_count: 5,
+            end_line: 10,
+            ..FunctionAnalysisResult::sample(name, 8, complexity)
+        }
+    }
+
+    #[test]
+    fn test_average_complexity_badge() {
+        let results = [sample_result("a", 2), sample_result("b", 5)];
+        let badge = Badge::from_results(&results, BadgeMetric::AverageComplexity);
+
+        assert_eq!(badge.message, "3.5");
+        assert_eq!(badge.color, "4c1");
+        assert_eq!(
+            badge.to_shields_json(),
+            r#"{"schemaVersion":1,"label":"avg complexity","message":"3.5","color":"4c1"}"#
+        );
+    }
+
+    #[test]
+    fn test_worst_function_badge_svg() {
+        let results = [
+            sample_result("src/lib.rs::small", 2),
+            sample_result("src/lib.rs::parse<T>", 24),
+        ];
+        let badge = Badge::from_results(&results, BadgeMetric::WorstFunction);
+        let svg = badge.to_svg();
+
+        assert_eq!(badge.message, "parse<T> (24)");
+        assert_eq!(badge.color, "e05d44");
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.contains(">parse&lt;T&gt; (24)</text>"));
+    }
+}
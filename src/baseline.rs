@@ -0,0 +1,246 @@
+//! Metric baselines for ratcheting thresholds in legacy code
+//!
+//! `fnloc baseline write` snapshots the metrics of every function. With
+//! `--baseline <file>`, a function may exceed a limit only as far as it already
+//! did in the snapshot: existing offenders must not get worse, and new
+//! functions must stay within the limits.
+//!
+//! Functions are keyed by `path/to/file.rs::function` with the path relative to
+//! the project root, so `./src`, `src`, and absolute paths share one key.
+
+use crate::analyzer::FunctionAnalysisResult;
+use crate::config::{Thresholds, Violation};
+use crate::errors::{AnalysisError, AnalysisResult};
+use crate::output::formatter::create_output_file;
+use crate::source::read_text;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io;
+use std::io::Write;
+use std::path::{Component, Path};
+
+/// File written by `fnloc baseline write` when no other file is given
+pub const DEFAULT_BASELINE_FILE: &str = "fnloc-baseline.json";
+
+/// Metrics of one function at the time of the snapshot
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BaselineMetrics {
+    pub total: usize,
+    pub code: usize,
+    pub complexity: usize,
+    pub nesting: usize,
+}
+
+/// Snapshot of the metrics of every function, keyed by function name
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Baseline {
+    pub functions: BTreeMap<String, BaselineMetrics>,
+}
+
+impl Baseline {
+    /// Snapshots analysis results; functions sharing a name keep the worst value of each metric
+    pub fn from_results(results: &[FunctionAnalysisResult]) -> Self {
+        let mut functions: BTreeMap<String, BaselineMetrics> = BTreeMap::new();
+        for result in results {
+            let metrics = functions.entry(baseline_key(result)).or_default();
+            metrics.total = metrics.total.max(result.total);
+            metrics.code = metrics.code.max(result.code);
+            metrics.complexity = metrics.complexity.max(result.cyclomatic_complexity);
+            metrics.nesting = metrics.nesting.max(result.nesting_depth);
+        }
+        Self { functions }
+    }
+
+    /// Loads a baseline file; a file that cannot be read is a usage error naming the file
+    pub fn load(path: &Path) -> AnalysisResult<Self> {
+        let source = read_text(path).map_err(|e| match e {
+            AnalysisError::Io(e) if e.kind() == io::ErrorKind::NotFound => {
+                AnalysisError::PathNotFound {
+                    path: path.display().to_string(),
+                    hint: Some(format!(
+                        "write one with `fnloc baseline write --file {}`",
+                        path.display()
+                    )),
+                }
+            }
+            AnalysisError::Io(e) => AnalysisError::InvalidConfig {
+                message: format!("baseline {}: {e}", path.display()),
+            },
+            e => e,
+        })?;
+        let baseline: Self =
+            serde_json::from_str(&source).map_err(|e| AnalysisError::InvalidConfig {
+                message: format!("baseline {}: {e}", path.display()),
+            })?;
+        let functions = baseline
+            .functions
+            .into_iter()
+            .map(|(key, metrics)| (normalize_key(&key), metrics))
+            .collect();
+        Ok(Self { functions })
+    }
+
+    /// Writes the baseline as pretty-printed JSON, creating parent directories as needed
+    pub fn write(&self, path: &Path) -> AnalysisResult<()> {
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        writeln!(create_output_file(path)?, "{json}")?;
+        Ok(())
+    }
+
+    /// Returns the limits a function must stay within
+    ///
+    /// For a function in the baseline, each configured limit is raised to the
+    /// value recorded for it; functions not in the baseline get the plain limits.
+    pub fn limits_for(&self, function: &str, limits: &Thresholds) -> Thresholds {
+        let Some(recorded) = self.functions.get(function) else {
+            return *limits;
+        };
+        let raise = |limit: Option<usize>, value: usize| limit.map(|limit| limit.max(value));
+        Thresholds {
+            total: raise(limits.total, recorded.total),
+            code: raise(limits.code, recorded.code),
+            complexity: raise(limits.complexity, recorded.complexity),
+            nesting: raise(limits.nesting, recorded.nesting),
+        }
+    }
+
+    /// Returns every function that exceeds its limits, see [`Baseline::limits_for`]
+    pub fn violations(
+        &self,
+        results: &[FunctionAnalysisResult],
+        limits: &Thresholds,
+    ) -> Vec<Violation> {
+        results
+            .iter()
+            .flat_map(|result| {
+                self.limits_for(&baseline_key(result), limits)
+                    .violations(result)
+            })
+            .collect()
+    }
+}
+
+/// Returns the key of a function in the baseline
+fn baseline_key(result: &FunctionAnalysisResult) -> String {
+    normalize_key(&result.qualified_name())
+}
+
+/// Rewrites the file part of a `path/to/file.rs::function` key relative to the project root
+fn normalize_key(key: &str) -> String {
+    match key.rfind(".rs::") {
+        Some(pos) => {
+            let (file, function) = key.split_at(pos + ".rs".len());
+            format!("{}{function}", project_relative(Path::new(file)))
+        }
+        None => key.to_string(),
+    }
+}
+
+/// Returns a path relative to the current directory with `/` separators and no `./`
+fn project_relative(path: &Path) -> String {
+    let root = std::env::current_dir().unwrap_or_default();
+    let path = path.strip_prefix(&root).unwrap_or(path);
+    path.components()
+        .filter(|component| *component != Component::CurDir)
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn sample_result(name: &str, code: usize, complexity: usize) -> FunctionAnalysisResult {
+        FunctionAnalysisResult {
+            nesting_depth: 1,
+            ..FunctionAnalysisResult::sample(name, code, complexity)
+        }
+    }
+
+    #[test]
+    fn test_ratchet_allows_existing_offenders_but_not_regressions() {
+        let limits = Thresholds {
+            code: Some(50),
+            complexity: Some(10),
+            ..Thresholds::default()
+        };
+        let baseline = Baseline::from_results(&[sample_result("legacy", 200, 30)]);
+
+        let unchanged = [sample_result("legacy", 200, 30)];
+        assert!(baseline.violations(&unchanged, &limits).is_empty());
+
+        let worse = [sample_result("legacy", 210, 30)];
+        let violations = baseline.violations(&worse, &limits);
+        assert_eq!(violations.len(), 1);
+        assert_eq!((violations[0].metric, violations[0].limit), ("code", 200));
+
+        let new = [sample_result("fresh", 60, 3)];
+        assert_eq!(baseline.violations(&new, &limits)[0].limit, 50);
+    }
+
+    #[test]
+    fn test_write_and_load_round_trip() {
+        let path = std::env::temp_dir().join("fnloc_baseline_round_trip/baseline.json");
+        let baseline =
+            Baseline::from_results(&[sample_result("a", 5, 2), sample_result("a", 3, 4)]);
+
+        baseline.write(&path).unwrap();
+        let loaded = Baseline::load(&path).unwrap();
+        assert_eq!(loaded, baseline);
+        assert_eq!(loaded.functions["a"].code, 5);
+        assert_eq!(loaded.functions["a"].complexity, 4);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_keys_do_not_depend_on_how_paths_are_typed() {
+        let limits = Thresholds {
+            code: Some(50),
+            ..Thresholds::default()
+        };
+        let in_src = |file: &str| FunctionAnalysisResult {
+            file: file.into(),
+            ..sample_result("legacy", 200, 3)
+        };
+        let baseline = Baseline::from_results(&[in_src("./src/lib.rs")]);
+        assert_eq!(
+            baseline.functions.keys().collect::<Vec<_>>(),
+            ["src/lib.rs::legacy"]
+        );
+        assert!(
+            baseline
+                .violations(&[in_src("src/lib.rs")], &limits)
+                .is_empty()
+        );
+
+        let absolute = std::env::current_dir().unwrap().join("src/lib.rs");
+        let absolute = in_src(absolute.to_str().unwrap());
+        assert!(baseline.violations(&[absolute], &limits).is_empty());
+    }
+
+    #[test]
+    fn test_load_normalizes_keys() {
+        let path = std::env::temp_dir().join("fnloc_baseline_keys/baseline.json");
+        let baseline = Baseline {
+            functions: BTreeMap::from([(
+                "./src/lib.rs::legacy".to_string(),
+                BaselineMetrics::default(),
+            )]),
+        };
+
+        baseline.write(&path).unwrap();
+        let loaded = Baseline::load(&path).unwrap();
+        assert!(loaded.functions.contains_key("src/lib.rs::legacy"));
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_missing_file_is_a_usage_error() {
+        let path = std::env::temp_dir().join("fnloc_baseline_missing.json");
+        let error = Baseline::load(&path).unwrap_err();
+        assert!(error.to_string().contains("fnloc_baseline_missing.json"));
+        assert_eq!(error.exit_code(), crate::exit_code::USAGE_ERROR);
+    }
+}
@@ -0,0 +1,107 @@
+//! Build-time metrics gate
+//!
+//! Lets a project enforce its `fnloc.toml` thresholds from `build.rs` or a test,
+//! without installing the CLI.
+
+use crate::analyze_all_files_with_options;
+use crate::analyzer::AnalysisOptions;
+use crate::config::{Config, Violation};
+use crate::errors::{AnalysisError, AnalysisResult};
+use crate::file_scanner::find_rust_files;
+use crate::normalize_path;
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// Runs the analysis configured by the project's `fnloc.toml` and panics with a
+/// readable violation list if any threshold is exceeded
+///
+/// The project root is taken from `CARGO_MANIFEST_DIR`, which Cargo sets for both
+/// build scripts and tests, falling back to the current directory.
+///
+/// Call it from `main` in `build.rs`, or from a test such as `fn code_metrics_gate()`:
+///
+/// ```no_run
+/// fnloc::build_check();
+/// ```
+pub fn build_check() {
+    let root = env::var_os("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("."));
+
+    if let Err(e) = check_project(&root) {
+        panic!("fnloc build check failed: {e}");
+    }
+}
+
+/// Analyzes a project using its `fnloc.toml` and returns an error listing every threshold violation
+pub fn check_project(root: &Path) -> AnalysisResult<()> {
+    let config = Config::load_from_dir(root)?;
+
+    let mut files = Vec::new();
+    for path in &config.paths {
+        files.extend(find_rust_files(&root.join(path).to_string_lossy())?);
+    }
+
+    // Report function names relative to the project root
+    let root_prefix = format!("{}/", normalize_path(&root.to_string_lossy()));
+    let options = AnalysisOptions {
+        complexity_rules: config.complexity_preset.rules(),
+        ..AnalysisOptions::default()
+    };
+    let violations: Vec<Violation> = analyze_all_files_with_options(&files, &options)
+        .iter()
+        .flat_map(|result| config.thresholds.violations(result))
+        .map(|mut violation| {
+            if let Some(relative) = violation.function.strip_prefix(&root_prefix) {
+                violation.function = relative.to_string();
+            }
+            violation.function = config.names.normalize(&violation.function);
+            violation
+        })
+        .collect();
+
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(AnalysisError::ThresholdsExceeded { violations })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// Creates a throwaway project with the given configuration
+    fn create_project(name: &str, config: &str) -> PathBuf {
+        let root = env::temp_dir().join(format!("fnloc_build_check_{name}"));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(
+            root.join("src/lib.rs"),
+            "fn branchy(x: i32) -> i32 {\n    if x > 0 { 1 } else if x < 0 { -1 } else { 0 }\n}\n",
+        )
+        .unwrap();
+        fs::write(root.join("fnloc.toml"), config).unwrap();
+        root
+    }
+
+    #[test]
+    fn test_check_project_passes_within_limits() {
+        let root = create_project("pass", "[thresholds]\ncomplexity = 10\n");
+        assert!(check_project(&root).is_ok());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_check_project_reports_violations() {
+        let root = create_project("fail", "[thresholds]\ncomplexity = 2\n");
+
+        let error = check_project(&root).unwrap_err();
+        let message = error.to_string();
+
+        assert!(matches!(error, AnalysisError::ThresholdsExceeded { .. }));
+        assert!(message.contains("fn src/lib.rs::branchy: complexity=3 exceeds limit 2"));
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,305 @@
+//! Call graph between the functions of the analyzed set
+//!
+//! Calls are resolved by function name only: a call to `helper(..)` or
+//! `module::helper(..)` links to every analyzed function named `helper`.
+//! Method calls are not resolved because only free functions are analyzed.
+//!
+//! `fnloc call-graph` exports the graph as Graphviz DOT or JSON, with the
+//! size and complexity of every function, for architecture visualizations.
+
+use crate::analyzer::FunctionAnalysisResult;
+use crate::normalize_path;
+use crate::source::read_source;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use syn::visit::{self, Visit};
+use syn::{Expr, Item, ItemFn};
+
+/// A parsed top-level function together with its qualified report name
+pub struct ParsedFunction {
+    /// Qualified name in the same `path::function` form used in analysis results
+    pub name: String,
+    pub item: ItemFn,
+}
+
+/// Parses all top-level functions from the given files
+///
+/// Files that cannot be read or parsed are skipped, matching `analyze_all_files`.
+pub fn parse_functions(file_paths: &[String]) -> Vec<ParsedFunction> {
+    let mut functions = Vec::new();
+
+    for path in file_paths {
+        let Ok(source) = read_source(path) else {
+            continue;
+        };
+        let Ok(parsed) = syn::parse_file(&source.text) else {
+            continue;
+        };
+        let normalized_path = normalize_path(path);
+        functions.extend(parsed.items.into_iter().filter_map(|item| match item {
+            Item::Fn(func) => Some(ParsedFunction {
+                name: format!("{}::{}", normalized_path, func.sig.ident),
+                item: func,
+            }),
+            _ => None,
+        }));
+    }
+
+    functions
+}
+
+/// Parses all top-level functions from a single source, using bare function names
+pub fn parse_functions_from_source(source: &str) -> Vec<ParsedFunction> {
+    let Ok(parsed) = syn::parse_file(source) else {
+        return Vec::new();
+    };
+
+    parsed
+        .items
+        .into_iter()
+        .filter_map(|item| match item {
+            Item::Fn(func) => Some(ParsedFunction {
+                name: func.sig.ident.to_string(),
+                item: func,
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Encoding of an exported call graph
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GraphStyle {
+    /// Graphviz DOT, e.g. for `dot -Tsvg` (default)
+    #[default]
+    Dot,
+    /// JSON object with `nodes` and `edges` arrays
+    Json,
+}
+
+/// Caller→callee edges between analyzed functions
+#[derive(Debug, Clone, Default)]
+pub struct CallGraph {
+    edges: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl CallGraph {
+    /// Builds the call graph of the given functions
+    pub fn build(functions: &[ParsedFunction]) -> Self {
+        let mut by_ident: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+        for function in functions {
+            by_ident
+                .entry(function.item.sig.ident.to_string())
+                .or_default()
+                .push(&function.name);
+        }
+
+        let mut edges = BTreeMap::new();
+        for function in functions {
+            let mut collector = CallCollector::default();
+            collector.visit_block(&function.item.block);
+
+            let callees: BTreeSet<String> = collector
+                .called_idents
+                .iter()
+                .filter_map(|ident| by_ident.get(ident))
+                .flatten()
+                .map(|name| name.to_string())
+                .collect();
+            edges.insert(function.name.clone(), callees);
+        }
+
+        Self { edges }
+    }
+
+    /// Returns the names of all functions in the graph
+    pub fn functions(&self) -> impl Iterator<Item = &str> {
+        self.edges.keys().map(String::as_str)
+    }
+
+    /// Returns the functions called by the given function
+    pub fn callees(&self, function: &str) -> impl Iterator<Item = &str> {
+        self.edges
+            .get(function)
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+    }
+
+    /// Returns the functions that call the given function
+    pub fn callers<'a>(&'a self, function: &'a str) -> impl Iterator<Item = &'a str> {
+        self.edges
+            .iter()
+            .filter(move |(_, callees)| callees.contains(function))
+            .map(|(caller, _)| caller.as_str())
+    }
+
+    /// Writes the graph in the given encoding, annotating every function with
+    /// the metrics of its analysis result
+    pub fn render(&self, style: GraphStyle, results: &[FunctionAnalysisResult]) -> String {
+        let metrics: HashMap<String, &FunctionAnalysisResult> = results
+            .iter()
+            .map(|result| (result.qualified_name(), result))
+            .collect();
+        match style {
+            GraphStyle::Dot => self.to_dot(&metrics),
+            GraphStyle::Json => self.to_json(&metrics),
+        }
+    }
+
+    fn to_dot(&self, metrics: &HashMap<String, &FunctionAnalysisResult>) -> String {
+        let mut lines = vec![
+            "digraph calls {".to_string(),
+            "  node [shape=box];".to_string(),
+        ];
+        for function in self.functions() {
+            let label = match metrics.get(function) {
+                Some(result) => format!(
+                    "{function}\\ncode={} cc={}",
+                    result.code, result.cyclomatic_complexity
+                ),
+                None => function.to_string(),
+            };
+            lines.push(format!(
+                "  {} [label={}];",
+                dot_quote(function),
+                dot_quote(&label)
+            ));
+        }
+        for (caller, callees) in &self.edges {
+            for callee in callees {
+                lines.push(format!("  {} -> {};", dot_quote(caller), dot_quote(callee)));
+            }
+        }
+        lines.push("}".to_string());
+        lines.join("\n")
+    }
+
+    fn to_json(&self, metrics: &HashMap<String, &FunctionAnalysisResult>) -> String {
+        let graph = JsonGraph {
+            nodes: self
+                .functions()
+                .map(|name| {
+                    let result = metrics.get(name);
+                    JsonNode {
+                        name,
+                        file: result.and_then(|result| result.source_file()),
+                        start_line: result.map(|result| result.start_line),
+                        code: result.map(|result| result.code),
+                        complexity: result.map(|result| result.cyclomatic_complexity),
+                    }
+                })
+                .collect(),
+            edges: self
+                .edges
+                .iter()
+                .flat_map(|(caller, callees)| {
+                    callees
+                        .iter()
+                        .map(move |callee| JsonEdge { caller, callee })
+                })
+                .collect(),
+        };
+        serde_json::to_string_pretty(&graph).unwrap_or_default()
+    }
+}
+
+/// Serialized form of a call graph; metrics are null for functions without results
+#[derive(Serialize)]
+struct JsonGraph<'a> {
+    nodes: Vec<JsonNode<'a>>,
+    edges: Vec<JsonEdge<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonNode<'a> {
+    name: &'a str,
+    file: Option<&'a str>,
+    start_line: Option<usize>,
+    code: Option<usize>,
+    complexity: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct JsonEdge<'a> {
+    caller: &'a str,
+    callee: &'a str,
+}
+
+/// Quotes a DOT identifier; names contain no backslashes, so only quotes are escaped
+fn dot_quote(text: &str) -> String {
+    let escaped = text.replace('"', "\\\"");
+    format!("\"{escaped}\"")
+}
+
+/// AST visitor collecting the final path segment of every called function
+#[derive(Default)]
+struct CallCollector {
+    called_idents: BTreeSet<String>,
+}
+
+impl<'ast> Visit<'ast> for CallCollector {
+    fn visit_expr(&mut self, expr: &'ast Expr) {
+        if let Expr::Call(call) = expr
+            && let Expr::Path(path) = call.func.as_ref()
+            && let Some(segment) = path.path.segments.last()
+        {
+            self.called_idents.insert(segment.ident.to_string());
+        }
+        visit::visit_expr(self, expr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_call_graph() {
+        let source = r#"
+            fn main() { helper(); utils::format(1); std::process::exit(0); }
+            fn helper() { format(2); }
+            fn format(x: i32) {}
+        "#;
+
+        let graph = CallGraph::build(&parse_functions_from_source(source));
+
+        assert_eq!(
+            graph.callees("main").collect::<Vec<_>>(),
+            ["format", "helper"]
+        );
+        assert_eq!(graph.callees("helper").collect::<Vec<_>>(), ["format"]);
+        assert_eq!(graph.callees("format").count(), 0);
+        assert_eq!(
+            graph.callers("format").collect::<Vec<_>>(),
+            ["helper", "main"]
+        );
+    }
+
+    #[test]
+    fn test_render_dot_and_json() {
+        let source = "fn main() { helper(); }\nfn helper() {}\n";
+        let graph = CallGraph::build(&parse_functions_from_source(source));
+        let results = crate::analyzer::analyze_source(source, &Default::default()).unwrap();
+
+        assert_eq!(
+            graph.render(GraphStyle::Dot, &results),
+            "digraph calls {\n  node [shape=box];\n  \
+             \"helper\" [label=\"helper\\ncode=1 cc=1\"];\n  \
+             \"main\" [label=\"main\\ncode=1 cc=1\"];\n  \
+             \"main\" -> \"helper\";\n}"
+        );
+
+        let json: serde_json::Value =
+            serde_json::from_str(&graph.render(GraphStyle::Json, &results)).unwrap();
+        assert_eq!(json["nodes"][1]["name"], "main");
+        assert_eq!(json["nodes"][1]["complexity"], 1);
+        assert_eq!(json["edges"][0]["caller"], "main");
+        assert_eq!(json["edges"][0]["callee"], "helper");
+    }
+
+    #[test]
+    fn test_unparsable_source_yields_no_functions() {
+        assert!(parse_functions_from_source("fn broken(").is_empty());
+    }
+}
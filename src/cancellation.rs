@@ -0,0 +1,32 @@
+//! Cooperative cancellation of a running analysis
+//!
+//! Embedders such as editors or servers keep a clone of the token given in
+//! [`AnalysisOptions`](crate::AnalysisOptions) and cancel it from another
+//! thread; the analysis stops starting new files and returns the results of
+//! the files it already finished.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Shared flag that asks a running analysis to stop
+///
+/// Clones share the flag, so cancelling any clone cancels them all.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Creates a token that is not cancelled
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Asks the analysis using this token to stop
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns true once the token has been cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
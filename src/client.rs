@@ -1,10 +1,19 @@
-use clap::Parser;
+use crate::analyzer::{ComplexityPreset, METRIC_KEYS};
+use crate::badge::{BadgeMetric, BadgeStyle};
+use crate::baseline::DEFAULT_BASELINE_FILE;
+use crate::config::FailOn;
+use crate::daemon::DEFAULT_SOCKET;
+use crate::output::Column;
+use clap::builder::PossibleValuesParser;
+use clap::{Args, Parser, Subcommand};
+use std::ffi::OsString;
+use std::path::PathBuf;
 
 /// Function analyzer for Rust code - counts lines of code, comments, and empty lines per function
 ///
 /// Can be used as a standalone command or as a Cargo subcommand:
-/// - Standalone: fnloc [OPTIONS] [DIRECTORY]
-/// - Cargo subcommand: cargo fnloc [OPTIONS] [DIRECTORY]
+/// - Standalone: fnloc [OPTIONS] [PATH]...
+/// - Cargo subcommand: cargo fnloc [OPTIONS] [PATH]...
 #[derive(Parser)]
 #[command(name = "fnloc")]
 #[command(version = "0.1.0")]
@@ -13,21 +22,626 @@ use clap::Parser;
     long_about = "Function analyzer for Rust code that counts lines of code, comments, empty lines, cyclomatic complexity, and nesting depth per function.\n\nCan be used as a standalone command or as a Cargo subcommand."
 )]
 pub struct Client {
-    /// Directory to analyze for Rust files
-    #[arg(value_name = "DIRECTORY")]
+    /// Command to run instead of the function report
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Directories and Rust files to analyze
+    #[arg(value_name = "PATH")]
     #[arg(default_value = "./src")]
-    #[arg(help = "Directory to scan for Rust files")]
-    pub directory: String,
+    #[arg(help = "Directories to scan and Rust files to analyze; results are merged")]
+    pub paths: Vec<String>,
+
+    /// Glob patterns of paths to skip
+    #[arg(long = "exclude", value_name = "GLOB")]
+    #[arg(help = "Skip files and directories matching GLOB, relative to each PATH (repeatable)")]
+    pub exclude: Vec<String>,
+
+    /// Glob patterns restricting which files are analyzed
+    #[arg(long = "include", value_name = "GLOB")]
+    #[arg(help = "Only analyze files matching GLOB, relative to each PATH (repeatable)")]
+    pub include: Vec<String>,
+
+    /// Maximum directory depth to scan
+    #[arg(long = "max-depth", value_name = "N")]
+    #[arg(
+        help = "Descend at most N directory levels; 1 analyzes only files directly in each PATH"
+    )]
+    pub max_depth: Option<usize>,
+
+    /// Follow symbolic links while scanning
+    #[arg(long = "follow-symlinks")]
+    #[arg(help = "Follow symbolic links; cycles are skipped and each file is analyzed once")]
+    pub follow_symlinks: bool,
+
+    /// Scan build output and vendored directories
+    #[arg(long = "no-default-excludes")]
+    #[arg(help = "Also analyze target/, vendor/, and third_party/ directories")]
+    pub no_default_excludes: bool,
+
+    /// Scan files marked as generated
+    #[arg(long = "include-generated")]
+    #[arg(help = "Also analyze files marked with @generated or DO NOT EDIT near the top")]
+    pub include_generated: bool,
+
+    /// Scan files that are ignored by git
+    #[arg(long = "no-ignore")]
+    #[arg(help = "Also analyze files ignored by .gitignore, .ignore, and git exclude files")]
+    pub no_ignore: bool,
+
+    /// Analyze only files changed relative to a git revision
+    #[arg(long = "changed")]
+    #[arg(
+        help = "Only analyze .rs files added or modified since the diff base (untracked files included)"
+    )]
+    pub changed: bool,
+
+    /// Git revision that --changed compares against
+    #[arg(long = "diff-base", value_name = "REF", requires = "changed")]
+    #[arg(help = "Revision to compare against with --changed [default: HEAD]")]
+    pub diff_base: Option<String>,
+
+    /// Git revision to analyze instead of the working tree
+    #[arg(
+        long = "at",
+        value_name = "REF",
+        conflicts_with_all = [
+            "changed",
+            "max_params",
+            "refactoring_hints",
+            "wmc",
+            "panic_report",
+            "duplicates",
+            "near_duplicates",
+            "owners",
+            "unreferenced"
+        ]
+    )]
+    #[arg(help = "Analyze the files as of a git revision, without checking it out")]
+    pub at: Option<String>,
 
     /// Output format
-    #[arg(short = 'f', long = "format")]
+    #[arg(short = 'f', long = "format", global = true)]
     #[arg(value_enum)]
     #[arg(default_value = "table")]
     #[arg(help = "Output format")]
     pub format: OutputFormat,
+
+    /// File to write the report to instead of stdout
+    #[arg(short = 'o', long = "output", value_name = "FILE")]
+    #[arg(help = "Write results and reports to FILE, creating parent directories as needed")]
+    pub output: Option<PathBuf>,
+
+    /// Columns of table and CSV output
+    #[arg(
+        long = "columns",
+        value_name = "COLUMNS",
+        value_enum,
+        value_delimiter = ','
+    )]
+    #[arg(help = "Comma-separated columns to show in table and CSV output, in order")]
+    pub columns: Option<Vec<Column>>,
+
+    /// Grouping of table rows
+    #[arg(long = "group-by", value_name = "KEY", value_enum)]
+    #[arg(help = "Section table output per file with subtotals, or roll functions up per module")]
+    pub group_by: Option<GroupBy>,
+
+    /// Append a totals row to table and CSV output
+    #[arg(long = "totals")]
+    #[arg(
+        help = "Add a final row with summed lines, the function count, and the maximum complexity"
+    )]
+    pub totals: bool,
+
+    /// Suppress the banner before table output
+    #[arg(short = 'q', long = "quiet")]
+    #[arg(help = "Do not print the \"Analyzing N Rust files...\" banner")]
+    pub quiet: bool,
+
+    /// Suppress the CSV header line
+    #[arg(long = "no-header")]
+    #[arg(help = "Omit the column titles of CSV output, e.g. to concatenate reports")]
+    pub no_header: bool,
+
+    /// Report per-file parse and analysis times
+    #[arg(long = "timings")]
+    #[arg(help = "Show parse and analysis times and the slowest files on stderr")]
+    pub timings: bool,
+
+    /// Hide the progress bar
+    #[arg(long = "no-progress")]
+    #[arg(help = "Do not show a progress bar on stderr while files are analyzed")]
+    pub no_progress: bool,
+
+    /// Line width of aligned table output
+    #[arg(long = "width", value_name = "N")]
+    #[arg(
+        help = "Align table rows to N characters (default: the terminal width when on a terminal)"
+    )]
+    pub width: Option<usize>,
+
+    /// Keep function names whole in aligned table output
+    #[arg(long = "no-truncate")]
+    #[arg(help = "Never shorten function names to fit aligned table rows")]
+    pub no_truncate: bool,
+
+    /// Template file for the template output format
+    #[arg(long = "template", value_name = "FILE")]
+    #[arg(
+        help = "MiniJinja template rendered by --format template with `functions` and `summary`"
+    )]
+    pub template: Option<PathBuf>,
+
+    /// When to color table output
+    #[arg(
+        long = "color",
+        value_name = "WHEN",
+        value_enum,
+        default_value = "auto"
+    )]
+    #[arg(
+        help = "Highlight table metrics against the fnloc.toml thresholds; auto colors terminals unless NO_COLOR is set"
+    )]
+    pub color: ColorChoice,
+
+    /// Links from table rows to the functions in an editor
+    #[arg(long = "editor-links", value_name = "STYLE", value_enum)]
+    #[arg(
+        help = "Show a command opening each function (code, editor) or link function names (osc8) in table output"
+    )]
+    pub editor_links: Option<EditorLinks>,
+
+    /// Field delimiter for CSV output
+    #[arg(long = "delimiter", value_name = "CHAR", value_parser = parse_delimiter)]
+    #[arg(default_value = ",")]
+    #[arg(help = "Field delimiter for CSV output; use 'tab' or '\\t' for TSV")]
+    pub delimiter: u8,
+
+    /// Indent JSON output
+    #[arg(long = "json-pretty", conflicts_with = "json_compact")]
+    #[arg(help = "Indent JSON output for reading")]
+    pub json_pretty: bool,
+
+    /// Emit JSON output on a single line
+    #[arg(long = "json-compact")]
+    #[arg(help = "Emit JSON output on a single line for pipelines")]
+    pub json_compact: bool,
+
+    /// Metric used to order the reported functions
+    #[arg(long = "sort", value_name = "KEY")]
+    #[arg(value_enum)]
+    #[arg(default_value = "code")]
+    #[arg(help = "Sort functions by this metric (largest first; name sorts alphabetically)")]
+    pub sort: SortBy,
+
+    /// Maximum number of functions to report
+    #[arg(long = "limit", value_name = "N")]
+    #[arg(help = "Only show the first N functions in sort order")]
+    pub limit: Option<usize>,
+
+    /// Minimum number of code lines for a function to be reported
+    #[arg(long = "min-lines", value_name = "N")]
+    #[arg(help = "Only show functions with at least N code lines")]
+    pub min_lines: Option<usize>,
+
+    /// Minimum cyclomatic complexity for a function to be reported
+    #[arg(long = "min-complexity", value_name = "N")]
+    #[arg(help = "Only show functions with cyclomatic complexity of at least N")]
+    pub min_complexity: Option<usize>,
+
+    /// Minimum nesting depth for a function to be reported
+    #[arg(long = "min-nesting", value_name = "N")]
+    #[arg(help = "Only show functions with nesting depth of at least N")]
+    pub min_nesting: Option<usize>,
+
+    /// Leave test code out of the results
+    #[arg(long = "exclude-tests", conflicts_with = "only_tests")]
+    #[arg(
+        help = "Skip #[test], #[bench], and #[cfg(test)] functions and the functions of #[cfg(test)] modules"
+    )]
+    pub exclude_tests: bool,
+
+    /// Report test code only
+    #[arg(long = "only-tests")]
+    #[arg(help = "Only show test functions, the opposite of --exclude-tests")]
+    pub only_tests: bool,
+
+    /// Report summary statistics over all functions
+    #[arg(long = "summary")]
+    #[arg(
+        help = "Show totals, mean, median, p90, and max of every metric after the results (a `summary` key in JSON)"
+    )]
+    pub summary: bool,
+
+    /// Report the weighted code-health score
+    #[arg(long = "health")]
+    #[arg(
+        help = "Show a 0-100 health score with a grade per file, weighted by [health] in fnloc.toml"
+    )]
+    pub health: bool,
+
+    /// Limits that fail the run when exceeded
+    #[arg(long = "fail-on", value_name = "LIMITS")]
+    #[arg(
+        help = "Exit non-zero and list only the violations if any function exceeds a limit, e.g. code=100,complexity=15,nesting=5 or grade=D"
+    )]
+    pub fail_on: Option<FailOn>,
+
+    /// Baseline file for ratchet mode
+    #[arg(long = "baseline", value_name = "FILE")]
+    #[arg(
+        help = "Fail only on functions worse than in FILE (see `fnloc baseline write`) or new functions over the limits of --fail-on or fnloc.toml"
+    )]
+    pub baseline: Option<PathBuf>,
+
+    /// Report functions meeting the rules of fnloc.toml
+    #[arg(long = "rules")]
+    #[arg(
+        help = "List functions meeting a [[rules]] condition of fnloc.toml, e.g. code > 80 and fan_out > 20"
+    )]
+    pub rules: bool,
+
+    /// Metric whose distribution is shown as a histogram
+    #[arg(long = "histogram", value_name = "METRIC")]
+    #[arg(value_parser = PossibleValuesParser::new(METRIC_KEYS))]
+    #[arg(
+        help = "Show an ASCII histogram of METRIC after the results (a `histogram` key in JSON)"
+    )]
+    pub histogram: Option<String>,
+
+    /// Report wall time, peak memory, and throughput of the run
+    #[arg(long = "stats")]
+    #[arg(help = "Show wall time, peak RSS, files/sec, and functions/sec after the results")]
+    pub stats: bool,
+
+    /// Include AST-based statement and expression counts
+    #[arg(long = "ast-counts")]
+    #[arg(help = "Show statement and expression counts in every output format")]
+    pub ast_counts: bool,
+
+    /// Cyclomatic complexity counting preset
+    #[arg(long = "complexity-preset", value_name = "PRESET")]
+    #[arg(value_enum)]
+    #[arg(
+        help = "Which decision points add cyclomatic complexity [default: from fnloc.toml, else strict]"
+    )]
+    pub complexity_preset: Option<ComplexityPreset>,
+
+    /// Markers counted as technical debt in function comments
+    #[arg(long = "todo-markers", value_name = "MARKERS", value_delimiter = ',')]
+    #[arg(
+        help = "Comma-separated comment markers to count per function [default: TODO,FIXME,HACK]"
+    )]
+    pub todo_markers: Option<Vec<String>>,
+
+    /// Number of files analyzed in parallel
+    #[arg(short = 'j', long = "jobs", value_name = "N")]
+    #[arg(help = "Analyze N files in parallel [default: one per CPU]")]
+    pub jobs: Option<usize>,
+
+    /// Maximum number of files read at the same time
+    #[arg(long = "max-open-files", value_name = "N")]
+    #[arg(
+        help = "Read at most N files at once, e.g. on network filesystems; parsing still uses every job"
+    )]
+    pub max_open_files: Option<usize>,
+
+    /// Seconds after which the analysis of a single file is abandoned
+    #[arg(long = "file-timeout", value_name = "SECS")]
+    #[arg(help = "Skip files whose analysis takes longer than SECS seconds, with a warning")]
+    pub file_timeout: Option<u64>,
+
+    /// Analyze the parseable items of files with syntax errors
+    #[arg(long = "recover")]
+    #[arg(
+        help = "Analyze the items that parse in files with syntax errors, warning about the rest"
+    )]
+    pub recover: bool,
+
+    /// Estimate the functions of files that do not parse from their braces
+    #[arg(long = "heuristic-fallback")]
+    #[arg(
+        help = "Count the lines of functions in code that does not parse, without complexity metrics"
+    )]
+    pub heuristic_fallback: bool,
+
+    /// Size above which a file is skipped with a warning instead of parsed
+    #[arg(
+        long = "max-file-size",
+        value_name = "SIZE",
+        default_value = "10M",
+        value_parser = parse_size
+    )]
+    #[arg(
+        help = "Skip files larger than SIZE bytes (K, M, and G suffixes allowed; 0 for no limit)"
+    )]
+    pub max_file_size: u64,
+
+    /// Fail when any file could not be fully analyzed
+    #[arg(long = "strict")]
+    #[arg(
+        help = "Exit with an error if any file could not be read or parsed, or was only partly analyzed"
+    )]
+    pub strict: bool,
+
+    /// Socket of a running `fnloc daemon` that analyzes the files instead
+    #[arg(
+        long = "daemon",
+        value_name = "SOCKET",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = DEFAULT_SOCKET
+    )]
+    #[arg(
+        help = "Get the results from the fnloc daemon on SOCKET (default target/fnloc.sock), analyzing locally if it cannot answer"
+    )]
+    pub daemon: Option<PathBuf>,
+
+    /// Minimum comment density percentage before a warning is printed
+    #[arg(long = "min-comment-density", value_name = "PERCENT")]
+    #[arg(help = "Warn about functions whose comment density is below PERCENT")]
+    pub min_comment_density: Option<f64>,
+
+    /// Strip generic arguments from reported function names
+    #[arg(long = "strip-generics")]
+    #[arg(help = "Remove generic arguments such as <T> from reported names")]
+    pub strip_generics: bool,
+
+    /// Leading path segments to strip from reported function names
+    #[arg(long = "strip-crate-prefix", value_name = "NAME")]
+    #[arg(help = "Remove a leading NAME:: from reported names (repeatable)")]
+    pub strip_crate_prefixes: Vec<String>,
+
+    /// Strip symbol hash suffixes from reported function names
+    #[arg(long = "strip-hash-suffix")]
+    #[arg(help = "Remove symbol hash suffixes such as ::h0123456789abcdef from reported names")]
+    pub strip_hash_suffix: bool,
+
+    /// Parameter count above which parameter struct suggestions are reported
+    #[arg(long = "max-params", value_name = "N")]
+    #[arg(help = "Suggest parameter structs for functions with more than N parameters")]
+    pub max_params: Option<usize>,
+
+    /// Suggest where to split functions exceeding the configured limits
+    #[arg(long = "refactoring-hints")]
+    #[arg(
+        help = "Point out the match, if/else chain, or loop to extract in functions over the limits of fnloc.toml"
+    )]
+    pub refactoring_hints: bool,
+
+    /// Report Weighted Methods per Class for implemented types
+    #[arg(long = "wmc")]
+    #[arg(help = "Show the summed method complexity per type (WMC), heaviest first")]
+    pub wmc: bool,
+
+    /// Report which functions can panic and how callers treat returned Results
+    #[arg(long = "panic-report")]
+    #[arg(help = "Show a panic reachability report after the results")]
+    pub panic_report: bool,
+
+    /// Report groups of functions with identical bodies
+    #[arg(long = "duplicates")]
+    #[arg(help = "List copy-pasted functions whose bodies have the same tokens, across files")]
+    pub duplicates: bool,
+
+    /// Similarity in percent above which function pairs are reported as near-duplicates
+    #[arg(
+        long = "near-duplicates",
+        value_name = "PERCENT",
+        value_parser = clap::value_parser!(u8).range(1..=100)
+    )]
+    #[arg(
+        help = "List function pairs sharing at least PERCENT of their code once identifiers and literals are ignored"
+    )]
+    pub near_duplicates: Option<u8>,
+
+    /// Report who owns the most complex code according to git blame
+    #[arg(long = "owners")]
+    #[arg(
+        help = "Attribute function lines to authors with git blame and list who owns the most complexity"
+    )]
+    pub owners: bool,
+
+    /// Report private functions that nothing in the analyzed set refers to
+    #[arg(long = "unreferenced")]
+    #[arg(
+        help = "List private functions that appear to be unused (heuristic: check before deleting)"
+    )]
+    pub unreferenced: bool,
+}
+
+impl Client {
+    /// Parses the process arguments, whether run directly or as `cargo fnloc`
+    pub fn parse_args() -> Self {
+        Self::parse_from(strip_cargo_subcommand(std::env::args_os()))
+    }
+}
+
+/// Removes the `fnloc` argument cargo passes first when running `cargo fnloc`
+///
+/// Cargo runs `cargo fnloc ARGS` as `cargo-fnloc fnloc ARGS`. A directory that
+/// is really named `fnloc` can still be given as `./fnloc`.
+pub fn strip_cargo_subcommand(args: impl IntoIterator<Item = OsString>) -> Vec<OsString> {
+    let mut args: Vec<OsString> = args.into_iter().collect();
+    if args.get(1).is_some_and(|arg| arg == "fnloc") {
+        args.remove(1);
+    }
+    args
+}
+
+/// Commands besides the default function report
+#[derive(Subcommand, Clone, Debug)]
+pub enum Command {
+    /// Generate a code-health badge from the analysis
+    Badge(BadgeArgs),
+    /// Manage the metric baseline used by --baseline
+    Baseline(BaselineArgs),
+    /// Compare two JSON reports, or a report and the current tree, per function
+    Diff(DiffArgs),
+    /// Report metric trends over the git history, as CSV or JSON
+    History(HistoryArgs),
+    /// Check the staged Rust files against the thresholds, as a pre-commit hook
+    ///
+    /// The staged content is analyzed, so unstaged edits do not affect the
+    /// outcome. Limits come from --fail-on or `[thresholds]` in fnloc.toml, and
+    /// --baseline applies as for the function report.
+    Hook,
+    /// Run a language server on stdin and stdout that shows metrics in editors
+    ///
+    /// Open files get an inlay hint such as `code=84 cc=17` on the first line
+    /// of each function, and functions over the `[thresholds]` in fnloc.toml
+    /// (or the recommended limits) are published as warnings.
+    Lsp,
+    /// Serve JSON reports over HTTP from a long-lived process
+    ///
+    /// `GET /report` analyzes the given paths, reusing the results of files
+    /// unchanged since the last request; `POST /analyze` analyzes the Rust
+    /// source sent as the request body.
+    Serve(ServeArgs),
+    /// Keep analysis results in memory and answer commands run with --daemon
+    ///
+    /// Only files changed since the previous query are parsed again. The
+    /// daemon serves the current directory with the analysis options given
+    /// before `daemon`; other queries are analyzed by the command itself.
+    Daemon(DaemonArgs),
+    /// Export the calls between analyzed functions as Graphviz DOT or JSON
+    ///
+    /// Calls are resolved by name within the analyzed files; every function
+    /// is annotated with its code lines and complexity. Graphviz DOT is
+    /// written unless `--format json` is given.
+    CallGraph(CallGraphArgs),
+}
+
+/// Options of `fnloc history`; `--format` may also be given after `history`
+#[derive(Args, Clone, Debug)]
+pub struct HistoryArgs {
+    /// Directories and Rust files to analyze
+    #[arg(value_name = "PATH")]
+    #[arg(default_value = "./src")]
+    #[arg(help = "Directories and Rust files to analyze in each commit")]
+    pub paths: Vec<String>,
+
+    /// Oldest commit date to analyze
+    #[arg(long = "since", value_name = "DATE")]
+    #[arg(help = "Only analyze commits after DATE, e.g. 2024-01-01 or \"6 months ago\"")]
+    pub since: Option<String>,
+
+    /// Number of commits between analyzed snapshots
+    #[arg(long = "step", value_name = "N", default_value_t = 1)]
+    #[arg(value_parser = clap::value_parser!(u32).range(1..))]
+    #[arg(help = "Analyze every Nth commit, counting back from HEAD")]
+    pub step: u32,
+}
+
+/// Options of `fnloc diff`; scanning options before `diff` still apply
+#[derive(Args, Clone, Debug)]
+pub struct DiffArgs {
+    /// Report to compare against
+    #[arg(value_name = "OLD", required_unless_present = "at")]
+    #[arg(help = "JSON report written with --format json")]
+    pub old: Option<PathBuf>,
+
+    /// Git revision analyzed in place of an OLD report
+    #[arg(long = "at", value_name = "REF", conflicts_with = "old")]
+    #[arg(help = "Compare against --path as of a git revision instead of an OLD report")]
+    pub at: Option<String>,
+
+    /// Report with the changes; the current tree is analyzed if omitted
+    #[arg(value_name = "NEW")]
+    #[arg(help = "JSON report with the changes (default: analyze --path)")]
+    pub new: Option<PathBuf>,
+
+    /// Directories and Rust files analyzed when no NEW report is given
+    #[arg(long = "path", value_name = "PATH", default_value = "./src")]
+    #[arg(help = "Directories to scan and Rust files to analyze when NEW is omitted")]
+    pub paths: Vec<String>,
 }
 
-#[derive(clap::ValueEnum, Clone, Debug)]
+/// Options of `fnloc baseline`
+#[derive(Args, Clone, Debug)]
+pub struct BaselineArgs {
+    #[command(subcommand)]
+    pub action: BaselineAction,
+}
+
+/// Actions of `fnloc baseline`
+#[derive(Subcommand, Clone, Debug)]
+pub enum BaselineAction {
+    /// Snapshot the current metrics of every function
+    Write(BaselineWriteArgs),
+}
+
+/// Options of `fnloc baseline write`; scanning options before `baseline` still apply
+#[derive(Args, Clone, Debug)]
+pub struct BaselineWriteArgs {
+    /// Directories and Rust files to analyze
+    #[arg(value_name = "PATH")]
+    #[arg(default_value = "./src")]
+    #[arg(help = "Directories to scan and Rust files to analyze; results are merged")]
+    pub paths: Vec<String>,
+
+    /// File to write the baseline to
+    #[arg(long = "file", value_name = "FILE", default_value = DEFAULT_BASELINE_FILE)]
+    #[arg(help = "Baseline file to write")]
+    pub file: PathBuf,
+}
+
+/// Options of `fnloc serve`; scanning and analysis options before `serve` still apply
+#[derive(Args, Clone, Debug)]
+pub struct ServeArgs {
+    /// Directories and Rust files reported by `GET /report`
+    #[arg(value_name = "PATH")]
+    #[arg(default_value = "./src")]
+    #[arg(help = "Directories to scan and Rust files to analyze for GET /report")]
+    pub paths: Vec<String>,
+
+    /// Port to listen on, on the loopback interface
+    #[arg(long = "port", default_value_t = crate::http_server::DEFAULT_PORT)]
+    #[arg(help = "Port to listen on at 127.0.0.1")]
+    pub port: u16,
+}
+
+/// Options of `fnloc daemon`; analysis options before `daemon` still apply
+#[derive(Args, Clone, Debug)]
+pub struct DaemonArgs {
+    /// Unix socket to listen on
+    #[arg(long = "socket", value_name = "SOCKET", default_value = DEFAULT_SOCKET)]
+    #[arg(help = "Unix socket to listen on")]
+    pub socket: PathBuf,
+}
+
+/// Options of `fnloc call-graph`; scanning options before `call-graph` still apply
+#[derive(Args, Clone, Debug)]
+pub struct CallGraphArgs {
+    /// Directories and Rust files to analyze
+    #[arg(value_name = "PATH")]
+    #[arg(default_value = "./src")]
+    #[arg(help = "Directories to scan and Rust files to analyze; results are merged")]
+    pub paths: Vec<String>,
+}
+
+/// Options of `fnloc badge`; scanning options before `badge` still apply
+#[derive(Args, Clone, Debug)]
+pub struct BadgeArgs {
+    /// Directories and Rust files to analyze
+    #[arg(value_name = "PATH")]
+    #[arg(default_value = "./src")]
+    #[arg(help = "Directories to scan and Rust files to analyze; results are merged")]
+    pub paths: Vec<String>,
+
+    /// Metric shown on the badge
+    #[arg(long = "metric", value_enum, default_value = "average-complexity")]
+    #[arg(help = "Metric shown on the badge")]
+    pub metric: BadgeMetric,
+
+    /// Badge encoding
+    #[arg(long = "style", value_enum, default_value = "svg")]
+    #[arg(help = "Write an SVG image or shields.io endpoint JSON")]
+    pub style: BadgeStyle,
+}
+
+#[derive(clap::ValueEnum, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum OutputFormat {
     /// Table format (default)
     Table,
@@ -35,4 +649,117 @@ pub enum OutputFormat {
     Json,
     /// CSV format
     Csv,
+    /// JSON Lines, streamed as each file is analyzed
+    Jsonl,
+    /// GitHub-flavored markdown table
+    Markdown,
+    /// Code Climate issues for threshold breaches (GitLab Code Quality)
+    #[value(name = "codeclimate")]
+    CodeClimate,
+    /// Compiler-style `path:line:col: warning[...]` lines for threshold breaches
+    Diagnostics,
+    /// YAML format
+    Yaml,
+    /// TOML format
+    Toml,
+    /// User-defined MiniJinja template, see --template
+    Template,
+}
+
+/// How table rows link to their functions in an editor
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EditorLinks {
+    /// A `code --goto FILE:LINE` command below each row
+    Code,
+    /// An `$EDITOR +LINE FILE` command below each row
+    Editor,
+    /// OSC 8 hyperlinks on function names, for terminals that support them
+    Osc8,
+}
+
+/// When to use ANSI colors
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Color when writing to a terminal and NO_COLOR is not set (default)
+    #[default]
+    Auto,
+    /// Always color
+    Always,
+    /// Never color
+    Never,
+}
+
+/// Key by which table rows are grouped into sections
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GroupBy {
+    /// Source file of the function
+    File,
+    /// Rust module path derived from the source file; one aggregate row per module
+    Module,
+}
+
+/// Ordering of the reported functions
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum SortBy {
+    /// Total lines, largest first
+    Total,
+    /// Code lines, largest first (default)
+    #[default]
+    Code,
+    /// Comment lines, largest first
+    Comment,
+    /// Cyclomatic complexity, highest first
+    Complexity,
+    /// Nesting depth, deepest first
+    Nesting,
+    /// Function name, alphabetically
+    Name,
+}
+
+impl OutputFormat {
+    /// Returns the name used on the command line and in the format registry
+    pub fn name(&self) -> &'static str {
+        match self {
+            OutputFormat::Table => "table",
+            OutputFormat::Json => "json",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Jsonl => "jsonl",
+            OutputFormat::Markdown => "markdown",
+            OutputFormat::CodeClimate => "codeclimate",
+            OutputFormat::Diagnostics => "diagnostics",
+            OutputFormat::Yaml => "yaml",
+            OutputFormat::Toml => "toml",
+            OutputFormat::Template => "template",
+        }
+    }
+}
+
+/// Parses a size in bytes, with an optional binary `K`, `M`, or `G` suffix
+fn parse_size(value: &str) -> Result<u64, String> {
+    let upper = value.trim().to_ascii_uppercase();
+    let digits = upper.trim_end_matches("IB").trim_end_matches('B');
+    let (digits, multiplier) = match digits.chars().last() {
+        Some('K') => (&digits[..digits.len() - 1], 1 << 10),
+        Some('M') => (&digits[..digits.len() - 1], 1 << 20),
+        Some('G') => (&digits[..digits.len() - 1], 1 << 30),
+        _ => (digits, 1),
+    };
+    digits
+        .parse::<u64>()
+        .ok()
+        .and_then(|number| number.checked_mul(multiplier))
+        .ok_or_else(|| format!("expected a size such as 500K or 10M, got '{value}'"))
+}
+
+/// Parses a CSV delimiter: a single ASCII character, or `tab` / `\t`
+fn parse_delimiter(value: &str) -> Result<u8, String> {
+    match value {
+        "tab" | "\\t" | "\t" => Ok(b'\t'),
+        _ => match value.as_bytes() {
+            [byte] if byte.is_ascii() => Ok(*byte),
+            _ => Err(format!(
+                "delimiter must be a single ASCII character, got '{value}'"
+            )),
+        },
+    }
 }
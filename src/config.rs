@@ -0,0 +1,394 @@
+//! Project configuration loaded from `fnloc.toml`
+//!
+//! Example configuration:
+//!
+//! ```toml
+//! paths = ["src"]
+//! complexity_preset = "strict"
+//!
+//! [names]
+//! strip_generics = true
+//!
+//! [thresholds]
+//! code = 100
+//! complexity = 15
+//! nesting = 5
+//!
+//! [grades]
+//! complexity = [5, 10, 20, 30]
+//!
+//! [[rules]]
+//! name = "god function"
+//! when = "code > 80 and complexity > 15 and fan_out > 20"
+//!
+//! [teams]
+//! platform = ["Alice Example", "bob@example.com"]
+//!
+//! [health]
+//! complexity = 0.5
+//! long_functions = 0.3
+//! comment_density = 0.2
+//! long_function_lines = 60
+//! ```
+
+use crate::analyzer::{ComplexityPreset, FunctionAnalysisResult};
+use crate::errors::{AnalysisError, AnalysisResult};
+use crate::grades::{Grade, GradeBoundaries};
+use crate::health::HealthWeights;
+use crate::name_normalization::NameNormalization;
+use crate::ownership::Teams;
+use crate::rules::Rule;
+use crate::source::read_text;
+use serde::Deserialize;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// File name of the project configuration file
+pub const CONFIG_FILE_NAME: &str = "fnloc.toml";
+
+/// Project configuration
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Paths to analyze, relative to the directory containing the configuration file
+    pub paths: Vec<String>,
+    /// Cyclomatic complexity counting preset
+    pub complexity_preset: ComplexityPreset,
+    /// Normalization applied to reported function names
+    pub names: NameNormalization,
+    /// Per-function metric limits
+    pub thresholds: Thresholds,
+    /// Boundaries of the per-function letter grades
+    pub grades: GradeBoundaries,
+    /// Weights of the `--health` score
+    pub health: HealthWeights,
+    /// Named conditions reported with `--rules`
+    pub rules: Vec<Rule>,
+    /// Authors grouped into teams by `--owners`, by name or email
+    pub teams: Teams,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            paths: vec!["src".to_string()],
+            complexity_preset: ComplexityPreset::default(),
+            names: NameNormalization::default(),
+            thresholds: Thresholds::default(),
+            grades: GradeBoundaries::default(),
+            health: HealthWeights::default(),
+            rules: Vec::new(),
+            teams: Teams::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Parses a configuration from TOML source
+    pub fn from_toml_str(source: &str) -> AnalysisResult<Self> {
+        toml::from_str(source).map_err(|e| AnalysisError::InvalidConfig {
+            message: e.to_string(),
+        })
+    }
+
+    /// Loads a configuration file
+    pub fn load(path: &Path) -> AnalysisResult<Self> {
+        let source = read_text(path)?;
+        Self::from_toml_str(&source)
+    }
+
+    /// Loads `fnloc.toml` from a directory, falling back to defaults if it does not exist
+    pub fn load_from_dir(dir: &Path) -> AnalysisResult<Self> {
+        let path = dir.join(CONFIG_FILE_NAME);
+        if path.is_file() {
+            Self::load(&path)
+        } else {
+            Ok(Self::default())
+        }
+    }
+}
+
+/// Per-function metric limits; a function violates a limit when its metric exceeds it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Thresholds {
+    pub total: Option<usize>,
+    pub code: Option<usize>,
+    pub complexity: Option<usize>,
+    pub nesting: Option<usize>,
+}
+
+impl Thresholds {
+    /// Limits used where some are required but none are configured
+    pub const RECOMMENDED: Thresholds = Thresholds {
+        total: None,
+        code: Some(100),
+        complexity: Some(15),
+        nesting: Some(5),
+    };
+
+    /// Returns true if no limit is configured
+    pub fn is_empty(&self) -> bool {
+        self.total.is_none()
+            && self.code.is_none()
+            && self.complexity.is_none()
+            && self.nesting.is_none()
+    }
+
+    /// Returns every limit exceeded by the given function
+    pub fn violations(&self, result: &FunctionAnalysisResult) -> Vec<Violation> {
+        let metrics = [
+            ("total", result.total, self.total),
+            ("code", result.code, self.code),
+            ("complexity", result.cyclomatic_complexity, self.complexity),
+            ("nesting", result.nesting_depth, self.nesting),
+        ];
+
+        metrics
+            .into_iter()
+            .filter_map(|(metric, value, limit)| {
+                let limit = limit?;
+                (value > limit).then(|| Violation {
+                    function: result.qualified_name(),
+                    metric,
+                    value,
+                    limit,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Parses limits written as `metric=limit` pairs, e.g. `code=100,complexity=15`
+impl FromStr for Thresholds {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let mut thresholds = Thresholds::default();
+        for pair in spec
+            .split(',')
+            .map(str::trim)
+            .filter(|pair| !pair.is_empty())
+        {
+            let (metric, limit) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("expected METRIC=LIMIT, got '{pair}'"))?;
+            let limit = limit
+                .trim()
+                .parse()
+                .map_err(|_| format!("limit of {metric} must be a number, got '{limit}'"))?;
+            let slot = match metric.trim() {
+                "total" => &mut thresholds.total,
+                "code" => &mut thresholds.code,
+                "complexity" => &mut thresholds.complexity,
+                "nesting" => &mut thresholds.nesting,
+                other => {
+                    return Err(format!(
+                        "unknown metric '{other}', expected total, code, complexity, or nesting"
+                    ));
+                }
+            };
+            *slot = Some(limit);
+        }
+        if thresholds.is_empty() {
+            return Err("at least one METRIC=LIMIT is required".to_string());
+        }
+        Ok(thresholds)
+    }
+}
+
+/// Limits given with `--fail-on`: metric limits plus an optional failing grade
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FailOn {
+    pub limits: Thresholds,
+    /// Functions graded this or worse fail
+    pub grade: Option<Grade>,
+}
+
+/// Parses `metric=limit` pairs as for [`Thresholds`], plus `grade=<letter>`
+impl FromStr for FailOn {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let mut grade = None;
+        let mut limits = Vec::new();
+        for pair in spec
+            .split(',')
+            .map(str::trim)
+            .filter(|pair| !pair.is_empty())
+        {
+            match pair.split_once('=') {
+                Some((metric, letter)) if metric.trim() == "grade" => grade = Some(letter.parse()?),
+                _ => limits.push(pair),
+            }
+        }
+        let limits = if limits.is_empty() && grade.is_some() {
+            Thresholds::default()
+        } else {
+            limits.join(",").parse()?
+        };
+        Ok(Self { limits, grade })
+    }
+}
+
+/// A single function metric exceeding its configured limit
+///
+/// For the `grade` metric, value and limit are grade ranks (A = 0) and the
+/// limit is the first failing grade.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub function: String,
+    pub metric: &'static str,
+    pub value: usize,
+    pub limit: usize,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.metric == "grade" {
+            return write!(
+                f,
+                "fn {}: grade={} fails limit grade={}",
+                self.function,
+                Grade::from_rank(self.value),
+                Grade::from_rank(self.limit)
+            );
+        }
+        write!(
+            f,
+            "fn {}: {}={} exceeds limit {}",
+            self.function, self.metric, self.value, self.limit
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_result() -> FunctionAnalysisResult {
+        FunctionAnalysisResult {
+            total: 120,
+            comment: 10,
+            empty: 10,
+            nesting_depth: 3,
+            stmt_count: 50,
+            expr_count: 200,
+            end_line: 120,
+            ..FunctionAnalysisResult::sample("big", 100, 20)
+        }
+    }
+
+    #[test]
+    fn test_default_config() {
+        let config = Config::from_toml_str("").unwrap();
+        assert_eq!(config.paths, vec!["src"]);
+        assert!(config.thresholds.is_empty());
+        assert_eq!(config.health, HealthWeights::default());
+    }
+
+    #[test]
+    fn test_parse_health_weights() {
+        let config = Config::from_toml_str("[health]\ncomment_density = 0.0\n").unwrap();
+        assert_eq!(config.health.comment_density, 0.0);
+        assert_eq!(
+            config.health.complexity,
+            HealthWeights::default().complexity
+        );
+    }
+
+    #[test]
+    fn test_parse_thresholds() {
+        let config = Config::from_toml_str(
+            "paths = [\"src\", \"tests\"]\n[thresholds]\ncode = 80\ncomplexity = 15\n",
+        )
+        .unwrap();
+
+        assert_eq!(config.paths, vec!["src", "tests"]);
+        assert_eq!(config.thresholds.code, Some(80));
+        assert_eq!(config.thresholds.complexity, Some(15));
+        assert_eq!(config.thresholds.nesting, None);
+    }
+
+    #[test]
+    fn test_parse_complexity_preset() {
+        let config = Config::from_toml_str("complexity_preset = \"mccabe\"\n").unwrap();
+        assert_eq!(config.complexity_preset, ComplexityPreset::Mccabe);
+    }
+
+    #[test]
+    fn test_parse_name_normalization() {
+        let config = Config::from_toml_str(
+            "[names]\nstrip_generics = true\nstrip_crate_prefixes = [\"crate\"]\n",
+        )
+        .unwrap();
+
+        assert!(config.names.strip_generics);
+        assert_eq!(config.names.strip_crate_prefixes, vec!["crate"]);
+        assert!(!config.names.strip_hash_suffix);
+    }
+
+    #[test]
+    fn test_unknown_keys_are_rejected() {
+        let result = Config::from_toml_str("[thresholds]\ncomplexitty = 15\n");
+        assert!(matches!(result, Err(AnalysisError::InvalidConfig { .. })));
+    }
+
+    #[test]
+    fn test_parse_limits_from_command_line() {
+        let thresholds: Thresholds = "code=100, complexity=15".parse().unwrap();
+        assert_eq!(thresholds.code, Some(100));
+        assert_eq!(thresholds.complexity, Some(15));
+        assert_eq!(thresholds.nesting, None);
+
+        assert!("lines=10".parse::<Thresholds>().is_err());
+        assert!("code=many".parse::<Thresholds>().is_err());
+        assert!("".parse::<Thresholds>().is_err());
+    }
+
+    #[test]
+    fn test_parse_rules() {
+        let config = Config::from_toml_str(
+            "[[rules]]\nname = \"god function\"\nwhen = \"code > 80 and fan_out > 20\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.rules[0].name, "god function");
+        assert!(config.rules[0].when.uses_call_graph());
+
+        let invalid = "[[rules]]\nname = \"x\"\nwhen = \"lines > 3\"\n";
+        assert!(Config::from_toml_str(invalid).is_err());
+    }
+
+    #[test]
+    fn test_parse_fail_on_with_grade() {
+        let fail_on: FailOn = "grade=D".parse().unwrap();
+        assert_eq!(fail_on.grade, Some(Grade::D));
+        assert!(fail_on.limits.is_empty());
+
+        let fail_on: FailOn = "code=100,grade=c".parse().unwrap();
+        assert_eq!(fail_on.limits.code, Some(100));
+        assert_eq!(fail_on.grade, Some(Grade::C));
+        assert!("grade=E".parse::<FailOn>().is_err());
+    }
+
+    #[test]
+    fn test_violations() {
+        let thresholds = Thresholds {
+            code: Some(80),
+            complexity: Some(20),
+            nesting: Some(2),
+            ..Thresholds::default()
+        };
+
+        let violations = thresholds.violations(&sample_result());
+
+        assert_eq!(violations.len(), 2);
+        assert_eq!(violations[0].metric, "code");
+        assert_eq!(violations[1].metric, "nesting");
+        assert_eq!(
+            violations[0].to_string(),
+            "fn big: code=100 exceeds limit 80"
+        );
+    }
+}
@@ -0,0 +1,262 @@
+//! Background daemon for `fnloc daemon`
+//!
+//! The daemon listens on a unix socket and keeps the results of every file it
+//! analyzed in a [`WarmCache`]. Commands run with `--daemon` send it the list
+//! of files to analyze instead of parsing them, so on a big repository only
+//! the files changed since the previous run are parsed again.
+//!
+//! A query is one JSON line with the files, the working directory, and a key of
+//! the analysis options; the answer is one JSON line with the results of each
+//! file. Queries from another directory or with other analysis options are
+//! refused, so the command analyzes the files itself rather than getting
+//! results it did not ask for. A command also analyzes the files itself if
+//! the daemon does not answer within [`QUERY_TIMEOUT`].
+
+use crate::analyzer::AnalysisOptions;
+use crate::warm_cache::{FileResults, WarmCache};
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Socket used when none is given, relative to the directory of the daemon
+pub const DEFAULT_SOCKET: &str = "target/fnloc.sock";
+
+/// Time after which a command stops waiting for the daemon's answer
+///
+/// A cold daemon analyzes the whole query before it answers, so this is far
+/// longer than an answer from the cache takes.
+pub const QUERY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Time the daemon waits for a client to send its query or read the answer
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Files a command asks the daemon to analyze
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Query {
+    /// Working directory of the command, which relative paths are based on
+    pub dir: PathBuf,
+    /// Analysis options of the command, see [`options_key`]
+    pub options: String,
+    pub files: Vec<String>,
+}
+
+impl Query {
+    /// Creates a query for files of the current directory
+    pub fn new(files: &[String], options: &AnalysisOptions) -> io::Result<Self> {
+        Ok(Self {
+            dir: std::env::current_dir()?,
+            options: options_key(options),
+            files: files.to_vec(),
+        })
+    }
+}
+
+/// The daemon's answer to a query
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Answer {
+    /// Results of every file, in query order
+    Files(Vec<FileResults>),
+    /// Why the daemon did not analyze the files
+    Refused(String),
+}
+
+/// Returns a key identifying the options that change analysis results
+///
+/// Scheduling options such as the number of jobs or the timeouts are left out,
+/// as they do not change the results of the files that are analyzed.
+pub fn options_key(options: &AnalysisOptions) -> String {
+    format!(
+        "markers={:?} rules={:?} recover={} heuristic={} max_size={:?}",
+        options.todo_markers,
+        options.complexity_rules,
+        options.recover,
+        options.heuristic_fallback,
+        options.max_file_size
+    )
+}
+
+/// Answers queries from the cache of one directory
+#[derive(Debug)]
+pub struct Daemon {
+    dir: PathBuf,
+    options: AnalysisOptions,
+    cache: WarmCache,
+}
+
+impl Daemon {
+    /// Creates a daemon analyzing files of `dir` with `options`
+    pub fn new(dir: PathBuf, options: AnalysisOptions) -> Self {
+        Self {
+            dir,
+            options,
+            cache: WarmCache::default(),
+        }
+    }
+
+    /// Analyzes the files of a query, reusing the results of unchanged files
+    pub fn answer(&mut self, query: &Query) -> Answer {
+        if query.dir != self.dir {
+            return Answer::Refused(format!(
+                "the daemon serves {}, not {}",
+                self.dir.display(),
+                query.dir.display()
+            ));
+        }
+        if query.options != options_key(&self.options) {
+            return Answer::Refused(
+                "the daemon was started with other analysis options".to_string(),
+            );
+        }
+        Answer::Files(self.cache.analyze_files(&query.files, &self.options))
+    }
+}
+
+/// Answers queries on `socket` until the listener fails
+///
+/// A socket file left behind by a daemon that is no longer running is
+/// replaced; if a daemon still answers on it, an error is returned.
+#[cfg(unix)]
+pub fn run(socket: &Path, mut daemon: Daemon) -> io::Result<()> {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::{UnixListener, UnixStream};
+
+    if socket.exists() {
+        if UnixStream::connect(socket).is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                format!("a daemon is already listening on {}", socket.display()),
+            ));
+        }
+        std::fs::remove_file(socket)?;
+    }
+    if let Some(parent) = socket.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let listener = UnixListener::bind(socket)?;
+    for stream in listener.incoming() {
+        let mut stream = stream?;
+        let mut line = String::new();
+        // A broken or stalled connection only affects its own client
+        let timeouts = stream
+            .set_read_timeout(Some(CLIENT_TIMEOUT))
+            .and_then(|()| stream.set_write_timeout(Some(CLIENT_TIMEOUT)));
+        if timeouts.is_err() || BufReader::new(&stream).read_line(&mut line).is_err() {
+            continue;
+        }
+        let answer = match serde_json::from_str::<Query>(&line) {
+            Ok(query) => daemon.answer(&query),
+            Err(e) => Answer::Refused(format!("invalid query: {e}")),
+        };
+        let Ok(encoded) = serde_json::to_string(&answer) else {
+            continue;
+        };
+        let _ = writeln!(stream, "{encoded}");
+    }
+    Ok(())
+}
+
+/// Sends a query to the daemon listening on `socket` and waits for its answer
+/// for at most `timeout`
+#[cfg(unix)]
+pub fn query(socket: &Path, query: &Query, timeout: Duration) -> io::Result<Answer> {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+
+    let mut stream = UnixStream::connect(socket)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    let timed_out = |e: io::Error| match e.kind() {
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => io::Error::new(
+            io::ErrorKind::TimedOut,
+            format!("no answer within {}s", timeout.as_secs_f64()),
+        ),
+        _ => e,
+    };
+    writeln!(stream, "{}", serde_json::to_string(query)?).map_err(timed_out)?;
+    let mut line = String::new();
+    BufReader::new(&stream)
+        .read_line(&mut line)
+        .map_err(timed_out)?;
+    Ok(serde_json::from_str(&line)?)
+}
+
+#[cfg(not(unix))]
+pub fn run(_socket: &Path, _daemon: Daemon) -> io::Result<()> {
+    Err(unsupported())
+}
+
+#[cfg(not(unix))]
+pub fn query(_socket: &Path, _query: &Query, _timeout: Duration) -> io::Result<Answer> {
+    Err(unsupported())
+}
+
+#[cfg(not(unix))]
+fn unsupported() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "the fnloc daemon needs unix sockets",
+    )
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_daemon_answers_queries_over_its_socket() {
+        let dir = std::env::temp_dir().join(format!("fnloc_daemon_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("lib.rs");
+        fs::write(&source, "fn cached() {}\n").unwrap();
+        let socket = dir.join("fnloc.sock");
+        let options = AnalysisOptions::default();
+
+        let daemon = Daemon::new(dir.clone(), options.clone());
+        let listening = socket.clone();
+        std::thread::spawn(move || run(&listening, daemon));
+        while !socket.exists() {
+            std::thread::yield_now();
+        }
+
+        let files = vec![source.to_string_lossy().to_string()];
+        let mut query_for = Query {
+            dir: dir.clone(),
+            options: options_key(&options),
+            files,
+        };
+        let Answer::Files(results) = query(&socket, &query_for, QUERY_TIMEOUT).unwrap() else {
+            panic!("query refused");
+        };
+        assert_eq!(results[0].functions[0].name, "cached");
+
+        query_for.options = options_key(&AnalysisOptions {
+            recover: true,
+            ..options
+        });
+        assert!(matches!(
+            query(&socket, &query_for, QUERY_TIMEOUT).unwrap(),
+            Answer::Refused(_)
+        ));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_query_to_a_hung_daemon_times_out() {
+        let dir = std::env::temp_dir().join(format!("fnloc_daemon_hung_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let socket = dir.join("fnloc.sock");
+        // Accepts connections but never answers
+        let _listener = std::os::unix::net::UnixListener::bind(&socket).unwrap();
+        let query_for = Query::new(&[], &AnalysisOptions::default()).unwrap();
+
+        let error = query(&socket, &query_for, Duration::from_millis(50)).unwrap_err();
+
+        assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
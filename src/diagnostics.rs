@@ -0,0 +1,70 @@
+//! Warnings raised while analyzing
+//!
+//! The library never prints warnings itself. They are collected as
+//! [`Diagnostic`]s on the [`AnalysisReport`](crate::AnalysisReport), and the
+//! command-line tool decides whether and where to print them.
+
+use crate::errors::AnalysisError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// What a diagnostic is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DiagnosticKind {
+    /// A file could not be read or parsed and has no results
+    AnalysisFailed,
+    /// A file is not valid UTF-8; invalid bytes were replaced before analysis
+    InvalidUtf8,
+    /// Lines of a file with syntax errors were skipped by `--recover`, or
+    /// estimated by `--heuristic-fallback`
+    UnparsedRegion,
+    /// A function is below `--min-comment-density`
+    LowCommentDensity,
+    /// The daemon selected with `--daemon` did not answer; the files were
+    /// analyzed locally
+    DaemonUnavailable,
+    /// A file could not be blamed for `--owners`; its functions have no owner
+    OwnersUnknown,
+}
+
+/// A warning about a file or function, raised without stopping the analysis
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    /// File the warning is about
+    pub file: String,
+    pub kind: DiagnosticKind,
+    /// Human-readable description, including the file or function concerned
+    pub message: String,
+    /// Error that stopped the analysis of the file, for [`DiagnosticKind::AnalysisFailed`]
+    pub error: Option<String>,
+}
+
+impl Diagnostic {
+    /// Creates a diagnostic about the given file
+    pub fn new(file: impl Into<String>, kind: DiagnosticKind, message: impl Into<String>) -> Self {
+        Self {
+            file: file.into(),
+            kind,
+            message: message.into(),
+            error: None,
+        }
+    }
+
+    /// Creates the diagnostic for a file that could not be analyzed
+    pub fn analysis_failed(file: &str, error: &AnalysisError) -> Self {
+        Self {
+            error: Some(error.to_string()),
+            ..Self::new(
+                file,
+                DiagnosticKind::AnalysisFailed,
+                format!("Failed to analyze file {file}: {error}"),
+            )
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
@@ -0,0 +1,423 @@
+//! Detection of copy-pasted functions
+//!
+//! Two functions are duplicates when their bodies consist of the same token
+//! stream. Tokens ignore formatting and comments, so a copy that was only
+//! reindented or commented differently is still found. Functions are compared
+//! across all analyzed files, including methods of `impl` blocks; bodies below
+//! [`MIN_DUPLICATE_TOKENS`] are skipped, as short bodies such as
+//! `{ Self::default() }` repeat by design.
+//!
+//! Near-duplicates are copies that were edited afterwards, typically by
+//! renaming variables or changing constants. They are found by replacing every
+//! identifier and literal with a placeholder, cutting the resulting token
+//! sequence into overlapping shingles of [`SHINGLE_SIZE`] tokens, and comparing
+//! the shingle sets of two functions by their Jaccard similarity. Bodies with
+//! fewer than [`MIN_DISTINCT_SHINGLES`] distinct shingles are not compared: a
+//! `match` that maps each variant to a literal repeats one pattern per arm, so
+//! any two such tables would look alike however long they are.
+
+use crate::normalize_path;
+use crate::source::read_source;
+use crate::type_complexity::type_name;
+use proc_macro2::{Delimiter, TokenStream, TokenTree};
+use quote::ToTokens;
+use std::collections::{HashMap, HashSet};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use syn::spanned::Spanned;
+use syn::{Block, ImplItem, Item, Signature};
+
+/// Token count below which function bodies are not compared
+pub const MIN_DUPLICATE_TOKENS: usize = 30;
+
+/// Number of consecutive normalized tokens forming one shingle
+pub const SHINGLE_SIZE: usize = 5;
+
+/// Distinct shingles a body needs to be compared for near-duplicates
+pub const MIN_DISTINCT_SHINGLES: usize = 20;
+
+/// Keywords kept when identifiers are replaced, as they carry the structure
+const KEYWORDS: [&str; 38] = [
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while",
+];
+
+/// A function taking part in a duplicate group
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateLocation {
+    /// Qualified name in the `path::function` form used in analysis results
+    pub function: String,
+    /// 1-based line number where the function starts
+    pub start_line: usize,
+}
+
+/// Functions whose bodies are identical
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateGroup {
+    /// Lines of each copy, from the signature to the closing brace
+    pub lines: usize,
+    /// Every copy, in the order of the files and of the functions in them
+    pub locations: Vec<DuplicateLocation>,
+}
+
+impl DuplicateGroup {
+    /// Returns the lines that would go away if only one copy were kept
+    pub fn redundant_lines(&self) -> usize {
+        self.lines * (self.locations.len() - 1)
+    }
+}
+
+/// Two functions whose bodies are largely the same after normalization
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimilarPair {
+    /// Share of the shingles the bodies have in common, from 0 to 1
+    pub similarity: f64,
+    pub first: DuplicateLocation,
+    pub second: DuplicateLocation,
+}
+
+/// A function body prepared for comparison
+pub(crate) struct FunctionBody {
+    pub(crate) location: DuplicateLocation,
+    pub(crate) lines: usize,
+    pub(crate) tokens: TokenStream,
+}
+
+/// Finds groups of identical function bodies in the given files, most redundant lines first
+///
+/// Files that cannot be read or parsed are skipped, matching `analyze_all_files`.
+pub fn find_duplicates(file_paths: &[String]) -> Vec<DuplicateGroup> {
+    group_identical(collect_bodies(file_paths))
+}
+
+/// Finds groups of identical function bodies in a single source, using bare function names
+pub fn find_duplicates_in_source(source: &str) -> Vec<DuplicateGroup> {
+    let mut bodies = Vec::new();
+    if let Ok(parsed) = syn::parse_file(source) {
+        add_bodies(&parsed.items, "", &mut bodies);
+    }
+    group_identical(bodies)
+}
+
+/// Collects the bodies of the free functions and methods of the given files
+pub(crate) fn collect_bodies(file_paths: &[String]) -> Vec<FunctionBody> {
+    let mut bodies = Vec::new();
+    for path in file_paths {
+        if let Ok(source) = read_source(path)
+            && let Ok(parsed) = syn::parse_file(&source.text)
+        {
+            let prefix = format!("{}::", normalize_path(path));
+            add_bodies(&parsed.items, &prefix, &mut bodies);
+        }
+    }
+    bodies
+}
+
+fn add_bodies(items: &[Item], prefix: &str, bodies: &mut Vec<FunctionBody>) {
+    for item in items {
+        match item {
+            Item::Fn(func) => {
+                let name = format!("{prefix}{}", func.sig.ident);
+                bodies.extend(function_body(name, &func.sig, &func.block));
+            }
+            Item::Impl(item_impl) => {
+                let type_prefix = match type_name(&item_impl.self_ty) {
+                    Some(type_name) => format!("{prefix}{type_name}::"),
+                    None => prefix.to_string(),
+                };
+                for impl_item in &item_impl.items {
+                    if let ImplItem::Fn(method) = impl_item {
+                        let name = format!("{type_prefix}{}", method.sig.ident);
+                        bodies.extend(function_body(name, &method.sig, &method.block));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Prepares a body for comparison, unless it is too short to be worth reporting
+fn function_body(function: String, sig: &Signature, block: &Block) -> Option<FunctionBody> {
+    let tokens = block.to_token_stream();
+    let start_line = sig.span().start().line;
+    let end_line = block.brace_token.span.close().end().line;
+    (token_count(&tokens) >= MIN_DUPLICATE_TOKENS).then(|| FunctionBody {
+        location: DuplicateLocation {
+            function,
+            start_line,
+        },
+        lines: end_line - start_line + 1,
+        tokens,
+    })
+}
+
+/// Finds pairs of functions in the given files whose similarity is at least
+/// `threshold` (from 0 to 1), most similar first
+///
+/// Files that cannot be read or parsed are skipped, matching `analyze_all_files`.
+pub fn find_similar(file_paths: &[String], threshold: f64) -> Vec<SimilarPair> {
+    similar_pairs(collect_bodies(file_paths), threshold)
+}
+
+/// Finds similar functions in a single source, using bare function names
+pub fn find_similar_in_source(source: &str, threshold: f64) -> Vec<SimilarPair> {
+    let mut bodies = Vec::new();
+    if let Ok(parsed) = syn::parse_file(source) {
+        add_bodies(&parsed.items, "", &mut bodies);
+    }
+    similar_pairs(bodies, threshold)
+}
+
+fn similar_pairs(bodies: Vec<FunctionBody>, threshold: f64) -> Vec<SimilarPair> {
+    let mut shingled: Vec<(DuplicateLocation, HashSet<u64>)> = bodies
+        .into_iter()
+        .map(|body| (body.location, shingles(&body.tokens)))
+        .filter(|(_, shingles)| shingles.len() >= MIN_DISTINCT_SHINGLES)
+        .collect();
+    // Sets whose sizes differ by more than the threshold allows cannot be
+    // similar enough, so each set is only compared with sets of close size
+    shingled.sort_by_key(|(_, shingles)| shingles.len());
+
+    let mut pairs = Vec::new();
+    for (index, (first, first_shingles)) in shingled.iter().enumerate() {
+        for (second, second_shingles) in &shingled[index + 1..] {
+            if (first_shingles.len() as f64) < threshold * second_shingles.len() as f64 {
+                break;
+            }
+            let shared = first_shingles.intersection(second_shingles).count();
+            let similarity =
+                shared as f64 / (first_shingles.len() + second_shingles.len() - shared) as f64;
+            if similarity >= threshold {
+                let (first, second) = if first.function <= second.function {
+                    (first, second)
+                } else {
+                    (second, first)
+                };
+                pairs.push(SimilarPair {
+                    similarity,
+                    first: first.clone(),
+                    second: second.clone(),
+                });
+            }
+        }
+    }
+    pairs.sort_by(|a, b| {
+        b.similarity
+            .total_cmp(&a.similarity)
+            .then_with(|| a.first.function.cmp(&b.first.function))
+            .then_with(|| a.second.function.cmp(&b.second.function))
+    });
+    pairs
+}
+
+/// Returns the hashes of all runs of [`SHINGLE_SIZE`] normalized tokens
+fn shingles(tokens: &TokenStream) -> HashSet<u64> {
+    let mut normalized = Vec::new();
+    normalize_tokens(tokens, &mut normalized);
+    normalized
+        .windows(SHINGLE_SIZE.min(normalized.len()).max(1))
+        .map(|window| {
+            let mut hasher = DefaultHasher::new();
+            window.hash(&mut hasher);
+            hasher.finish()
+        })
+        .collect()
+}
+
+/// Flattens a token stream, replacing identifiers and literals with placeholders
+fn normalize_tokens(tokens: &TokenStream, normalized: &mut Vec<String>) {
+    for token in tokens.clone() {
+        match token {
+            TokenTree::Group(group) => {
+                let (open, close) = match group.delimiter() {
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::Brace => ("{", "}"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Delimiter::None => ("", ""),
+                };
+                normalized.push(open.to_string());
+                normalize_tokens(&group.stream(), normalized);
+                normalized.push(close.to_string());
+            }
+            TokenTree::Ident(ident) => {
+                let ident = ident.to_string();
+                if KEYWORDS.contains(&ident.as_str()) {
+                    normalized.push(ident);
+                } else {
+                    normalized.push("$ident".to_string());
+                }
+            }
+            TokenTree::Literal(_) => normalized.push("$literal".to_string()),
+            TokenTree::Punct(punct) => normalized.push(punct.as_char().to_string()),
+        }
+    }
+}
+
+/// Counts the tokens of a stream, including those nested in groups
+pub(crate) fn token_count(tokens: &TokenStream) -> usize {
+    tokens
+        .clone()
+        .into_iter()
+        .map(|token| match token {
+            TokenTree::Group(group) => 2 + token_count(&group.stream()),
+            _ => 1,
+        })
+        .sum()
+}
+
+/// Groups bodies with the same token stream, keeping groups of two or more
+fn group_identical(bodies: Vec<FunctionBody>) -> Vec<DuplicateGroup> {
+    let mut groups: Vec<DuplicateGroup> = Vec::new();
+    let mut by_tokens: HashMap<u64, Vec<(String, usize)>> = HashMap::new();
+    for body in bodies {
+        let text = body.tokens.to_string();
+        let mut hasher = DefaultHasher::new();
+        text.hash(&mut hasher);
+        // Hash collisions are told apart by comparing the text
+        let candidates = by_tokens.entry(hasher.finish()).or_default();
+        match candidates.iter().find(|(candidate, _)| *candidate == text) {
+            Some(&(_, index)) => groups[index].locations.push(body.location),
+            None => {
+                candidates.push((text, groups.len()));
+                groups.push(DuplicateGroup {
+                    lines: body.lines,
+                    locations: vec![body.location],
+                });
+            }
+        }
+    }
+
+    groups.retain(|group| group.locations.len() > 1);
+    // Stable, so ties keep the order in which the groups were found
+    groups.sort_by_key(|group| std::cmp::Reverse(group.redundant_lines()));
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identical_bodies_are_grouped_regardless_of_formatting() {
+        let source = r#"
+fn parse(input: &str) -> usize {
+    let mut total = 0;
+    for part in input.split(',') {
+        total += part.trim().len();
+    }
+    total
+}
+
+struct Reader;
+
+impl Reader {
+    // Copied from parse
+    fn read(text: &str) -> usize {
+        let mut total = 0;
+        for part in text.split(',') { total += part.trim().len(); }
+        total
+    }
+
+    fn count(input: &str) -> usize {
+        let mut total = 0;
+        // Same tokens on fewer lines
+        for part in input.split(',') { total += part.trim().len(); }
+        total
+    }
+}
+
+fn short() -> usize { 0 }
+fn also_short() -> usize { 0 }
+"#;
+
+        let groups = find_duplicates_in_source(source);
+
+        // `read` renames `input`, so only `count` is an exact copy
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].lines, 7);
+        assert_eq!(
+            groups[0].locations,
+            [
+                DuplicateLocation {
+                    function: "parse".to_string(),
+                    start_line: 2,
+                },
+                DuplicateLocation {
+                    function: "Reader::count".to_string(),
+                    start_line: 20,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_renamed_copies_are_similar() {
+        let source = r#"
+fn sum_lengths(input: &str) -> usize {
+    let mut total = 0;
+    for part in input.split(',') {
+        total += part.trim().len();
+    }
+    total
+}
+
+fn count_fields(text: &str) -> usize {
+    let mut count = 0;
+    for field in text.split(';') {
+        count += field.trim().len();
+    }
+    count
+}
+
+fn unrelated(values: &[u32]) -> u32 {
+    if values.is_empty() {
+        return 0;
+    }
+    values.iter().map(|value| value * 2).filter(|value| *value > 10).sum::<u32>() + 1
+}
+"#;
+
+        let pairs = find_similar_in_source(source, 0.8);
+
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].similarity, 1.0);
+        assert_eq!(pairs[0].first.function, "count_fields");
+        assert_eq!(pairs[0].second.function, "sum_lengths");
+        assert!(find_duplicates_in_source(source).is_empty());
+    }
+
+    #[test]
+    fn test_unrelated_match_tables_are_not_similar() {
+        let source = r#"
+impl Error {
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Io => "io",
+            Self::Parse => "parse",
+            Self::Timeout => "timeout",
+            Self::Config => "config",
+            Self::Git => "git",
+            Self::Cancelled => "cancelled",
+        }
+    }
+}
+
+impl Status {
+    fn reason(&self) -> &'static str {
+        match self {
+            Self::Ok => "OK",
+            Self::BadRequest => "Bad Request",
+            Self::NotFound => "Not Found",
+            Self::NotAllowed => "Method Not Allowed",
+            Self::TooLarge => "Payload Too Large",
+            Self::Internal => "Internal Server Error",
+        }
+    }
+}
+"#;
+
+        assert!(find_similar_in_source(source, 0.8).is_empty());
+    }
+}
@@ -1,3 +1,6 @@
+use crate::config::Violation;
+use crate::exit_code;
+use serde_json::json;
 use thiserror::Error;
 
 /// Errors that can occur during function analysis
@@ -6,12 +9,214 @@ pub enum AnalysisError {
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
+    /// Rust source that syn cannot parse; `line` and `column` are 1-based
+    #[error("Failed to parse {file}:{line}:{column}: {message}")]
+    ParseError {
+        file: String,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+
+    #[error("File is not valid UTF-8: {file}")]
+    NonUtf8File { file: String },
+
+    #[error("File too large: {file} has {size} bytes (limit {limit})")]
+    FileTooLarge { file: String, size: u64, limit: u64 },
+
     #[error("Directory not accessible: {directory}")]
     DirectoryNotAccessible { directory: String },
 
+    /// A path given to analyze that does not exist, with a suggested correction if one was found
+    #[error("Path not found: {path}{}", .hint.as_ref().map(|hint| format!("\n  hint: {hint}")).unwrap_or_default())]
+    PathNotFound { path: String, hint: Option<String> },
+
     #[error("No Rust files found in directory: {directory}")]
     NoRustFiles { directory: String },
+
+    /// A file given to analyze that does not have the `.rs` extension
+    #[error("Not a Rust source file: {path}")]
+    NotRustFile { path: String },
+
+    #[error("Invalid configuration: {message}")]
+    InvalidConfig { message: String },
+
+    #[error("Invalid glob pattern '{pattern}': {message}")]
+    InvalidPattern { pattern: String, message: String },
+
+    #[error("Invalid template: {message}")]
+    InvalidTemplate { message: String },
+
+    #[error("Git error: {message}")]
+    Git { message: String },
+
+    #[error("Invalid fixture: {message}")]
+    InvalidFixture { message: String },
+
+    /// A JSON report given to `fnloc diff` that cannot be read as a report
+    #[error("Invalid report {path}: {message}")]
+    InvalidReport { path: String, message: String },
+
+    #[error("Analysis panicked: {message}")]
+    AnalysisPanicked { message: String },
+
+    #[error("Analysis timed out after {}s", .timeout.as_secs_f64())]
+    AnalysisTimedOut { timeout: std::time::Duration },
+
+    #[error("{} threshold violation(s):\n{}", .violations.len(), format_violations(.violations))]
+    ThresholdsExceeded { violations: Vec<Violation> },
+
+    /// Files that were skipped or only partly analyzed, with `--strict`
+    #[error("{} file(s) could not be fully analyzed:\n{}", .files.len(), format_files(.files))]
+    IncompleteAnalysis { files: Vec<String> },
+}
+
+impl AnalysisError {
+    /// Attributes a parse error without a file to the given file
+    pub fn in_file(self, path: &str) -> Self {
+        match self {
+            Self::ParseError {
+                file,
+                line,
+                column,
+                message,
+            } if file.is_empty() => Self::ParseError {
+                file: path.to_string(),
+                line,
+                column,
+                message,
+            },
+            error => error,
+        }
+    }
+
+    /// Returns a stable snake_case name of the kind of error, for machine-readable output
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Io(_) => "io",
+            Self::ParseError { .. } => "parse_error",
+            Self::NonUtf8File { .. } => "non_utf8_file",
+            Self::FileTooLarge { .. } => "file_too_large",
+            Self::DirectoryNotAccessible { .. } => "directory_not_accessible",
+            Self::PathNotFound { .. } => "path_not_found",
+            Self::NoRustFiles { .. } => "no_rust_files",
+            Self::NotRustFile { .. } => "not_rust_file",
+            Self::InvalidConfig { .. } => "invalid_config",
+            Self::InvalidPattern { .. } => "invalid_pattern",
+            Self::InvalidTemplate { .. } => "invalid_template",
+            Self::Git { .. } => "git",
+            Self::InvalidFixture { .. } => "invalid_fixture",
+            Self::InvalidReport { .. } => "invalid_report",
+            Self::AnalysisPanicked { .. } => "analysis_panicked",
+            Self::AnalysisTimedOut { .. } => "analysis_timed_out",
+            Self::ThresholdsExceeded { .. } => "thresholds_exceeded",
+            Self::IncompleteAnalysis { .. } => "incomplete_analysis",
+        }
+    }
+
+    /// Returns the exit code of the command-line tool for this error, see [`exit_code`]
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::ThresholdsExceeded { .. } => exit_code::VIOLATIONS,
+            Self::DirectoryNotAccessible { .. }
+            | Self::PathNotFound { .. }
+            | Self::NoRustFiles { .. }
+            | Self::NotRustFile { .. }
+            | Self::InvalidConfig { .. }
+            | Self::InvalidPattern { .. }
+            | Self::InvalidTemplate { .. }
+            | Self::InvalidFixture { .. } => exit_code::USAGE_ERROR,
+            _ => exit_code::ANALYSIS_ERROR,
+        }
+    }
+
+    /// Formats the error as a single-line JSON object, as written with `--format json`
+    ///
+    /// The object has one `error` field holding the `kind`, the human-readable
+    /// `message`, the `exit_code`, and for some kinds the details: the
+    /// `violations` of exceeded thresholds or the incompletely analyzed `files`.
+    pub fn to_json(&self) -> String {
+        let mut error = json!({
+            "kind": self.kind(),
+            "message": self.to_string(),
+            "exit_code": self.exit_code(),
+        });
+        match self {
+            Self::ThresholdsExceeded { violations } => {
+                error["violations"] = violations
+                    .iter()
+                    .map(|violation| {
+                        json!({
+                            "function": violation.function,
+                            "metric": violation.metric,
+                            "value": violation.value,
+                            "limit": violation.limit,
+                        })
+                    })
+                    .collect();
+            }
+            Self::IncompleteAnalysis { files } => error["files"] = json!(files),
+            _ => {}
+        }
+        json!({ "error": error }).to_string()
+    }
+}
+
+/// Formats violations as an indented list, one per line
+fn format_violations(violations: &[Violation]) -> String {
+    violations
+        .iter()
+        .map(|violation| format!("  - {violation}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Formats file paths as an indented list, one per line
+fn format_files(files: &[String]) -> String {
+    files
+        .iter()
+        .map(|file| format!("  - {file}"))
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
 /// Result type alias for convenience
 pub type AnalysisResult<T> = Result<T, AnalysisError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_error_carries_kind_and_details() {
+        let error = AnalysisError::ThresholdsExceeded {
+            violations: vec![Violation {
+                function: "src/lib.rs::run".to_string(),
+                metric: "code",
+                value: 120,
+                limit: 80,
+            }],
+        };
+
+        let parsed: serde_json::Value = serde_json::from_str(&error.to_json()).unwrap();
+
+        assert_eq!(parsed["error"]["kind"], "thresholds_exceeded");
+        assert_eq!(parsed["error"]["message"], error.to_string());
+        assert_eq!(parsed["error"]["exit_code"], exit_code::VIOLATIONS);
+        assert_eq!(parsed["error"]["violations"][0]["limit"], 80);
+        assert!(!error.to_json().contains('\n'));
+    }
+
+    #[test]
+    fn test_exit_codes_separate_violations_from_misconfiguration() {
+        let invalid = AnalysisError::InvalidConfig {
+            message: "unknown metric".to_string(),
+        };
+        let failed = AnalysisError::Git {
+            message: "not a repository".to_string(),
+        };
+
+        assert_eq!(invalid.exit_code(), exit_code::USAGE_ERROR);
+        assert_eq!(failed.exit_code(), exit_code::ANALYSIS_ERROR);
+    }
+}
@@ -0,0 +1,19 @@
+//! Exit codes of the command-line tool
+//!
+//! CI scripts can tell "the code is too complex" from "fnloc is misconfigured"
+//! by the exit code alone. [`AnalysisError::exit_code`](crate::errors::AnalysisError::exit_code)
+//! maps every error to one of these codes.
+
+/// Every file was analyzed and no limit was exceeded
+pub const SUCCESS: i32 = 0;
+
+/// A function exceeds a limit of `--fail-on`, `--baseline`, or `[thresholds]`
+pub const VIOLATIONS: i32 = 1;
+
+/// Invalid arguments or configuration, such as an unknown flag, a malformed
+/// glob or template, or a path without Rust files
+pub const USAGE_ERROR: i32 = 2;
+
+/// The analysis itself failed, e.g. on an I/O or git error, or a file that
+/// could not be fully analyzed with `--strict`
+pub const ANALYSIS_ERROR: i32 = 3;
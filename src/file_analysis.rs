@@ -0,0 +1,191 @@
+//! Streaming analysis of a set of files
+//!
+//! [`Analyzer::iter_files`] analyzes the files on a background thread and
+//! yields one [`FileAnalysis`] per file as soon as its batch is done, so
+//! consumers can render results incrementally instead of waiting for the
+//! whole tree. Callbacks registered on the analyzer report progress and
+//! per-file issues while the files are analyzed, e.g. for GUIs or build tools.
+
+use crate::analyzer::{AnalysisOptions, FunctionAnalysisResult, PhaseTimings};
+use crate::diagnostics::Diagnostic;
+use crate::errors::AnalysisError;
+use std::fmt;
+use std::ops::ControlFlow;
+use std::sync::{Arc, mpsc};
+use std::thread;
+
+/// Outcome of analyzing a single file
+#[derive(Debug)]
+pub struct FileAnalysis {
+    /// Path of the file as it was given to the analyzer
+    pub path: String,
+    /// Results for every function in the file; empty if the file failed
+    pub functions: Vec<FunctionAnalysisResult>,
+    /// Time spent reading, parsing, and analyzing the file
+    pub timings: PhaseTimings,
+    /// Warnings about the file, including why it failed
+    pub diagnostics: Vec<Diagnostic>,
+    /// Why the file could not be analyzed, if it failed
+    pub error: Option<AnalysisError>,
+}
+
+/// A file that was analyzed, before it is handed to the caller
+pub(crate) struct AnalyzedFile {
+    pub(crate) functions: Vec<FunctionAnalysisResult>,
+    pub(crate) timings: PhaseTimings,
+    pub(crate) diagnostics: Vec<Diagnostic>,
+}
+
+/// Callback shared between the analyzer's clones and its analysis threads
+type Hook<F> = Option<Arc<F>>;
+
+/// Callback receiving each warning about a file
+type WarningHook = dyn Fn(&Diagnostic) + Send + Sync;
+
+/// Callbacks invoked while files are analyzed
+///
+/// Callbacks run on the analysis threads, so they must be thread-safe and
+/// should return quickly.
+#[derive(Clone, Default)]
+struct Hooks {
+    file_start: Hook<dyn Fn(&str) + Send + Sync>,
+    file_done: Hook<dyn Fn(&FileAnalysis) + Send + Sync>,
+    warning: Hook<WarningHook>,
+}
+
+impl fmt::Debug for Hooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Hooks")
+            .field("file_start", &self.file_start.is_some())
+            .field("file_done", &self.file_done.is_some())
+            .field("warning", &self.warning.is_some())
+            .finish()
+    }
+}
+
+/// Analyzes a fixed set of files with shared options
+#[derive(Debug, Clone, Default)]
+pub struct Analyzer {
+    files: Vec<String>,
+    revision: Option<String>,
+    options: AnalysisOptions,
+    hooks: Hooks,
+}
+
+impl Analyzer {
+    /// Creates an analyzer for the given files with default options
+    pub fn new(files: Vec<String>) -> Self {
+        Self {
+            files,
+            ..Self::default()
+        }
+    }
+
+    /// Sets the options used for every file
+    pub fn with_options(mut self, options: AnalysisOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Reads the files as of a git revision instead of the working tree
+    pub fn at_revision(mut self, revision: impl Into<String>) -> Self {
+        self.revision = Some(revision.into());
+        self
+    }
+
+    /// Calls `callback` with the path of each file when its analysis starts
+    pub fn on_file_start(mut self, callback: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        self.hooks.file_start = Some(Arc::new(callback));
+        self
+    }
+
+    /// Calls `callback` with each file's outcome when its analysis is done
+    pub fn on_file_done(
+        mut self,
+        callback: impl Fn(&FileAnalysis) + Send + Sync + 'static,
+    ) -> Self {
+        self.hooks.file_done = Some(Arc::new(callback));
+        self
+    }
+
+    /// Calls `callback` with each warning about a file, including failures to analyze it
+    pub fn on_warning(mut self, callback: impl Fn(&Diagnostic) + Send + Sync + 'static) -> Self {
+        self.hooks.warning = Some(Arc::new(callback));
+        self
+    }
+
+    /// Analyzes all files and returns the results of every function, in input order
+    pub fn analyze(&self) -> Vec<FunctionAnalysisResult> {
+        self.iter_files().flat_map(|file| file.functions).collect()
+    }
+
+    /// Starts analyzing the files and returns an iterator over the finished files
+    ///
+    /// Files arrive in input order. Dropping the iterator stops the analysis
+    /// after the batch in progress; cancelling `options.cancellation` ends the
+    /// iterator early.
+    pub fn iter_files(&self) -> FileAnalyses {
+        let (sender, receiver) = mpsc::channel();
+        let analyzer = self.clone();
+        thread::spawn(move || {
+            let hooks = &analyzer.hooks;
+            let on_start = |path: &str| {
+                if let Some(file_start) = &hooks.file_start {
+                    file_start(path);
+                }
+            };
+            crate::analyze_files_in_batches(
+                &analyzer.files,
+                analyzer.revision.as_deref(),
+                &analyzer.options,
+                &on_start,
+                |path, analyzed| {
+                    let file = match analyzed {
+                        Ok(analyzed) => FileAnalysis {
+                            path: path.to_string(),
+                            functions: analyzed.functions,
+                            timings: analyzed.timings,
+                            diagnostics: analyzed.diagnostics,
+                            error: None,
+                        },
+                        Err(error) => FileAnalysis {
+                            path: path.to_string(),
+                            functions: Vec::new(),
+                            timings: PhaseTimings::default(),
+                            diagnostics: vec![Diagnostic::analysis_failed(path, &error)],
+                            error: Some(error),
+                        },
+                    };
+                    if let Some(warning) = &hooks.warning {
+                        file.diagnostics
+                            .iter()
+                            .for_each(|diagnostic| warning(diagnostic));
+                    }
+                    if let Some(file_done) = &hooks.file_done {
+                        file_done(&file);
+                    }
+                    // The receiver is gone once the iterator is dropped
+                    match sender.send(file) {
+                        Ok(()) => ControlFlow::Continue(()),
+                        Err(_) => ControlFlow::Break(()),
+                    }
+                },
+            );
+        });
+        FileAnalyses { receiver }
+    }
+}
+
+/// Iterator over files as they finish analyzing, returned by [`Analyzer::iter_files`]
+#[derive(Debug)]
+pub struct FileAnalyses {
+    receiver: mpsc::Receiver<FileAnalysis>,
+}
+
+impl Iterator for FileAnalyses {
+    type Item = FileAnalysis;
+
+    fn next(&mut self) -> Option<FileAnalysis> {
+        self.receiver.recv().ok()
+    }
+}
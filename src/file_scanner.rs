@@ -1,28 +1,153 @@
 use crate::errors::{AnalysisError, AnalysisResult};
-use walkdir::WalkDir;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::WalkBuilder;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
 
-/// Recursively finds all Rust files in a directory using walkdir
+/// Build output and vendored-dependency directories skipped by default
+pub const DEFAULT_EXCLUDED_DIRS: [&str; 3] = ["target", "vendor", "third_party"];
+
+/// Markers identifying generated files, e.g. protobuf or bindgen output
+pub const GENERATED_MARKERS: [&str; 2] = ["@generated", "DO NOT EDIT"];
+
+/// Number of leading lines searched for a generated-file marker
+pub const GENERATED_MARKER_LINES: usize = 10;
+
+/// Options controlling which files are scanned
+#[derive(Debug, Clone, Default)]
+pub struct ScanOptions {
+    /// Glob patterns of files and directories to skip, matched against paths
+    /// relative to the scanned directory (e.g. `target/**`, `**/generated/*.rs`)
+    pub exclude: Vec<String>,
+    /// Glob patterns restricting the scan to matching files; empty means all files
+    /// Exclusions take precedence over inclusions
+    pub include: Vec<String>,
+    /// Also scan files ignored by `.gitignore`, `.ignore`, and git exclude files
+    pub no_ignore: bool,
+    /// Maximum directory depth to descend; 1 scans only files directly in the root
+    pub max_depth: Option<usize>,
+    /// Follow symbolic links to directories; cycles are skipped
+    pub follow_symlinks: bool,
+    /// Also scan directories in `DEFAULT_EXCLUDED_DIRS`
+    pub no_default_excludes: bool,
+    /// Also scan files marked as generated (see `GENERATED_MARKERS`)
+    pub include_generated: bool,
+}
+
+/// Recursively finds all Rust files in a directory, skipping files ignored by git
 /// Returns a Result containing the list of Rust file paths or an error
 pub fn find_rust_files(dir: &str) -> AnalysisResult<Vec<String>> {
+    find_rust_files_with_options(dir, &ScanOptions::default())
+}
+
+/// Finds all Rust files in several directories and files, merging the results
+///
+/// Each path is scanned like `find_rust_files_with_options`, with patterns relative
+/// to that path. Files reached through more than one path are listed once.
+/// It is only an error if no Rust files are found at all.
+pub fn find_rust_files_in_paths(
+    paths: &[String],
+    options: &ScanOptions,
+) -> AnalysisResult<Vec<String>> {
+    let mut rust_files = Vec::new();
+    let mut seen_files = HashSet::new();
+
+    for path in paths {
+        let files = match find_rust_files_with_options(path, options) {
+            Ok(files) => files,
+            Err(AnalysisError::NoRustFiles { .. }) => continue,
+            Err(e) => return Err(e),
+        };
+        rust_files.extend(
+            files
+                .into_iter()
+                .filter(|file| is_first_visit(&mut seen_files, Path::new(file))),
+        );
+    }
+
+    if rust_files.is_empty() {
+        return Err(AnalysisError::NoRustFiles {
+            directory: paths.join(", "),
+        });
+    }
+
+    Ok(rust_files)
+}
+
+/// Recursively finds all Rust files in a directory, honoring include and exclude patterns
+///
+/// Files are deduplicated by their canonical path, so a file reachable through
+/// symbolic links and its real path is listed once, under the first path found.
+/// An excluded directory is not descended into, so nothing below it is scanned.
+/// Include patterns only apply to files, so `src/api/**` still walks through `src`.
+/// Unless `no_ignore` is set, paths ignored with gitignore semantics are skipped;
+/// `.gitignore` files are honored even outside a git repository.
+///
+/// If `dir` is a Rust file it is returned as-is: an explicitly named file is
+/// always analyzed, regardless of patterns and ignore files. Other files are
+/// rejected with [`AnalysisError::NotRustFile`].
+pub fn find_rust_files_with_options(
+    dir: &str,
+    options: &ScanOptions,
+) -> AnalysisResult<Vec<String>> {
+    if Path::new(dir).is_file() {
+        if !is_rust_file(Path::new(dir)) {
+            return Err(AnalysisError::NotRustFile {
+                path: dir.to_string(),
+            });
+        }
+        return Ok(vec![dir.to_string()]);
+    }
+    if !Path::new(dir).exists() {
+        return Err(AnalysisError::PathNotFound {
+            path: dir.to_string(),
+            hint: missing_path_hint(dir),
+        });
+    }
+
+    let exclude = build_glob_set(&options.exclude)?;
+    let include = build_glob_set(&options.include)?;
     let mut rust_files = Vec::new();
+    let mut seen_files = HashSet::new();
+
+    let root = Path::new(dir).to_path_buf();
+    let skip_default_excluded = !options.no_default_excludes;
+    let walker = WalkBuilder::new(dir)
+        .standard_filters(!options.no_ignore)
+        .hidden(false)
+        .require_git(false)
+        .max_depth(options.max_depth)
+        .follow_links(options.follow_symlinks)
+        .filter_entry(move |entry| {
+            let default_excluded =
+                skip_default_excluded && entry.depth() > 0 && is_default_excluded_dir(entry);
+            !default_excluded && !is_match(&exclude, &root, entry.path())
+        })
+        .build();
 
-    for entry in WalkDir::new(dir) {
-        let entry = entry.map_err(|e| {
-            if e.path().is_some() {
-                AnalysisError::DirectoryNotAccessible {
-                    directory: dir.to_string(),
-                }
-            } else {
-                AnalysisError::Io(std::io::Error::other(format!(
-                    "Error reading directory entry: {e}"
-                )))
-            }
+    for entry in walker {
+        let entry = match entry {
+            Err(e) if is_symlink_loop(&e) => continue,
+            entry => entry,
+        };
+        let entry = entry.map_err(|e| match e {
+            ignore::Error::WithPath { .. } => AnalysisError::DirectoryNotAccessible {
+                directory: dir.to_string(),
+            },
+            _ => AnalysisError::Io(std::io::Error::other(format!(
+                "Error reading directory entry: {e}"
+            ))),
         })?;
 
-        if entry.path().extension().is_some_and(|ext| ext == "rs") {
-            if let Some(path_str) = entry.path().to_str() {
-                rust_files.push(path_str.to_string());
-            }
+        if is_rust_file(entry.path())
+            && (options.include.is_empty() || is_match(&include, Path::new(dir), entry.path()))
+            && let Some(path_str) = entry.path().to_str()
+            && is_first_visit(&mut seen_files, entry.path())
+            && (options.include_generated || !is_generated_file(entry.path()))
+        {
+            rust_files.push(path_str.to_string());
         }
     }
 
@@ -34,3 +159,458 @@ pub fn find_rust_files(dir: &str) -> AnalysisResult<Vec<String>> {
 
     Ok(rust_files)
 }
+
+/// Returns true if the path has the `.rs` extension of Rust source files
+fn is_rust_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "rs")
+}
+
+/// Returns true if the entry is a directory named in `DEFAULT_EXCLUDED_DIRS`
+fn is_default_excluded_dir(entry: &ignore::DirEntry) -> bool {
+    entry
+        .file_type()
+        .is_some_and(|file_type| file_type.is_dir())
+        && entry
+            .file_name()
+            .to_str()
+            .is_some_and(|name| DEFAULT_EXCLUDED_DIRS.contains(&name))
+}
+
+/// Returns true if a comment in the first lines of the file contains a generated-file marker
+/// Files that cannot be read are not considered generated
+pub fn is_generated_file(path: &Path) -> bool {
+    let Ok(file) = File::open(path) else {
+        return false;
+    };
+    BufReader::new(file)
+        .split(b'\n')
+        .take(GENERATED_MARKER_LINES)
+        .map_while(Result::ok)
+        .any(|line| {
+            let line = String::from_utf8_lossy(&line);
+            let trimmed = line.trim_start();
+            (trimmed.starts_with("//") || trimmed.starts_with("/*") || trimmed.starts_with('*'))
+                && GENERATED_MARKERS.iter().any(|marker| line.contains(marker))
+        })
+}
+
+/// Returns true if the walk error was caused by a symbolic link cycle
+fn is_symlink_loop(error: &ignore::Error) -> bool {
+    match error {
+        ignore::Error::Loop { .. } => true,
+        ignore::Error::WithPath { err, .. }
+        | ignore::Error::WithDepth { err, .. }
+        | ignore::Error::WithLineNumber { err, .. } => is_symlink_loop(err),
+        _ => false,
+    }
+}
+
+/// Records the canonical path of a file, returning false if it was already seen
+/// Files whose canonical path cannot be resolved are always treated as new
+fn is_first_visit(seen_files: &mut HashSet<PathBuf>, path: &Path) -> bool {
+    match path.canonicalize() {
+        Ok(canonical) => seen_files.insert(canonical),
+        Err(_) => true,
+    }
+}
+
+/// Suggests what was meant by a path that does not exist
+///
+/// A sibling whose name is a close match, such as `src` for `sr`, is
+/// suggested first. Otherwise a `Cargo.toml` in the parent directory (or the
+/// current one) points to the workspace members or the package sources.
+pub fn missing_path_hint(path: &str) -> Option<String> {
+    let path = Path::new(path);
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    if let Some(name) = path.file_name().and_then(|name| name.to_str())
+        && let Some(sibling) = closest_sibling(parent, name)
+    {
+        let corrected = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.join(sibling),
+            _ => PathBuf::from(sibling),
+        };
+        return Some(format!("did you mean `{}`?", corrected.display()));
+    }
+    let manifest_dir = if parent.is_dir() {
+        parent
+    } else {
+        Path::new(".")
+    };
+    cargo_manifest_hint(manifest_dir)
+}
+
+/// Returns the entry of `dir` whose name is closest to `name`, if any is close enough
+///
+/// Up to one edit per three characters is tolerated, and at least one.
+fn closest_sibling(dir: &Path, name: &str) -> Option<String> {
+    let max_distance = (name.chars().count() / 3).max(1);
+    std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .map(|candidate| (edit_distance(name, &candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+/// Describes what to analyze in a directory holding a `Cargo.toml`
+fn cargo_manifest_hint(dir: &Path) -> Option<String> {
+    let in_dir = |relative: &str| {
+        if dir == Path::new(".") {
+            PathBuf::from(relative)
+        } else {
+            dir.join(relative)
+        }
+    };
+    let manifest_path = in_dir("Cargo.toml");
+    let manifest: toml::Table = std::fs::read_to_string(&manifest_path).ok()?.parse().ok()?;
+    let members: Vec<&str> = manifest
+        .get("workspace")
+        .and_then(|workspace| workspace.get("members"))
+        .and_then(|members| members.as_array())
+        .map(|members| {
+            members
+                .iter()
+                .filter_map(|member| member.as_str())
+                .collect()
+        })
+        .unwrap_or_default();
+    if !members.is_empty() {
+        let paths: Vec<String> = members
+            .iter()
+            .map(|member| in_dir(member).join("src").display().to_string())
+            .collect();
+        return Some(format!(
+            "`{}` defines a workspace; analyze its members with `fnloc {}`",
+            manifest_path.display(),
+            paths.join(" ")
+        ));
+    }
+    let sources = in_dir("src");
+    sources.is_dir().then(|| {
+        format!(
+            "`{}` found; the package sources are in `{}`",
+            manifest_path.display(),
+            sources.display()
+        )
+    })
+}
+
+/// Returns the Levenshtein distance between two strings, counted in characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, &b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Compiles glob patterns into a single matcher
+fn build_glob_set(patterns: &[String]) -> AnalysisResult<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern).map_err(|e| AnalysisError::InvalidPattern {
+            pattern: pattern.clone(),
+            message: e.kind().to_string(),
+        })?;
+        builder.add(glob);
+    }
+    builder.build().map_err(|e| AnalysisError::InvalidPattern {
+        pattern: patterns.join(", "),
+        message: e.to_string(),
+    })
+}
+
+/// Returns true if the path, relative to the scan root, matches the glob set
+/// The scan root itself never matches
+fn is_match(globs: &GlobSet, root: &Path, path: &Path) -> bool {
+    match path.strip_prefix(root) {
+        Ok(relative) if relative.as_os_str().is_empty() => false,
+        Ok(relative) => globs.is_match(relative),
+        Err(_) => globs.is_match(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn create_tree(name: &str, files: &[&str]) -> std::path::PathBuf {
+        let root = std::env::temp_dir().join(format!("fnloc_scan_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        for file in files {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "fn f() {}\n").unwrap();
+        }
+        root
+    }
+
+    fn relative_files(root: &Path, options: &ScanOptions) -> Vec<String> {
+        let mut files: Vec<String> = find_rust_files_with_options(&root.to_string_lossy(), options)
+            .unwrap()
+            .iter()
+            .map(|file| {
+                Path::new(file)
+                    .strip_prefix(root)
+                    .unwrap()
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            })
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn test_missing_path_hints() {
+        let root = create_tree("hints", &["crates/core/src/lib.rs"]);
+        fs::write(
+            root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"crates/core\"]\n",
+        )
+        .unwrap();
+
+        let typo = root.join("crates/cor");
+        let hint = missing_path_hint(&typo.to_string_lossy()).unwrap();
+        assert!(hint.contains("did you mean"), "{hint}");
+        assert!(hint.contains(&root.join("crates/core").display().to_string()));
+
+        let error = find_rust_files(&root.join("src").to_string_lossy()).unwrap_err();
+        assert!(
+            matches!(&error, AnalysisError::PathNotFound { hint: Some(hint), .. }
+                if hint.contains("defines a workspace")),
+            "{error}"
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_exclude_patterns() {
+        let root = create_tree(
+            "exclude",
+            &[
+                "lib.rs",
+                "target/debug/build.rs",
+                "api/gen/schema.rs",
+                "api/mod.rs",
+            ],
+        );
+        let options = ScanOptions {
+            exclude: vec!["target".to_string(), "**/gen/*.rs".to_string()],
+            ..ScanOptions::default()
+        };
+
+        assert_eq!(relative_files(&root, &options), ["api/mod.rs", "lib.rs"]);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_include_patterns_combine_with_exclude() {
+        let root = create_tree(
+            "include",
+            &["lib.rs", "api/v1/routes.rs", "api/v1/gen.rs", "api/mod.rs"],
+        );
+        let options = ScanOptions {
+            include: vec!["api/**".to_string()],
+            exclude: vec!["**/gen.rs".to_string()],
+            ..ScanOptions::default()
+        };
+
+        assert_eq!(
+            relative_files(&root, &options),
+            ["api/mod.rs", "api/v1/routes.rs"]
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_gitignored_files_are_skipped_unless_disabled() {
+        let root = create_tree("gitignore", &["lib.rs", "out/build.rs", "gen/api.rs"]);
+        fs::write(root.join(".gitignore"), "out/\ngen/api.rs\n").unwrap();
+
+        assert_eq!(relative_files(&root, &ScanOptions::default()), ["lib.rs"]);
+
+        let options = ScanOptions {
+            no_ignore: true,
+            ..ScanOptions::default()
+        };
+        assert_eq!(
+            relative_files(&root, &options),
+            ["gen/api.rs", "lib.rs", "out/build.rs"]
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_max_depth_limits_recursion() {
+        let root = create_tree("depth", &["lib.rs", "api/mod.rs", "api/v1/routes.rs"]);
+        let top_level = ScanOptions {
+            max_depth: Some(1),
+            ..ScanOptions::default()
+        };
+        let two_levels = ScanOptions {
+            max_depth: Some(2),
+            ..ScanOptions::default()
+        };
+
+        assert_eq!(relative_files(&root, &top_level), ["lib.rs"]);
+        assert_eq!(relative_files(&root, &two_levels), ["api/mod.rs", "lib.rs"]);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_follow_symlinks_skips_cycles_and_duplicates() {
+        use std::os::unix::fs::symlink;
+
+        let root = create_tree("symlinks", &["lib.rs", "api/mod.rs"]);
+        symlink(root.join("api"), root.join("api_link")).unwrap();
+        symlink(&root, root.join("api/parent")).unwrap();
+
+        assert_eq!(
+            relative_files(&root, &ScanOptions::default()),
+            ["api/mod.rs", "lib.rs"]
+        );
+
+        let options = ScanOptions {
+            follow_symlinks: true,
+            ..ScanOptions::default()
+        };
+        assert_eq!(relative_files(&root, &options).len(), 2);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinked_files_are_listed_once() {
+        use std::os::unix::fs::symlink;
+
+        let root = create_tree("file_symlinks", &["lib.rs"]);
+        symlink(root.join("lib.rs"), root.join("alias.rs")).unwrap();
+
+        let files = relative_files(&root, &ScanOptions::default());
+        assert_eq!(files.len(), 1, "{files:?}");
+
+        let paths = vec![
+            root.to_string_lossy().into_owned(),
+            root.join("alias.rs").to_string_lossy().into_owned(),
+        ];
+        let files = find_rust_files_in_paths(&paths, &ScanOptions::default()).unwrap();
+        assert_eq!(files.len(), 1);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_multiple_paths_are_merged() {
+        let root = create_tree(
+            "paths",
+            &["src/lib.rs", "tests/it.rs", "build.rs", "docs/x.md"],
+        );
+        let paths: Vec<String> = ["src", "tests", "build.rs", "src/lib.rs"]
+            .iter()
+            .map(|path| root.join(path).to_string_lossy().into_owned())
+            .collect();
+
+        let files = find_rust_files_in_paths(&paths, &ScanOptions::default()).unwrap();
+        assert_eq!(files.len(), 3);
+
+        let no_rust = vec![root.join("docs").to_string_lossy().into_owned()];
+        let result = find_rust_files_in_paths(&no_rust, &ScanOptions::default());
+        assert!(matches!(result, Err(AnalysisError::NoRustFiles { .. })));
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_explicit_file_bypasses_filters() {
+        let root = create_tree("single_file", &["gen/api.rs"]);
+        fs::write(root.join(".gitignore"), "gen/\n").unwrap();
+        let file = root.join("gen/api.rs").to_string_lossy().into_owned();
+        let options = ScanOptions {
+            exclude: vec!["**".to_string()],
+            ..ScanOptions::default()
+        };
+
+        assert_eq!(
+            find_rust_files_with_options(&file, &options).unwrap(),
+            std::slice::from_ref(&file)
+        );
+
+        let readme = root.join("README.md");
+        fs::write(&readme, "# Not Rust\n").unwrap();
+        let paths = vec![file, readme.to_string_lossy().into_owned()];
+        let result = find_rust_files_in_paths(&paths, &ScanOptions::default());
+        assert!(matches!(result, Err(AnalysisError::NotRustFile { path }) if path == paths[1]));
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_build_and_vendor_dirs_are_skipped_by_default() {
+        let root = create_tree(
+            "default_excludes",
+            &[
+                "lib.rs",
+                "target/debug/out.rs",
+                "vendor/dep/lib.rs",
+                "api/target.rs",
+            ],
+        );
+
+        assert_eq!(
+            relative_files(&root, &ScanOptions::default()),
+            ["api/target.rs", "lib.rs"]
+        );
+
+        let options = ScanOptions {
+            no_default_excludes: true,
+            ..ScanOptions::default()
+        };
+        assert_eq!(relative_files(&root, &options).len(), 4);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_generated_files_are_skipped_by_default() {
+        let root = create_tree("generated", &["lib.rs", "proto.rs", "bindings.rs"]);
+        fs::write(
+            root.join("proto.rs"),
+            "// This file is @generated by prost-build.\nfn f() {}\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("bindings.rs"),
+            "/* automatically generated by rust-bindgen */\n// DO NOT EDIT\nfn g() {}\n",
+        )
+        .unwrap();
+
+        assert_eq!(relative_files(&root, &ScanOptions::default()), ["lib.rs"]);
+
+        let options = ScanOptions {
+            include_generated: true,
+            ..ScanOptions::default()
+        };
+        assert_eq!(relative_files(&root, &options).len(), 3);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_pattern_is_reported() {
+        let options = ScanOptions {
+            exclude: vec!["src/[".to_string()],
+            ..ScanOptions::default()
+        };
+
+        let result = find_rust_files_with_options(".", &options);
+        assert!(matches!(result, Err(AnalysisError::InvalidPattern { .. })));
+    }
+}
@@ -0,0 +1,213 @@
+//! Expected-results fixtures for regression testing analysis output
+//!
+//! A fixture lists functions and the metric values they are expected to have:
+//!
+//! ```toml
+//! [[function]]
+//! name = "simple_function"
+//! total = 3
+//! code = 3
+//! complexity = 1
+//! ```
+//!
+//! Any metric returned by [`FunctionAnalysisResult::metrics`] may be listed, so
+//! newly added metrics can be covered without changing the fixture loader.
+//! Metrics that are not listed for a function are not compared.
+
+use crate::analyzer::FunctionAnalysisResult;
+use crate::errors::{AnalysisError, AnalysisResult};
+use crate::source::read_text;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+/// Maximum difference tolerated between expected and actual values
+/// Fixtures only need to specify fractional metrics to one decimal place
+const TOLERANCE: f64 = 0.05;
+
+/// A set of expected per-function metrics
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Fixture {
+    #[serde(rename = "function", default)]
+    pub functions: Vec<ExpectedFunction>,
+}
+
+/// Expected metrics for one function
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExpectedFunction {
+    /// Function name, either bare (`foo`) or qualified with its file (`src/lib.rs::foo`)
+    pub name: String,
+    #[serde(flatten)]
+    pub metrics: BTreeMap<String, f64>,
+}
+
+/// A difference between a fixture and actual analysis results
+#[derive(Debug, Clone, PartialEq)]
+pub enum FixtureMismatch {
+    MissingFunction {
+        function: String,
+    },
+    UnknownMetric {
+        function: String,
+        metric: String,
+    },
+    MetricMismatch {
+        function: String,
+        metric: String,
+        expected: f64,
+        actual: f64,
+    },
+}
+
+impl fmt::Display for FixtureMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FixtureMismatch::MissingFunction { function } => {
+                write!(f, "Function '{function}' not found in analysis results")
+            }
+            FixtureMismatch::UnknownMetric { function, metric } => {
+                write!(f, "Unknown metric '{metric}' for function '{function}'")
+            }
+            FixtureMismatch::MetricMismatch {
+                function,
+                metric,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{metric} mismatch for function '{function}': expected {expected}, got {actual}"
+            ),
+        }
+    }
+}
+
+impl Fixture {
+    /// Parses a fixture from TOML source
+    pub fn from_toml_str(source: &str) -> AnalysisResult<Self> {
+        toml::from_str(source).map_err(|e| AnalysisError::InvalidFixture {
+            message: e.to_string(),
+        })
+    }
+
+    /// Loads a fixture file
+    pub fn load(path: &Path) -> AnalysisResult<Self> {
+        let source = read_text(path)?;
+        Self::from_toml_str(&source)
+    }
+
+    /// Compares the fixture against analysis results and returns every mismatch
+    pub fn compare(&self, results: &[FunctionAnalysisResult]) -> Vec<FixtureMismatch> {
+        let mut mismatches = Vec::new();
+
+        for expected in &self.functions {
+            let Some(actual) = find_result(results, &expected.name) else {
+                mismatches.push(FixtureMismatch::MissingFunction {
+                    function: expected.name.clone(),
+                });
+                continue;
+            };
+
+            let actual_metrics = actual.metrics();
+            for (metric, expected_value) in &expected.metrics {
+                match actual_metrics.iter().find(|(name, _)| name == metric) {
+                    None => mismatches.push(FixtureMismatch::UnknownMetric {
+                        function: expected.name.clone(),
+                        metric: metric.clone(),
+                    }),
+                    Some((_, actual_value)) => {
+                        if (expected_value - actual_value).abs() > TOLERANCE {
+                            mismatches.push(FixtureMismatch::MetricMismatch {
+                                function: expected.name.clone(),
+                                metric: metric.clone(),
+                                expected: *expected_value,
+                                actual: *actual_value,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        mismatches
+    }
+}
+
+/// Finds a result by bare or file-qualified function name
+fn find_result<'a>(
+    results: &'a [FunctionAnalysisResult],
+    name: &str,
+) -> Option<&'a FunctionAnalysisResult> {
+    results
+        .iter()
+        .find(|result| result.name == name || result.qualified_name() == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn sample_result() -> FunctionAnalysisResult {
+        FunctionAnalysisResult {
+            file: PathBuf::from("src/lib.rs"),
+            total: 5,
+            comment: 1,
+            empty: 1,
+            nesting_depth: 1,
+            stmt_count: 2,
+            expr_count: 4,
+            end_line: 5,
+            ..FunctionAnalysisResult::sample("sample", 3, 2)
+        }
+    }
+
+    #[test]
+    fn test_matching_fixture() {
+        let fixture = Fixture::from_toml_str(
+            "[[function]]\nname = \"sample\"\ntotal = 5\ncode = 3\ncomment_density = 25.0\n",
+        )
+        .unwrap();
+
+        assert!(fixture.compare(&[sample_result()]).is_empty());
+    }
+
+    #[test]
+    fn test_metric_mismatch() {
+        let fixture =
+            Fixture::from_toml_str("[[function]]\nname = \"sample\"\ncomplexity = 3\n").unwrap();
+
+        let mismatches = fixture.compare(&[sample_result()]);
+
+        assert_eq!(
+            mismatches,
+            vec![FixtureMismatch::MetricMismatch {
+                function: "sample".to_string(),
+                metric: "complexity".to_string(),
+                expected: 3.0,
+                actual: 2.0,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_missing_function_and_unknown_metric() {
+        let fixture = Fixture::from_toml_str(
+            "[[function]]\nname = \"absent\"\n\n[[function]]\nname = \"sample\"\nbogus = 1\n",
+        )
+        .unwrap();
+
+        let mismatches = fixture.compare(&[sample_result()]);
+
+        assert_eq!(mismatches.len(), 2);
+        assert!(matches!(
+            mismatches[0],
+            FixtureMismatch::MissingFunction { .. }
+        ));
+        assert!(matches!(
+            mismatches[1],
+            FixtureMismatch::UnknownMetric { .. }
+        ));
+    }
+}
@@ -0,0 +1,186 @@
+//! Files changed relative to a git base revision
+//!
+//! Used by `--changed` to restrict analysis to modified and added files, which
+//! keeps pre-merge checks fast and focused on the code under review, and by
+//! `fnloc hook` to check only the files staged for the next commit.
+
+use crate::errors::{AnalysisError, AnalysisResult};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Revision compared against when no base is given
+pub const DEFAULT_DIFF_BASE: &str = "HEAD";
+
+/// Returns the canonical paths of Rust files added or modified since `base`
+///
+/// Changes are taken from the merge base of `base` and `HEAD` up to the working
+/// tree, so staged, unstaged, and committed changes on the current branch are
+/// all included, as are untracked files that are not ignored. Deleted files
+/// are not reported.
+pub fn changed_rust_files(repo_dir: &Path, base: &str) -> AnalysisResult<HashSet<PathBuf>> {
+    let toplevel = PathBuf::from(git(repo_dir, &["rev-parse", "--show-toplevel"])?.trim());
+    let merge_base = git(repo_dir, &["merge-base", base, "HEAD"])?;
+
+    let changed = git(
+        repo_dir,
+        &[
+            "diff",
+            "--name-only",
+            "--diff-filter=ACMR",
+            merge_base.trim(),
+        ],
+    )?;
+    let untracked = git(
+        repo_dir,
+        &["ls-files", "--others", "--exclude-standard", "--full-name"],
+    )?;
+
+    Ok(changed
+        .lines()
+        .chain(untracked.lines())
+        .filter(|file| file.ends_with(".rs"))
+        .filter_map(|file| toplevel.join(file).canonicalize().ok())
+        .collect())
+}
+
+/// Returns the Rust files added or modified in the index, relative to `repo_dir`
+///
+/// Only files below `repo_dir` are listed. Their staged content can be read
+/// with [`INDEX_REVISION`](crate::git_revision::INDEX_REVISION).
+pub fn staged_rust_files(repo_dir: &Path) -> AnalysisResult<Vec<String>> {
+    let staged = git(
+        repo_dir,
+        &[
+            "diff",
+            "--cached",
+            "--name-only",
+            "--diff-filter=ACMR",
+            "--relative",
+        ],
+    )?;
+    Ok(staged
+        .lines()
+        .filter(|file| file.ends_with(".rs"))
+        .map(str::to_string)
+        .collect())
+}
+
+/// Keeps only the files contained in the changed set
+pub fn retain_changed(files: &mut Vec<String>, changed: &HashSet<PathBuf>) {
+    files.retain(|file| {
+        Path::new(file)
+            .canonicalize()
+            .is_ok_and(|canonical| changed.contains(&canonical))
+    });
+}
+
+/// Runs a git command in the given directory and returns its standard output
+pub(crate) fn git(dir: &Path, args: &[&str]) -> AnalysisResult<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|e| AnalysisError::Git {
+            message: format!("failed to run git: {e}"),
+        })?;
+
+    if !output.status.success() {
+        return Err(AnalysisError::Git {
+            message: format!(
+                "git {} failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        });
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn run_git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args([
+                "-c",
+                "user.name=fnloc",
+                "-c",
+                "user.email=fnloc@example.com",
+            ])
+            .args(args)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {args:?} failed");
+    }
+
+    #[test]
+    fn test_changed_rust_files() {
+        let repo = std::env::temp_dir().join(format!("fnloc_git_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&repo);
+        fs::create_dir_all(repo.join("src")).unwrap();
+        fs::write(repo.join("src/old.rs"), "fn old() {}\n").unwrap();
+        fs::write(repo.join("src/same.rs"), "fn same() {}\n").unwrap();
+        fs::write(repo.join("src/gone.rs"), "fn gone() {}\n").unwrap();
+        run_git(&repo, &["init", "-q"]);
+        run_git(&repo, &["add", "."]);
+        run_git(&repo, &["commit", "-q", "-m", "initial"]);
+
+        fs::write(repo.join("src/old.rs"), "fn old() { changed(); }\n").unwrap();
+        fs::write(repo.join("src/new.rs"), "fn new() {}\n").unwrap();
+        fs::write(repo.join("notes.txt"), "not rust\n").unwrap();
+        fs::remove_file(repo.join("src/gone.rs")).unwrap();
+
+        let changed = changed_rust_files(&repo, DEFAULT_DIFF_BASE).unwrap();
+        let mut names: Vec<String> = changed
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["new.rs", "old.rs"]);
+
+        let mut files = vec![
+            repo.join("src/old.rs").to_string_lossy().into_owned(),
+            repo.join("src/same.rs").to_string_lossy().into_owned(),
+        ];
+        retain_changed(&mut files, &changed);
+        assert_eq!(files.len(), 1);
+        assert!(files[0].ends_with("old.rs"));
+
+        fs::remove_dir_all(&repo).unwrap();
+    }
+
+    #[test]
+    fn test_staged_rust_files() {
+        let repo = std::env::temp_dir().join(format!("fnloc_staged_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&repo);
+        fs::create_dir_all(repo.join("src")).unwrap();
+        fs::write(repo.join("src/lib.rs"), "fn lib() {}\n").unwrap();
+        run_git(&repo, &["init", "-q"]);
+        run_git(&repo, &["add", "."]);
+        run_git(&repo, &["commit", "-q", "-m", "initial"]);
+
+        fs::write(repo.join("src/lib.rs"), "fn lib() { staged(); }\n").unwrap();
+        fs::write(repo.join("src/unstaged.rs"), "fn unstaged() {}\n").unwrap();
+        fs::write(repo.join("notes.txt"), "not rust\n").unwrap();
+        run_git(&repo, &["add", "src/lib.rs", "notes.txt"]);
+
+        assert_eq!(staged_rust_files(&repo).unwrap(), ["src/lib.rs"]);
+        assert_eq!(staged_rust_files(&repo.join("src")).unwrap(), ["lib.rs"]);
+
+        fs::remove_dir_all(&repo).unwrap();
+    }
+
+    #[test]
+    fn test_unknown_base_is_reported() {
+        let result = changed_rust_files(Path::new("."), "no-such-revision-for-fnloc");
+        assert!(matches!(result, Err(AnalysisError::Git { .. })));
+    }
+}
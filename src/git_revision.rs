@@ -0,0 +1,128 @@
+//! Reading the tree of a git revision without checking it out
+//!
+//! Used by `--at <ref>` and `fnloc diff --at <ref>` to analyze another
+//! revision, e.g. the target branch in CI, without a second worktree. Only
+//! tracked `.rs` files are listed; scan options such as `--exclude` apply to
+//! the working tree only.
+
+use crate::errors::{AnalysisError, AnalysisResult};
+use crate::git_changes::git;
+use crate::source::{SourceText, decode_source};
+use std::path::Path;
+use std::process::Command;
+
+/// Revision naming the index, so [`read_file_at`] reads the staged content of a file
+pub const INDEX_REVISION: &str = "";
+
+/// Lists the Rust files of a revision below the given paths, relative to `dir`
+///
+/// Paths keep the form they were given in, so `./src` lists `./src/lib.rs` as a
+/// working-tree scan would, and function names match between the two.
+pub fn rust_files_at(dir: &Path, revision: &str, paths: &[String]) -> AnalysisResult<Vec<String>> {
+    Ok(rust_blobs_at(dir, revision, paths)?
+        .into_iter()
+        .map(|(file, _)| file)
+        .collect())
+}
+
+/// Lists the Rust files of a revision like [`rust_files_at`], each with its blob id
+///
+/// Files with the same blob id have the same content, so analysis results can
+/// be reused across revisions.
+pub fn rust_blobs_at(
+    dir: &Path,
+    revision: &str,
+    paths: &[String],
+) -> AnalysisResult<Vec<(String, String)>> {
+    let mut files = Vec::new();
+    for path in paths {
+        let listed = git(dir, &["ls-tree", "-r", revision, "--", path])?;
+        let prefix = if path.starts_with("./") { "./" } else { "" };
+        // Each line is `<mode> <type> <blob>\t<path>`
+        files.extend(listed.lines().filter_map(|line| {
+            let (object, file) = line.split_once('\t')?;
+            let blob = object.rsplit(' ').next()?;
+            file.ends_with(".rs")
+                .then(|| (format!("{prefix}{file}"), blob.to_string()))
+        }));
+    }
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+/// Reads a file as of a revision, decoded like a file on disk
+pub fn read_file_at(dir: &Path, revision: &str, path: &str) -> AnalysisResult<SourceText> {
+    let relative = path.strip_prefix("./").unwrap_or(path);
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["show", &format!("{revision}:./{relative}")])
+        .output()
+        .map_err(|e| AnalysisError::Git {
+            message: format!("failed to run git: {e}"),
+        })?;
+    if !output.status.success() {
+        return Err(AnalysisError::Git {
+            message: format!(
+                "cannot read {path} at {revision}: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        });
+    }
+    Ok(decode_source(output.stdout))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn run_git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args([
+                "-c",
+                "user.name=fnloc",
+                "-c",
+                "user.email=fnloc@example.com",
+            ])
+            .args(args)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {args:?} failed");
+    }
+
+    #[test]
+    fn test_reads_files_at_revision() {
+        let repo = std::env::temp_dir().join(format!("fnloc_revision_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&repo);
+        fs::create_dir_all(repo.join("src")).unwrap();
+        fs::write(repo.join("src/lib.rs"), "fn old() {}\n").unwrap();
+        fs::write(repo.join("src/notes.txt"), "not rust\n").unwrap();
+        run_git(&repo, &["init", "-q"]);
+        run_git(&repo, &["add", "."]);
+        run_git(&repo, &["commit", "-q", "-m", "initial"]);
+
+        fs::write(repo.join("src/lib.rs"), "fn new() {}\n").unwrap();
+        fs::write(repo.join("src/added.rs"), "fn added() {}\n").unwrap();
+
+        let files = rust_files_at(&repo, "HEAD", &["./src".to_string()]).unwrap();
+        assert_eq!(files, ["./src/lib.rs"]);
+        let source = read_file_at(&repo, "HEAD", &files[0]).unwrap();
+        assert_eq!(source.text, "fn old() {}\n");
+        assert!(matches!(
+            read_file_at(&repo, "HEAD", "src/added.rs"),
+            Err(AnalysisError::Git { .. })
+        ));
+
+        run_git(&repo, &["add", "src/added.rs"]);
+        fs::write(repo.join("src/added.rs"), "fn unstaged() {}\n").unwrap();
+        let staged = read_file_at(&repo, INDEX_REVISION, "src/added.rs").unwrap();
+        assert_eq!(staged.text, "fn added() {}\n");
+
+        fs::remove_dir_all(&repo).unwrap();
+    }
+}
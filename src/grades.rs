@@ -0,0 +1,147 @@
+//! Letter grades per function
+//!
+//! Every function gets a grade from A to F for its complexity, size, and
+//! nesting; the worst of the three is the function's grade. The boundaries are
+//! configured in the `[grades]` table of `fnloc.toml`, and `--fail-on grade=D`
+//! fails a run on any function graded D or worse.
+
+use crate::analyzer::FunctionAnalysisResult;
+use crate::config::Violation;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Letter grade, from best to worst
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Grade {
+    A,
+    B,
+    C,
+    D,
+    F,
+}
+
+impl Grade {
+    /// All grades, from best to worst
+    pub const ALL: [Grade; 5] = [Grade::A, Grade::B, Grade::C, Grade::D, Grade::F];
+
+    /// Returns the position of the grade, 0 for A up to 4 for F
+    pub fn rank(self) -> usize {
+        self as usize
+    }
+
+    /// Returns the grade at a position, F for anything beyond it
+    pub fn from_rank(rank: usize) -> Self {
+        Self::ALL.get(rank).copied().unwrap_or(Grade::F)
+    }
+}
+
+impl fmt::Display for Grade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl FromStr for Grade {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|grade| grade.to_string().eq_ignore_ascii_case(value.trim()))
+            .ok_or_else(|| format!("grade must be one of A, B, C, D, or F, got '{value}'"))
+    }
+}
+
+/// Highest value of each metric that still earns grades A, B, C, and D; anything higher is F
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GradeBoundaries {
+    pub complexity: [usize; 4],
+    pub code: [usize; 4],
+    pub nesting: [usize; 4],
+}
+
+impl Default for GradeBoundaries {
+    fn default() -> Self {
+        Self {
+            complexity: [5, 10, 20, 30],
+            code: [20, 50, 100, 200],
+            nesting: [2, 3, 4, 6],
+        }
+    }
+}
+
+impl GradeBoundaries {
+    /// Grades a function by its worst metric
+    pub fn grade(&self, result: &FunctionAnalysisResult) -> Grade {
+        [
+            metric_grade(result.cyclomatic_complexity, &self.complexity),
+            metric_grade(result.code, &self.code),
+            metric_grade(result.nesting_depth, &self.nesting),
+        ]
+        .into_iter()
+        .max()
+        .unwrap_or(Grade::A)
+    }
+
+    /// Returns a violation if the function is graded `fail_on` or worse
+    ///
+    /// The violation reports grade ranks as its value and limit, see [`Grade::rank`].
+    pub fn violation(&self, result: &FunctionAnalysisResult, fail_on: Grade) -> Option<Violation> {
+        let grade = self.grade(result);
+        (grade >= fail_on).then(|| Violation {
+            function: result.qualified_name(),
+            metric: "grade",
+            value: grade.rank(),
+            limit: fail_on.rank(),
+        })
+    }
+}
+
+/// Grades one metric against its boundaries
+fn metric_grade(value: usize, boundaries: &[usize; 4]) -> Grade {
+    let rank = boundaries
+        .iter()
+        .position(|&boundary| value <= boundary)
+        .unwrap_or(boundaries.len());
+    Grade::from_rank(rank)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_result(code: usize, complexity: usize, nesting: usize) -> FunctionAnalysisResult {
+        FunctionAnalysisResult {
+            nesting_depth: nesting,
+            ..FunctionAnalysisResult::sample("f", code, complexity)
+        }
+    }
+
+    #[test]
+    fn test_worst_metric_decides_the_grade() {
+        let boundaries = GradeBoundaries::default();
+
+        assert_eq!(boundaries.grade(&sample_result(10, 2, 1)), Grade::A);
+        assert_eq!(boundaries.grade(&sample_result(10, 12, 1)), Grade::C);
+        assert_eq!(boundaries.grade(&sample_result(500, 2, 1)), Grade::F);
+    }
+
+    #[test]
+    fn test_fail_on_grade_includes_worse_grades() {
+        let boundaries = GradeBoundaries::default();
+
+        let violation = boundaries
+            .violation(&sample_result(10, 25, 1), Grade::D)
+            .unwrap();
+        assert_eq!(violation.to_string(), "fn f: grade=D fails limit grade=D");
+        assert!(
+            boundaries
+                .violation(&sample_result(10, 25, 1), Grade::F)
+                .is_none()
+        );
+        assert_eq!("d".parse::<Grade>(), Ok(Grade::D));
+        assert!("E".parse::<Grade>().is_err());
+    }
+}
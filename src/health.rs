@@ -0,0 +1,218 @@
+//! Aggregate code-health score
+//!
+//! `--health` condenses an analysis into one number from 0 to 100 that teams
+//! can track over time, together with a grade per source file. The score is a
+//! weighted mean of three sub-scores:
+//!
+//! - average cyclomatic complexity: 100 at 1, minus 10 per additional point
+//! - long functions: the percentage of functions within `long_function_lines` code lines
+//! - comment density: 5 points per percent, full marks from 20%
+//!
+//! The weights are configured in the `[health]` table of `fnloc.toml`.
+
+use crate::analyzer::FunctionAnalysisResult;
+use serde::{Deserialize, Serialize};
+
+/// Weights of the sub-scores and the size above which a function counts as long
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HealthWeights {
+    pub complexity: f64,
+    pub long_functions: f64,
+    pub comment_density: f64,
+    /// Functions with more code lines than this are long
+    pub long_function_lines: usize,
+}
+
+impl Default for HealthWeights {
+    fn default() -> Self {
+        Self {
+            complexity: 0.4,
+            long_functions: 0.4,
+            comment_density: 0.2,
+            long_function_lines: 50,
+        }
+    }
+}
+
+/// Score of a set of functions with the sub-scores it is made of
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct HealthScore {
+    #[serde(serialize_with = "round_to_tenth")]
+    pub score: f64,
+    pub grade: char,
+    #[serde(serialize_with = "round_to_tenth")]
+    pub average_complexity: f64,
+    /// Percentage of functions longer than the configured limit
+    #[serde(serialize_with = "round_to_tenth")]
+    pub long_function_percent: f64,
+    #[serde(serialize_with = "round_to_tenth")]
+    pub comment_density: f64,
+}
+
+/// Score of one source file
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FileHealth {
+    pub file: String,
+    pub functions: usize,
+    #[serde(flatten)]
+    pub health: HealthScore,
+}
+
+/// Project score plus a score per file, worst file first
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HealthReport {
+    #[serde(flatten)]
+    pub project: HealthScore,
+    pub files: Vec<FileHealth>,
+}
+
+impl HealthReport {
+    /// Scores all functions together and grouped by their source file
+    pub fn new(results: &[FunctionAnalysisResult], weights: &HealthWeights) -> Self {
+        let mut by_file: Vec<(String, Vec<&FunctionAnalysisResult>)> = Vec::new();
+        for result in results {
+            let file = result.source_file().unwrap_or("(unknown file)");
+            match by_file.iter_mut().find(|(name, _)| name == file) {
+                Some((_, functions)) => functions.push(result),
+                None => by_file.push((file.to_string(), vec![result])),
+            }
+        }
+
+        let mut files: Vec<FileHealth> = by_file
+            .into_iter()
+            .map(|(file, functions)| FileHealth {
+                file,
+                functions: functions.len(),
+                health: HealthScore::new(&functions, weights),
+            })
+            .collect();
+        files.sort_by(|a, b| a.health.score.total_cmp(&b.health.score));
+
+        Self {
+            project: HealthScore::new(&results.iter().collect::<Vec<_>>(), weights),
+            files,
+        }
+    }
+}
+
+impl HealthScore {
+    /// Scores a set of functions; without functions the score is 100
+    pub fn new(results: &[&FunctionAnalysisResult], weights: &HealthWeights) -> Self {
+        let count = results.len().max(1) as f64;
+        let complexity: usize = results.iter().map(|r| r.cyclomatic_complexity).sum();
+        let average_complexity = if results.is_empty() {
+            1.0
+        } else {
+            complexity as f64 / count
+        };
+        let long = results
+            .iter()
+            .filter(|r| r.code > weights.long_function_lines)
+            .count();
+        let long_function_percent = long as f64 * 100.0 / count;
+        let code: usize = results.iter().map(|r| r.code).sum();
+        let comment: usize = results.iter().map(|r| r.comment).sum();
+        let comment_density = if code + comment == 0 {
+            0.0
+        } else {
+            comment as f64 * 100.0 / (code + comment) as f64
+        };
+
+        let sub_scores = [
+            (
+                weights.complexity,
+                100.0 - (average_complexity - 1.0) * 10.0,
+            ),
+            (weights.long_functions, 100.0 - long_function_percent),
+            (weights.comment_density, comment_density * 5.0),
+        ];
+        let total_weight: f64 = sub_scores.iter().map(|(weight, _)| weight).sum();
+        let score = if results.is_empty() || total_weight <= 0.0 {
+            100.0
+        } else {
+            sub_scores
+                .iter()
+                .map(|(weight, score)| weight * score.clamp(0.0, 100.0))
+                .sum::<f64>()
+                / total_weight
+        };
+
+        Self {
+            score,
+            grade: grade(score),
+            average_complexity,
+            long_function_percent,
+            comment_density,
+        }
+    }
+}
+
+/// Maps a score to a school grade: A from 90, B from 80, C from 70, D from 60, F below
+fn grade(score: f64) -> char {
+    match score {
+        s if s >= 90.0 => 'A',
+        s if s >= 80.0 => 'B',
+        s if s >= 70.0 => 'C',
+        s if s >= 60.0 => 'D',
+        _ => 'F',
+    }
+}
+
+/// Serializes a float rounded to one decimal place
+fn round_to_tenth<S: serde::Serializer>(value: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64((value * 10.0).round() / 10.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn sample_result(
+        name: &str,
+        code: usize,
+        comment: usize,
+        complexity: usize,
+    ) -> FunctionAnalysisResult {
+        let (file, name) = name.rsplit_once("::").unwrap_or(("", name));
+        FunctionAnalysisResult {
+            file: PathBuf::from(file),
+            total: code + comment,
+            comment,
+            nesting_depth: 1,
+            end_line: code + comment,
+            ..FunctionAnalysisResult::sample(name, code, complexity)
+        }
+    }
+
+    #[test]
+    fn test_weighted_score() {
+        let results = [
+            sample_result("src/a.rs::small", 8, 2, 1),
+            sample_result("src/b.rs::huge", 60, 0, 5),
+        ];
+        let report = HealthReport::new(&results, &HealthWeights::default());
+
+        // complexity 3 -> 80, half the functions long -> 50, density 2/70 -> 14.3
+        assert!((report.project.score - 54.86).abs() < 0.01);
+        assert_eq!(report.project.grade, 'F');
+        assert_eq!(report.files[0].file, "src/b.rs");
+        assert_eq!(report.files[1].health.grade, 'A');
+    }
+
+    #[test]
+    fn test_weights_select_sub_scores() {
+        let weights = HealthWeights {
+            complexity: 1.0,
+            long_functions: 0.0,
+            comment_density: 0.0,
+            ..HealthWeights::default()
+        };
+        let score = HealthScore::new(&[&sample_result("f", 100, 0, 3)], &weights);
+
+        assert_eq!(score.score, 80.0);
+        assert_eq!(score.grade, 'B');
+        assert_eq!(HealthScore::new(&[], &weights).score, 100.0);
+    }
+}
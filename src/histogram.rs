@@ -0,0 +1,136 @@
+//! Distribution of a metric over all functions
+//!
+//! `--histogram <metric>` shows the shape of a codebase rather than only its
+//! worst offenders. Counts such as lines or complexity are bucketed by powers
+//! of two (0, 1, 2–3, 4–7, …), which keeps the long tail readable; comment
+//! density is bucketed in steps of 10 percentage points.
+
+use crate::analyzer::FunctionAnalysisResult;
+use serde::Serialize;
+
+/// Width in characters of the longest bar
+const BAR_WIDTH: usize = 40;
+
+/// Number of functions whose metric lies in an inclusive range
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Bucket {
+    pub min: u64,
+    pub max: u64,
+    pub count: usize,
+}
+
+/// Distribution of one metric, as consecutive buckets from the lowest to the highest value
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Histogram {
+    pub metric: String,
+    pub buckets: Vec<Bucket>,
+}
+
+impl Histogram {
+    /// Buckets the given metric of every function; fractional values are rounded down
+    pub fn new(results: &[FunctionAnalysisResult], metric: &str) -> Self {
+        let linear = metric == "comment_density";
+        let mut counts: Vec<usize> = Vec::new();
+        for value in results.iter().filter_map(|result| result.metric(metric)) {
+            let index = bucket_index(value as u64, linear);
+            if counts.len() <= index {
+                counts.resize(index + 1, 0);
+            }
+            counts[index] += 1;
+        }
+
+        let first = counts.iter().position(|&count| count > 0).unwrap_or(0);
+        let buckets = counts
+            .iter()
+            .enumerate()
+            .skip(first)
+            .map(|(index, &count)| {
+                let (min, max) = bucket_range(index, linear);
+                Bucket { min, max, count }
+            })
+            .collect();
+
+        Self {
+            metric: metric.to_string(),
+            buckets,
+        }
+    }
+
+    /// Renders the histogram as one labeled bar per bucket
+    pub fn to_ascii(&self) -> String {
+        let functions: usize = self.buckets.iter().map(|bucket| bucket.count).sum();
+        let largest = self.buckets.iter().map(|bucket| bucket.count).max();
+        let mut lines = vec![format!(
+            "Histogram of {} ({functions} functions):",
+            self.metric
+        )];
+        for bucket in &self.buckets {
+            let range = if bucket.min == bucket.max {
+                bucket.min.to_string()
+            } else {
+                format!("{}-{}", bucket.min, bucket.max)
+            };
+            let bar = "#".repeat((bucket.count * BAR_WIDTH).div_ceil(largest.unwrap_or(1)));
+            lines.push(format!("  {range:>11} | {bar} {}", bucket.count));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Returns the bucket of a value: powers of two, or steps of 10 for percentages
+fn bucket_index(value: u64, linear: bool) -> usize {
+    if linear {
+        (value.min(100) / 10).min(9) as usize
+    } else {
+        (u64::BITS - value.leading_zeros()) as usize
+    }
+}
+
+/// Returns the inclusive value range of a bucket
+fn bucket_range(index: usize, linear: bool) -> (u64, u64) {
+    match (linear, index) {
+        (true, 9) => (90, 100),
+        (true, _) => (index as u64 * 10, index as u64 * 10 + 9),
+        (false, 0) => (0, 0),
+        (false, _) => (1 << (index - 1), (1 << index) - 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_result(complexity: usize) -> FunctionAnalysisResult {
+        FunctionAnalysisResult::sample("f", 3, complexity)
+    }
+
+    #[test]
+    fn test_power_of_two_buckets() {
+        let results: Vec<_> = [1, 2, 3, 9, 12].into_iter().map(sample_result).collect();
+        let histogram = Histogram::new(&results, "complexity");
+
+        let ranges: Vec<_> = histogram
+            .buckets
+            .iter()
+            .map(|bucket| (bucket.min, bucket.max, bucket.count))
+            .collect();
+        assert_eq!(ranges, [(1, 1, 1), (2, 3, 2), (4, 7, 0), (8, 15, 2)]);
+    }
+
+    #[test]
+    fn test_percentage_buckets_and_ascii() {
+        let histogram = Histogram::new(&[sample_result(1)], "comment_density");
+        assert_eq!(
+            histogram.buckets,
+            [Bucket {
+                min: 0,
+                max: 9,
+                count: 1
+            }]
+        );
+
+        let ascii = histogram.to_ascii();
+        assert!(ascii.starts_with("Histogram of comment_density (1 functions):"));
+        assert!(ascii.ends_with(&format!("0-9 | {} 1", "#".repeat(BAR_WIDTH))));
+    }
+}
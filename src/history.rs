@@ -0,0 +1,153 @@
+//! Metric trends over git history
+//!
+//! `fnloc history` analyzes a series of commits and reports one point per
+//! commit — code lines, average complexity, and the worst function — as CSV
+//! or JSON for trend charts. Files are read from git without checking the
+//! commits out, and a file is only analyzed again when its content changed.
+
+use crate::analyzer::FunctionAnalysisResult;
+use crate::errors::AnalysisResult;
+use crate::git_changes::git;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// A commit of the analyzed history
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Commit {
+    pub hash: String,
+    /// Committer date in ISO 8601 format
+    pub date: String,
+}
+
+/// Metrics of one commit
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HistoryPoint {
+    pub commit: String,
+    pub date: String,
+    pub files: usize,
+    pub functions: usize,
+    /// Code lines over all functions
+    pub code: usize,
+    pub average_complexity: f64,
+    /// Name of the most complex function
+    pub worst_function: Option<String>,
+    pub worst_complexity: usize,
+    pub worst_code: usize,
+}
+
+impl HistoryPoint {
+    /// Summarizes the functions of a commit, found in `files` files
+    pub fn new(commit: &Commit, files: usize, results: &[FunctionAnalysisResult]) -> Self {
+        let complexity: usize = results.iter().map(|r| r.cyclomatic_complexity).sum();
+        let average = complexity as f64 / results.len().max(1) as f64;
+        let worst = results.iter().max_by_key(|r| r.cyclomatic_complexity);
+        Self {
+            commit: commit.hash.clone(),
+            date: commit.date.clone(),
+            files,
+            functions: results.len(),
+            code: results.iter().map(|r| r.code).sum(),
+            average_complexity: (average * 100.0).round() / 100.0,
+            worst_function: worst.map(FunctionAnalysisResult::qualified_name),
+            worst_complexity: worst.map_or(0, |r| r.cyclomatic_complexity),
+            worst_code: worst.map_or(0, |r| r.code),
+        }
+    }
+}
+
+/// Analysis results of file contents, keyed by git blob id
+///
+/// Unchanged files keep their blob id from commit to commit, so each version
+/// of a file is analyzed once however many commits contain it.
+#[derive(Debug, Default)]
+pub struct BlobCache {
+    results: HashMap<String, Vec<FunctionAnalysisResult>>,
+}
+
+impl BlobCache {
+    /// Returns the results for a blob, analyzing it with `analyze` on first use
+    pub fn get_or_analyze(
+        &mut self,
+        blob: &str,
+        analyze: impl FnOnce() -> Vec<FunctionAnalysisResult>,
+    ) -> &[FunctionAnalysisResult] {
+        self.results.entry(blob.to_string()).or_insert_with(analyze)
+    }
+}
+
+/// Lists the first-parent commits of `HEAD`, oldest first
+///
+/// Only commits after `since` (any date git understands) are listed, and only
+/// every `step`-th one counting back from `HEAD`, which is always included.
+pub fn commits(dir: &Path, since: Option<&str>, step: usize) -> AnalysisResult<Vec<Commit>> {
+    let since = since.map(|since| format!("--since={since}"));
+    let mut args = vec!["log", "--first-parent", "--format=%H %cI"];
+    args.extend(since.as_deref());
+    args.push("HEAD");
+
+    let log = git(dir, &args)?;
+    let mut commits: Vec<Commit> = log
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .step_by(step.max(1))
+        .map(|(hash, date)| Commit {
+            hash: hash.to_string(),
+            date: date.to_string(),
+        })
+        .collect();
+    commits.reverse();
+    Ok(commits)
+}
+
+/// Writes history points as CSV with a header line
+pub fn to_csv(points: &[HistoryPoint], delimiter: u8) -> String {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(Vec::new());
+    for point in points {
+        // Points only contain strings and numbers, so serialization cannot fail
+        let _ = writer.serialize(point);
+    }
+    let bytes = writer.into_inner().unwrap_or_default();
+    String::from_utf8_lossy(&bytes).trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_result(name: &str, code: usize, complexity: usize) -> FunctionAnalysisResult {
+        FunctionAnalysisResult {
+            nesting_depth: 1,
+            ..FunctionAnalysisResult::sample(name, code, complexity)
+        }
+    }
+
+    #[test]
+    fn test_history_point_and_csv() {
+        let commit = Commit {
+            hash: "abc".to_string(),
+            date: "2026-01-02T03:04:05+00:00".to_string(),
+        };
+        let results = [sample_result("a", 10, 2), sample_result("b", 30, 7)];
+        let point = HistoryPoint::new(&commit, 1, &results);
+
+        assert_eq!(point.code, 40);
+        assert_eq!(point.average_complexity, 4.5);
+        assert_eq!(point.worst_function.as_deref(), Some("b"));
+        assert_eq!(
+            to_csv(&[point], b','),
+            "commit,date,files,functions,code,average_complexity,worst_function,worst_complexity,worst_code\n\
+             abc,2026-01-02T03:04:05+00:00,1,2,40,4.5,b,7,30"
+        );
+    }
+
+    #[test]
+    fn test_blob_cache_analyzes_once() {
+        let mut cache = BlobCache::default();
+        cache.get_or_analyze("blob", || vec![sample_result("a", 1, 1)]);
+        let cached = cache.get_or_analyze("blob", || unreachable!("analyzed twice"));
+        assert_eq!(cached.len(), 1);
+    }
+}
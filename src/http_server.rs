@@ -0,0 +1,392 @@
+//! HTTP API for `fnloc serve`
+//!
+//! A long-lived process answers metric queries over plain HTTP/1.1, so
+//! dashboards and bots do not pay for a fresh scan on every question:
+//!
+//! - `GET /report` analyzes the configured paths and returns the functions as
+//!   the JSON array of `--format json`. Unchanged files are served from a
+//!   [`WarmCache`], and the rows of unchanged functions from a [`RowCache`].
+//! - `POST /analyze` analyzes the Rust source in the request body and returns
+//!   its functions the same way.
+//!
+//! Failures are answered with the JSON error object of `--format json`.
+//! Requests are handled one at a time, and every response closes the
+//! connection. A client that stalls for [`IO_TIMEOUT`] is disconnected, so it
+//! cannot hold up the clients behind it, and a body larger than the file size
+//! limit is rejected from its `Content-Length` before it is read.
+
+use crate::analyzer::{self, AnalysisOptions};
+use crate::client::OutputFormat;
+use crate::diagnostics::Diagnostic;
+use crate::errors::{AnalysisError, AnalysisResult};
+use crate::output::{Format, FormatOptions, JsonFormat, OutputFormatter, RowCache};
+use crate::report::AnalysisReport;
+use crate::source;
+use crate::warm_cache::WarmCache;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+/// Port used when `--port` is not given
+pub const DEFAULT_PORT: u16 = 7878;
+
+/// Maximum size of the request line and headers together
+pub const MAX_HEADER_BYTES: u64 = 16 * 1024;
+
+/// Time a client may take to send the next part of its request or accept the response
+pub const IO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// An HTTP request, reduced to what the API needs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    pub method: String,
+    /// Request target without the query string
+    pub path: String,
+    pub body: Vec<u8>,
+}
+
+/// A JSON response
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub status: u16,
+    pub body: String,
+}
+
+impl Response {
+    fn ok(body: String) -> Self {
+        Self { status: 200, body }
+    }
+
+    fn error(status: u16, error: &AnalysisError) -> Self {
+        Self {
+            status,
+            body: error.to_json(),
+        }
+    }
+
+    /// Returns an error response for a request the API cannot route
+    fn unroutable(status: u16, message: &str) -> Self {
+        Self {
+            status,
+            body: serde_json::json!({ "error": { "message": message } }).to_string(),
+        }
+    }
+}
+
+/// Answers API requests, keeping the results of unchanged files between requests
+pub struct ReportService<F> {
+    options: AnalysisOptions,
+    /// Lists the files of `GET /report`; called on every request, so new and
+    /// deleted files are picked up
+    list_files: F,
+    cache: WarmCache,
+    /// Formats the rows of the JSON array
+    formatter: OutputFormatter,
+    /// Rows of `GET /report`, so only the rows of changed functions are formatted again
+    rows: RowCache,
+}
+
+impl<F: FnMut() -> AnalysisResult<Vec<String>>> ReportService<F> {
+    pub fn new(options: AnalysisOptions, list_files: F) -> Self {
+        Self {
+            options,
+            list_files,
+            cache: WarmCache::default(),
+            formatter: OutputFormatter::with_format(OutputFormat::Json).with_writer(io::sink()),
+            rows: RowCache::new(),
+        }
+    }
+
+    /// Routes a request to its endpoint
+    pub fn handle(&mut self, request: &Request) -> Response {
+        match (request.method.as_str(), request.path.as_str()) {
+            ("GET", "/report") => self.report(),
+            ("POST", "/analyze") => self.analyze(&request.body),
+            (_, "/report" | "/analyze") => Response::unroutable(405, "Method not allowed"),
+            _ => Response::unroutable(404, "Not found"),
+        }
+    }
+
+    fn report(&mut self) -> Response {
+        let files = match (self.list_files)() {
+            Ok(files) => files,
+            Err(e) => return Response::error(500, &e),
+        };
+        let (functions, diagnostics) = self.cache.analyze(&files, &self.options);
+        let rows = self.rows.render_rows(&self.formatter, &functions);
+        json_response(rows, diagnostics)
+    }
+
+    fn analyze(&self, body: &[u8]) -> Response {
+        if let Err(e) = source::check_size(
+            "request body",
+            body.len() as u64,
+            self.options.max_file_size,
+        ) {
+            return Response::error(413, &e);
+        }
+        let source = source::decode_source(body.to_vec());
+        match analyzer::analyze_source(&source.text, &self.options) {
+            Ok(functions) => {
+                let rows = functions
+                    .iter()
+                    .map(|function| self.formatter.format_function_row(function))
+                    .collect();
+                json_response(rows, Vec::new())
+            }
+            Err(e) => Response::error(422, &e),
+        }
+    }
+}
+
+/// Joins the rows of functions and of the files that failed into the JSON array
+/// of `--format json`
+fn json_response(mut rows: Vec<String>, diagnostics: Vec<Diagnostic>) -> Response {
+    let report = AnalysisReport {
+        diagnostics,
+        ..AnalysisReport::default()
+    };
+    rows.extend(
+        report
+            .file_errors()
+            .iter()
+            .filter_map(|error| JsonFormat.error_row(error, &FormatOptions::default())),
+    );
+    Response::ok(JsonFormat::join_rows(&rows))
+}
+
+/// Accepts connections until the listener fails, answering each request with `handle`
+///
+/// Request bodies larger than `max_body` bytes are answered with 413 without
+/// being read; unlimited if None.
+pub fn serve(
+    listener: &TcpListener,
+    max_body: Option<u64>,
+    mut handle: impl FnMut(&Request) -> Response,
+) -> io::Result<()> {
+    for stream in listener.incoming() {
+        let mut stream = stream?;
+        // A broken or stalled connection only affects its own client
+        let _ = respond(&mut stream, max_body, &mut handle);
+    }
+    Ok(())
+}
+
+fn respond(
+    stream: &mut TcpStream,
+    max_body: Option<u64>,
+    handle: &mut impl FnMut(&Request) -> Response,
+) -> io::Result<()> {
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    let response = match read_request(&mut BufReader::new(&*stream), max_body)? {
+        Ok(request) => handle(&request),
+        Err(rejection) => rejection,
+    };
+    write_response(stream, &response)
+}
+
+/// Reads a request, or returns the response rejecting it
+///
+/// Requests that are not valid HTTP are rejected with 400, requests whose line
+/// and headers exceed [`MAX_HEADER_BYTES`] with 431, and requests announcing a
+/// body larger than `max_body` bytes with 413, before the body is read.
+pub fn read_request(
+    input: &mut impl BufRead,
+    max_body: Option<u64>,
+) -> io::Result<Result<Request, Response>> {
+    let malformed = || Ok(Err(Response::unroutable(400, "Malformed request")));
+    let too_large = || Ok(Err(Response::unroutable(431, "Request headers too large")));
+    // A line cut off by the limit rather than by a newline means the head is too large
+    let mut head = (&mut *input).take(MAX_HEADER_BYTES);
+    let mut line = String::new();
+    head.read_line(&mut line)?;
+    if head.limit() == 0 && !line.ends_with('\n') {
+        return too_large();
+    }
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return malformed();
+    };
+    let path = target.split('?').next().unwrap_or(target).to_string();
+    let method = method.to_string();
+
+    let mut length: u64 = 0;
+    loop {
+        let mut header = String::new();
+        if head.read_line(&mut header)? == 0 {
+            if head.limit() == 0 {
+                return too_large();
+            }
+            return malformed();
+        }
+        if head.limit() == 0 && !header.ends_with('\n') {
+            return too_large();
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':')
+            && name.eq_ignore_ascii_case("Content-Length")
+        {
+            let Ok(value) = value.trim().parse() else {
+                return malformed();
+            };
+            length = value;
+        }
+    }
+    if let Err(e) = source::check_size("request body", length, max_body) {
+        return Ok(Err(Response::error(413, &e)));
+    }
+
+    // The body grows with the bytes received rather than the announced length
+    let mut body = Vec::new();
+    input.take(length).read_to_end(&mut body)?;
+    if body.len() as u64 != length {
+        return malformed();
+    }
+    Ok(Ok(Request { method, path, body }))
+}
+
+/// Writes a JSON response and marks the connection as closed
+pub fn write_response(output: &mut impl Write, response: &Response) -> io::Result<()> {
+    write!(
+        output,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        reason(response.status),
+        response.body.len(),
+        response.body
+    )?;
+    output.flush()
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        431 => "Request Header Fields Too Large",
+        422 => "Unprocessable Entity",
+        _ => "Internal Server Error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::Formatter;
+
+    fn request(method: &str, path: &str, body: &str) -> Request {
+        Request {
+            method: method.to_string(),
+            path: path.to_string(),
+            body: body.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_read_request_with_body() {
+        let raw =
+            "POST /analyze?pretty HTTP/1.1\r\nHost: x\r\ncontent-length: 11\r\n\r\nfn main(){}";
+        let request = read_request(&mut raw.as_bytes(), None).unwrap().unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/analyze");
+        assert_eq!(request.body, b"fn main(){}");
+
+        let malformed = read_request(&mut "\r\n".as_bytes(), None).unwrap();
+        assert_eq!(malformed.unwrap_err().status, 400);
+    }
+
+    #[test]
+    fn test_oversized_body_is_rejected_before_it_is_read() {
+        // The announced body is never sent; reading it would fail
+        let raw = "POST /analyze HTTP/1.1\r\nContent-Length: 4096\r\n\r\n";
+
+        let rejection = read_request(&mut raw.as_bytes(), Some(1024))
+            .unwrap()
+            .unwrap_err();
+
+        assert_eq!(rejection.status, 413);
+        assert!(rejection.body.contains("\"kind\":\"file_too_large\""));
+    }
+
+    #[test]
+    fn test_oversized_headers_are_rejected() {
+        let long_line = format!(
+            "GET /{} HTTP/1.1\r\n\r\n",
+            "a".repeat(MAX_HEADER_BYTES as usize)
+        );
+        let rejection = read_request(&mut long_line.as_bytes(), None)
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(rejection.status, 431);
+
+        let many_headers = format!("GET / HTTP/1.1\r\n{}\r\n", "X-A: b\r\n".repeat(4096));
+        let rejection = read_request(&mut many_headers.as_bytes(), None)
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(rejection.status, 431);
+    }
+
+    #[test]
+    fn test_report_is_the_json_array_of_the_files() {
+        let dir = std::env::temp_dir().join(format!("fnloc_http_report_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let lib = dir.join("lib.rs");
+        let broken = dir.join("broken.rs");
+        std::fs::write(&lib, "fn a() {}\nfn b() {\n    a();\n}\n").unwrap();
+        std::fs::write(&broken, "fn c( {\n").unwrap();
+        let files: Vec<String> = [&lib, &broken]
+            .iter()
+            .map(|path| path.to_string_lossy().to_string())
+            .collect();
+        let listed = files.clone();
+        let mut service = ReportService::new(AnalysisOptions::default(), || Ok(listed.clone()));
+
+        let first = service.handle(&request("GET", "/report", ""));
+        std::fs::write(&lib, "fn a() {}\nfn b() {\n    a();\n    a();\n}\n").unwrap();
+        let second = service.handle(&request("GET", "/report", ""));
+
+        let (functions, diagnostics) =
+            crate::analyze_all_files_timed(&files, None, &AnalysisOptions::default(), |_, _, _| {});
+        let report = AnalysisReport {
+            functions,
+            diagnostics,
+            ..AnalysisReport::default()
+        };
+        let mut expected = Vec::new();
+        JsonFormat.write_report(&report, &mut expected).unwrap();
+        assert_ne!(first.body, second.body);
+        assert_eq!(second.body, String::from_utf8(expected).unwrap().trim_end());
+        assert_eq!(service.rows.len(), 2);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_endpoints() {
+        let mut service = ReportService::new(AnalysisOptions::default(), || {
+            Err(AnalysisError::NoRustFiles {
+                directory: "src".to_string(),
+            })
+        });
+
+        let analyzed = service.handle(&request("POST", "/analyze", "fn a() {\n    b();\n}\n"));
+        assert_eq!(analyzed.status, 200);
+        let functions: serde_json::Value = serde_json::from_str(&analyzed.body).unwrap();
+        assert_eq!(functions[0]["name"], "a");
+        assert_eq!(functions[0]["code"], 3);
+
+        let broken = service.handle(&request("POST", "/analyze", "fn a( {"));
+        assert_eq!(broken.status, 422);
+        assert!(broken.body.contains("\"kind\":\"parse_error\""));
+
+        assert_eq!(service.handle(&request("GET", "/report", "")).status, 500);
+        assert_eq!(service.handle(&request("GET", "/analyze", "")).status, 405);
+        assert_eq!(service.handle(&request("GET", "/", "")).status, 404);
+    }
+}
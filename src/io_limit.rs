@@ -0,0 +1,87 @@
+//! Limit on the number of files read at the same time
+//!
+//! Parallel analysis opens one file per thread. On network filesystems and
+//! constrained CI runners `--max-open-files` keeps reads to a few at a time
+//! while parsing and analysis still use every thread.
+
+use std::sync::{Condvar, Mutex, PoisonError};
+
+/// Counting semaphore bounding concurrent file reads
+#[derive(Debug)]
+pub struct OpenFileLimit {
+    available: Mutex<usize>,
+    released: Condvar,
+}
+
+/// Permission to read one file, returned to the limit when dropped
+#[derive(Debug)]
+pub struct OpenFilePermit<'a> {
+    limit: &'a OpenFileLimit,
+}
+
+impl OpenFileLimit {
+    /// Creates a limit allowing `max_open` simultaneous reads, at least one
+    pub fn new(max_open: usize) -> Self {
+        Self {
+            available: Mutex::new(max_open.max(1)),
+            released: Condvar::new(),
+        }
+    }
+
+    /// Waits until fewer than the maximum number of files are being read
+    pub fn acquire(&self) -> OpenFilePermit<'_> {
+        // The count stays consistent even if a reader panicked, so poisoning is ignored
+        let mut available = self
+            .available
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        while *available == 0 {
+            available = self
+                .released
+                .wait(available)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        *available -= 1;
+        OpenFilePermit { limit: self }
+    }
+}
+
+impl Drop for OpenFilePermit<'_> {
+    fn drop(&mut self) {
+        *self
+            .limit
+            .available
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) += 1;
+        self.limit.released.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_reads_are_bounded() {
+        let limit = OpenFileLimit::new(2);
+        let reading = AtomicUsize::new(0);
+        let most_reading = AtomicUsize::new(0);
+
+        thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    let _permit = limit.acquire();
+                    let now = reading.fetch_add(1, Ordering::SeqCst) + 1;
+                    most_reading.fetch_max(now, Ordering::SeqCst);
+                    thread::sleep(Duration::from_millis(5));
+                    reading.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+        });
+
+        assert!(most_reading.load(Ordering::SeqCst) <= 2);
+    }
+}
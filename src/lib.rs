@@ -3,84 +3,1158 @@
 //! A tool for analyzing Rust source code and counting lines of code,
 //! comments, and empty lines per function.
 
+pub mod advisor;
 pub mod analyzer;
+pub mod badge;
+pub mod baseline;
+pub mod build_check;
+pub mod call_graph;
+pub mod cancellation;
 pub mod client;
+pub mod config;
+pub mod daemon;
+pub mod diagnostics;
+pub mod duplicates;
 pub mod errors;
+pub mod exit_code;
+pub mod file_analysis;
 pub mod file_scanner;
-pub mod output_formatter;
+pub mod fixtures;
+pub mod git_changes;
+pub mod git_revision;
+pub mod grades;
+pub mod health;
+pub mod histogram;
+pub mod history;
+pub mod http_server;
+pub mod io_limit;
+pub mod lsp;
+pub mod name_normalization;
+pub mod output;
+pub mod ownership;
+pub mod panic_report;
+pub mod report;
+pub mod report_diff;
+pub mod rules;
+pub mod run_stats;
+pub mod source;
+pub mod summary;
+pub mod timings;
+pub mod type_complexity;
+pub mod unreferenced;
+pub mod warm_cache;
 
 // Re-export commonly used types for convenience
-pub use analyzer::FunctionAnalysisResult;
-pub use client::{Client, OutputFormat};
+pub use analyzer::{AnalysisOptions, FunctionAnalysisResult, PhaseTimings};
+pub use build_check::build_check;
+pub use cancellation::CancellationToken;
+pub use client::{Client, Command, GroupBy, OutputFormat, SortBy};
+pub use diagnostics::{Diagnostic, DiagnosticKind};
 pub use errors::{AnalysisError, AnalysisResult};
+pub use file_analysis::{Analyzer, FileAnalysis};
+pub use report::{AnalysisReport, FileError};
 
 // Internal imports for the run_analysis function
-use analyzer::{analyze_function_complete, extract_function_spans};
-use file_scanner::find_rust_files;
-use output_formatter::OutputFormatter;
-use std::fs;
-use std::path::Path;
+use badge::Badge;
+use baseline::Baseline;
+use client::{
+    BadgeArgs, BaselineAction, BaselineArgs, CallGraphArgs, ColorChoice, DaemonArgs, DiffArgs,
+    HistoryArgs, ServeArgs,
+};
+use config::{Config, FailOn, Thresholds};
+use file_analysis::AnalyzedFile;
+use file_scanner::{ScanOptions, find_rust_files_in_paths};
+use grades::GradeBoundaries;
+use indicatif::{ProgressBar, ProgressStyle};
+use io_limit::OpenFileLimit;
+use name_normalization::NameNormalization;
+use output::formatter::create_output_file;
+use output::{Column, FormatRegistry, JsonStyle, OutputFormatter, ReportSections, TemplateFormat};
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::io::{self, IsTerminal, Write};
+use std::ops::ControlFlow;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
 
 /// Normalizes file path separators to forward slashes for consistent output across platforms
-fn normalize_path(path: &str) -> String {
+pub(crate) fn normalize_path(path: &str) -> String {
     Path::new(path)
         .components()
         .map(|component| component.as_os_str().to_string_lossy())
         .collect::<Vec<_>>()
         .join("/")
 }
-/// Runs the function analysis for all Rust files in the configured directory
-pub fn run_analysis(cli: &Client) -> AnalysisResult<()> {
-    let formatter = OutputFormatter::with_format(cli.format.clone());
+/// Runs the function analysis for all Rust files in the configured paths
+///
+/// Results and reports are written as selected on the command line. Functions
+/// exceeding `--fail-on` limits are returned in the report rather than as an
+/// error, so the caller decides how to exit.
+pub fn run_analysis(cli: &Client) -> AnalysisResult<AnalysisReport> {
+    match &cli.command {
+        Some(Command::Badge(args)) => return run_badge(cli, args),
+        Some(Command::Baseline(args)) => return run_baseline(cli, args),
+        Some(Command::Diff(args)) => return run_diff(cli, args),
+        Some(Command::History(args)) => return run_history(cli, args),
+        Some(Command::Hook) => return run_hook(cli),
+        Some(Command::Lsp) => return run_lsp(cli),
+        Some(Command::Serve(args)) => return run_serve(cli, args),
+        Some(Command::Daemon(args)) => return run_daemon(cli, args),
+        Some(Command::CallGraph(args)) => return run_call_graph(cli, args),
+        None => {}
+    }
+    if cli.fail_on.is_some() || cli.baseline.is_some() {
+        return enforce_limits(cli);
+    }
+
+    let start = Instant::now();
+    let mut formatter = output_formatter(cli)?
+        .with_ast_counts(cli.ast_counts)
+        .with_json_style(json_style(cli))
+        .with_csv_delimiter(cli.delimiter)
+        .with_columns(cli.columns.clone())
+        .with_group_by(cli.group_by)
+        .with_totals(cli.totals)
+        .with_quiet(cli.quiet)
+        .with_no_header(cli.no_header)
+        .with_width(table_width(cli))
+        .with_truncate_names(!cli.no_truncate)
+        .with_grades(grade_boundaries(cli)?)
+        .with_color(use_color(cli))
+        .with_editor_links(cli.editor_links)
+        .with_thresholds(report_thresholds(cli)?)
+        .with_sort(cli.sort)
+        .with_limit(cli.limit);
+    if let Some(path) = &cli.output {
+        formatter = formatter.with_output_file(path)?;
+    }
+
+    let files = collect_files(cli, &cli.paths)?;
+    formatter.display_analysis_header(files.len())?;
+
+    // Analyze all functions across all files; streaming formats print each file's rows at once
+    let options = analysis_options(cli)?;
+    let normalization = name_normalization(cli)?;
+    let mut analyzed_functions = 0;
+    let mut remaining_rows = cli.limit.unwrap_or(usize::MAX);
+    let mut stream_result = Ok(());
+    // Streamed rows are dropped once written unless a report needs every result
+    let keep_results = !formatter.is_streaming() || needs_all_results(cli);
+    let progress = progress_bar(cli, files.len());
+    let mut file_timings = timings::Timings::default();
+    let revision = cli.at.as_deref();
+    let (all_results, diagnostics) = analyze_selected_files(
+        cli,
+        &files,
+        revision,
+        &options,
+        |path, timings, file_results| {
+            progress.inc(1);
+            if cli.timings {
+                file_timings.record(path, timings);
+            }
+            analyzed_functions += file_results.len();
+            if !normalization.is_identity() {
+                for result in file_results.iter_mut() {
+                    result.name = normalization.normalize(&result.name);
+                }
+            }
+            file_results.retain(|result| is_selected(cli, result));
+
+            if formatter.is_streaming() && stream_result.is_ok() {
+                let shown = file_results.len().min(remaining_rows);
+                stream_result = formatter.display_streamed_rows(file_results.iter().take(shown));
+                remaining_rows -= shown;
+            }
+            if !keep_results {
+                file_results.clear();
+            }
+        },
+    );
+    progress.finish_and_clear();
+    stream_result?;
+    if cli.timings {
+        eprintln!("{}", file_timings.to_text());
+    }
+    let mut report = AnalysisReport::new(files, all_results, start.elapsed());
+    report.diagnostics = diagnostics;
+    let file_errors = report.file_errors();
+    let (files, all_results) = (&report.files, &report.functions);
+
+    let stats = cli
+        .stats
+        .then(|| run_stats::RunStats::measure(start, files.len(), analyzed_functions));
+    let histogram = cli
+        .histogram
+        .as_deref()
+        .map(|metric| histogram::Histogram::new(all_results, metric));
+    let health = if cli.health {
+        let weights = Config::load_from_dir(Path::new("."))?.health;
+        Some(health::HealthReport::new(all_results, &weights))
+    } else {
+        None
+    };
+    let sections = ReportSections {
+        health: health.as_ref(),
+        summary: cli.summary.then_some(&report.summary),
+        histogram: histogram.as_ref(),
+        stats: stats.as_ref(),
+        file_errors: &file_errors,
+    };
+
+    // Display results in the requested order (code lines descending by default)
+    formatter.display_report(&report, &sections)?;
+
+    if let Some(min_density) = cli.min_comment_density {
+        let warnings = low_comment_density_warnings(all_results, min_density);
+        report.diagnostics.extend(warnings);
+    }
+
+    if let Some(max_params) = cli.max_params {
+        let suggestions = advisor::suggest_parameter_structs_for_files(files, max_params);
+        formatter.display_parameter_struct_suggestions(&suggestions, max_params)?;
+    }
+
+    if cli.refactoring_hints {
+        let suggestions = advisor::suggest_refactorings(all_results, &configured_thresholds()?);
+        formatter.display_refactoring_suggestions(&suggestions)?;
+    }
+
+    if cli.wmc {
+        let types = type_complexity::weighted_methods_per_class(files, options.complexity_rules);
+        formatter.display_type_complexity(&types)?;
+    }
+
+    if cli.rules {
+        let matches = evaluate_rules(cli, files, all_results)?;
+        formatter.display_rule_matches(&matches)?;
+    }
+
+    if cli.duplicates {
+        let groups = duplicates::find_duplicates(files);
+        formatter.display_duplicates(&groups)?;
+    }
+
+    if let Some(percent) = cli.near_duplicates {
+        let pairs = duplicates::find_similar(files, f64::from(percent) / 100.0);
+        formatter.display_similar_functions(&pairs, percent)?;
+    }
+
+    if cli.owners {
+        let teams = Config::load_from_dir(Path::new("."))?.teams;
+        let (owners, warnings) = ownership::find_owners(all_results, &teams);
+        formatter.display_owners(&owners)?;
+        report.diagnostics.extend(warnings);
+    }
+
+    if cli.unreferenced {
+        let functions = unreferenced::find_unreferenced(files, all_results);
+        formatter.display_unreferenced(&functions)?;
+    }
+
+    if cli.panic_report {
+        let functions = call_graph::parse_functions(files);
+        let entries = panic_report::build_panic_report(&functions);
+        formatter.display_panic_report(&entries)?;
+    }
 
-    let files = find_rust_files(&cli.directory)?;
+    formatter.flush()?;
+    Ok(report)
+}
 
-    formatter.display_analysis_header(files.len());
+/// Returns true if a report selected on the command line is computed from all results
+fn needs_all_results(cli: &Client) -> bool {
+    cli.summary
+        || cli.histogram.is_some()
+        || cli.health
+        || cli.rules
+        || cli.min_comment_density.is_some()
+        || cli.owners
+        || cli.refactoring_hints
+        || cli.unreferenced
+}
 
-    // Analyze all functions across all files
-    let all_results = analyze_all_files(&files);
+/// Evaluates the rules of `fnloc.toml`; the call graph is only built if a rule needs it
+fn evaluate_rules(
+    cli: &Client,
+    files: &[String],
+    results: &[FunctionAnalysisResult],
+) -> AnalysisResult<Vec<rules::RuleMatch>> {
+    let config = Config::load_from_dir(Path::new("."))?;
+    let mut calls = HashMap::new();
+    if config.rules.iter().any(|rule| rule.when.uses_call_graph()) {
+        let graph = call_graph::CallGraph::build(&call_graph::parse_functions(files));
+        let normalization = name_normalization(cli)?;
+        calls = rules::call_counts(&graph)
+            .into_iter()
+            .map(|(name, counts)| (normalization.normalize(&name), counts))
+            .collect();
+    }
+    Ok(rules::evaluate(&config.rules, results, &calls))
+}
 
-    // Display results (sorted by code lines descending - default behavior)
-    formatter.display_results_sorted_by_code(&all_results);
+/// Creates the formatter for the output format selected on the command line
+/// A `--template` file replaces the default template of the template format
+fn output_formatter(cli: &Client) -> AnalysisResult<OutputFormatter> {
+    let Some(path) = &cli.template else {
+        return Ok(OutputFormatter::with_format(cli.format.clone()));
+    };
+    let mut registry = FormatRegistry::default();
+    registry.register(
+        OutputFormat::Template.name(),
+        Arc::new(TemplateFormat::from_file(path)?),
+    );
+    Ok(OutputFormatter::from_registry(&registry, cli.format.name())
+        .unwrap_or_else(|| OutputFormatter::with_format(cli.format.clone())))
+}
 
+/// Prints a code-health badge for the analyzed functions
+fn run_badge(cli: &Client, args: &BadgeArgs) -> AnalysisResult<AnalysisReport> {
+    let start = Instant::now();
+    let files = collect_files(cli, &args.paths)?;
+    let mut diagnostics = Vec::new();
+    let results = analyze_normalized(cli, &files, cli.at.as_deref(), &mut diagnostics)?;
+
+    let badge = Badge::from_results(&results, args.metric).render(args.style);
+    write_output(cli, &badge)?;
+    Ok(AnalysisReport {
+        diagnostics,
+        ..AnalysisReport::new(files, results, start.elapsed())
+    })
+}
+
+/// Prints the call graph of the analyzed functions, as JSON with `--format json` and DOT otherwise
+fn run_call_graph(cli: &Client, args: &CallGraphArgs) -> AnalysisResult<AnalysisReport> {
+    let start = Instant::now();
+    let files = collect_files(cli, &args.paths)?;
+    let mut diagnostics = Vec::new();
+    let results = analyze_normalized(cli, &files, cli.at.as_deref(), &mut diagnostics)?;
+
+    let graph = call_graph::CallGraph::build(&call_graph::parse_functions(&files));
+    let style = if cli.format == OutputFormat::Json {
+        call_graph::GraphStyle::Json
+    } else {
+        call_graph::GraphStyle::Dot
+    };
+    let rendered = graph.render(style, &results);
+    write_output(cli, &rendered)?;
+    Ok(AnalysisReport {
+        diagnostics,
+        ..AnalysisReport::new(files, results, start.elapsed())
+    })
+}
+
+/// Writes the output of a subcommand to `--output` or stdout
+///
+/// A closed stdout (e.g. `| head`) only means nobody reads further output, so it is not an error.
+fn write_output(cli: &Client, rendered: &str) -> AnalysisResult<()> {
+    match &cli.output {
+        Some(path) => writeln!(create_output_file(path)?, "{rendered}")?,
+        None => match writeln!(io::stdout().lock(), "{rendered}") {
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {}
+            written => written?,
+        },
+    }
     Ok(())
 }
 
+/// Writes a snapshot of the current metrics for `--baseline`
+fn run_baseline(cli: &Client, args: &BaselineArgs) -> AnalysisResult<AnalysisReport> {
+    let BaselineAction::Write(write_args) = &args.action;
+    let start = Instant::now();
+    let files = collect_files(cli, &write_args.paths)?;
+    let mut diagnostics = Vec::new();
+    let results = analyze_normalized(cli, &files, cli.at.as_deref(), &mut diagnostics)?;
+    Baseline::from_results(&results).write(&write_args.file)?;
+    eprintln!(
+        "Wrote baseline of {} functions to {}",
+        results.len(),
+        write_args.file.display()
+    );
+    Ok(AnalysisReport {
+        diagnostics,
+        ..AnalysisReport::new(files, results, start.elapsed())
+    })
+}
+
+/// Prints the per-function changes between two reports, as JSON with `--format json`
+fn run_diff(cli: &Client, args: &DiffArgs) -> AnalysisResult<AnalysisReport> {
+    let mut diagnostics = Vec::new();
+    let old = match (&args.old, &args.at) {
+        (Some(path), _) => report_diff::load_report(path)?,
+        (None, revision) => {
+            let revision = revision
+                .as_deref()
+                .unwrap_or(git_changes::DEFAULT_DIFF_BASE);
+            let files = git_revision::rust_files_at(Path::new("."), revision, &args.paths)?;
+            report_diff::report_metrics(&analyze_normalized(
+                cli,
+                &files,
+                Some(revision),
+                &mut diagnostics,
+            )?)
+        }
+    };
+    let new = match &args.new {
+        Some(path) => report_diff::load_report(path)?,
+        None => {
+            let files = collect_files(cli, &args.paths)?;
+            report_diff::report_metrics(&analyze_normalized(
+                cli,
+                &files,
+                cli.at.as_deref(),
+                &mut diagnostics,
+            )?)
+        }
+    };
+
+    let deltas = report_diff::diff_reports(&old, &new);
+    let rendered = if cli.format == OutputFormat::Json {
+        serde_json::to_string_pretty(&deltas).map_err(io::Error::other)?
+    } else {
+        report_diff::format_deltas(&deltas)
+    };
+    write_output(cli, &rendered)?;
+    Ok(AnalysisReport {
+        diagnostics,
+        ..AnalysisReport::default()
+    })
+}
+
+/// Prints one line of metrics per commit, as JSON with `--format json` and CSV otherwise
+fn run_history(cli: &Client, args: &HistoryArgs) -> AnalysisResult<AnalysisReport> {
+    let repo = Path::new(".");
+    let options = analysis_options(cli)?;
+    let normalization = name_normalization(cli)?;
+    let mut cache = history::BlobCache::default();
+    let mut points = Vec::new();
+    let mut diagnostics = Vec::new();
+
+    for commit in history::commits(repo, args.since.as_deref(), args.step as usize)? {
+        let blobs = git_revision::rust_blobs_at(repo, &commit.hash, &args.paths)?;
+        let mut results = Vec::new();
+        for (path, blob) in &blobs {
+            let file_results = cache.get_or_analyze(blob, || {
+                analyze_file_functions_at(path, &commit.hash, &options).unwrap_or_else(|e| {
+                    diagnostics.push(Diagnostic::new(
+                        path,
+                        DiagnosticKind::AnalysisFailed,
+                        format!("Failed to analyze file {path} at {}: {e}", commit.hash),
+                    ));
+                    Vec::new()
+                })
+            });
+            let file = PathBuf::from(normalize_path(path));
+            results.extend(file_results.iter().cloned().map(|mut result| {
+                result.name = normalization.normalize(&result.name);
+                result.file = file.clone();
+                result
+            }));
+        }
+        results.retain(|result| is_selected(cli, result));
+        points.push(history::HistoryPoint::new(&commit, blobs.len(), &results));
+    }
+
+    let rendered = if cli.format == OutputFormat::Json {
+        serde_json::to_string_pretty(&points).map_err(io::Error::other)?
+    } else {
+        history::to_csv(&points, cli.delimiter)
+    };
+    write_output(cli, &rendered)?;
+    Ok(AnalysisReport {
+        diagnostics,
+        ..AnalysisReport::default()
+    })
+}
+
+/// Reports the list of violations if any function of the scanned files exceeds one of the limits
+fn enforce_limits(cli: &Client) -> AnalysisResult<AnalysisReport> {
+    let start = Instant::now();
+    let files = collect_files(cli, &cli.paths)?;
+    check_limits(cli, files, cli.at.as_deref(), start)
+}
+
+/// Runs `fnloc hook`: checks the staged content of the staged Rust files
+/// against the limits, passing if no Rust file is staged
+fn run_hook(cli: &Client) -> AnalysisResult<AnalysisReport> {
+    let start = Instant::now();
+    let files = git_changes::staged_rust_files(Path::new("."))?;
+    if files.is_empty() {
+        return Ok(AnalysisReport::default());
+    }
+    check_limits(cli, files, Some(git_revision::INDEX_REVISION), start)
+}
+
+/// Serves metrics to an editor over the Language Server Protocol until it exits
+fn run_lsp(cli: &Client) -> AnalysisResult<AnalysisReport> {
+    let thresholds = configured_thresholds()?;
+    lsp::serve(
+        std::io::stdin().lock(),
+        std::io::stdout().lock(),
+        &analysis_options(cli)?,
+        &thresholds,
+    )?;
+    Ok(AnalysisReport::default())
+}
+
+/// Answers HTTP API requests on the loopback interface until the process is stopped
+fn run_serve(cli: &Client, args: &ServeArgs) -> AnalysisResult<AnalysisReport> {
+    let listener = std::net::TcpListener::bind(("127.0.0.1", args.port))?;
+    eprintln!("Listening on http://{}", listener.local_addr()?);
+    let options = analysis_options(cli)?;
+    let max_body = options.max_file_size;
+    let mut service = http_server::ReportService::new(options, || collect_files(cli, &args.paths));
+    http_server::serve(&listener, max_body, |request| service.handle(request))?;
+    Ok(AnalysisReport::default())
+}
+
+/// Analyzes files, as of `revision` if given, and reports the violations if any
+/// function exceeds one of the limits
+///
+/// The limits come from `--fail-on`, else from `fnloc.toml`, else the
+/// recommended ones. With `--baseline`, functions in the baseline may exceed
+/// them as far as they already did. Nothing else is reported, so the output of
+/// a failing CI gate is just the violations.
+fn check_limits(
+    cli: &Client,
+    files: Vec<String>,
+    revision: Option<&str>,
+    start: Instant,
+) -> AnalysisResult<AnalysisReport> {
+    let config = Config::load_from_dir(Path::new("."))?;
+    let fail_on = cli.fail_on.unwrap_or(FailOn {
+        limits: if config.thresholds.is_empty() {
+            Thresholds::RECOMMENDED
+        } else {
+            config.thresholds
+        },
+        grade: None,
+    });
+    let mut diagnostics = Vec::new();
+    let mut results = analyze_normalized(cli, &files, revision, &mut diagnostics)?;
+    results.retain(|result| is_selected(cli, result));
+    let mut violations = match &cli.baseline {
+        Some(path) => Baseline::load(path)?.violations(&results, &fail_on.limits),
+        None => results
+            .iter()
+            .flat_map(|result| fail_on.limits.violations(result))
+            .collect(),
+    };
+    if let Some(grade) = fail_on.grade {
+        violations.extend(
+            results
+                .iter()
+                .filter_map(|result| config.grades.violation(result, grade)),
+        );
+    }
+
+    Ok(AnalysisReport {
+        violations,
+        diagnostics,
+        ..AnalysisReport::new(files, results, start.elapsed())
+    })
+}
+
+/// Answers queries of commands run with `--daemon` until the process is stopped
+fn run_daemon(cli: &Client, args: &DaemonArgs) -> AnalysisResult<AnalysisReport> {
+    let daemon = daemon::Daemon::new(std::env::current_dir()?, analysis_options(cli)?);
+    eprintln!("Listening on {}", args.socket.display());
+    daemon::run(&args.socket, daemon)?;
+    Ok(AnalysisReport::default())
+}
+
+/// Analyzes files like [`analyze_all_files_timed`], through the daemon selected
+/// with `--daemon` if it answers
+///
+/// Revisions are always analyzed locally, as the daemon only caches the
+/// working tree. Results from the daemon are handed to `on_file` with zero
+/// timings.
+fn analyze_selected_files(
+    cli: &Client,
+    files: &[String],
+    revision: Option<&str>,
+    options: &AnalysisOptions,
+    mut on_file: impl FnMut(&str, PhaseTimings, &mut Vec<FunctionAnalysisResult>),
+) -> (Vec<FunctionAnalysisResult>, Vec<Diagnostic>) {
+    let Some(socket) = cli.daemon.as_deref().filter(|_| revision.is_none()) else {
+        return analyze_all_files_timed(files, revision, options, on_file);
+    };
+    let answer = daemon::Query::new(files, options)
+        .and_then(|query| daemon::query(socket, &query, daemon::QUERY_TIMEOUT));
+    let reason = match answer {
+        Ok(daemon::Answer::Files(answered)) => {
+            let mut all_results = Vec::new();
+            let mut diagnostics = Vec::new();
+            for mut file in answered {
+                on_file(&file.path, PhaseTimings::default(), &mut file.functions);
+                all_results.extend(file.functions);
+                diagnostics.extend(file.diagnostics);
+            }
+            return (all_results, diagnostics);
+        }
+        Ok(daemon::Answer::Refused(reason)) => reason,
+        Err(e) => e.to_string(),
+    };
+    let socket = socket.display().to_string();
+    let (all_results, mut diagnostics) = analyze_all_files_timed(files, revision, options, on_file);
+    diagnostics.push(Diagnostic::new(
+        &socket,
+        DiagnosticKind::DaemonUnavailable,
+        format!(
+            "The fnloc daemon on {socket} did not answer ({reason}); files were analyzed locally"
+        ),
+    ));
+    (all_results, diagnostics)
+}
+
+/// Analyzes files, as of `revision` if given, and normalizes the function names as
+/// configured in `fnloc.toml` and on the command line, adding any warnings to `diagnostics`
+fn analyze_normalized(
+    cli: &Client,
+    files: &[String],
+    revision: Option<&str>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> AnalysisResult<Vec<FunctionAnalysisResult>> {
+    let options = analysis_options(cli)?;
+    let progress = progress_bar(cli, files.len());
+    let (mut results, file_diagnostics) =
+        analyze_selected_files(cli, files, revision, &options, |_, _, _| progress.inc(1));
+    diagnostics.extend(file_diagnostics);
+    progress.finish_and_clear();
+    let normalization = name_normalization(cli)?;
+    for result in &mut results {
+        result.name = normalization.normalize(&result.name);
+    }
+    Ok(results)
+}
+
+/// Finds the Rust files to analyze in the given paths using the scan options on the command line
+///
+/// With `--at`, the files are listed from the git revision instead; scan
+/// options only apply to the working tree.
+fn collect_files(cli: &Client, paths: &[String]) -> AnalysisResult<Vec<String>> {
+    if let Some(revision) = &cli.at {
+        return git_revision::rust_files_at(Path::new("."), revision, paths);
+    }
+    let scan_options = ScanOptions {
+        exclude: cli.exclude.clone(),
+        include: cli.include.clone(),
+        no_ignore: cli.no_ignore,
+        max_depth: cli.max_depth,
+        follow_symlinks: cli.follow_symlinks,
+        no_default_excludes: cli.no_default_excludes,
+        include_generated: cli.include_generated,
+    };
+    let mut files = find_rust_files_in_paths(paths, &scan_options)?;
+
+    if cli.changed {
+        let base = cli
+            .diff_base
+            .as_deref()
+            .unwrap_or(git_changes::DEFAULT_DIFF_BASE);
+        let changed = git_changes::changed_rust_files(Path::new("."), base)?;
+        git_changes::retain_changed(&mut files, &changed);
+    }
+
+    Ok(files)
+}
+
+/// Builds the analysis options selected on the command line
+///
+/// Without `--complexity-preset`, the preset of `fnloc.toml` is used, so the
+/// command line counts complexity like the build-time check.
+fn analysis_options(cli: &Client) -> AnalysisResult<AnalysisOptions> {
+    let complexity_preset = match cli.complexity_preset {
+        Some(preset) => preset,
+        None => Config::load_from_dir(Path::new("."))?.complexity_preset,
+    };
+    let mut options = AnalysisOptions {
+        complexity_rules: complexity_preset.rules(),
+        jobs: cli.jobs.unwrap_or(0),
+        max_open_files: cli.max_open_files,
+        file_timeout: cli.file_timeout.map(Duration::from_secs),
+        recover: cli.recover,
+        heuristic_fallback: cli.heuristic_fallback,
+        // A limit of 0 disables the guard
+        max_file_size: Some(cli.max_file_size).filter(|&limit| limit > 0),
+        ..AnalysisOptions::default()
+    };
+    if let Some(markers) = &cli.todo_markers {
+        options.todo_markers = markers.clone();
+    }
+    Ok(options)
+}
+
+/// Returns the JSON layout selected on the command line
+fn json_style(cli: &Client) -> JsonStyle {
+    if cli.json_pretty {
+        JsonStyle::Pretty
+    } else if cli.json_compact {
+        JsonStyle::Compact
+    } else {
+        JsonStyle::Lines
+    }
+}
+
+/// Returns the limits highlighted in colored tables and reported by the Code Climate and
+/// diagnostics formats
+/// They are read from `fnloc.toml` in the current directory, falling back to recommended limits
+fn report_thresholds(cli: &Client) -> AnalysisResult<Thresholds> {
+    let reports_breaches = matches!(
+        cli.format,
+        OutputFormat::CodeClimate | OutputFormat::Diagnostics
+    );
+    if !reports_breaches && !use_color(cli) {
+        return Ok(Thresholds::default());
+    }
+    configured_thresholds()
+}
+
+/// Returns the limits of `fnloc.toml`, or the recommended ones if none are configured
+fn configured_thresholds() -> AnalysisResult<Thresholds> {
+    let thresholds = Config::load_from_dir(Path::new("."))?.thresholds;
+    Ok(if thresholds.is_empty() {
+        Thresholds::RECOMMENDED
+    } else {
+        thresholds
+    })
+}
+
+/// Returns the grade boundaries from `fnloc.toml` if a grade column is shown
+fn grade_boundaries(cli: &Client) -> AnalysisResult<GradeBoundaries> {
+    let shows_grades = cli
+        .columns
+        .as_ref()
+        .is_some_and(|columns| columns.contains(&Column::Grade));
+    if !shows_grades {
+        return Ok(GradeBoundaries::default());
+    }
+    Ok(Config::load_from_dir(Path::new("."))?.grades)
+}
+
+/// Returns true if table output should be colored
+/// `auto` colors only a terminal stdout, and only if NO_COLOR is unset or empty
+fn use_color(cli: &Client) -> bool {
+    match cli.color {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            cli.output.is_none()
+                && io::stdout().is_terminal()
+                && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+        }
+    }
+}
+
+/// Creates a progress bar over the files to analyze, drawn on stderr
+///
+/// The bar is hidden with `--no-progress` or `--quiet`, and when stderr is not
+/// a terminal, so logs and pipes stay clean.
+fn progress_bar(cli: &Client, files: usize) -> ProgressBar {
+    if cli.no_progress || cli.quiet || !io::stderr().is_terminal() {
+        return ProgressBar::hidden();
+    }
+    let style = ProgressStyle::with_template("{bar:40} {pos}/{len} files ({eta} remaining)")
+        .unwrap_or_else(|_| ProgressStyle::default_bar());
+    ProgressBar::new(files as u64).with_style(style)
+}
+
+/// Returns the line width to align table rows to
+/// Without `--width`, rows are aligned only on a terminal, to its width or to `COLUMNS`
+fn table_width(cli: &Client) -> Option<usize> {
+    if cli.width.is_some() || cli.output.is_some() || !io::stdout().is_terminal() {
+        return cli.width;
+    }
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse().ok())
+        .or_else(|| terminal_size::terminal_size().map(|(width, _)| usize::from(width.0)))
+}
+
+/// Builds the function-name normalization from `[names]` in `fnloc.toml` and the command line
+///
+/// The flags switch stripping on in addition to the configuration, and any
+/// `--strip-crate-prefix` replaces the configured prefixes.
+fn name_normalization(cli: &Client) -> AnalysisResult<NameNormalization> {
+    let names = Config::load_from_dir(Path::new("."))?.names;
+    let strip_crate_prefixes = if cli.strip_crate_prefixes.is_empty() {
+        names.strip_crate_prefixes
+    } else {
+        cli.strip_crate_prefixes.clone()
+    };
+    Ok(NameNormalization {
+        strip_generics: cli.strip_generics || names.strip_generics,
+        strip_crate_prefixes,
+        strip_hash_suffix: cli.strip_hash_suffix || names.strip_hash_suffix,
+    })
+}
+
+/// Returns true if the function passes the test code filters and reaches every
+/// minimum given on the command line
+/// Minimums compose: a function must satisfy all of them to be reported
+fn is_selected(cli: &Client, result: &FunctionAnalysisResult) -> bool {
+    if (cli.exclude_tests && result.is_test) || (cli.only_tests && !result.is_test) {
+        return false;
+    }
+    let minimums = [
+        (result.code, cli.min_lines),
+        (result.cyclomatic_complexity, cli.min_complexity),
+        (result.nesting_depth, cli.min_nesting),
+    ];
+    minimums
+        .into_iter()
+        .all(|(value, minimum)| minimum.is_none_or(|minimum| value >= minimum))
+}
+
+/// Returns a warning for every function whose comment density is below the minimum
+/// Functions without code or comment lines are skipped
+fn low_comment_density_warnings(
+    results: &[FunctionAnalysisResult],
+    min_density: f64,
+) -> Vec<Diagnostic> {
+    results
+        .iter()
+        .filter(|result| result.code + result.comment > 0)
+        .filter(|result| result.comment_density() < min_density)
+        .map(|result| {
+            Diagnostic::new(
+                result.source_file().unwrap_or_default(),
+                DiagnosticKind::LowCommentDensity,
+                format!(
+                    "fn {} has comment density {:.1}% (minimum {min_density:.1}%)",
+                    result.qualified_name(),
+                    result.comment_density()
+                ),
+            )
+        })
+        .collect()
+}
+
 /// Analyzes all functions in a Rust file and returns analysis results
 pub fn analyze_file_functions(path: &str) -> AnalysisResult<Vec<FunctionAnalysisResult>> {
-    let source = fs::read_to_string(path).map_err(AnalysisError::Io)?;
-    let function_spans = extract_function_spans(&source)?;
+    analyze_file_functions_with_options(path, &AnalysisOptions::default())
+}
 
-    let results = function_spans
-        .iter()
-        .map(|span| analyze_function_complete(span, &source))
-        .collect();
+/// Analyzes all functions in a Rust file using the given options
+/// The source is decoded leniently and normalized (BOM, CRLF) before analysis
+pub fn analyze_file_functions_with_options(
+    path: &str,
+    options: &AnalysisOptions,
+) -> AnalysisResult<Vec<FunctionAnalysisResult>> {
+    analyze_file_timed(path, None, options, None).map(|file| file.functions)
+}
 
-    Ok(results)
+/// Analyzes all functions in a Rust file as of a git revision, without checking it out
+pub fn analyze_file_functions_at(
+    path: &str,
+    revision: &str,
+    options: &AnalysisOptions,
+) -> AnalysisResult<Vec<FunctionAnalysisResult>> {
+    analyze_file_timed(path, Some(revision), options, None).map(|file| file.functions)
+}
+
+/// Analyzes all functions in a Rust file, as of `revision` if given
+///
+/// Reading the file, including waiting for a free slot of `open_files`, counts
+/// towards its parse time. Files above `options.max_file_size` are not parsed;
+/// in the working tree they are not even read.
+fn analyze_file_timed(
+    path: &str,
+    revision: Option<&str>,
+    options: &AnalysisOptions,
+    open_files: Option<&OpenFileLimit>,
+) -> AnalysisResult<AnalyzedFile> {
+    let start = Instant::now();
+    let permit = open_files.map(OpenFileLimit::acquire);
+    let source = match revision {
+        Some(revision) => {
+            let source = git_revision::read_file_at(Path::new("."), revision, path)?;
+            source::check_size(path, source.text.len() as u64, options.max_file_size)?;
+            source
+        }
+        None => {
+            if options.max_file_size.is_some() {
+                let size = std::fs::metadata(path)?.len();
+                source::check_size(path, size, options.max_file_size)?;
+            }
+            source::read_source(path).map_err(AnalysisError::Io)?
+        }
+    };
+    drop(permit);
+    let mut diagnostics = Vec::new();
+    if source.lossy {
+        diagnostics.push(Diagnostic::new(
+            path,
+            DiagnosticKind::InvalidUtf8,
+            format!("{path} is not valid UTF-8; invalid bytes were replaced"),
+        ));
+    }
+    let read = start.elapsed();
+    let (functions, mut timings) = match analyzer::analyze_source_timed(&source.text, options) {
+        Err(AnalysisError::ParseError { .. }) if options.recover || options.heuristic_fallback => {
+            recover_items(path, &source.text, options, &mut diagnostics)
+        }
+        analyzed => analyzed.map_err(|e| e.in_file(path))?,
+    };
+    timings.parse += read;
+    Ok(AnalyzedFile {
+        functions,
+        timings,
+        diagnostics,
+    })
+}
+
+/// Analyzes the items of a file with syntax errors that parse on their own,
+/// adding a diagnostic for every region that does not
+///
+/// With the heuristic fallback the functions of those regions are kept with
+/// line counts only, so the diagnostic says they were estimated.
+fn recover_items(
+    path: &str,
+    source: &str,
+    options: &AnalysisOptions,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> (Vec<FunctionAnalysisResult>, PhaseTimings) {
+    let start = Instant::now();
+    let (functions, regions) = analyzer::analyze_recovering(source, options);
+    let outcome = if options.heuristic_fallback {
+        "its functions were estimated without complexity metrics"
+    } else {
+        "was skipped"
+    };
+    diagnostics.extend(regions.iter().map(|region| {
+        Diagnostic::new(
+            path,
+            DiagnosticKind::UnparsedRegion,
+            format!(
+                "{path}:{}-{} does not parse and {outcome}: {}",
+                region.start_line, region.end_line, region.message
+            ),
+        )
+    }));
+    // Items are parsed and analyzed in turn, so the time is not split by phase
+    let timings = PhaseTimings {
+        parse: start.elapsed(),
+        analysis: Duration::ZERO,
+    };
+    (functions, timings)
+}
+
+/// Analyzes a file like `analyze_file_timed`, turning a panic or a timeout into an error
+///
+/// With `options.file_timeout` the file is analyzed on its own thread; when
+/// the time is up that thread is abandoned and finishes in the background.
+fn analyze_file_guarded(
+    path: &str,
+    revision: Option<&str>,
+    options: &AnalysisOptions,
+    open_files: Option<&Arc<OpenFileLimit>>,
+) -> AnalysisResult<AnalyzedFile> {
+    let Some(timeout) = options.file_timeout else {
+        return analyze_file_isolated(path, revision, options, open_files.map(Arc::as_ref));
+    };
+
+    let (sender, receiver) = mpsc::channel();
+    let path_owned = path.to_string();
+    let revision = revision.map(str::to_string);
+    let options = options.clone();
+    let open_files = open_files.cloned();
+    thread::Builder::new()
+        .name(format!("fnloc {path}"))
+        .spawn(move || {
+            let analyzed = analyze_file_isolated(
+                &path_owned,
+                revision.as_deref(),
+                &options,
+                open_files.as_deref(),
+            );
+            // The receiver is gone if the analysis timed out
+            let _ = sender.send(analyzed);
+        })?;
+    match receiver.recv_timeout(timeout) {
+        Ok(analyzed) => analyzed,
+        Err(_) => Err(AnalysisError::AnalysisTimedOut { timeout }),
+    }
+}
+
+/// Analyzes a file like `analyze_file_timed`, turning a panic into an error
+fn analyze_file_isolated(
+    path: &str,
+    revision: Option<&str>,
+    options: &AnalysisOptions,
+    open_files: Option<&OpenFileLimit>,
+) -> AnalysisResult<AnalyzedFile> {
+    panic::catch_unwind(AssertUnwindSafe(|| {
+        analyze_file_timed(path, revision, options, open_files)
+    }))
+    .unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        Err(AnalysisError::AnalysisPanicked { message })
+    })
 }
 
 /// Analyzes all functions across multiple files and returns unsorted results
 pub fn analyze_all_files(file_paths: &[String]) -> Vec<FunctionAnalysisResult> {
+    analyze_all_files_with_options(file_paths, &AnalysisOptions::default())
+}
+
+/// Analyzes all functions across multiple files using the given options
+pub fn analyze_all_files_with_options(
+    file_paths: &[String],
+    options: &AnalysisOptions,
+) -> Vec<FunctionAnalysisResult> {
+    analyze_all_files_with_callback(file_paths, options, |_| {})
+}
+
+/// Analyzes all functions across multiple files, handing each file's results to `on_file`
+///
+/// The callback runs as soon as a file is analyzed, before its results are
+/// collected, and may modify or drop them (e.g. to rename, filter, or stream them).
+pub fn analyze_all_files_with_callback(
+    file_paths: &[String],
+    options: &AnalysisOptions,
+    on_file: impl FnMut(&mut Vec<FunctionAnalysisResult>),
+) -> Vec<FunctionAnalysisResult> {
+    analyze_all_files_at_with_callback(file_paths, None, options, on_file)
+}
+
+/// Like [`analyze_all_files_with_callback`], reading the files as of `revision` if given
+///
+/// Files are analyzed in parallel on `options.jobs` threads, a batch at a
+/// time; results are still handed to `on_file` and returned in input order.
+/// Once `options.cancellation` is cancelled no further files are analyzed, and
+/// the results of the files analyzed so far are returned.
+pub fn analyze_all_files_at_with_callback(
+    file_paths: &[String],
+    revision: Option<&str>,
+    options: &AnalysisOptions,
+    mut on_file: impl FnMut(&mut Vec<FunctionAnalysisResult>),
+) -> Vec<FunctionAnalysisResult> {
+    let (results, _) =
+        analyze_all_files_timed(file_paths, revision, options, |_, _, file_results| {
+            on_file(file_results)
+        });
+    results
+}
+
+/// Like [`analyze_all_files_at_with_callback`], also handing `on_file` each file's
+/// path and the time spent parsing and analyzing it
+///
+/// Files that cannot be analyzed are skipped. They and any other warnings are
+/// returned as diagnostics next to the results, rather than printed.
+pub fn analyze_all_files_timed(
+    file_paths: &[String],
+    revision: Option<&str>,
+    options: &AnalysisOptions,
+    mut on_file: impl FnMut(&str, PhaseTimings, &mut Vec<FunctionAnalysisResult>),
+) -> (Vec<FunctionAnalysisResult>, Vec<Diagnostic>) {
     let mut all_results = Vec::new();
+    let mut diagnostics = Vec::new();
+    analyze_files_in_batches(file_paths, revision, options, &|_| {}, |path, analyzed| {
+        match analyzed {
+            Ok(mut file) => {
+                on_file(path, file.timings, &mut file.functions);
+                all_results.extend(file.functions);
+                diagnostics.extend(file.diagnostics);
+            }
+            // Continue processing other files
+            Err(e) => diagnostics.push(Diagnostic::analysis_failed(path, &e)),
+        }
+        ControlFlow::Continue(())
+    });
+    (all_results, diagnostics)
+}
 
-    for path in file_paths {
-        match analyze_file_functions(path) {
-            Ok(mut file_results) => {
-                // Add file path information to each result for context
+/// Analyzes files in parallel batches, handing each file's outcome to `on_analyzed` in input order
+///
+/// `on_start` runs on the worker thread just before a file is read. Results
+/// already carry their normalized file path. Analysis stops once
+/// `options.cancellation` is cancelled or `on_analyzed` breaks.
+pub(crate) fn analyze_files_in_batches(
+    file_paths: &[String],
+    revision: Option<&str>,
+    options: &AnalysisOptions,
+    on_start: &(dyn Fn(&str) + Sync),
+    mut on_analyzed: impl FnMut(&str, AnalysisResult<AnalyzedFile>) -> ControlFlow<()>,
+) {
+    let cancellation = &options.cancellation;
+    let open_files = options
+        .max_open_files
+        .map(|max| Arc::new(OpenFileLimit::new(max)));
+    let analyze = |path: &String| {
+        (!cancellation.is_cancelled()).then(|| {
+            on_start(path);
+            analyze_file_guarded(path, revision, options, open_files.as_ref())
+        })
+    };
+    // Without a thread pool, e.g. if no threads can be spawned, files are analyzed in turn
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(options.jobs)
+        .build()
+        .ok();
+    let batch_size = pool
+        .as_ref()
+        .map_or(1, |pool| pool.current_num_threads() * 4);
+
+    for batch in file_paths.chunks(batch_size) {
+        if cancellation.is_cancelled() {
+            break;
+        }
+        let analyzed: Vec<_> = match &pool {
+            Some(pool) => pool.install(|| batch.par_iter().map(analyze).collect()),
+            None => batch.iter().map(analyze).collect(),
+        };
+        // Files skipped after a cancellation have no results
+        let analyzed = batch.iter().zip(analyzed);
+        for (path, mut file_results) in
+            analyzed.filter_map(|(path, analyzed)| Some((path, analyzed?)))
+        {
+            if let Ok(analyzed) = &mut file_results {
                 // Normalize path separators for consistent output across platforms
-                let normalized_path = normalize_path(path);
-                for result in &mut file_results {
-                    // We'll modify the name to include the normalized file path
-                    result.name = format!("{}::{}", normalized_path, result.name);
+                let file = PathBuf::from(normalize_path(path));
+                for result in &mut analyzed.functions {
+                    result.file = file.clone();
                 }
-                all_results.extend(file_results);
             }
-            Err(e) => {
-                eprintln!("Warning: Failed to analyze file {path}: {e}");
-                // Continue processing other files
+            if on_analyzed(path, file_results).is_break() {
+                return;
             }
         }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    all_results
+    #[test]
+    fn test_file_timeout_abandons_slow_analysis() {
+        // With the only open-file slot taken, the analysis cannot finish before the timeout
+        let open_files = Arc::new(OpenFileLimit::new(1));
+        let permit = open_files.acquire();
+        let options = AnalysisOptions {
+            file_timeout: Some(Duration::from_millis(10)),
+            ..AnalysisOptions::default()
+        };
+
+        let analyzed = analyze_file_guarded(
+            "tests/test_sample/sample.rs",
+            None,
+            &options,
+            Some(&open_files),
+        );
+        assert!(matches!(
+            analyzed,
+            Err(AnalysisError::AnalysisTimedOut { .. })
+        ));
+        drop(permit);
+    }
 }
@@ -0,0 +1,349 @@
+//! Minimal language server for `fnloc lsp`
+//!
+//! Speaks the Language Server Protocol over stdin and stdout, so any editor
+//! with an LSP client can show the metrics of the functions in open files
+//! without a dedicated plugin. Every function gets an inlay hint such as
+//! `code=84 cc=17` at the end of its first line, and functions over the
+//! configured thresholds are published as warnings.
+//!
+//! Only full document sync is supported: the client sends the whole text on
+//! every change, and the file is analyzed again from that text. Files with
+//! syntax errors are analyzed with recovery, so the functions around an edit in
+//! progress keep their hints.
+
+use crate::analyzer::{self, AnalysisOptions, FunctionAnalysisResult};
+use crate::config::Thresholds;
+use crate::errors::AnalysisResult;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+
+/// `TextDocumentSyncKind.Full`
+const FULL_SYNC: u8 = 1;
+/// `DiagnosticSeverity.Warning`
+const WARNING: u8 = 2;
+/// `InlayHintKind.Type`, the closest kind to a metric annotation
+const TYPE_HINT: u8 = 1;
+/// JSON-RPC error code for requests the server does not implement
+const METHOD_NOT_FOUND: i32 = -32601;
+
+/// Runs the language server until the client sends `exit` or closes the input
+///
+/// Open documents are analyzed with `options`, and functions exceeding any of
+/// `thresholds` are published as warnings.
+pub fn serve(
+    input: impl BufRead,
+    output: impl Write,
+    options: &AnalysisOptions,
+    thresholds: &Thresholds,
+) -> AnalysisResult<()> {
+    let mut server = Server {
+        output,
+        options,
+        thresholds,
+        documents: HashMap::new(),
+    };
+    let mut input = input;
+    while let Some(message) = read_message(&mut input)? {
+        if !server.handle(&message)? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// State of a running server: the open documents and their latest metrics
+struct Server<'a, W> {
+    output: W,
+    options: &'a AnalysisOptions,
+    thresholds: &'a Thresholds,
+    /// Functions of each open document, by URI
+    documents: HashMap<String, Vec<FunctionAnalysisResult>>,
+}
+
+impl<W: Write> Server<'_, W> {
+    /// Handles one message; returns false once the client asked to exit
+    fn handle(&mut self, message: &Value) -> io::Result<bool> {
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+
+        match method {
+            "initialize" => self.respond(
+                message,
+                json!({
+                    "capabilities": {
+                        "textDocumentSync": FULL_SYNC,
+                        "inlayHintProvider": true,
+                    },
+                    "serverInfo": {
+                        "name": "fnloc",
+                        "version": env!("CARGO_PKG_VERSION"),
+                    },
+                }),
+            )?,
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                self.open(uri, text)?;
+            }
+            "textDocument/didChange" => {
+                // With full sync the last change holds the whole text
+                let changes = params["contentChanges"].as_array();
+                if let Some(text) = changes
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str())
+                {
+                    self.open(uri, text)?;
+                }
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(uri);
+                self.publish_diagnostics(uri, Vec::new())?;
+            }
+            "textDocument/inlayHint" => {
+                let hints = self.inlay_hints(uri, &params["range"]);
+                self.respond(message, Value::Array(hints))?;
+            }
+            "shutdown" => self.respond(message, Value::Null)?,
+            "exit" => return Ok(false),
+            // Requests need an answer; unknown notifications are ignored
+            _ if message.get("id").is_some() => self.send(&json!({
+                "jsonrpc": "2.0",
+                "id": message["id"],
+                "error": {
+                    "code": METHOD_NOT_FOUND,
+                    "message": format!("Method not supported: {method}"),
+                },
+            }))?,
+            _ => {}
+        }
+        Ok(true)
+    }
+
+    /// Analyzes the text of a document and publishes its warnings
+    fn open(&mut self, uri: &str, text: &str) -> io::Result<()> {
+        let functions = analyze_text(text, self.options);
+        let diagnostics = functions
+            .iter()
+            .filter_map(|function| self.diagnostic(function))
+            .collect();
+        self.documents.insert(uri.to_string(), functions);
+        self.publish_diagnostics(uri, diagnostics)
+    }
+
+    /// Returns the warning for a function exceeding the thresholds, if any
+    fn diagnostic(&self, function: &FunctionAnalysisResult) -> Option<Value> {
+        let violations = self.thresholds.violations(function);
+        if violations.is_empty() {
+            return None;
+        }
+        let exceeded: Vec<String> = violations
+            .iter()
+            .map(|violation| {
+                format!(
+                    "{}={} (limit {})",
+                    violation.metric, violation.value, violation.limit
+                )
+            })
+            .collect();
+        Some(json!({
+            "range": line_range(function.start_line),
+            "severity": WARNING,
+            "source": "fnloc",
+            "message": format!("{} exceeds {}", function.name, exceeded.join(", ")),
+        }))
+    }
+
+    /// Returns the hints of the functions that start within the requested range
+    fn inlay_hints(&self, uri: &str, range: &Value) -> Vec<Value> {
+        let first = range["start"]["line"].as_u64().unwrap_or(0) as usize;
+        let last = range["end"]["line"].as_u64().unwrap_or(u64::MAX) as usize;
+        self.documents
+            .get(uri)
+            .into_iter()
+            .flatten()
+            .filter(|function| (first..=last).contains(&(function.start_line - 1)))
+            .map(|function| {
+                json!({
+                    "position": { "line": function.start_line - 1, "character": u32::MAX },
+                    "label": hint_label(function),
+                    "kind": TYPE_HINT,
+                    "paddingLeft": true,
+                })
+            })
+            .collect()
+    }
+
+    fn publish_diagnostics(&mut self, uri: &str, diagnostics: Vec<Value>) -> io::Result<()> {
+        self.send(&json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": { "uri": uri, "diagnostics": diagnostics },
+        }))
+    }
+
+    fn respond(&mut self, request: &Value, result: Value) -> io::Result<()> {
+        self.send(&json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }))
+    }
+
+    fn send(&mut self, message: &Value) -> io::Result<()> {
+        let body = message.to_string();
+        write!(self.output, "Content-Length: {}\r\n\r\n{body}", body.len())?;
+        self.output.flush()
+    }
+}
+
+/// Analyzes the text of a document, recovering from syntax errors
+fn analyze_text(text: &str, options: &AnalysisOptions) -> Vec<FunctionAnalysisResult> {
+    analyzer::analyze_source(text, options).unwrap_or_else(|_| {
+        let recovering = AnalysisOptions {
+            recover: true,
+            ..options.clone()
+        };
+        analyzer::analyze_recovering(text, &recovering).0
+    })
+}
+
+/// Returns the hint shown after the first line of a function
+fn hint_label(function: &FunctionAnalysisResult) -> String {
+    if function.ast_metrics {
+        format!(
+            "code={} cc={}",
+            function.code, function.cyclomatic_complexity
+        )
+    } else {
+        format!("code={}", function.code)
+    }
+}
+
+/// Returns the LSP range covering the whole of a 1-based line
+fn line_range(line: usize) -> Value {
+    json!({
+        "start": { "line": line - 1, "character": 0 },
+        "end": { "line": line, "character": 0 },
+    })
+}
+
+/// Reads one `Content-Length` framed message; returns `None` at the end of the input
+fn read_message(input: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':')
+            && name.eq_ignore_ascii_case("Content-Length")
+        {
+            length = value.trim().parse::<usize>().ok();
+        }
+    }
+    let length = length.ok_or_else(|| invalid_message("missing Content-Length header"))?;
+
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|error| invalid_message(&error.to_string()))
+}
+
+fn invalid_message(reason: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Invalid LSP message: {reason}"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(message: Value) -> String {
+        let body = message.to_string();
+        format!("Content-Length: {}\r\n\r\n{body}", body.len())
+    }
+
+    /// Runs the server on the given messages and returns the messages it sent
+    fn run(messages: Vec<Value>, thresholds: &Thresholds) -> Vec<Value> {
+        let input: String = messages.into_iter().map(frame).collect();
+        let mut output = Vec::new();
+        serve(
+            input.as_bytes(),
+            &mut output,
+            &AnalysisOptions::default(),
+            thresholds,
+        )
+        .unwrap();
+
+        let mut sent = Vec::new();
+        let mut reader = output.as_slice();
+        while let Some(message) = read_message(&mut reader).unwrap() {
+            sent.push(message);
+        }
+        sent
+    }
+
+    #[test]
+    fn test_open_documents_get_hints_and_warnings() {
+        let uri = "file:///src/lib.rs";
+        let text =
+            "fn small() {}\n\nfn branchy(x: u32) -> u32 {\n    if x > 1 { 1 } else { 2 }\n}\n";
+        let thresholds = Thresholds {
+            complexity: Some(1),
+            ..Thresholds::default()
+        };
+        let sent = run(
+            vec![
+                json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {}}),
+                json!({"jsonrpc": "2.0", "method": "textDocument/didOpen",
+                    "params": {"textDocument": {"uri": uri, "text": text}}}),
+                json!({"jsonrpc": "2.0", "id": 2, "method": "textDocument/inlayHint",
+                    "params": {"textDocument": {"uri": uri},
+                        "range": {"start": {"line": 0, "character": 0}, "end": {"line": 9, "character": 0}}}}),
+                json!({"jsonrpc": "2.0", "id": 3, "method": "textDocument/hover", "params": {}}),
+                json!({"jsonrpc": "2.0", "method": "exit"}),
+            ],
+            &thresholds,
+        );
+
+        assert_eq!(sent[0]["result"]["capabilities"]["inlayHintProvider"], true);
+
+        let diagnostics = sent[1]["params"]["diagnostics"].as_array().unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0]["range"]["start"]["line"], 2);
+        assert_eq!(
+            diagnostics[0]["message"],
+            "branchy exceeds complexity=2 (limit 1)"
+        );
+
+        let hints = sent[2]["result"].as_array().unwrap();
+        let labels: Vec<_> = hints.iter().map(|hint| &hint["label"]).collect();
+        assert_eq!(labels, ["code=1 cc=1", "code=3 cc=2"]);
+        assert_eq!(hints[1]["position"]["line"], 2);
+
+        assert_eq!(sent[3]["error"]["code"], METHOD_NOT_FOUND);
+    }
+
+    #[test]
+    fn test_functions_around_a_syntax_error_keep_their_hints() {
+        let uri = "file:///src/main.rs";
+        let text = "fn main() {\n    run();\n}\n\nfn editing( {\n";
+        let sent = run(
+            vec![
+                json!({"jsonrpc": "2.0", "method": "textDocument/didOpen",
+                    "params": {"textDocument": {"uri": uri, "text": text}}}),
+                json!({"jsonrpc": "2.0", "id": 1, "method": "textDocument/inlayHint",
+                    "params": {"textDocument": {"uri": uri}, "range": {}}}),
+            ],
+            &Thresholds::default(),
+        );
+
+        let hints = sent[1]["result"].as_array().unwrap();
+        assert_eq!(hints.len(), 1);
+        assert_eq!(hints[0]["label"], "code=3 cc=1");
+    }
+}
@@ -1,13 +1,30 @@
-use clap::Parser;
-use fnloc::Client;
+use fnloc::{Client, OutputFormat, exit_code};
 
 fn main() {
-    let cli = Client::parse();
+    let cli = Client::parse_args();
 
-    if let Err(e) = fnloc::run_analysis(&cli) {
-        eprintln!("Error: {e}");
-        std::process::exit(1);
+    // Limit violations and files not fully analyzed with --strict are reported
+    // like any other error; the exit code tells them apart, see `exit_code`
+    let outcome = fnloc::run_analysis(&cli).and_then(|report| {
+        for diagnostic in &report.diagnostics {
+            eprintln!("Warning: {diagnostic}");
+        }
+        let report = report.into_result()?;
+        if cli.strict {
+            report.require_complete()
+        } else {
+            Ok(report)
+        }
+    });
+    if let Err(e) = outcome {
+        // Machine formats get an object that wrappers can parse instead of plain text
+        if matches!(cli.format, OutputFormat::Json | OutputFormat::Jsonl) {
+            eprintln!("{}", e.to_json());
+        } else {
+            eprintln!("Error: {e}");
+        }
+        std::process::exit(e.exit_code());
     }
 
-    std::process::exit(0);
+    std::process::exit(exit_code::SUCCESS);
 }
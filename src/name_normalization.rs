@@ -0,0 +1,135 @@
+//! Function-name normalization for reports
+//!
+//! Names in reports are `path/to/file.rs::function`. Normalization rewrites the
+//! function part only, so the same logical function keeps a stable name across
+//! runs, output formats, and baselines even when its spelling varies (generic
+//! arguments, crate prefixes, or symbol hash suffixes from expanded code).
+
+use serde::Deserialize;
+
+/// Options controlling how function names are normalized
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NameNormalization {
+    /// Remove generic argument lists such as `<T>` or `::<u8>`
+    pub strip_generics: bool,
+    /// Leading path segments (e.g. `crate`, `my_crate`) to remove from names
+    pub strip_crate_prefixes: Vec<String>,
+    /// Remove symbol hash suffixes such as `::h0123456789abcdef`
+    pub strip_hash_suffix: bool,
+}
+
+impl NameNormalization {
+    /// Returns true if normalization leaves every name unchanged
+    pub fn is_identity(&self) -> bool {
+        !self.strip_generics && self.strip_crate_prefixes.is_empty() && !self.strip_hash_suffix
+    }
+
+    /// Normalizes a report name, leaving any leading file path untouched
+    pub fn normalize(&self, name: &str) -> String {
+        let (file, function) = match name.rfind(".rs::") {
+            Some(pos) => name.split_at(pos + ".rs::".len()),
+            None => ("", name),
+        };
+
+        let mut function = function.to_string();
+        if self.strip_hash_suffix {
+            function = strip_hash_suffix(&function).to_string();
+        }
+        if self.strip_generics {
+            function = strip_generics(&function);
+        }
+        for prefix in &self.strip_crate_prefixes {
+            if let Some(rest) = function.strip_prefix(&format!("{prefix}::")) {
+                function = rest.to_string();
+            }
+        }
+
+        format!("{file}{function}")
+    }
+}
+
+/// Removes a trailing `::h` followed by 16 hex digits (legacy symbol mangling)
+fn strip_hash_suffix(name: &str) -> &str {
+    match name.rsplit_once("::h") {
+        Some((base, hash)) if hash.len() == 16 && hash.chars().all(|c| c.is_ascii_hexdigit()) => {
+            base
+        }
+        _ => name,
+    }
+}
+
+/// Removes all (possibly nested) generic argument lists, including turbofish separators
+fn strip_generics(name: &str) -> String {
+    let mut result = String::with_capacity(name.len());
+    let mut depth = 0usize;
+
+    for ch in name.chars() {
+        match ch {
+            '<' => depth += 1,
+            '>' if depth > 0 => depth -= 1,
+            _ if depth == 0 => result.push(ch),
+            _ => {}
+        }
+    }
+
+    // `foo::<T>` leaves a dangling separator, `Vec<T>::push` may leave a doubled one
+    while result.contains("::::") {
+        result = result.replace("::::", "::");
+    }
+    result.trim_end_matches("::").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identity_by_default() {
+        let normalization = NameNormalization::default();
+        assert!(normalization.is_identity());
+        assert_eq!(
+            normalization.normalize("src/lib.rs::parse::<T>"),
+            "src/lib.rs::parse::<T>"
+        );
+    }
+
+    #[test]
+    fn test_strip_generics() {
+        let normalization = NameNormalization {
+            strip_generics: true,
+            ..NameNormalization::default()
+        };
+
+        assert_eq!(normalization.normalize("parse::<Vec<u8>>"), "parse");
+        assert_eq!(normalization.normalize("Wrapper<T>::get"), "Wrapper::get");
+    }
+
+    #[test]
+    fn test_strip_crate_prefix_keeps_file_path() {
+        let normalization = NameNormalization {
+            strip_crate_prefixes: vec!["crate".to_string(), "my_crate".to_string()],
+            ..NameNormalization::default()
+        };
+
+        assert_eq!(
+            normalization.normalize("src/lib.rs::my_crate::util::helper"),
+            "src/lib.rs::util::helper"
+        );
+        assert_eq!(normalization.normalize("crate::run"), "run");
+    }
+
+    #[test]
+    fn test_strip_hash_suffix() {
+        let normalization = NameNormalization {
+            strip_hash_suffix: true,
+            ..NameNormalization::default()
+        };
+
+        assert_eq!(
+            normalization.normalize("my_crate::run::h0123456789abcdef"),
+            "my_crate::run"
+        );
+        assert_eq!(normalization.normalize("run::handler"), "run::handler");
+    }
+}
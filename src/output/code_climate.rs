@@ -0,0 +1,179 @@
+use super::json::to_json;
+use super::{Format, FormatOptions, JsonStyle};
+use crate::analyzer::FunctionAnalysisResult;
+use crate::config::Violation;
+use serde::Serialize;
+
+/// Code Climate issue JSON, as read by the GitLab Code Quality widget
+///
+/// Only functions breaching the configured thresholds appear, one issue per
+/// exceeded metric. The output is an array of issues even when it is empty.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CodeClimateFormat;
+
+/// A single Code Climate issue
+#[derive(Serialize)]
+struct Issue {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    check_name: String,
+    description: String,
+    categories: [&'static str; 1],
+    severity: &'static str,
+    fingerprint: String,
+    location: Location,
+}
+
+#[derive(Serialize)]
+struct Location {
+    path: String,
+    lines: Lines,
+}
+
+#[derive(Serialize)]
+struct Lines {
+    begin: usize,
+    end: usize,
+}
+
+impl Issue {
+    fn new(result: &FunctionAnalysisResult, violation: &Violation) -> Self {
+        let path = result.source_file().unwrap_or_default();
+        let check_name = format!("fnloc/{}", violation.metric);
+        Self {
+            kind: "issue",
+            description: format!(
+                "Function {} has {} {} (limit {})",
+                result.name, violation.metric, violation.value, violation.limit
+            ),
+            categories: ["Complexity"],
+            severity: severity(violation),
+            // Stable across runs as long as the function keeps its name and file
+            fingerprint: fingerprint(&format!("{}:{check_name}", result.qualified_name())),
+            check_name,
+            location: Location {
+                path: path.strip_prefix("./").unwrap_or(path).to_string(),
+                lines: Lines {
+                    begin: result.start_line,
+                    end: result.end_line,
+                },
+            },
+        }
+    }
+}
+
+impl Format for CodeClimateFormat {
+    /// Formats the issues of a single function as single-line objects, one per line
+    fn row(&self, result: &FunctionAnalysisResult, options: &FormatOptions) -> String {
+        issues(result, options)
+            .iter()
+            .map(|issue| to_json(issue, false))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn render(&self, results: &[FunctionAnalysisResult], options: &FormatOptions) -> String {
+        let issues: Vec<Issue> = results
+            .iter()
+            .flat_map(|result| issues(result, options))
+            .collect();
+        to_json(&issues, options.json_style != JsonStyle::Compact)
+    }
+}
+
+/// Returns one issue per threshold the function exceeds
+fn issues(result: &FunctionAnalysisResult, options: &FormatOptions) -> Vec<Issue> {
+    options
+        .thresholds
+        .violations(result)
+        .iter()
+        .map(|violation| Issue::new(result, violation))
+        .collect()
+}
+
+/// Grades how far a metric exceeds its limit
+fn severity(violation: &Violation) -> &'static str {
+    let ratio = violation.value as f64 / violation.limit.max(1) as f64;
+    if ratio >= 2.0 {
+        "critical"
+    } else if ratio >= 1.5 {
+        "major"
+    } else {
+        "minor"
+    }
+}
+
+/// Hashes a key with 64-bit FNV-1a, which unlike `DefaultHasher` is stable across releases
+fn fingerprint(key: &str) -> String {
+    let hash = key.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{hash:016x}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Thresholds;
+    use std::path::PathBuf;
+
+    fn sample_result(complexity: usize) -> FunctionAnalysisResult {
+        FunctionAnalysisResult {
+            file: PathBuf::from("./src/lib.rs"),
+            total: 30,
+            comment: 3,
+            empty: 2,
+            nesting_depth: 2,
+            stmt_count: 10,
+            expr_count: 20,
+            start_line: 12,
+            end_line: 41,
+            ..FunctionAnalysisResult::sample("parse", 25, complexity)
+        }
+    }
+
+    fn options() -> FormatOptions {
+        FormatOptions {
+            thresholds: Thresholds {
+                complexity: Some(10),
+                nesting: Some(5),
+                ..Thresholds::default()
+            },
+            ..FormatOptions::default()
+        }
+    }
+
+    #[test]
+    fn test_issue_for_breached_threshold() {
+        let rendered = CodeClimateFormat.render(&[sample_result(25)], &options());
+        let issues: serde_json::Value = serde_json::from_str(&rendered).unwrap();
+        let issue = &issues[0];
+
+        assert_eq!(issues.as_array().unwrap().len(), 1);
+        assert_eq!(issue["type"], "issue");
+        assert_eq!(issue["check_name"], "fnloc/complexity");
+        assert_eq!(issue["severity"], "critical");
+        assert_eq!(issue["location"]["path"], "src/lib.rs");
+        assert_eq!(issue["location"]["lines"]["begin"], 12);
+        assert_eq!(issue["location"]["lines"]["end"], 41);
+        assert_eq!(issue["fingerprint"].as_str().unwrap().len(), 16);
+    }
+
+    #[test]
+    fn test_fingerprint_ignores_metric_value() {
+        let first = CodeClimateFormat.row(&sample_result(12), &options());
+        let second = CodeClimateFormat.row(&sample_result(14), &options());
+        let fingerprint = |row: &str| {
+            let issue: serde_json::Value = serde_json::from_str(row).unwrap();
+            issue["fingerprint"].as_str().unwrap().to_string()
+        };
+
+        assert_eq!(fingerprint(&first), fingerprint(&second));
+    }
+
+    #[test]
+    fn test_no_issues_within_limits() {
+        let rendered = CodeClimateFormat.render(&[sample_result(3)], &options());
+        assert_eq!(rendered, "[]");
+    }
+}
@@ -0,0 +1,270 @@
+use super::{FormatOptions, Totals};
+use crate::analyzer::FunctionAnalysisResult;
+
+/// A column that can be selected with `--columns` for table and CSV output
+///
+/// Columns are named like the labels of table rows; where JSON output uses a
+/// different key, that key is accepted as an alias.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Column {
+    /// Function name, including its file path
+    Name,
+    /// Total lines
+    Total,
+    /// Code lines
+    Code,
+    /// Comment lines
+    Comment,
+    /// Empty lines
+    Empty,
+    /// Code lines as a percentage of total lines
+    #[value(alias = "code_pct")]
+    CodePct,
+    /// Comment lines as a percentage of total lines
+    #[value(alias = "comment_pct")]
+    CommentPct,
+    /// Empty lines as a percentage of total lines
+    #[value(alias = "empty_pct")]
+    EmptyPct,
+    /// Cyclomatic complexity
+    Complexity,
+    /// Maximum nesting depth
+    Nesting,
+    /// Comment density percentage
+    #[value(alias = "comment_density")]
+    Density,
+    /// TODO/FIXME/HACK markers
+    #[value(alias = "todo_count")]
+    Todos,
+    /// Signature complexity
+    #[value(alias = "signature_complexity")]
+    Signature,
+    /// Timeout/retry protection of async functions
+    Resilience,
+    /// Letter grade from A to F, see `[grades]` in fnloc.toml
+    Grade,
+    /// Statement count
+    Stmts,
+    /// Expression count
+    Exprs,
+    /// Why a file could not be analyzed; empty for functions
+    Error,
+}
+
+/// Columns shown when none are selected, followed by `Stmts` and `Exprs` with `--ast-counts`
+pub const DEFAULT_COLUMNS: [Column; 11] = [
+    Column::Name,
+    Column::Total,
+    Column::Code,
+    Column::Comment,
+    Column::Empty,
+    Column::Complexity,
+    Column::Nesting,
+    Column::Density,
+    Column::Todos,
+    Column::Signature,
+    Column::Resilience,
+];
+
+impl Column {
+    /// Returns the column title used in CSV headers
+    pub fn title(self) -> &'static str {
+        match self {
+            Column::Name => "Function",
+            Column::Total => "Total Lines",
+            Column::Code => "Code Lines",
+            Column::Comment => "Comment Lines",
+            Column::Empty => "Empty Lines",
+            Column::CodePct => "Code %",
+            Column::CommentPct => "Comment %",
+            Column::EmptyPct => "Empty %",
+            Column::Complexity => "Cyclomatic Complexity",
+            Column::Nesting => "Nesting Depth",
+            Column::Density => "Comment Density",
+            Column::Todos => "TODO Count",
+            Column::Signature => "Signature Complexity",
+            Column::Resilience => "Resilience",
+            Column::Grade => "Grade",
+            Column::Stmts => "Statements",
+            Column::Exprs => "Expressions",
+            Column::Error => "Error",
+        }
+    }
+
+    /// Returns the plain value of the column for a function, as written to CSV
+    pub fn value(self, result: &FunctionAnalysisResult, options: &FormatOptions) -> String {
+        if !result.ast_metrics && self.needs_ast() {
+            return "n/a".to_string();
+        }
+        match self {
+            Column::Name => result.qualified_name(),
+            Column::Total => result.total.to_string(),
+            Column::Code => result.code.to_string(),
+            Column::Comment => result.comment.to_string(),
+            Column::Empty => result.empty.to_string(),
+            Column::CodePct => format!("{:.1}", percent_of_total(result.code, result)),
+            Column::CommentPct => format!("{:.1}", percent_of_total(result.comment, result)),
+            Column::EmptyPct => format!("{:.1}", percent_of_total(result.empty, result)),
+            Column::Complexity => result.cyclomatic_complexity.to_string(),
+            Column::Nesting => result.nesting_depth.to_string(),
+            Column::Density => format!("{:.1}", result.comment_density()),
+            Column::Todos => result.todo_count.to_string(),
+            Column::Signature => result.signature_complexity.to_string(),
+            Column::Resilience => result
+                .resilience
+                .map(|resilience| resilience.as_str())
+                .unwrap_or_default()
+                .to_string(),
+            Column::Grade => options.grades.grade(result).to_string(),
+            Column::Stmts => result.stmt_count.to_string(),
+            Column::Exprs => result.expr_count.to_string(),
+            Column::Error => String::new(),
+        }
+    }
+
+    /// Returns the value of the column in a totals row
+    ///
+    /// Line and count columns are summed, complexity-like columns take the
+    /// maximum, and percentages are computed over the summed lines.
+    pub fn total_value(self, totals: &Totals) -> String {
+        let percent = |lines: usize| {
+            if totals.total == 0 {
+                0.0
+            } else {
+                lines as f64 * 100.0 / totals.total as f64
+            }
+        };
+        match self {
+            Column::Name => format!("TOTAL ({} functions)", totals.functions),
+            Column::Total => totals.total.to_string(),
+            Column::Code => totals.code.to_string(),
+            Column::Comment => totals.comment.to_string(),
+            Column::Empty => totals.empty.to_string(),
+            Column::CodePct => format!("{:.1}", percent(totals.code)),
+            Column::CommentPct => format!("{:.1}", percent(totals.comment)),
+            Column::EmptyPct => format!("{:.1}", percent(totals.empty)),
+            Column::Complexity => totals.max_complexity.to_string(),
+            Column::Nesting => totals.max_nesting.to_string(),
+            Column::Density => {
+                let counted = totals.code + totals.comment;
+                let density = if counted == 0 {
+                    0.0
+                } else {
+                    totals.comment as f64 * 100.0 / counted as f64
+                };
+                format!("{density:.1}")
+            }
+            Column::Todos => totals.todos.to_string(),
+            Column::Signature => totals.max_signature.to_string(),
+            Column::Resilience | Column::Grade | Column::Error => String::new(),
+            Column::Stmts => totals.stmts.to_string(),
+            Column::Exprs => totals.exprs.to_string(),
+        }
+    }
+
+    /// Returns the labeled value of the column for a function, as shown in the table
+    pub fn table_cell(self, result: &FunctionAnalysisResult, options: &FormatOptions) -> String {
+        let value = self.value(result, options);
+        match self {
+            Column::Name => format!("fn {value}"),
+            Column::Total => format!("total={value} lines"),
+            Column::Density | Column::CodePct | Column::CommentPct | Column::EmptyPct => {
+                format!("{}={value}%", self.table_label())
+            }
+            Column::Resilience if value.is_empty() => "resilience=-".to_string(),
+            _ => format!("{}={value}", self.table_label()),
+        }
+    }
+
+    /// Returns the label of the column in table rows
+    fn table_label(self) -> &'static str {
+        match self {
+            Column::Name => "fn",
+            Column::Total => "total",
+            Column::Code => "code",
+            Column::Comment => "comment",
+            Column::Empty => "empty",
+            Column::CodePct => "code_pct",
+            Column::CommentPct => "comment_pct",
+            Column::EmptyPct => "empty_pct",
+            Column::Complexity => "complexity",
+            Column::Nesting => "nesting",
+            Column::Density => "density",
+            Column::Todos => "todos",
+            Column::Signature => "signature",
+            Column::Resilience => "resilience",
+            Column::Grade => "grade",
+            Column::Stmts => "stmts",
+            Column::Exprs => "exprs",
+            Column::Error => "error",
+        }
+    }
+
+    /// Returns true if the column is computed from the syntax tree, and so is
+    /// unavailable for functions found by the heuristic fallback
+    fn needs_ast(self) -> bool {
+        matches!(
+            self,
+            Column::Complexity
+                | Column::Nesting
+                | Column::Signature
+                | Column::Stmts
+                | Column::Exprs
+        )
+    }
+}
+
+/// Returns a line count as a percentage of the function's total lines
+fn percent_of_total(lines: usize, result: &FunctionAnalysisResult) -> f64 {
+    if result.total == 0 {
+        0.0
+    } else {
+        lines as f64 * 100.0 / result.total as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::ValueEnum;
+
+    #[test]
+    fn test_json_keys_are_accepted() {
+        assert_eq!(
+            Column::from_str("comment_density", false),
+            Ok(Column::Density)
+        );
+        assert_eq!(Column::from_str("todo_count", false), Ok(Column::Todos));
+        assert!(Column::from_str("lines", false).is_err());
+    }
+
+    #[test]
+    fn test_percentage_columns() {
+        let result = FunctionAnalysisResult {
+            total: 8,
+            comment: 5,
+            empty: 1,
+            end_line: 8,
+            ..FunctionAnalysisResult::sample("stub", 2, 1)
+        };
+
+        let options = FormatOptions::default();
+        assert_eq!(Column::CodePct.value(&result, &options), "25.0");
+        assert_eq!(
+            Column::CommentPct.table_cell(&result, &options),
+            "comment_pct=62.5%"
+        );
+        assert_eq!(Column::Grade.table_cell(&result, &options), "grade=A");
+        assert_eq!(Column::from_str("empty_pct", false), Ok(Column::EmptyPct));
+
+        let estimated = FunctionAnalysisResult {
+            ast_metrics: false,
+            ..result
+        };
+        assert_eq!(
+            Column::Complexity.table_cell(&estimated, &options),
+            "complexity=n/a"
+        );
+        assert_eq!(Column::Code.value(&estimated, &options), "2");
+    }
+}
@@ -0,0 +1,176 @@
+use super::{Column, Format, FormatOptions, Totals};
+use crate::analyzer::FunctionAnalysisResult;
+use crate::report::FileError;
+
+/// RFC 4180 comma-separated values with a header line
+///
+/// Fields containing the delimiter, quotes, or line breaks are quoted. The
+/// delimiter is configurable, e.g. a tab for TSV output. Files that could not
+/// be analyzed get a record of their own, with the path in the function column
+/// and the reason in the error column that ends the default columns.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CsvFormat;
+
+impl Format for CsvFormat {
+    fn header(&self, _file_count: usize, options: &FormatOptions) -> Option<String> {
+        if options.no_header {
+            return None;
+        }
+        let titles: Vec<&str> = csv_columns(options)
+            .into_iter()
+            .map(Column::title)
+            .collect();
+        Some(write_record(&titles, options.csv_delimiter))
+    }
+
+    fn row(&self, result: &FunctionAnalysisResult, options: &FormatOptions) -> String {
+        let fields: Vec<String> = csv_columns(options)
+            .into_iter()
+            .map(|column| column.value(result, options))
+            .collect();
+        write_record(&fields, options.csv_delimiter)
+    }
+
+    /// Leaves the metric fields empty; without a selected error column the record has no reason
+    fn error_row(&self, error: &FileError, options: &FormatOptions) -> Option<String> {
+        let fields: Vec<&str> = csv_columns(options)
+            .into_iter()
+            .map(|column| match column {
+                Column::Name => error.file.as_str(),
+                Column::Error => error.error.as_str(),
+                _ => "",
+            })
+            .collect();
+        Some(write_record(&fields, options.csv_delimiter))
+    }
+
+    /// Writes one record per function, followed by a totals record with `--totals`
+    fn render(&self, results: &[FunctionAnalysisResult], options: &FormatOptions) -> String {
+        let mut records: Vec<String> = results
+            .iter()
+            .map(|result| self.row(result, options))
+            .collect();
+        if options.totals && !results.is_empty() {
+            let totals = Totals::new(results);
+            let fields: Vec<String> = csv_columns(options)
+                .into_iter()
+                .map(|column| column.total_value(&totals))
+                .collect();
+            records.push(write_record(&fields, options.csv_delimiter));
+        }
+        records.join("\n")
+    }
+}
+
+/// Returns the columns of CSV records: the selected columns, or the default
+/// columns followed by the error column
+fn csv_columns(options: &FormatOptions) -> Vec<Column> {
+    let mut columns = options.columns();
+    if options.columns.is_none() {
+        columns.push(Column::Error);
+    }
+    columns
+}
+
+/// Writes a single record with RFC 4180 quoting, without the line terminator
+fn write_record<T: AsRef<[u8]>>(fields: &[T], delimiter: u8) -> String {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(Vec::new());
+    // Writing to an in-memory buffer cannot fail
+    let _ = writer.write_record(fields);
+    let bytes = writer.into_inner().unwrap_or_default();
+    String::from_utf8_lossy(&bytes)
+        .trim_end_matches(['\r', '\n'])
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_result(name: &str) -> FunctionAnalysisResult {
+        FunctionAnalysisResult {
+            total: 5,
+            comment: 1,
+            empty: 1,
+            stmt_count: 1,
+            expr_count: 1,
+            end_line: 5,
+            ..FunctionAnalysisResult::sample(name, 3, 1)
+        }
+    }
+
+    #[test]
+    fn test_names_with_delimiters_are_quoted() {
+        let row = CsvFormat.row(&sample_result("map<K, V>"), &FormatOptions::default());
+        assert_eq!(row, "\"map<K, V>\",5,3,1,1,1,0,25.0,0,0,,");
+
+        let row = CsvFormat.row(&sample_result("say \"hi\""), &FormatOptions::default());
+        assert!(row.starts_with("\"say \"\"hi\"\"\","));
+    }
+
+    #[test]
+    fn test_selected_columns_in_order() {
+        let options = FormatOptions {
+            columns: Some(vec![Column::Complexity, Column::Name]),
+            ..FormatOptions::default()
+        };
+
+        let header = CsvFormat.header(0, &options).unwrap();
+        assert_eq!(header, "Cyclomatic Complexity,Function");
+
+        let no_header = FormatOptions {
+            no_header: true,
+            ..options.clone()
+        };
+        assert_eq!(CsvFormat.header(0, &no_header), None);
+        assert_eq!(CsvFormat.row(&sample_result("a"), &options), "1,a");
+    }
+
+    #[test]
+    fn test_tab_delimiter() {
+        let options = FormatOptions {
+            csv_delimiter: b'\t',
+            ..FormatOptions::default()
+        };
+
+        let header = CsvFormat.header(0, &options).unwrap();
+        assert!(header.starts_with("Function\tTotal Lines\t"));
+        assert_eq!(
+            CsvFormat.row(&sample_result("map<K, V>"), &options),
+            "map<K, V>\t5\t3\t1\t1\t1\t0\t25.0\t0\t0\t\t"
+        );
+    }
+
+    #[test]
+    fn test_totals_record() {
+        let options = FormatOptions {
+            totals: true,
+            columns: Some(vec![Column::Name, Column::Code, Column::Complexity]),
+            ..FormatOptions::default()
+        };
+        let results = [sample_result("a"), sample_result("b")];
+
+        assert_eq!(
+            CsvFormat.render(&results, &options),
+            "a,3,1\nb,3,1\nTOTAL (2 functions),6,1"
+        );
+    }
+
+    #[test]
+    fn test_file_error_record() {
+        let error = FileError {
+            file: "src/broken.rs".to_string(),
+            error: "expected `;`".to_string(),
+        };
+        let options = FormatOptions::default();
+
+        let header = CsvFormat.header(0, &options).unwrap();
+        assert!(header.ends_with(",Resilience,Error"));
+        assert_eq!(
+            CsvFormat.error_row(&error, &options).unwrap(),
+            "src/broken.rs,,,,,,,,,,,expected `;`"
+        );
+    }
+}
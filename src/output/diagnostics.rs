@@ -0,0 +1,112 @@
+use super::{Format, FormatOptions};
+use crate::analyzer::FunctionAnalysisResult;
+use crate::config::Violation;
+use crate::report::FileError;
+
+/// Compiler-style warnings, one line per exceeded threshold
+///
+/// Lines read `path:line:col: warning[fnloc::metric]: message`, which the
+/// error parsers of vim (`:cfile`), VS Code problem matchers, and most
+/// terminals recognize, so each line jumps to the function it is about. Only
+/// functions breaching the configured thresholds appear; files that could not
+/// be analyzed are reported as errors.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DiagnosticsFormat;
+
+impl Format for DiagnosticsFormat {
+    fn row(&self, result: &FunctionAnalysisResult, options: &FormatOptions) -> String {
+        options
+            .thresholds
+            .violations(result)
+            .iter()
+            .map(|violation| warning(result, violation))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Formats the warnings of all functions, skipping those within the limits
+    fn render(&self, results: &[FunctionAnalysisResult], options: &FormatOptions) -> String {
+        results
+            .iter()
+            .map(|result| self.row(result, options))
+            .filter(|row| !row.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn error_row(&self, error: &FileError, _options: &FormatOptions) -> Option<String> {
+        Some(format!(
+            "{}:1:1: error[fnloc::analysis]: {}",
+            display_path(&error.file),
+            error.error
+        ))
+    }
+}
+
+fn warning(result: &FunctionAnalysisResult, violation: &Violation) -> String {
+    format!(
+        "{}:{}:1: warning[fnloc::{}]: fn {} has {} {} (limit {})",
+        display_path(result.source_file().unwrap_or_default()),
+        result.start_line,
+        violation.metric,
+        result.name,
+        violation.metric,
+        violation.value,
+        violation.limit
+    )
+}
+
+/// Returns the path without a leading `./`, as compilers print it
+fn display_path(path: &str) -> &str {
+    path.strip_prefix("./").unwrap_or(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Thresholds;
+    use std::path::PathBuf;
+
+    fn sample_result(name: &str, complexity: usize) -> FunctionAnalysisResult {
+        FunctionAnalysisResult {
+            file: PathBuf::from("./src/lib.rs"),
+            total: 30,
+            comment: 3,
+            empty: 2,
+            nesting_depth: 2,
+            stmt_count: 10,
+            expr_count: 20,
+            start_line: 12,
+            end_line: 41,
+            ..FunctionAnalysisResult::sample(name, 25, complexity)
+        }
+    }
+
+    #[test]
+    fn test_one_line_per_exceeded_threshold() {
+        let options = FormatOptions {
+            thresholds: Thresholds {
+                code: Some(30),
+                complexity: Some(15),
+                ..Thresholds::default()
+            },
+            ..FormatOptions::default()
+        };
+        let errors = [FileError {
+            file: "./src/broken.rs".to_string(),
+            error: "Parse error".to_string(),
+        }];
+
+        let rendered = DiagnosticsFormat.render_with_errors(
+            &[sample_result("foo", 18), sample_result("small", 1)],
+            &errors,
+            &options,
+        );
+
+        assert_eq!(
+            rendered,
+            "src/lib.rs:12:1: warning[fnloc::complexity]: fn foo has complexity 18 (limit 15)\n\
+             src/broken.rs:1:1: error[fnloc::analysis]: Parse error"
+        );
+    }
+}
@@ -0,0 +1,121 @@
+//! Links from table rows to the functions in an editor, for `--editor-links`
+//!
+//! Commands are printed on an indented line below each row, ready to paste
+//! into a shell. OSC 8 hyperlinks instead turn the function name into a
+//! `file://` link that supporting terminals open with one click; the line
+//! number is passed as the URI fragment, which terminals such as kitty hand to
+//! the editor.
+
+use crate::analyzer::FunctionAnalysisResult;
+use crate::client::EditorLinks;
+use std::path::Path;
+
+/// Returns the row followed by the command opening its function, if commands were requested
+pub fn with_command(
+    row: String,
+    result: &FunctionAnalysisResult,
+    links: Option<EditorLinks>,
+) -> String {
+    let Some(file) = result.source_file() else {
+        return row;
+    };
+    let file = shell_quote(file);
+    let line = result.start_line;
+    match links {
+        Some(EditorLinks::Code) => format!("{row}\n    code --goto {file}:{line}"),
+        Some(EditorLinks::Editor) => format!("{row}\n    $EDITOR +{line} {file}"),
+        Some(EditorLinks::Osc8) | None => row,
+    }
+}
+
+/// Wraps text in an OSC 8 hyperlink to the first line of the function
+pub fn hyperlink(text: String, result: &FunctionAnalysisResult) -> String {
+    let Some(file) = result.source_file() else {
+        return text;
+    };
+    let path = Path::new(file);
+    let absolute = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        match std::env::current_dir() {
+            Ok(dir) => dir.join(path),
+            Err(_) => return text,
+        }
+    };
+    // Windows paths such as `C:\src` become `/C:/src`
+    let mut path = absolute.to_string_lossy().replace('\\', "/");
+    if !path.starts_with('/') {
+        path.insert(0, '/');
+    }
+    let uri = format!("file://{}#{}", percent_encode(&path), result.start_line);
+    format!("\x1b]8;;{uri}\x1b\\{text}\x1b]8;;\x1b\\")
+}
+
+/// Quotes a path for POSIX shells if it contains anything but safe characters
+fn shell_quote(path: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "/._-+:@,".contains(c);
+    if path.chars().all(safe) {
+        path.to_string()
+    } else {
+        format!("'{}'", path.replace('\'', r"'\''"))
+    }
+}
+
+/// Percent-encodes a path for a URI, keeping unreserved characters and slashes
+fn percent_encode(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-._~:".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn result_in(file: &str) -> FunctionAnalysisResult {
+        FunctionAnalysisResult {
+            file: PathBuf::from(file),
+            stmt_count: 1,
+            expr_count: 1,
+            start_line: 12,
+            end_line: 14,
+            ..FunctionAnalysisResult::sample("parse", 3, 1)
+        }
+    }
+
+    #[test]
+    fn test_commands_open_the_first_line() {
+        let result = result_in("src/lib.rs");
+        let row = || "  - parse".to_string();
+
+        assert_eq!(
+            with_command(row(), &result, Some(EditorLinks::Code)),
+            "  - parse\n    code --goto src/lib.rs:12"
+        );
+        assert_eq!(
+            with_command(row(), &result, Some(EditorLinks::Editor)),
+            "  - parse\n    $EDITOR +12 src/lib.rs"
+        );
+        assert_eq!(
+            with_command(row(), &result_in("my src/a.rs"), Some(EditorLinks::Editor)),
+            "  - parse\n    $EDITOR +12 'my src/a.rs'"
+        );
+        assert_eq!(with_command(row(), &result, Some(EditorLinks::Osc8)), row());
+    }
+
+    #[test]
+    fn test_hyperlink_to_absolute_file_uri() {
+        let linked = hyperlink("parse".to_string(), &result_in("/work/my crate/lib.rs"));
+        assert_eq!(
+            linked,
+            "\x1b]8;;file:///work/my%20crate/lib.rs#12\x1b\\parse\x1b]8;;\x1b\\"
+        );
+    }
+}
@@ -0,0 +1,809 @@
+use super::registry::builtin_format;
+use super::table::{health_summary, stats_summary, summary_table};
+use super::{Column, Format, FormatOptions, FormatRegistry, Formatter, JsonStyle, ReportSections};
+use crate::advisor::{ParameterStructSuggestion, RefactoringSuggestion};
+use crate::analyzer::FunctionAnalysisResult;
+use crate::client::{EditorLinks, GroupBy, OutputFormat, SortBy};
+use crate::config::Thresholds;
+use crate::duplicates::{DuplicateGroup, SimilarPair};
+use crate::grades::GradeBoundaries;
+use crate::ownership::Owner;
+use crate::panic_report::{PanicReachability, PanicReportEntry};
+use crate::report::{AnalysisReport, FileError};
+use crate::rules::RuleMatch;
+use crate::type_complexity::TypeComplexity;
+use crate::unreferenced::UnreferencedFunction;
+use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::fs::{self, File};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Handles the formatting and display of analysis results
+pub struct OutputFormatter {
+    format_name: String,
+    format: Arc<dyn Format>,
+    options: FormatOptions,
+    sort_by: SortBy,
+    limit: Option<usize>,
+    /// Destination of the results and reports; buffered stdout unless another writer is set
+    output: RefCell<Box<dyn Write>>,
+    /// Writes whole reports in place of the format, if set
+    formatter: Option<RefCell<Box<dyn Formatter>>>,
+}
+
+impl OutputFormatter {
+    /// Creates a new OutputFormatter instance with default table format
+    pub fn new() -> Self {
+        Self::with_format(OutputFormat::Table)
+    }
+
+    /// Creates a new OutputFormatter instance with specified format
+    pub fn with_format(format: OutputFormat) -> Self {
+        Self {
+            format_name: format.name().to_string(),
+            format: builtin_format(&format),
+            options: FormatOptions::default(),
+            sort_by: SortBy::default(),
+            limit: None,
+            output: RefCell::new(stdout_writer()),
+            formatter: None,
+        }
+    }
+
+    /// Creates an OutputFormatter for a format or formatter registered under the given name
+    /// Returns None if no such format is registered
+    pub fn from_registry(registry: &FormatRegistry, name: &str) -> Option<Self> {
+        if let Some(formatter) = registry.formatter(name) {
+            return Some(
+                Self {
+                    format_name: name.to_string(),
+                    ..Self::new()
+                }
+                .with_boxed_formatter(formatter),
+            );
+        }
+        registry.get(name).map(|format| Self {
+            format_name: name.to_string(),
+            format,
+            options: FormatOptions::default(),
+            sort_by: SortBy::default(),
+            limit: None,
+            output: RefCell::new(stdout_writer()),
+            formatter: None,
+        })
+    }
+
+    /// Enables or disables the statement and expression count columns
+    pub fn with_ast_counts(mut self, show_ast_counts: bool) -> Self {
+        self.options.show_ast_counts = show_ast_counts;
+        self
+    }
+
+    /// Sets the layout of JSON output
+    pub fn with_json_style(mut self, json_style: JsonStyle) -> Self {
+        self.options.json_style = json_style;
+        self
+    }
+
+    /// Sets the field delimiter of CSV output
+    pub fn with_csv_delimiter(mut self, delimiter: u8) -> Self {
+        self.options.csv_delimiter = delimiter;
+        self
+    }
+
+    /// Enables or disables ANSI colors in table output
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.options.color = color;
+        self
+    }
+
+    /// Sets the limits highlighted in colored tables and reported by the Code Climate format
+    pub fn with_thresholds(mut self, thresholds: Thresholds) -> Self {
+        self.options.thresholds = thresholds;
+        self
+    }
+
+    /// Selects the columns of table and CSV output, in order; None shows the default columns
+    pub fn with_columns(mut self, columns: Option<Vec<Column>>) -> Self {
+        self.options.columns = columns;
+        self
+    }
+
+    /// Groups table rows into sections with subtotals; None lists them flat
+    pub fn with_group_by(mut self, group_by: Option<GroupBy>) -> Self {
+        self.options.group_by = group_by;
+        self
+    }
+
+    /// Appends a row adding up all listed functions to table and CSV output
+    pub fn with_totals(mut self, totals: bool) -> Self {
+        self.options.totals = totals;
+        self
+    }
+
+    /// Suppresses the banner printed before table output
+    pub fn with_quiet(mut self, quiet: bool) -> Self {
+        self.options.quiet = quiet;
+        self
+    }
+
+    /// Suppresses the column titles of CSV output
+    pub fn with_no_header(mut self, no_header: bool) -> Self {
+        self.options.no_header = no_header;
+        self
+    }
+
+    /// Aligns table rows to a line width; None keeps compact rows
+    pub fn with_width(mut self, width: Option<usize>) -> Self {
+        self.options.width = width;
+        self
+    }
+
+    /// Enables or disables shortening function names to fit aligned table rows
+    pub fn with_truncate_names(mut self, truncate_names: bool) -> Self {
+        self.options.truncate_names = truncate_names;
+        self
+    }
+
+    /// Sets the boundaries of the letter grades shown in the grade column
+    pub fn with_grades(mut self, grades: GradeBoundaries) -> Self {
+        self.options.grades = grades;
+        self
+    }
+
+    /// Sets the editor commands or hyperlinks added to table rows
+    pub fn with_editor_links(mut self, editor_links: Option<EditorLinks>) -> Self {
+        self.options.editor_links = editor_links;
+        self
+    }
+
+    /// Sets the order in which results are displayed
+    pub fn with_sort(mut self, sort_by: SortBy) -> Self {
+        self.sort_by = sort_by;
+        self
+    }
+
+    /// Limits the displayed results to the first N in sort order
+    pub fn with_limit(mut self, limit: Option<usize>) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Writes the results and reports to a file instead of stdout
+    /// Missing parent directories are created and an existing file is replaced
+    pub fn with_output_file(self, path: &Path) -> io::Result<Self> {
+        let file = create_output_file(path)?;
+        Ok(self.with_writer(io::BufWriter::new(file)))
+    }
+
+    /// Writes the results and reports to the given writer instead of stdout
+    ///
+    /// The writer is used as is; wrap unbuffered writers in a `BufWriter`.
+    pub fn with_writer(self, writer: impl Write + 'static) -> Self {
+        *self.output.borrow_mut() = Box::new(writer);
+        self
+    }
+
+    /// Writes whole reports with the given formatter instead of the configured format
+    ///
+    /// The formatter receives every function of the report; sorting and the limit do not apply.
+    pub fn with_formatter(self, formatter: impl Formatter + 'static) -> Self {
+        self.with_boxed_formatter(Box::new(formatter))
+    }
+
+    fn with_boxed_formatter(mut self, formatter: Box<dyn Formatter>) -> Self {
+        self.formatter = Some(RefCell::new(formatter));
+        self
+    }
+
+    /// Displays the header information for the analysis
+    pub fn display_analysis_header(&self, file_count: usize) -> io::Result<()> {
+        if self.formatter.is_some() {
+            return Ok(());
+        }
+        match self.format.header(file_count, &self.options) {
+            Some(header) => self.write_line(&header),
+            None => Ok(()),
+        }
+    }
+
+    /// Displays all analysis results in the configured sort order
+    pub fn display_results(&self, results: &[FunctionAnalysisResult]) -> io::Result<()> {
+        let rendered = self.render_results(results);
+        if rendered.is_empty() {
+            return Ok(());
+        }
+        self.write_line(&rendered)
+    }
+
+    /// Formats all analysis results in the configured sort order
+    pub fn render_results(&self, results: &[FunctionAnalysisResult]) -> String {
+        self.format.render(&self.sorted(results), &self.options)
+    }
+
+    /// Displays all analysis results in the configured sort order, followed by the requested sections
+    /// Formats that cannot embed the sections get them on stderr instead
+    pub fn display_results_with_sections(
+        &self,
+        results: &[FunctionAnalysisResult],
+        sections: &ReportSections,
+    ) -> io::Result<()> {
+        let sorted_results = self.sorted(results);
+        if sections.is_empty() {
+            return self.display_results_with_errors(&sorted_results, sections.file_errors);
+        }
+        match self
+            .format
+            .render_with_sections(&sorted_results, sections, &self.options)
+        {
+            Some(rendered) => self.write_line(&rendered),
+            None => {
+                self.display_results_with_errors(&sorted_results, sections.file_errors)?;
+                self.display_sections(sections);
+                Ok(())
+            }
+        }
+    }
+
+    /// Displays results already in order, followed by an entry per file that could not be analyzed
+    fn display_results_with_errors(
+        &self,
+        results: &[FunctionAnalysisResult],
+        errors: &[FileError],
+    ) -> io::Result<()> {
+        let rendered = self
+            .format
+            .render_with_errors(results, errors, &self.options);
+        if rendered.is_empty() {
+            return Ok(());
+        }
+        self.write_line(&rendered)
+    }
+
+    /// Displays the functions of a report in the configured sort order, followed by the requested sections
+    ///
+    /// Streaming formats have already written the rows, so only the sections are displayed.
+    pub fn display_report(
+        &self,
+        report: &AnalysisReport,
+        sections: &ReportSections,
+    ) -> io::Result<()> {
+        if let Some(formatter) = &self.formatter {
+            formatter
+                .borrow_mut()
+                .write_report(report, &mut **self.output.borrow_mut())?;
+            self.display_sections(sections);
+            Ok(())
+        } else if self.is_streaming() {
+            // Failed files are only known once every file was analyzed
+            for error in sections.file_errors {
+                if let Some(row) = self.format.error_row(error, &self.options) {
+                    self.write_line(&row)?;
+                }
+            }
+            self.display_sections(sections);
+            Ok(())
+        } else {
+            self.display_results_with_sections(&report.functions, sections)
+        }
+    }
+
+    /// Displays the requested sections on stderr, keeping stdout limited to the results
+    pub fn display_sections(&self, sections: &ReportSections) {
+        if let Some(health) = sections.health {
+            eprintln!("{}", health_summary(health));
+        }
+        if let Some(summary) = sections.summary {
+            eprintln!("Summary:\n{}", summary_table(summary));
+        }
+        if let Some(histogram) = sections.histogram {
+            eprintln!("{}", histogram.to_ascii());
+        }
+        if let Some(stats) = sections.stats {
+            eprintln!("Run statistics: {}", stats_summary(stats));
+        }
+    }
+
+    /// Returns true if results should be displayed per file as they are analyzed
+    pub fn is_streaming(&self) -> bool {
+        self.formatter.is_none() && self.format.is_streaming()
+    }
+
+    /// Displays rows for a batch of results immediately, in the given order
+    pub fn display_streamed_rows<'a>(
+        &self,
+        results: impl IntoIterator<Item = &'a FunctionAnalysisResult>,
+    ) -> io::Result<()> {
+        let mut output = self.output.borrow_mut();
+        let written = results
+            .into_iter()
+            .try_for_each(|result| writeln!(output, "{}", self.format_function_row(result)))
+            .and_then(|()| output.flush());
+        match written {
+            // A closed pipe (e.g. `| head`) only means nobody reads further rows
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+            written => written,
+        }
+    }
+
+    /// Flushes everything written so far to the output
+    pub fn flush(&self) -> io::Result<()> {
+        self.output.borrow_mut().flush()
+    }
+
+    /// Writes text followed by a newline to the output
+    fn write_line(&self, text: &str) -> io::Result<()> {
+        writeln!(self.output.borrow_mut(), "{text}")
+    }
+
+    /// Returns the results in the configured sort order, truncated to the configured limit
+    /// Numeric metrics sort largest first; ties keep their original order
+    pub fn sorted(&self, results: &[FunctionAnalysisResult]) -> Vec<FunctionAnalysisResult> {
+        let mut sorted_results = results.to_vec();
+        match self.sort_by {
+            SortBy::Total => sorted_results.sort_by_key(|result| Reverse(result.total)),
+            SortBy::Code => sorted_results.sort_by_key(|result| Reverse(result.code)),
+            SortBy::Comment => sorted_results.sort_by_key(|result| Reverse(result.comment)),
+            SortBy::Complexity => {
+                sorted_results.sort_by_key(|result| Reverse(result.cyclomatic_complexity))
+            }
+            SortBy::Nesting => sorted_results.sort_by_key(|result| Reverse(result.nesting_depth)),
+            SortBy::Name => {
+                sorted_results.sort_by(|a, b| (&a.file, &a.name).cmp(&(&b.file, &b.name)))
+            }
+        }
+        if let Some(limit) = self.limit {
+            sorted_results.truncate(limit);
+        }
+        sorted_results
+    }
+
+    /// Displays parameter struct suggestions for long parameter lists
+    /// Suggestions are only shown in table format to keep machine-readable output valid
+    pub fn display_parameter_struct_suggestions(
+        &self,
+        suggestions: &[ParameterStructSuggestion],
+        max_params: usize,
+    ) -> io::Result<()> {
+        self.write_report(self.render_parameter_struct_suggestions(suggestions, max_params))
+    }
+
+    /// Formats parameter struct suggestions, or returns None if they are not shown
+    pub fn render_parameter_struct_suggestions(
+        &self,
+        suggestions: &[ParameterStructSuggestion],
+        max_params: usize,
+    ) -> Option<String> {
+        if !self.format.shows_reports() || suggestions.is_empty() {
+            return None;
+        }
+
+        let mut lines = vec![format!(
+            "\nParameter struct suggestions (functions with more than {max_params} parameters):"
+        )];
+        for suggestion in suggestions {
+            lines.push(format!(
+                "  - {} shared by: {}",
+                suggestion.struct_definition(),
+                suggestion.functions.join(", ")
+            ));
+        }
+        Some(lines.join("\n"))
+    }
+
+    /// Displays Weighted Methods per Class for each type, in table format only
+    pub fn display_type_complexity(&self, types: &[TypeComplexity]) -> io::Result<()> {
+        self.write_report(self.render_type_complexity(types))
+    }
+
+    /// Formats Weighted Methods per Class, or returns None if it is not shown
+    pub fn render_type_complexity(&self, types: &[TypeComplexity]) -> Option<String> {
+        if !self.format.shows_reports() || types.is_empty() {
+            return None;
+        }
+
+        let mut lines = vec!["\nWeighted Methods per Class:".to_string()];
+        for entry in types {
+            lines.push(format!(
+                "  - type {}: wmc={}, methods={}, max_complexity={}",
+                entry.type_name,
+                entry.weighted_methods,
+                entry.method_count,
+                entry.max_method_complexity
+            ));
+        }
+        Some(lines.join("\n"))
+    }
+
+    /// Displays the functions meeting a rule of `fnloc.toml`, in table format only
+    pub fn display_rule_matches(&self, matches: &[RuleMatch]) -> io::Result<()> {
+        self.write_report(self.render_rule_matches(matches))
+    }
+
+    /// Formats the rule matches, or returns None if they are not shown
+    pub fn render_rule_matches(&self, matches: &[RuleMatch]) -> Option<String> {
+        if !self.format.shows_reports() {
+            return None;
+        }
+
+        let mut lines = vec!["\nRule violations:".to_string()];
+        if matches.is_empty() {
+            lines.push("  none".to_string());
+        }
+        for rule_match in matches {
+            lines.push(format!(
+                "  - fn {}: {} ({})",
+                rule_match.function, rule_match.rule, rule_match.condition
+            ));
+        }
+        Some(lines.join("\n"))
+    }
+
+    /// Displays the groups of functions with identical bodies, in table format only
+    pub fn display_duplicates(&self, groups: &[DuplicateGroup]) -> io::Result<()> {
+        self.write_report(self.render_duplicates(groups))
+    }
+
+    /// Formats the duplicate groups, or returns None if they are not shown
+    pub fn render_duplicates(&self, groups: &[DuplicateGroup]) -> Option<String> {
+        if !self.format.shows_reports() {
+            return None;
+        }
+
+        let mut lines = vec!["\nDuplicate functions:".to_string()];
+        if groups.is_empty() {
+            lines.push("  none".to_string());
+        }
+        for group in groups {
+            let copies: Vec<String> = group
+                .locations
+                .iter()
+                .map(|location| format!("{} (line {})", location.function, location.start_line))
+                .collect();
+            lines.push(format!(
+                "  - {} copies of {} lines: {}",
+                group.locations.len(),
+                group.lines,
+                copies.join(", ")
+            ));
+        }
+        Some(lines.join("\n"))
+    }
+
+    /// Displays the near-duplicate function pairs, in table format only
+    pub fn display_similar_functions(&self, pairs: &[SimilarPair], percent: u8) -> io::Result<()> {
+        self.write_report(self.render_similar_functions(pairs, percent))
+    }
+
+    /// Formats the near-duplicate pairs, or returns None if they are not shown
+    pub fn render_similar_functions(&self, pairs: &[SimilarPair], percent: u8) -> Option<String> {
+        if !self.format.shows_reports() {
+            return None;
+        }
+
+        let mut lines = vec![format!(
+            "\nNear-duplicate functions (at least {percent}% similar):"
+        )];
+        if pairs.is_empty() {
+            lines.push("  none".to_string());
+        }
+        for pair in pairs {
+            lines.push(format!(
+                "  - {:.0}%: {} (line {}), {} (line {})",
+                pair.similarity * 100.0,
+                pair.first.function,
+                pair.first.start_line,
+                pair.second.function,
+                pair.second.start_line
+            ));
+        }
+        Some(lines.join("\n"))
+    }
+
+    /// Displays the owners of the most complex code, in table format only
+    pub fn display_owners(&self, owners: &[Owner]) -> io::Result<()> {
+        self.write_report(self.render_owners(owners))
+    }
+
+    /// Formats the code ownership report, or returns None if it is not shown
+    pub fn render_owners(&self, owners: &[Owner]) -> Option<String> {
+        if !self.format.shows_reports() {
+            return None;
+        }
+
+        let mut lines = vec!["\nOwners of complex code:".to_string()];
+        if owners.is_empty() {
+            lines.push("  none".to_string());
+        }
+        let total: f64 = owners.iter().map(|owner| owner.complexity).sum();
+        for owner in owners {
+            let share = if total > 0.0 {
+                owner.complexity / total * 100.0
+            } else {
+                0.0
+            };
+            lines.push(format!(
+                "  - {}: complexity {:.1} ({share:.0}%), main author of {} functions, {} lines",
+                owner.name, owner.complexity, owner.functions, owner.lines
+            ));
+        }
+        Some(lines.join("\n"))
+    }
+
+    /// Displays the refactoring hints of functions over their limits, in table format only
+    pub fn display_refactoring_suggestions(
+        &self,
+        suggestions: &[RefactoringSuggestion],
+    ) -> io::Result<()> {
+        self.write_report(self.render_refactoring_suggestions(suggestions))
+    }
+
+    /// Formats the refactoring hints, or returns None if they are not shown
+    pub fn render_refactoring_suggestions(
+        &self,
+        suggestions: &[RefactoringSuggestion],
+    ) -> Option<String> {
+        if !self.format.shows_reports() {
+            return None;
+        }
+
+        let mut lines = vec!["\nRefactoring hints:".to_string()];
+        if suggestions.is_empty() {
+            lines.push("  none".to_string());
+        }
+        for suggestion in suggestions {
+            lines.push(format!(
+                "  - fn {} (line {}):",
+                suggestion.function, suggestion.start_line
+            ));
+            for hint in &suggestion.hints {
+                lines.push(format!("      {}: {}", hint.metric, hint.message));
+            }
+        }
+        Some(lines.join("\n"))
+    }
+
+    /// Displays the private functions without references, in table format only
+    pub fn display_unreferenced(&self, functions: &[UnreferencedFunction]) -> io::Result<()> {
+        self.write_report(self.render_unreferenced(functions))
+    }
+
+    /// Formats the unreferenced functions, or returns None if they are not shown
+    pub fn render_unreferenced(&self, functions: &[UnreferencedFunction]) -> Option<String> {
+        if !self.format.shows_reports() {
+            return None;
+        }
+
+        let mut lines = vec![
+            "\nUnreferenced private functions (heuristic, check before deleting):".to_string(),
+        ];
+        if functions.is_empty() {
+            lines.push("  none".to_string());
+        }
+        for function in functions {
+            lines.push(format!(
+                "  - fn {} (line {}): code={} cc={}",
+                function.function, function.start_line, function.code, function.complexity
+            ));
+        }
+        Some(lines.join("\n"))
+    }
+
+    /// Displays the panic reachability report
+    /// Only functions that can panic or return a Result are listed, in table format only
+    pub fn display_panic_report(&self, entries: &[PanicReportEntry]) -> io::Result<()> {
+        self.write_report(self.render_panic_report(entries))
+    }
+
+    /// Formats the panic reachability report, or returns None if it is not shown
+    pub fn render_panic_report(&self, entries: &[PanicReportEntry]) -> Option<String> {
+        if !self.format.shows_reports() {
+            return None;
+        }
+
+        let mut lines = vec!["\nPanic reachability report:".to_string()];
+        for entry in entries {
+            let panics = match &entry.reachability {
+                PanicReachability::Direct { sites } => format!("panics directly ({sites} sites)"),
+                PanicReachability::Transitive { via } => format!("may panic via {via}"),
+                PanicReachability::None if entry.returns_result => "no panic found".to_string(),
+                PanicReachability::None => continue,
+            };
+            let handling = if entry.returns_result {
+                let callers = &entry.caller_handling;
+                format!(
+                    "; returns Result (callers: {} propagate, {} handle, {} unwrap)",
+                    callers.propagated, callers.handled, callers.unwrapped
+                )
+            } else {
+                String::new()
+            };
+            lines.push(format!("  - fn {}: {}{}", entry.function, panics, handling));
+        }
+        Some(lines.join("\n"))
+    }
+
+    /// Writes a report formatted by one of the `render_*` methods, if it is shown
+    fn write_report(&self, report: Option<String>) -> io::Result<()> {
+        match report {
+            Some(report) => self.write_line(&report),
+            None => Ok(()),
+        }
+    }
+
+    /// Formats the row for a single function in the configured output format
+    /// JSON rows are single objects without a trailing separator
+    pub fn format_function_row(&self, result: &FunctionAnalysisResult) -> String {
+        self.format.row(result, &self.options)
+    }
+
+    /// Returns a fingerprint identifying the rendered row for a result
+    /// Two results with the same fingerprint render to the same row under this formatter
+    pub fn row_fingerprint(&self, result: &FunctionAnalysisResult) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.format_name.hash(&mut hasher);
+        self.options.hash(&mut hasher);
+        result.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+/// Returns locked, buffered stdout; rows are flushed per streamed batch and by [`OutputFormatter::flush`]
+fn stdout_writer() -> Box<dyn Write> {
+    Box::new(io::BufWriter::new(io::stdout().lock()))
+}
+
+/// Creates a report file, including any missing parent directories
+pub fn create_output_file(path: &Path) -> io::Result<File> {
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent)?;
+    }
+    File::create(path)
+}
+
+impl Default for OutputFormatter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::JsonlFormat;
+
+    fn sample_result(name: &str, code: usize, complexity: usize) -> FunctionAnalysisResult {
+        FunctionAnalysisResult {
+            total: code + 2,
+            comment: 1,
+            empty: 1,
+            stmt_count: 1,
+            expr_count: 1,
+            end_line: code + 2,
+            ..FunctionAnalysisResult::sample(name, code, complexity)
+        }
+    }
+
+    fn sorted_names(
+        formatter: &OutputFormatter,
+        results: &[FunctionAnalysisResult],
+    ) -> Vec<String> {
+        formatter
+            .sorted(results)
+            .into_iter()
+            .map(|result| result.name)
+            .collect()
+    }
+
+    #[test]
+    fn test_sort_defaults_to_code_lines() {
+        let results = vec![sample_result("b", 3, 9), sample_result("a", 10, 1)];
+        let formatter = OutputFormatter::new();
+
+        assert_eq!(sorted_names(&formatter, &results), ["a", "b"]);
+    }
+
+    #[test]
+    fn test_sort_by_complexity_and_name() {
+        let results = vec![
+            sample_result("c", 10, 1),
+            sample_result("a", 3, 9),
+            sample_result("b", 5, 4),
+        ];
+
+        let by_complexity = OutputFormatter::new().with_sort(SortBy::Complexity);
+        assert_eq!(sorted_names(&by_complexity, &results), ["a", "b", "c"]);
+
+        let by_name = OutputFormatter::new().with_sort(SortBy::Name);
+        assert_eq!(sorted_names(&by_name, &results), ["a", "b", "c"]);
+
+        let by_code = OutputFormatter::new().with_sort(SortBy::Code);
+        assert_eq!(sorted_names(&by_code, &results), ["c", "b", "a"]);
+    }
+
+    #[test]
+    fn test_limit_keeps_first_results_in_sort_order() {
+        let results = vec![
+            sample_result("small", 2, 1),
+            sample_result("large", 20, 1),
+            sample_result("medium", 10, 1),
+        ];
+
+        let formatter = OutputFormatter::new().with_limit(Some(2));
+        assert_eq!(sorted_names(&formatter, &results), ["large", "medium"]);
+    }
+
+    /// Writer appending to a buffer that the test keeps a handle to
+    #[derive(Clone, Default)]
+    struct SharedBuffer(std::rc::Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(bytes)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_results_are_written_to_injected_writer() {
+        let buffer = SharedBuffer::default();
+        let formatter = OutputFormatter::with_format(OutputFormat::Csv)
+            .with_columns(Some(vec![Column::Name, Column::Code]))
+            .with_writer(buffer.clone());
+        let results = [sample_result("a", 3, 1), sample_result("b", 7, 2)];
+
+        formatter.display_analysis_header(1).unwrap();
+        formatter.display_results(&results).unwrap();
+        formatter.flush().unwrap();
+
+        let written = String::from_utf8(buffer.0.borrow().clone()).unwrap();
+        assert_eq!(written, "Function,Code Lines\nb,7\na,3\n");
+        assert_eq!(formatter.render_results(&results), "b,7\na,3");
+        assert_eq!(formatter.render_rule_matches(&[]), None);
+        assert_eq!(
+            OutputFormatter::new().render_rule_matches(&[]).as_deref(),
+            Some("\nRule violations:\n  none")
+        );
+    }
+
+    /// Formatter writing only the number of functions in the report
+    struct CountFormatter;
+
+    impl Formatter for CountFormatter {
+        fn write_report(&mut self, report: &AnalysisReport, w: &mut dyn Write) -> io::Result<()> {
+            writeln!(w, "{} functions", report.functions.len())
+        }
+    }
+
+    #[test]
+    fn test_formatters_write_whole_reports() {
+        let results = vec![sample_result("a", 3, 1), sample_result("b", 7, 2)];
+        let report = AnalysisReport::new(Vec::new(), results, std::time::Duration::ZERO);
+
+        let buffer = SharedBuffer::default();
+        let formatter = OutputFormatter::with_format(OutputFormat::Jsonl)
+            .with_formatter(CountFormatter)
+            .with_writer(buffer.clone());
+        assert!(!formatter.is_streaming());
+        formatter.display_analysis_header(1).unwrap();
+        formatter
+            .display_report(&report, &ReportSections::default())
+            .unwrap();
+        assert_eq!(buffer.0.borrow().as_slice(), b"2 functions\n");
+
+        let mut jsonl = Vec::new();
+        JsonlFormat.write_report(&report, &mut jsonl).unwrap();
+        let jsonl = String::from_utf8(jsonl).unwrap();
+        assert_eq!(jsonl.lines().count(), 2);
+        assert!(jsonl.starts_with(r#"{"name":"a","#));
+    }
+}
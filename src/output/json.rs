@@ -0,0 +1,359 @@
+use super::{Format, FormatOptions, JsonStyle, ReportSections};
+use crate::analyzer::FunctionAnalysisResult;
+use crate::health::HealthReport;
+use crate::histogram::Histogram;
+use crate::report::FileError;
+use crate::run_stats::RunStats;
+use crate::summary::Summary;
+use serde::Serialize;
+
+/// JSON array with one object per function
+///
+/// Files that could not be analyzed follow the functions as objects with a
+/// `file` and an `error` field, so consumers can tell the coverage is incomplete.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonFormat;
+
+/// Serialized form of a single function result, shared by the serde-based formats
+#[derive(Serialize)]
+pub(super) struct JsonRow {
+    /// File-qualified name, kept for compatibility with earlier output
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file: Option<String>,
+    start_line: usize,
+    end_line: usize,
+    total: usize,
+    code: usize,
+    comment: usize,
+    empty: usize,
+    /// Null for functions analyzed without a syntax tree, as are the other
+    /// syntax-based metrics
+    complexity: Option<usize>,
+    nesting: Option<usize>,
+    comment_density: f64,
+    todo_count: usize,
+    signature_complexity: Option<usize>,
+    resilience: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stmts: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exprs: Option<usize>,
+    /// Only present, as true, for test code
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    is_test: bool,
+}
+
+impl JsonRow {
+    pub(super) fn new(result: &FunctionAnalysisResult, options: &FormatOptions) -> Self {
+        let ast_metric = |value: usize| result.ast_metrics.then_some(value);
+        let ast_count = |count: usize| options.show_ast_counts.then_some(count);
+        Self {
+            name: result.qualified_name(),
+            file: result.source_file().map(str::to_string),
+            start_line: result.start_line,
+            end_line: result.end_line,
+            total: result.total,
+            code: result.code,
+            comment: result.comment,
+            empty: result.empty,
+            complexity: ast_metric(result.cyclomatic_complexity),
+            nesting: ast_metric(result.nesting_depth),
+            comment_density: round_to(result.comment_density(), 1),
+            todo_count: result.todo_count,
+            signature_complexity: ast_metric(result.signature_complexity),
+            resilience: result.resilience.map(|resilience| resilience.as_str()),
+            stmts: ast_count(result.stmt_count),
+            exprs: ast_count(result.expr_count),
+            is_test: result.is_test,
+        }
+    }
+}
+
+/// Serialized form of a file that could not be analyzed
+#[derive(Serialize)]
+pub(super) struct JsonFileError<'a> {
+    file: &'a str,
+    error: &'a str,
+}
+
+impl<'a> From<&'a FileError> for JsonFileError<'a> {
+    fn from(error: &'a FileError) -> Self {
+        Self {
+            file: &error.file,
+            error: &error.error,
+        }
+    }
+}
+
+/// An entry of the JSON array: a function, or a file that could not be analyzed
+#[derive(Serialize)]
+#[serde(untagged)]
+enum JsonEntry<'a> {
+    Function(JsonRow),
+    FileError(JsonFileError<'a>),
+}
+
+impl JsonFormat {
+    /// Joins rows formatted by [`Format::row`] and [`Format::error_row`] into
+    /// the array written with the default JSON style
+    ///
+    /// Long-running processes use this to answer with rows kept in a
+    /// [`RowCache`](super::RowCache).
+    pub fn join_rows(rows: &[String]) -> String {
+        join_lines(rows, "")
+    }
+}
+
+/// Serialized form of run statistics
+#[derive(Serialize)]
+struct JsonStats {
+    wall_time_secs: f64,
+    peak_rss_bytes: Option<u64>,
+    files: usize,
+    functions: usize,
+    files_per_sec: f64,
+    functions_per_sec: f64,
+}
+
+impl From<&RunStats> for JsonStats {
+    fn from(stats: &RunStats) -> Self {
+        Self {
+            wall_time_secs: round_to(stats.wall_time.as_secs_f64(), 3),
+            peak_rss_bytes: stats.peak_rss_bytes,
+            files: stats.files,
+            functions: stats.functions,
+            files_per_sec: round_to(stats.files_per_sec(), 1),
+            functions_per_sec: round_to(stats.functions_per_sec(), 1),
+        }
+    }
+}
+
+/// Results wrapped in an object next to the requested report sections
+#[derive(Serialize)]
+pub(super) struct JsonEnvelope<'a> {
+    functions: Vec<JsonRow>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    health: Option<&'a HealthReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<&'a Summary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    histogram: Option<&'a Histogram>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stats: Option<JsonStats>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    errors: Vec<JsonFileError<'a>>,
+}
+
+impl<'a> JsonEnvelope<'a> {
+    pub(super) fn new(
+        results: &'a [FunctionAnalysisResult],
+        sections: &ReportSections<'a>,
+        options: &FormatOptions,
+    ) -> Self {
+        Self {
+            functions: rows(results, options),
+            health: sections.health,
+            summary: sections.summary,
+            histogram: sections.histogram,
+            stats: sections.stats.map(JsonStats::from),
+            errors: sections
+                .file_errors
+                .iter()
+                .map(JsonFileError::from)
+                .collect(),
+        }
+    }
+}
+
+impl Format for JsonFormat {
+    /// Formats a single function as a single-line JSON object without a trailing separator
+    fn row(&self, result: &FunctionAnalysisResult, options: &FormatOptions) -> String {
+        to_json(&JsonRow::new(result, options), false)
+    }
+
+    fn render(&self, results: &[FunctionAnalysisResult], options: &FormatOptions) -> String {
+        self.render_with_errors(results, &[], options)
+    }
+
+    fn error_row(&self, error: &FileError, _options: &FormatOptions) -> Option<String> {
+        Some(to_json(&JsonFileError::from(error), false))
+    }
+
+    /// Lists the files that could not be analyzed in the same array, after the functions
+    fn render_with_errors(
+        &self,
+        results: &[FunctionAnalysisResult],
+        errors: &[FileError],
+        options: &FormatOptions,
+    ) -> String {
+        let entries: Vec<JsonEntry> = rows(results, options)
+            .into_iter()
+            .map(JsonEntry::Function)
+            .chain(
+                errors
+                    .iter()
+                    .map(|error| JsonEntry::FileError(error.into())),
+            )
+            .collect();
+        match options.json_style {
+            JsonStyle::Lines => lines_array(&entries, ""),
+            JsonStyle::Pretty | JsonStyle::Compact => {
+                to_json(&entries, options.json_style == JsonStyle::Pretty)
+            }
+        }
+    }
+
+    /// Wraps the results in an object envelope next to an object per requested section
+    fn render_with_sections(
+        &self,
+        results: &[FunctionAnalysisResult],
+        sections: &ReportSections,
+        options: &FormatOptions,
+    ) -> Option<String> {
+        let rendered = match options.json_style {
+            JsonStyle::Lines => {
+                let mut fields = vec![format!(
+                    "  \"functions\": {}",
+                    lines_array(&rows(results, options), "  ")
+                )];
+                if let Some(health) = sections.health {
+                    fields.push(format!("  \"health\": {}", to_json(health, false)));
+                }
+                if let Some(summary) = sections.summary {
+                    fields.push(format!("  \"summary\": {}", to_json(summary, false)));
+                }
+                if let Some(histogram) = sections.histogram {
+                    fields.push(format!("  \"histogram\": {}", to_json(histogram, false)));
+                }
+                if let Some(stats) = sections.stats {
+                    fields.push(format!(
+                        "  \"stats\": {}",
+                        to_json(&JsonStats::from(stats), false)
+                    ));
+                }
+                if !sections.file_errors.is_empty() {
+                    let errors: Vec<JsonFileError> =
+                        sections.file_errors.iter().map(Into::into).collect();
+                    fields.push(format!("  \"errors\": {}", lines_array(&errors, "  ")));
+                }
+                format!("{{\n{}\n}}", fields.join(",\n"))
+            }
+            JsonStyle::Pretty | JsonStyle::Compact => to_json(
+                &JsonEnvelope::new(results, sections, options),
+                options.json_style == JsonStyle::Pretty,
+            ),
+        };
+        Some(rendered)
+    }
+}
+
+/// Converts results into their serialized rows
+pub(super) fn rows(results: &[FunctionAnalysisResult], options: &FormatOptions) -> Vec<JsonRow> {
+    results
+        .iter()
+        .map(|result| JsonRow::new(result, options))
+        .collect()
+}
+
+/// Formats entries as a JSON array with one single-line object per line
+fn lines_array<T: Serialize>(entries: &[T], indent: &str) -> String {
+    let rows: Vec<String> = entries.iter().map(|entry| to_json(entry, false)).collect();
+    join_lines(&rows, indent)
+}
+
+/// Joins single-line JSON objects into an array with one object per line
+fn join_lines(rows: &[String], indent: &str) -> String {
+    if rows.is_empty() {
+        return format!("[\n{indent}]");
+    }
+    let rows = rows
+        .iter()
+        .map(|row| format!("{indent}  {row}"))
+        .collect::<Vec<_>>()
+        .join(",\n");
+    format!("[\n{rows}\n{indent}]")
+}
+
+/// Serializes a value, indented if `pretty` is set
+/// The serialized types only contain strings and numbers, so serialization cannot fail
+pub(super) fn to_json<T: Serialize>(value: &T, pretty: bool) -> String {
+    let json = if pretty {
+        serde_json::to_string_pretty(value)
+    } else {
+        serde_json::to_string(value)
+    };
+    json.unwrap_or_default()
+}
+
+/// Rounds a value to the given number of decimal places
+fn round_to(value: f64, decimals: i32) -> f64 {
+    let factor = 10f64.powi(decimals);
+    (value * factor).round() / factor
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_result() -> FunctionAnalysisResult {
+        FunctionAnalysisResult {
+            total: 9,
+            comment: 2,
+            empty: 1,
+            nesting_depth: 1,
+            stmt_count: 4,
+            expr_count: 7,
+            end_line: 9,
+            ..FunctionAnalysisResult::sample("quote\"d", 6, 2)
+        }
+    }
+
+    #[test]
+    fn test_row_escapes_names_and_rounds_density() {
+        let row = JsonFormat.row(&sample_result(), &FormatOptions::default());
+        assert!(row.starts_with(r#"{"name":"quote\"d","start_line":1,"end_line":9,"total":9"#));
+        assert!(row.contains(r#""comment_density":25.0"#));
+        assert!(row.contains(r#""resilience":null"#));
+        assert!(!row.contains("stmts"));
+    }
+
+    #[test]
+    fn test_json_styles() {
+        let results = vec![sample_result(), sample_result()];
+        let style = |json_style| FormatOptions {
+            json_style,
+            ..FormatOptions::default()
+        };
+
+        let compact = JsonFormat.render(&results, &style(JsonStyle::Compact));
+        let pretty = JsonFormat.render(&results, &style(JsonStyle::Pretty));
+        let lines = JsonFormat.render(&results, &style(JsonStyle::Lines));
+
+        assert_eq!(compact.lines().count(), 1);
+        assert!(pretty.contains("\n    \"name\": "));
+        assert_eq!(lines.lines().count(), 4);
+        for rendered in [compact, pretty, lines] {
+            let parsed: serde_json::Value = serde_json::from_str(&rendered).unwrap();
+            assert_eq!(parsed.as_array().unwrap().len(), 2);
+        }
+    }
+
+    #[test]
+    fn test_file_errors_follow_the_functions() {
+        let errors = [FileError {
+            file: "src/broken.rs".to_string(),
+            error: "Failed to parse src/broken.rs:3:1: expected `;`".to_string(),
+        }];
+
+        let rendered =
+            JsonFormat.render_with_errors(&[sample_result()], &errors, &FormatOptions::default());
+
+        let parsed: serde_json::Value = serde_json::from_str(&rendered).unwrap();
+        let entries = parsed.as_array().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1]["file"], "src/broken.rs");
+        assert!(entries[1]["error"].as_str().unwrap().contains("expected"));
+        assert!(entries[0].get("error").is_none());
+    }
+}
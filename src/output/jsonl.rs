@@ -0,0 +1,47 @@
+use super::{Format, FormatOptions, JsonFormat};
+use crate::analyzer::FunctionAnalysisResult;
+use crate::report::FileError;
+
+/// JSON Lines: one JSON object per function, streamed as files are analyzed
+///
+/// Rows are written in analysis order rather than sort order, so downstream
+/// tools can process them without waiting for the whole run.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonlFormat;
+
+impl Format for JsonlFormat {
+    fn row(&self, result: &FunctionAnalysisResult, options: &FormatOptions) -> String {
+        JsonFormat.row(result, options)
+    }
+
+    /// Written after all function rows, once every file was analyzed
+    fn error_row(&self, error: &FileError, options: &FormatOptions) -> Option<String> {
+        JsonFormat.error_row(error, options)
+    }
+
+    fn is_streaming(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_one_object_per_line() {
+        let result = FunctionAnalysisResult {
+            stmt_count: 1,
+            expr_count: 1,
+            ..FunctionAnalysisResult::sample("f", 3, 1)
+        };
+
+        let rendered = JsonlFormat.render(&[result.clone(), result], &FormatOptions::default());
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(lines.len(), 2);
+        for line in lines {
+            let parsed: serde_json::Value = serde_json::from_str(line).unwrap();
+            assert_eq!(parsed["name"], "f");
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use super::table::highlight;
+use super::{Column, FormatOptions};
+use crate::analyzer::FunctionAnalysisResult;
+
+/// Text before the first cell of a row
+const ROW_PREFIX: &str = "  - ";
+/// Text between two cells of a row
+const CELL_SEPARATOR: &str = "  ";
+/// Narrowest the name column is truncated to, however narrow the terminal
+const MIN_NAME_WIDTH: usize = 24;
+
+/// Column widths for table rows aligned to fit a line width
+///
+/// Every cell is padded to the widest value of its column. If the rows would
+/// be wider than the line, the name column is shortened and names that do not
+/// fit lose their beginning, so the function name itself stays visible.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableLayout {
+    columns: Vec<Column>,
+    widths: Vec<usize>,
+}
+
+impl TableLayout {
+    /// Measures the cells of all results for the given line width
+    pub fn new(results: &[FunctionAnalysisResult], options: &FormatOptions, width: usize) -> Self {
+        let mut columns = options.columns();
+        // An empty resilience column is only kept when it was asked for
+        if options.columns.is_none() && results.iter().all(|r| r.resilience.is_none()) {
+            columns.retain(|&column| column != Column::Resilience);
+        }
+        let mut widths: Vec<usize> = columns
+            .iter()
+            .map(|column| {
+                results
+                    .iter()
+                    .map(|result| column.table_cell(result, options).chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        let name_index = columns.iter().position(|&column| column == Column::Name);
+        if let Some(name_index) = name_index.filter(|_| options.truncate_names) {
+            let others: usize = widths.iter().sum::<usize>() - widths[name_index];
+            let fixed = ROW_PREFIX.len() + CELL_SEPARATOR.len() * (columns.len() - 1) + others;
+            let available = width.saturating_sub(fixed).max(MIN_NAME_WIDTH);
+            widths[name_index] = widths[name_index].min(available);
+        }
+
+        Self { columns, widths }
+    }
+
+    /// Formats the aligned row of a function
+    pub fn row(&self, result: &FunctionAnalysisResult, options: &FormatOptions) -> String {
+        let last = self.columns.len().saturating_sub(1);
+        let cells: Vec<String> = self
+            .columns
+            .iter()
+            .zip(&self.widths)
+            .enumerate()
+            .map(|(index, (&column, &width))| {
+                let cell = ellipsize(column.table_cell(result, options), width);
+                // The last cell is not padded, so rows have no trailing spaces
+                let cell = if index == last {
+                    cell
+                } else {
+                    format!("{cell:<width$}")
+                };
+                highlight(cell, column, result, options)
+            })
+            .collect();
+        format!("{ROW_PREFIX}{}", cells.join(CELL_SEPARATOR))
+    }
+}
+
+/// Shortens text to at most `width` characters by replacing its beginning with an ellipsis
+fn ellipsize(text: String, width: usize) -> String {
+    let length = text.chars().count();
+    if length <= width {
+        return text;
+    }
+    let kept: String = text.chars().skip(length - width + 1).collect();
+    format!("…{kept}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_result(name: &str, code: usize) -> FunctionAnalysisResult {
+        FunctionAnalysisResult::sample(name, code, 1)
+    }
+
+    #[test]
+    fn test_cells_are_aligned() {
+        let results = [
+            sample_result("src/lib.rs::run", 120),
+            sample_result("src/a.rs::f", 8),
+        ];
+        let options = FormatOptions {
+            columns: Some(vec![Column::Name, Column::Code, Column::Complexity]),
+            ..FormatOptions::default()
+        };
+        let layout = TableLayout::new(&results, &options, 200);
+
+        assert_eq!(
+            layout.row(&results[0], &options),
+            "  - fn src/lib.rs::run  code=120  complexity=1"
+        );
+        assert_eq!(
+            layout.row(&results[1], &options),
+            "  - fn src/a.rs::f      code=8    complexity=1"
+        );
+    }
+
+    #[test]
+    fn test_long_names_are_ellipsized_unless_disabled() {
+        let long_name = format!("src/{}/lib.rs::parse_everything", "nested/".repeat(10));
+        let results = [sample_result(&long_name, 10)];
+        let mut options = FormatOptions {
+            columns: Some(vec![Column::Name, Column::Code]),
+            ..FormatOptions::default()
+        };
+
+        let row = TableLayout::new(&results, &options, 40).row(&results[0], &options);
+        assert_eq!(row.chars().count(), 40);
+        assert!(row.ends_with("/lib.rs::parse_everything  code=10"));
+        assert!(row.contains('…'));
+
+        options.truncate_names = false;
+        let row = TableLayout::new(&results, &options, 40).row(&results[0], &options);
+        assert!(row.contains(&long_name));
+    }
+}
@@ -0,0 +1,102 @@
+use super::{Format, FormatOptions};
+use crate::analyzer::FunctionAnalysisResult;
+
+/// GitHub-flavored markdown table, ready to paste into PR descriptions and wikis
+///
+/// Combine with `--limit` to show only the top N functions.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MarkdownFormat;
+
+impl Format for MarkdownFormat {
+    fn header(&self, _file_count: usize, options: &FormatOptions) -> Option<String> {
+        let mut columns = vec![
+            "Function",
+            "Total",
+            "Code",
+            "Comment",
+            "Empty",
+            "Complexity",
+            "Nesting",
+            "Density",
+            "TODOs",
+            "Signature",
+            "Resilience",
+        ];
+        if options.show_ast_counts {
+            columns.extend(["Stmts", "Exprs"]);
+        }
+        // Function names and resilience are left-aligned, metrics right-aligned
+        let alignments: Vec<&str> = columns
+            .iter()
+            .map(|&column| match column {
+                "Function" | "Resilience" => ":---",
+                _ => "---:",
+            })
+            .collect();
+        Some(format!(
+            "{}\n{}",
+            table_row(&columns),
+            table_row(&alignments)
+        ))
+    }
+
+    fn row(&self, result: &FunctionAnalysisResult, options: &FormatOptions) -> String {
+        let mut cells = vec![
+            format!("`{}`", result.qualified_name().replace('|', "\\|")),
+            result.total.to_string(),
+            result.code.to_string(),
+            result.comment.to_string(),
+            result.empty.to_string(),
+            result.cyclomatic_complexity.to_string(),
+            result.nesting_depth.to_string(),
+            format!("{:.1}%", result.comment_density()),
+            result.todo_count.to_string(),
+            result.signature_complexity.to_string(),
+            result
+                .resilience
+                .map(|r| r.as_str())
+                .unwrap_or_default()
+                .to_string(),
+        ];
+        if options.show_ast_counts {
+            cells.push(result.stmt_count.to_string());
+            cells.push(result.expr_count.to_string());
+        }
+        table_row(&cells)
+    }
+}
+
+/// Joins cells into a single markdown table row
+fn table_row<T: AsRef<str>>(cells: &[T]) -> String {
+    let cells: Vec<&str> = cells.iter().map(AsRef::as_ref).collect();
+    format!("| {} |", cells.join(" | "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markdown_table() {
+        let result = FunctionAnalysisResult {
+            total: 5,
+            comment: 1,
+            empty: 1,
+            nesting_depth: 1,
+            stmt_count: 1,
+            expr_count: 1,
+            end_line: 5,
+            ..FunctionAnalysisResult::sample("src/lib.rs::a|b", 3, 2)
+        };
+        let options = FormatOptions::default();
+
+        let header = MarkdownFormat.header(1, &options).unwrap();
+        let mut lines = header.lines();
+        assert!(lines.next().unwrap().starts_with("| Function | Total |"));
+        assert!(lines.next().unwrap().starts_with("| :--- | ---: |"));
+        assert_eq!(
+            MarkdownFormat.row(&result, &options),
+            "| `src/lib.rs::a\\|b` | 5 | 3 | 1 | 1 | 2 | 1 | 25.0% | 0 | 0 |  |"
+        );
+    }
+}
@@ -0,0 +1,257 @@
+//! Output formatting for analysis results
+//!
+//! Each output format lives in its own submodule and implements [`Format`].
+//! Formats are looked up by name in a [`FormatRegistry`]; the built-in formats
+//! are registered under the names of the [`OutputFormat`](crate::OutputFormat)
+//! variants, and additional formats can be registered at runtime.
+//!
+//! Every format is also a [`Formatter`], which writes a whole [`AnalysisReport`].
+//! Library users who need full control over the output implement [`Formatter`]
+//! directly and register it with [`FormatRegistry::register_formatter`].
+
+pub mod code_climate;
+pub mod columns;
+pub mod csv;
+pub mod diagnostics;
+pub mod editor_links;
+pub mod formatter;
+pub mod json;
+pub mod jsonl;
+pub mod layout;
+pub mod markdown;
+pub mod registry;
+pub mod row_cache;
+pub mod table;
+pub mod template;
+pub mod toml;
+pub mod totals;
+pub mod yaml;
+
+use crate::analyzer::FunctionAnalysisResult;
+use crate::client::{EditorLinks, GroupBy};
+use crate::config::Thresholds;
+use crate::grades::GradeBoundaries;
+use crate::health::HealthReport;
+use crate::histogram::Histogram;
+use crate::report::{AnalysisReport, FileError};
+use crate::run_stats::RunStats;
+use crate::summary::Summary;
+use std::io::{self, Write};
+
+// Re-export commonly used types for convenience
+pub use code_climate::CodeClimateFormat;
+pub use columns::Column;
+pub use csv::CsvFormat;
+pub use diagnostics::DiagnosticsFormat;
+pub use formatter::OutputFormatter;
+pub use json::JsonFormat;
+pub use jsonl::JsonlFormat;
+pub use layout::TableLayout;
+pub use markdown::MarkdownFormat;
+pub use registry::{FormatRegistry, FormatterFactory};
+pub use row_cache::RowCache;
+pub use table::TableFormat;
+pub use template::TemplateFormat;
+pub use toml::TomlFormat;
+pub use totals::Totals;
+pub use yaml::YamlFormat;
+
+/// Layout of JSON output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum JsonStyle {
+    /// One single-line object per line (default)
+    #[default]
+    Lines,
+    /// Indented, for reading
+    Pretty,
+    /// The whole document on a single line, for pipelines
+    Compact,
+}
+
+/// Options shared by every output format
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FormatOptions {
+    /// Include statement and expression counts
+    pub show_ast_counts: bool,
+    /// Layout of JSON output; ignored by other formats
+    pub json_style: JsonStyle,
+    /// Field delimiter of CSV output; ignored by other formats
+    pub csv_delimiter: u8,
+    /// Limits highlighted in colored table output and reported as Code Climate issues
+    pub thresholds: Thresholds,
+    /// Highlight table metrics with ANSI colors; ignored by other formats
+    pub color: bool,
+    /// Columns of table and CSV output, in order; None shows the default columns
+    pub columns: Option<Vec<Column>>,
+    /// Sections of table output, each followed by a subtotal row; ignored by other formats
+    pub group_by: Option<GroupBy>,
+    /// Append a row adding up all listed functions to table and CSV output
+    pub totals: bool,
+    /// Leave out the "Analyzing N Rust files..." banner of table output
+    pub quiet: bool,
+    /// Leave out the column titles of CSV output
+    pub no_header: bool,
+    /// Line width to align table rows to; None keeps compact rows
+    pub width: Option<usize>,
+    /// Shorten function names so aligned table rows fit the line width
+    pub truncate_names: bool,
+    /// Boundaries of the letter grades shown in the grade column
+    pub grades: GradeBoundaries,
+    /// Editor commands or hyperlinks added to table rows; ignored by other formats
+    pub editor_links: Option<EditorLinks>,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            show_ast_counts: false,
+            json_style: JsonStyle::default(),
+            csv_delimiter: b',',
+            thresholds: Thresholds::default(),
+            color: false,
+            columns: None,
+            group_by: None,
+            totals: false,
+            quiet: false,
+            no_header: false,
+            width: None,
+            truncate_names: true,
+            grades: GradeBoundaries::default(),
+            editor_links: None,
+        }
+    }
+}
+
+impl FormatOptions {
+    /// Returns the selected columns, or the default columns for the AST count setting
+    pub fn columns(&self) -> Vec<Column> {
+        match &self.columns {
+            Some(columns) => columns.clone(),
+            None if self.show_ast_counts => {
+                let mut columns = columns::DEFAULT_COLUMNS.to_vec();
+                columns.extend([Column::Stmts, Column::Exprs]);
+                columns
+            }
+            None => columns::DEFAULT_COLUMNS.to_vec(),
+        }
+    }
+}
+
+/// Optional sections reported next to the function results
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReportSections<'a> {
+    /// Code-health score of the project and its files, with `--health`
+    pub health: Option<&'a HealthReport>,
+    /// Summary statistics over all functions, with `--summary`
+    pub summary: Option<&'a Summary>,
+    /// Distribution of one metric, with `--histogram`
+    pub histogram: Option<&'a Histogram>,
+    /// Resource usage of the run, with `--stats`
+    pub stats: Option<&'a RunStats>,
+    /// Files that could not be analyzed, listed after the functions by formats
+    /// with [`Format::error_row`]; not a section of its own
+    pub file_errors: &'a [FileError],
+}
+
+impl ReportSections<'_> {
+    /// Returns true if no section was requested; file errors do not count
+    pub fn is_empty(&self) -> bool {
+        self.health.is_none()
+            && self.summary.is_none()
+            && self.histogram.is_none()
+            && self.stats.is_none()
+    }
+}
+
+/// An output format for function analysis results
+pub trait Format: Send + Sync {
+    /// Returns the text printed once before the results, if any
+    fn header(&self, _file_count: usize, _options: &FormatOptions) -> Option<String> {
+        None
+    }
+
+    /// Formats the row for a single function
+    fn row(&self, result: &FunctionAnalysisResult, options: &FormatOptions) -> String;
+
+    /// Formats all results; by default one row per line
+    fn render(&self, results: &[FunctionAnalysisResult], options: &FormatOptions) -> String {
+        results
+            .iter()
+            .map(|result| self.row(result, options))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Formats the entry for a file that could not be analyzed
+    ///
+    /// Returns None if the format has no such entries; the failure is then
+    /// only reported as a diagnostic.
+    fn error_row(&self, _error: &FileError, _options: &FormatOptions) -> Option<String> {
+        None
+    }
+
+    /// Formats all results followed by an entry per file that could not be analyzed
+    fn render_with_errors(
+        &self,
+        results: &[FunctionAnalysisResult],
+        errors: &[FileError],
+        options: &FormatOptions,
+    ) -> String {
+        let mut blocks = vec![self.render(results, options)];
+        blocks.extend(
+            errors
+                .iter()
+                .filter_map(|error| self.error_row(error, options)),
+        );
+        blocks.retain(|block| !block.is_empty());
+        blocks.join("\n")
+    }
+
+    /// Formats all results together with the requested report sections
+    ///
+    /// Returns None if the format has no place for the sections; they are then
+    /// written to stderr so the results stay parseable.
+    fn render_with_sections(
+        &self,
+        _results: &[FunctionAnalysisResult],
+        _sections: &ReportSections,
+        _options: &FormatOptions,
+    ) -> Option<String> {
+        None
+    }
+
+    /// Returns true if rows are written as soon as each file is analyzed
+    ///
+    /// Streaming formats receive rows in analysis order; sorting does not apply.
+    fn is_streaming(&self) -> bool {
+        false
+    }
+
+    /// Returns true if supplementary human-readable reports may follow the results
+    ///
+    /// Machine-readable formats return false so their output stays parseable.
+    fn shows_reports(&self) -> bool {
+        false
+    }
+}
+
+/// Writes a complete analysis report
+pub trait Formatter {
+    /// Writes the report to the given writer
+    fn write_report(&mut self, report: &AnalysisReport, w: &mut dyn Write) -> io::Result<()>;
+}
+
+/// Formats write the report's functions and file errors with the default options
+impl<F: Format + ?Sized> Formatter for F {
+    fn write_report(&mut self, report: &AnalysisReport, w: &mut dyn Write) -> io::Result<()> {
+        let rendered = self.render_with_errors(
+            &report.functions,
+            &report.file_errors(),
+            &FormatOptions::default(),
+        );
+        if rendered.is_empty() {
+            return Ok(());
+        }
+        writeln!(w, "{rendered}")
+    }
+}
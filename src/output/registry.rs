@@ -0,0 +1,170 @@
+use super::{
+    CodeClimateFormat, CsvFormat, DiagnosticsFormat, Format, Formatter, JsonFormat, JsonlFormat,
+    MarkdownFormat, TableFormat, TemplateFormat, TomlFormat, YamlFormat,
+};
+use crate::client::OutputFormat;
+use clap::ValueEnum;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+
+/// Creates a fresh instance of a registered [`Formatter`] for each output
+pub type FormatterFactory = Arc<dyn Fn() -> Box<dyn Formatter> + Send + Sync>;
+
+/// Output formats available by name
+///
+/// The default registry contains the built-in formats under the names of the
+/// [`OutputFormat`] variants (`table`, `json`, `csv`, `jsonl`, `markdown`, `codeclimate`,
+/// `yaml`, `toml`, `template`). Plugins can register additional formats, or replace built-in
+/// ones, with [`FormatRegistry::register`]. Formatters that write the whole report
+/// themselves are registered with [`FormatRegistry::register_formatter`].
+#[derive(Clone)]
+pub struct FormatRegistry {
+    formats: BTreeMap<String, Arc<dyn Format>>,
+    formatters: BTreeMap<String, FormatterFactory>,
+}
+
+impl FormatRegistry {
+    /// Creates a registry without any formats
+    pub fn empty() -> Self {
+        Self {
+            formats: BTreeMap::new(),
+            formatters: BTreeMap::new(),
+        }
+    }
+
+    /// Registers a format under the given name, returning the format it replaced
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        format: Arc<dyn Format>,
+    ) -> Option<Arc<dyn Format>> {
+        let name = name.into();
+        self.formatters.remove(&name);
+        self.formats.insert(name, format)
+    }
+
+    /// Registers a report formatter under the given name, replacing any format of that name
+    pub fn register_formatter(&mut self, name: impl Into<String>, factory: FormatterFactory) {
+        let name = name.into();
+        self.formats.remove(&name);
+        self.formatters.insert(name, factory);
+    }
+
+    /// Returns the format registered under the given name
+    pub fn get(&self, name: &str) -> Option<Arc<dyn Format>> {
+        self.formats.get(name).cloned()
+    }
+
+    /// Creates the report formatter registered under the given name
+    pub fn formatter(&self, name: &str) -> Option<Box<dyn Formatter>> {
+        self.formatters.get(name).map(|factory| factory())
+    }
+
+    /// Returns the names of all registered formats and formatters in sorted order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.formats
+            .keys()
+            .chain(self.formatters.keys())
+            .map(String::as_str)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+    }
+}
+
+impl Default for FormatRegistry {
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        for format in OutputFormat::value_variants() {
+            registry.register(format.name(), builtin_format(format));
+        }
+        registry
+    }
+}
+
+/// Returns the built-in implementation of an output format
+pub fn builtin_format(format: &OutputFormat) -> Arc<dyn Format> {
+    match format {
+        OutputFormat::Table => Arc::new(TableFormat),
+        OutputFormat::Json => Arc::new(JsonFormat),
+        OutputFormat::Csv => Arc::new(CsvFormat),
+        OutputFormat::Jsonl => Arc::new(JsonlFormat),
+        OutputFormat::Markdown => Arc::new(MarkdownFormat),
+        OutputFormat::CodeClimate => Arc::new(CodeClimateFormat),
+        OutputFormat::Diagnostics => Arc::new(DiagnosticsFormat),
+        OutputFormat::Yaml => Arc::new(YamlFormat),
+        OutputFormat::Toml => Arc::new(TomlFormat),
+        OutputFormat::Template => Arc::new(TemplateFormat::default()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::FunctionAnalysisResult;
+    use crate::output::FormatOptions;
+
+    struct NameOnlyFormat;
+
+    impl Format for NameOnlyFormat {
+        fn row(&self, result: &FunctionAnalysisResult, _options: &FormatOptions) -> String {
+            result.name.clone()
+        }
+    }
+
+    #[test]
+    fn test_default_registry_contains_builtin_formats() {
+        let registry = FormatRegistry::default();
+        assert_eq!(
+            registry.names().collect::<Vec<_>>(),
+            [
+                "codeclimate",
+                "csv",
+                "diagnostics",
+                "json",
+                "jsonl",
+                "markdown",
+                "table",
+                "template",
+                "toml",
+                "yaml"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_register_runtime_format() {
+        let mut registry = FormatRegistry::default();
+        assert!(
+            registry
+                .register("names", Arc::new(NameOnlyFormat))
+                .is_none()
+        );
+
+        let format = registry.get("names").unwrap();
+        let result = FunctionAnalysisResult::sample("f", 1, 1);
+        assert_eq!(format.row(&result, &FormatOptions::default()), "f");
+        assert!(!format.shows_reports());
+    }
+
+    #[test]
+    fn test_registered_formatter_replaces_format() {
+        struct Silent;
+
+        impl Formatter for Silent {
+            fn write_report(
+                &mut self,
+                _report: &crate::AnalysisReport,
+                _w: &mut dyn std::io::Write,
+            ) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut registry = FormatRegistry::default();
+        registry.register_formatter("json", Arc::new(|| Box::new(Silent) as Box<dyn Formatter>));
+
+        assert!(registry.get("json").is_none());
+        assert!(registry.formatter("json").is_some());
+        assert_eq!(registry.names().filter(|name| *name == "json").count(), 1);
+    }
+}
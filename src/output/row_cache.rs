@@ -0,0 +1,135 @@
+use super::OutputFormatter;
+use crate::analyzer::FunctionAnalysisResult;
+use std::collections::HashMap;
+
+/// Cache of rendered per-function rows keyed by result fingerprint
+///
+/// Long-lived processes such as `fnloc serve` re-render the whole result set
+/// after every change. Keeping rendered rows keyed by
+/// [`OutputFormatter::row_fingerprint`] means only rows whose result actually
+/// changed are formatted again, so re-rendering stays proportional to the change.
+#[derive(Debug, Default)]
+pub struct RowCache {
+    rows: HashMap<u64, String>,
+}
+
+impl RowCache {
+    /// Creates an empty row cache
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Renders rows for the given results, reusing cached rows where possible
+    ///
+    /// Rows for results that are no longer present are evicted, so the cache
+    /// never grows beyond the size of the most recent result set.
+    pub fn render_rows(
+        &mut self,
+        formatter: &OutputFormatter,
+        results: &[FunctionAnalysisResult],
+    ) -> Vec<String> {
+        let mut current = HashMap::with_capacity(results.len());
+
+        let rows = results
+            .iter()
+            .map(|result| {
+                let fingerprint = formatter.row_fingerprint(result);
+                let row = match self.rows.remove(&fingerprint) {
+                    Some(row) => row,
+                    None => current
+                        .get(&fingerprint)
+                        .cloned()
+                        .unwrap_or_else(|| formatter.format_function_row(result)),
+                };
+                current.insert(fingerprint, row.clone());
+                row
+            })
+            .collect();
+
+        self.rows = current;
+        rows
+    }
+
+    /// Returns the number of cached rows
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Returns true if no rows are cached
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::OutputFormat;
+
+    fn sample_result(name: &str, code: usize) -> FunctionAnalysisResult {
+        FunctionAnalysisResult {
+            total: code + 2,
+            comment: 1,
+            empty: 1,
+            stmt_count: 1,
+            expr_count: 1,
+            end_line: code + 2,
+            ..FunctionAnalysisResult::sample(name, code, 1)
+        }
+    }
+    #[test]
+    fn test_row_cache_reuses_unchanged_rows() {
+        let formatter = OutputFormatter::with_format(OutputFormat::Csv);
+        let mut cache = RowCache::new();
+        let results = vec![sample_result("a", 3), sample_result("b", 5)];
+
+        let first = cache.render_rows(&formatter, &results);
+        let second = cache.render_rows(&formatter, &results);
+
+        assert_eq!(first, second);
+        assert_eq!(first[0], "a,5,3,1,1,1,0,25.0,0,0,,");
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn test_only_changed_results_are_formatted_again() {
+        let formatter = OutputFormatter::with_format(OutputFormat::Csv);
+        let mut cache = RowCache::new();
+        cache.render_rows(&formatter, &[sample_result("a", 3), sample_result("b", 5)]);
+        // Mark the cached rows to tell them apart from freshly formatted ones
+        for row in cache.rows.values_mut() {
+            row.insert_str(0, "cached:");
+        }
+
+        let rows = cache.render_rows(&formatter, &[sample_result("a", 3), sample_result("b", 6)]);
+
+        assert_eq!(rows[0], "cached:a,5,3,1,1,1,0,25.0,0,0,,");
+        assert!(rows[1].starts_with("b,8,6,"));
+    }
+
+    #[test]
+    fn test_row_cache_evicts_stale_rows() {
+        let formatter = OutputFormatter::with_format(OutputFormat::Table);
+        let mut cache = RowCache::new();
+
+        cache.render_rows(&formatter, &[sample_result("a", 3), sample_result("b", 5)]);
+        let rows = cache.render_rows(&formatter, &[sample_result("a", 4)]);
+
+        assert_eq!(rows.len(), 1);
+        assert!(rows[0].contains("code=4"));
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_row_fingerprint_depends_on_format() {
+        let result = sample_result("a", 3);
+        let table = OutputFormatter::with_format(OutputFormat::Table);
+        let csv = OutputFormatter::with_format(OutputFormat::Csv);
+
+        assert_ne!(table.row_fingerprint(&result), csv.row_fingerprint(&result));
+        assert_eq!(
+            table.row_fingerprint(&result),
+            table.row_fingerprint(&result)
+        );
+    }
+}
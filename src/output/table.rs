@@ -0,0 +1,473 @@
+use super::editor_links;
+use super::{Column, Format, FormatOptions, ReportSections, TableLayout, Totals};
+use crate::analyzer::FunctionAnalysisResult;
+use crate::client::{EditorLinks, GroupBy};
+use crate::health::HealthReport;
+use crate::report::FileError;
+use crate::run_stats::RunStats;
+use crate::summary::Summary;
+use std::cmp::Reverse;
+
+/// Human-readable table format (default)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TableFormat;
+
+impl Format for TableFormat {
+    fn header(&self, file_count: usize, options: &FormatOptions) -> Option<String> {
+        (!options.quiet).then(|| format!("Analyzing {file_count} Rust files...\n"))
+    }
+
+    fn row(&self, result: &FunctionAnalysisResult, options: &FormatOptions) -> String {
+        let row = match &options.columns {
+            Some(columns) => selected_columns_row(result, columns, options),
+            None => default_row(result, options),
+        };
+        editor_links::with_command(row, result, options.editor_links)
+    }
+
+    /// Lists the rows flat, in one section per file, or as one aggregate row per module
+    /// With a line width, function rows are aligned by [`TableLayout`]
+    /// With `--totals`, a final row adds up all listed functions
+    fn render(&self, results: &[FunctionAnalysisResult], options: &FormatOptions) -> String {
+        let layout = options
+            .width
+            .map(|width| TableLayout::new(results, options, width));
+        let row = |result: &FunctionAnalysisResult| match &layout {
+            Some(layout) => editor_links::with_command(
+                layout.row(result, options),
+                result,
+                options.editor_links,
+            ),
+            None => self.row(result, options),
+        };
+        let listing = match options.group_by {
+            None => results.iter().map(row).collect::<Vec<_>>().join("\n"),
+            Some(GroupBy::Module) => module_rows(results),
+            Some(group_by) => group_results(results, group_by)
+                .into_iter()
+                .map(|(key, group)| {
+                    let mut lines = vec![format!("{key}:")];
+                    lines.extend(group.iter().map(|result| row(result)));
+                    lines.push(totals_row("subtotal", &Totals::new(group)));
+                    lines.join("\n")
+                })
+                .collect::<Vec<_>>()
+                .join("\n\n"),
+        };
+        if !options.totals || results.is_empty() {
+            return listing;
+        }
+        let separator = if options.group_by == Some(GroupBy::File) {
+            "\n\n"
+        } else {
+            "\n"
+        };
+        format!(
+            "{listing}{separator}{}",
+            totals_row("total", &Totals::new(results))
+        )
+    }
+
+    fn error_row(&self, error: &FileError, _options: &FormatOptions) -> Option<String> {
+        Some(format!("  - file {}: error={}", error.file, error.error))
+    }
+
+    fn render_with_sections(
+        &self,
+        results: &[FunctionAnalysisResult],
+        sections: &ReportSections,
+        options: &FormatOptions,
+    ) -> Option<String> {
+        // The health score leads, so it is seen before the function listing
+        let mut blocks = Vec::new();
+        if let Some(health) = sections.health {
+            blocks.push(health_summary(health));
+        }
+        blocks.push(self.render_with_errors(results, sections.file_errors, options));
+        if let Some(summary) = sections.summary {
+            blocks.push(format!("Summary:\n{}", summary_table(summary)));
+        }
+        if let Some(histogram) = sections.histogram {
+            blocks.push(histogram.to_ascii());
+        }
+        if let Some(stats) = sections.stats {
+            blocks.push(format!("Run statistics:\n  {}", stats_summary(stats)));
+        }
+        blocks.retain(|block| !block.is_empty());
+        Some(blocks.join("\n\n"))
+    }
+
+    fn shows_reports(&self) -> bool {
+        true
+    }
+}
+
+/// Formats the row with every metric, used when no columns are selected
+fn default_row(result: &FunctionAnalysisResult, options: &FormatOptions) -> String {
+    let ast_counts = if options.show_ast_counts {
+        format!(
+            ", {}, {}",
+            Column::Stmts.table_cell(result, options),
+            Column::Exprs.table_cell(result, options)
+        )
+    } else {
+        String::new()
+    };
+    let resilience = result
+        .resilience
+        .map(|resilience| format!(", resilience={}", resilience.as_str()))
+        .unwrap_or_default();
+    let cell =
+        |column: Column| highlight(column.table_cell(result, options), column, result, options);
+    format!(
+        "  - {}: {}, {}, comment={}, empty={}, {}, {}, density={:.1}%, todos={}, {}{}{}",
+        cell(Column::Name),
+        cell(Column::Total),
+        cell(Column::Code),
+        result.comment,
+        result.empty,
+        cell(Column::Complexity),
+        cell(Column::Nesting),
+        result.comment_density(),
+        result.todo_count,
+        Column::Signature.table_cell(result, options),
+        resilience,
+        ast_counts
+    )
+}
+
+/// Formats a row with only the selected columns, in order
+/// Like the default row, the function name is followed by a colon
+fn selected_columns_row(
+    result: &FunctionAnalysisResult,
+    columns: &[Column],
+    options: &FormatOptions,
+) -> String {
+    let mut row = String::from("  -");
+    let mut separator = " ";
+    for &column in columns {
+        row.push_str(separator);
+        row.push_str(&highlight(
+            column.table_cell(result, options),
+            column,
+            result,
+            options,
+        ));
+        separator = if column == Column::Name { ": " } else { ", " };
+    }
+    row
+}
+
+/// Splits results into groups, keeping their order within each group
+/// Groups appear in the order of their first result, so the sort order also ranks the groups
+fn group_results(
+    results: &[FunctionAnalysisResult],
+    group_by: GroupBy,
+) -> Vec<(String, Vec<&FunctionAnalysisResult>)> {
+    let mut groups: Vec<(String, Vec<&FunctionAnalysisResult>)> = Vec::new();
+    for result in results {
+        let key = match group_by {
+            GroupBy::File => result.source_file().unwrap_or("(unknown file)").to_string(),
+            GroupBy::Module => result
+                .module_path()
+                .unwrap_or_else(|| "(unknown module)".to_string()),
+        };
+        match groups.iter_mut().find(|(group, _)| *group == key) {
+            Some((_, group)) => group.push(result),
+            None => groups.push((key, vec![result])),
+        }
+    }
+    groups
+}
+
+/// Formats a row of line totals, e.g. the subtotal that closes a group
+fn totals_row(label: &str, totals: &Totals) -> String {
+    format!(
+        "  = {label}: functions={}, total={} lines, code={}, comment={}, empty={}, max complexity={}, max nesting={}",
+        totals.functions,
+        totals.total,
+        totals.code,
+        totals.comment,
+        totals.empty,
+        totals.max_complexity,
+        totals.max_nesting
+    )
+}
+
+/// Formats one aggregate row per module, ranked by combined size, largest first
+fn module_rows(results: &[FunctionAnalysisResult]) -> String {
+    let mut modules: Vec<_> = group_results(results, GroupBy::Module)
+        .into_iter()
+        .map(|(module, group)| (module, Totals::new(group)))
+        .collect();
+    modules.sort_by_key(|(_, totals)| Reverse(totals.code));
+    modules
+        .iter()
+        .map(|(module, totals)| module_row(module, totals))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Formats the aggregate row of a module
+fn module_row(module: &str, totals: &Totals) -> String {
+    format!(
+        "  - mod {module}: functions={}, total={} lines, code={}, comment={}, empty={}, complexity={}, max complexity={}, max nesting={}",
+        totals.functions,
+        totals.total,
+        totals.code,
+        totals.comment,
+        totals.empty,
+        totals.complexity,
+        totals.max_complexity,
+        totals.max_nesting
+    )
+}
+
+/// Colors a table cell by how close its metric is to the configured limit
+///
+/// Metrics above their limit are red, those above 80% of it yellow, and the
+/// rest green. The function name is red when any limit is exceeded. Cells
+/// without a limit, and all cells when color is off, are left unchanged. With
+/// `--editor-links osc8` the function name also links to the function.
+pub(super) fn highlight(
+    cell: String,
+    column: Column,
+    result: &FunctionAnalysisResult,
+    options: &FormatOptions,
+) -> String {
+    if column == Column::Name && options.editor_links == Some(EditorLinks::Osc8) {
+        let cell = highlight_metric(cell, column, result, options);
+        return editor_links::hyperlink(cell, result);
+    }
+    highlight_metric(cell, column, result, options)
+}
+
+/// Colors a table cell as described for [`highlight`], without links
+fn highlight_metric(
+    cell: String,
+    column: Column,
+    result: &FunctionAnalysisResult,
+    options: &FormatOptions,
+) -> String {
+    if !options.color {
+        return cell;
+    }
+    let thresholds = &options.thresholds;
+    let (value, limit) = match column {
+        Column::Name if !thresholds.violations(result).is_empty() => {
+            return format!("{RED}{cell}{RESET}");
+        }
+        Column::Total => (result.total, thresholds.total),
+        Column::Code => (result.code, thresholds.code),
+        Column::Complexity => (result.cyclomatic_complexity, thresholds.complexity),
+        Column::Nesting => (result.nesting_depth, thresholds.nesting),
+        _ => return cell,
+    };
+    let Some(limit) = limit else {
+        return cell;
+    };
+    let color = if value > limit {
+        RED
+    } else if value * 5 > limit * 4 {
+        YELLOW
+    } else {
+        GREEN
+    };
+    format!("{color}{cell}{RESET}")
+}
+
+const RED: &str = "\x1b[31m";
+const YELLOW: &str = "\x1b[33m";
+const GREEN: &str = "\x1b[32m";
+const RESET: &str = "\x1b[0m";
+
+/// Formats summary statistics as counts followed by an aligned table with one metric per line
+///
+/// When production and test code are summarized apart, a table for each
+/// follows, and then the ratio of their code lines.
+pub(crate) fn summary_table(summary: &Summary) -> String {
+    let mut lines = summary_lines(summary, "  ");
+    if let Some(split) = &summary.split {
+        lines.push("  production code:".to_string());
+        lines.extend(summary_lines(&split.production, "    "));
+        lines.push("  test code:".to_string());
+        lines.extend(summary_lines(&split.test, "    "));
+        lines.push(format!(
+            "  test_to_code_ratio={:.2}",
+            split.test_to_code_ratio
+        ));
+    }
+    lines.join("\n")
+}
+
+fn summary_lines(summary: &Summary, indent: &str) -> Vec<String> {
+    let mut lines = vec![
+        format!(
+            "{indent}files={}, functions={}, comment_density={:.1}%",
+            summary.files, summary.functions, summary.comment_density
+        ),
+        format!(
+            "{indent}{:<12} {:>10} {:>8} {:>8} {:>8} {:>8}",
+            "metric", "total", "mean", "median", "p90", "max"
+        ),
+    ];
+    for (metric, values) in &summary.metrics {
+        lines.push(format!(
+            "{indent}{:<12} {:>10} {:>8.1} {:>8.1} {:>8} {:>8}",
+            metric, values.total, values.mean, values.median, values.p90, values.max
+        ));
+    }
+    lines
+}
+
+/// Formats the health score with its sub-scores, followed by the grade of every file
+pub(crate) fn health_summary(health: &HealthReport) -> String {
+    let project = &health.project;
+    let mut lines = vec![
+        format!("Health score: {:.1} ({})", project.score, project.grade),
+        format!(
+            "  avg complexity={:.1}, long functions={:.1}%, comment density={:.1}%",
+            project.average_complexity, project.long_function_percent, project.comment_density
+        ),
+    ];
+    for file in &health.files {
+        lines.push(format!(
+            "  {} {:>5.1}  {} ({} functions)",
+            file.health.grade, file.health.score, file.file, file.functions
+        ));
+    }
+    lines.join("\n")
+}
+
+/// Formats run statistics as a single human-readable line
+pub(crate) fn stats_summary(stats: &RunStats) -> String {
+    let peak_rss = stats
+        .peak_rss_bytes
+        .map(|bytes| format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0)))
+        .unwrap_or_else(|| "unknown".to_string());
+    format!(
+        "wall_time={:.3}s, peak_rss={}, files/sec={:.1}, functions/sec={:.1}",
+        stats.wall_time.as_secs_f64(),
+        peak_rss,
+        stats.files_per_sec(),
+        stats.functions_per_sec()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Thresholds;
+    use std::path::PathBuf;
+
+    fn sample_result(complexity: usize) -> FunctionAnalysisResult {
+        named_result("src/lib.rs::parse", complexity)
+    }
+
+    fn named_result(name: &str, complexity: usize) -> FunctionAnalysisResult {
+        let (file, name) = name.rsplit_once("::").unwrap_or(("", name));
+        FunctionAnalysisResult {
+            file: PathBuf::from(file),
+            total: 12,
+            comment: 1,
+            empty: 1,
+            nesting_depth: 1,
+            stmt_count: 4,
+            expr_count: 8,
+            end_line: 12,
+            ..FunctionAnalysisResult::sample(name, 10, complexity)
+        }
+    }
+
+    #[test]
+    fn test_rows_are_plain_without_color() {
+        let row = TableFormat.row(&sample_result(20), &FormatOptions::default());
+        assert!(row.starts_with("  - fn src/lib.rs::parse: total=12 lines, code=10,"));
+        assert!(!row.contains('\x1b'));
+    }
+
+    #[test]
+    fn test_color_highlights_metrics_against_thresholds() {
+        let options = FormatOptions {
+            color: true,
+            thresholds: Thresholds {
+                complexity: Some(10),
+                nesting: Some(1),
+                code: Some(100),
+                ..Thresholds::default()
+            },
+            ..FormatOptions::default()
+        };
+
+        let row = TableFormat.row(&sample_result(20), &options);
+        assert!(row.contains(&format!("{RED}fn src/lib.rs::parse{RESET}")));
+        assert!(row.contains(&format!("{RED}complexity=20{RESET}")));
+        assert!(row.contains(&format!("{YELLOW}nesting=1{RESET}")));
+        assert!(row.contains(&format!("{GREEN}code=10{RESET}")));
+        assert!(row.contains(&format!("{RESET}: total=12 lines, ")));
+
+        let row = TableFormat.row(&sample_result(2), &options);
+        assert!(row.starts_with("  - fn src/lib.rs::parse:"));
+    }
+
+    #[test]
+    fn test_group_by_file_adds_sections_and_subtotals() {
+        let results = [
+            named_result("src/b.rs::large", 9),
+            named_result("src/a.rs::first", 2),
+            named_result("src/b.rs::small", 4),
+        ];
+        let options = FormatOptions {
+            group_by: Some(GroupBy::File),
+            columns: Some(vec![Column::Name, Column::Complexity]),
+            ..FormatOptions::default()
+        };
+
+        assert_eq!(
+            TableFormat.render(&results, &options),
+            "src/b.rs:
+  - fn src/b.rs::large: complexity=9
+  - fn src/b.rs::small: complexity=4
+  = subtotal: functions=2, total=24 lines, code=20, comment=2, empty=2, max complexity=9, max nesting=1
+
+src/a.rs:
+  - fn src/a.rs::first: complexity=2
+  = subtotal: functions=1, total=12 lines, code=10, comment=1, empty=1, max complexity=2, max nesting=1"
+        );
+    }
+
+    #[test]
+    fn test_group_by_module_rolls_up_functions() {
+        let results = [
+            named_result("src/lib.rs::run", 2),
+            named_result("src/output/mod.rs::render", 3),
+            named_result("src/output/mod.rs::row", 5),
+        ];
+        let options = FormatOptions {
+            group_by: Some(GroupBy::Module),
+            ..FormatOptions::default()
+        };
+
+        assert_eq!(
+            TableFormat.render(&results, &options),
+            "  - mod crate::output: functions=2, total=24 lines, code=20, comment=2, empty=2, complexity=8, max complexity=5, max nesting=1
+  - mod crate: functions=1, total=12 lines, code=10, comment=1, empty=1, complexity=2, max complexity=2, max nesting=1"
+        );
+    }
+
+    #[test]
+    fn test_totals_row_follows_the_listing() {
+        let results = [sample_result(3), sample_result(7)];
+        let options = FormatOptions {
+            totals: true,
+            columns: Some(vec![Column::Name]),
+            ..FormatOptions::default()
+        };
+
+        let rendered = TableFormat.render(&results, &options);
+        assert!(rendered.ends_with(
+            "  - fn src/lib.rs::parse\n  = total: functions=2, total=24 lines, code=20, comment=2, empty=2, max complexity=7, max nesting=1"
+        ));
+        assert_eq!(TableFormat.render(&[], &options), "");
+    }
+}
@@ -0,0 +1,132 @@
+use super::json::{JsonRow, rows};
+use super::{Format, FormatOptions, Totals};
+use crate::analyzer::FunctionAnalysisResult;
+use crate::errors::{AnalysisError, AnalysisResult};
+use crate::source::read_text;
+use minijinja::value::Serde;
+use minijinja::{Environment, Value, context};
+use std::path::Path;
+
+/// Template used by `--format template` when no `--template` file is given
+pub const DEFAULT_TEMPLATE: &str = "\
+{% for f in functions -%}
+{{ f.name }}: code={{ f.code }}, complexity={{ f.complexity }}, nesting={{ f.nesting }}
+{% endfor -%}
+{{ summary.functions }} functions, {{ summary.code }} code lines";
+
+/// Output rendered from a user-defined MiniJinja (Jinja2-style) template
+///
+/// The template sees `functions`, a list of objects with the JSON field names,
+/// and `summary`, an object with the function count, line totals, and the
+/// maximum complexity and nesting.
+#[derive(Debug, Clone)]
+pub struct TemplateFormat {
+    source: String,
+}
+
+impl TemplateFormat {
+    /// Creates a format from template source, failing if the template does not parse
+    pub fn new(source: impl Into<String>) -> AnalysisResult<Self> {
+        let source = source.into();
+        Environment::new()
+            .template_from_str(&source)
+            .map_err(|e| invalid_template(&e))?;
+        Ok(Self { source })
+    }
+
+    /// Loads a template file
+    pub fn from_file(path: &Path) -> AnalysisResult<Self> {
+        Self::new(read_text(path)?)
+    }
+
+    /// Renders the template for the given functions
+    fn render_functions(&self, functions: Vec<JsonRow>, summary: Totals) -> String {
+        let ctx = context! {
+            functions => Value::from(Serde(&functions)),
+            summary => Value::from(Serde(&summary)),
+        };
+        match Environment::new().render_str(&self.source, ctx) {
+            Ok(rendered) => rendered.trim_end().to_string(),
+            Err(e) => {
+                // Syntax errors are caught up front; this only covers errors raised while rendering
+                eprintln!("Warning: {}", invalid_template(&e));
+                String::new()
+            }
+        }
+    }
+}
+
+impl Default for TemplateFormat {
+    fn default() -> Self {
+        Self {
+            source: DEFAULT_TEMPLATE.to_string(),
+        }
+    }
+}
+
+impl Format for TemplateFormat {
+    /// Renders the template with a single function
+    fn row(&self, result: &FunctionAnalysisResult, options: &FormatOptions) -> String {
+        let results = std::slice::from_ref(result);
+        self.render_functions(rows(results, options), Totals::new(results))
+    }
+
+    fn render(&self, results: &[FunctionAnalysisResult], options: &FormatOptions) -> String {
+        self.render_functions(rows(results, options), Totals::new(results))
+    }
+}
+
+/// Converts a MiniJinja error, which includes the line number, into an analysis error
+fn invalid_template(error: &minijinja::Error) -> AnalysisError {
+    AnalysisError::InvalidTemplate {
+        message: error.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_result(name: &str, code: usize) -> FunctionAnalysisResult {
+        FunctionAnalysisResult {
+            total: code + 2,
+            comment: 1,
+            empty: 1,
+            nesting_depth: 1,
+            stmt_count: 2,
+            expr_count: 4,
+            end_line: code + 2,
+            ..FunctionAnalysisResult::sample(name, code, 3)
+        }
+    }
+
+    #[test]
+    fn test_template_sees_functions_and_summary() {
+        let format = TemplateFormat::new(
+            "{% for f in functions %}{{ f.name }}={{ f.code }};{% endfor %} {{ summary.code }}",
+        )
+        .unwrap();
+        let results = [sample_result("a", 4), sample_result("b", 6)];
+
+        assert_eq!(
+            format.render(&results, &FormatOptions::default()),
+            "a=4;b=6; 10"
+        );
+    }
+
+    #[test]
+    fn test_syntax_errors_are_reported() {
+        let error = TemplateFormat::new("{% for f in functions %}").unwrap_err();
+        assert!(matches!(error, AnalysisError::InvalidTemplate { .. }));
+    }
+
+    #[test]
+    fn test_default_template() {
+        let rendered =
+            TemplateFormat::default().render(&[sample_result("a", 4)], &FormatOptions::default());
+        assert_eq!(
+            rendered,
+            "a: code=4, complexity=3, nesting=1\n1 functions, 4 code lines"
+        );
+    }
+}
@@ -0,0 +1,71 @@
+use super::json::JsonEnvelope;
+use super::{Format, FormatOptions, ReportSections};
+use crate::analyzer::FunctionAnalysisResult;
+use serde::Serialize;
+
+/// TOML document with one `[[functions]]` table per function, using the JSON field names
+///
+/// TOML has no null, so a missing resilience value is omitted.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TomlFormat;
+
+impl Format for TomlFormat {
+    /// Formats a single function as one `[[functions]]` table
+    fn row(&self, result: &FunctionAnalysisResult, options: &FormatOptions) -> String {
+        self.render(std::slice::from_ref(result), options)
+    }
+
+    fn render(&self, results: &[FunctionAnalysisResult], options: &FormatOptions) -> String {
+        to_toml(&JsonEnvelope::new(
+            results,
+            &ReportSections::default(),
+            options,
+        ))
+    }
+
+    /// Adds a table per requested section after the functions
+    fn render_with_sections(
+        &self,
+        results: &[FunctionAnalysisResult],
+        sections: &ReportSections,
+        options: &FormatOptions,
+    ) -> Option<String> {
+        Some(to_toml(&JsonEnvelope::new(results, sections, options)))
+    }
+}
+
+/// Serializes a value without the trailing newline
+/// The serialized types only contain strings and numbers, so serialization cannot fail
+fn to_toml<T: Serialize>(value: &T) -> String {
+    toml::to_string(value)
+        .unwrap_or_default()
+        .trim_end()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toml_round_trips() {
+        let result = FunctionAnalysisResult {
+            total: 9,
+            comment: 2,
+            empty: 1,
+            nesting_depth: 1,
+            stmt_count: 4,
+            expr_count: 7,
+            end_line: 9,
+            ..FunctionAnalysisResult::sample("src/lib.rs::parse", 6, 2)
+        };
+
+        let rendered = TomlFormat.render(&[result.clone(), result], &FormatOptions::default());
+        let parsed: toml::Table = toml::from_str(&rendered).unwrap();
+        let functions = parsed["functions"].as_array().unwrap();
+
+        assert_eq!(functions.len(), 2);
+        assert_eq!(functions[0]["name"].as_str(), Some("src/lib.rs::parse"));
+        assert!(functions[0].get("resilience").is_none());
+    }
+}
@@ -0,0 +1,45 @@
+use crate::analyzer::FunctionAnalysisResult;
+use serde::Serialize;
+
+/// Line totals and worst-case metrics over a set of functions
+///
+/// Used for the `--totals` and subtotal rows, the rows of module rollups, and
+/// as `summary` in templates.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Totals {
+    pub functions: usize,
+    pub total: usize,
+    pub code: usize,
+    pub comment: usize,
+    pub empty: usize,
+    /// Sum of the cyclomatic complexities
+    pub complexity: usize,
+    pub max_complexity: usize,
+    pub max_nesting: usize,
+    pub todos: usize,
+    pub stmts: usize,
+    pub exprs: usize,
+    pub max_signature: usize,
+}
+
+impl Totals {
+    /// Adds up the given functions
+    pub fn new<'a>(results: impl IntoIterator<Item = &'a FunctionAnalysisResult>) -> Self {
+        results
+            .into_iter()
+            .fold(Self::default(), |totals, result| Self {
+                functions: totals.functions + 1,
+                total: totals.total + result.total,
+                code: totals.code + result.code,
+                comment: totals.comment + result.comment,
+                empty: totals.empty + result.empty,
+                complexity: totals.complexity + result.cyclomatic_complexity,
+                max_complexity: totals.max_complexity.max(result.cyclomatic_complexity),
+                max_nesting: totals.max_nesting.max(result.nesting_depth),
+                todos: totals.todos + result.todo_count,
+                stmts: totals.stmts + result.stmt_count,
+                exprs: totals.exprs + result.expr_count,
+                max_signature: totals.max_signature.max(result.signature_complexity),
+            })
+    }
+}
@@ -0,0 +1,65 @@
+use super::json::{JsonEnvelope, JsonRow, rows};
+use super::{Format, FormatOptions, ReportSections};
+use crate::analyzer::FunctionAnalysisResult;
+use serde::Serialize;
+
+/// YAML sequence with one mapping per function, using the JSON field names
+#[derive(Debug, Clone, Copy, Default)]
+pub struct YamlFormat;
+
+impl Format for YamlFormat {
+    /// Formats a single function as a one-item sequence
+    fn row(&self, result: &FunctionAnalysisResult, options: &FormatOptions) -> String {
+        to_yaml(&[JsonRow::new(result, options)])
+    }
+
+    fn render(&self, results: &[FunctionAnalysisResult], options: &FormatOptions) -> String {
+        to_yaml(&rows(results, options))
+    }
+
+    /// Wraps the results in a mapping next to the requested section mappings
+    fn render_with_sections(
+        &self,
+        results: &[FunctionAnalysisResult],
+        sections: &ReportSections,
+        options: &FormatOptions,
+    ) -> Option<String> {
+        Some(to_yaml(&JsonEnvelope::new(results, sections, options)))
+    }
+}
+
+/// Serializes a value without the trailing newline
+/// The serialized types only contain strings and numbers, so serialization cannot fail
+fn to_yaml<T: Serialize>(value: &T) -> String {
+    serde_yaml::to_string(value)
+        .unwrap_or_default()
+        .trim_end()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_yaml_round_trips() {
+        let result = FunctionAnalysisResult {
+            total: 9,
+            comment: 2,
+            empty: 1,
+            nesting_depth: 1,
+            stmt_count: 4,
+            expr_count: 7,
+            end_line: 9,
+            ..FunctionAnalysisResult::sample("src/lib.rs::parse: odd", 6, 2)
+        };
+
+        let rendered = YamlFormat.render(&[result.clone(), result], &FormatOptions::default());
+        let parsed: serde_yaml::Value = serde_yaml::from_str(&rendered).unwrap();
+        let functions = parsed.as_sequence().unwrap();
+
+        assert_eq!(functions.len(), 2);
+        assert_eq!(functions[0]["name"], "src/lib.rs::parse: odd");
+        assert_eq!(functions[0]["comment_density"], 25.0);
+    }
+}
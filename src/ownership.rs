@@ -0,0 +1,315 @@
+//! Ownership of complex code from git blame, for `--owners`
+//!
+//! Each line of a function is attributed to the author of the commit that last
+//! changed it, and the function's cyclomatic complexity is split among its
+//! authors by their share of its lines. Summed over all functions, this shows
+//! who owns the most complex code, e.g. to pick reviewers for a refactor.
+//!
+//! Authors can be grouped into teams in `fnloc.toml`:
+//!
+//! ```toml
+//! [teams]
+//! platform = ["Alice Example", "bob@example.com"]
+//! ```
+//!
+//! Authors who are in no team are listed under their own name. Lines changed
+//! in the working tree but not committed yet are listed under [`UNCOMMITTED`]
+//! rather than the "Not Committed Yet" pseudo-author of git blame.
+
+use crate::analyzer::FunctionAnalysisResult;
+use crate::diagnostics::{Diagnostic, DiagnosticKind};
+use crate::errors::AnalysisResult;
+use crate::git_changes::git;
+use rayon::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// Team names with their members, given by author name or email
+pub type Teams = BTreeMap<String, Vec<String>>;
+
+/// Owner of the lines that are not committed yet
+pub const UNCOMMITTED: &str = "(uncommitted)";
+
+/// Author of a line according to git blame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Author {
+    pub name: String,
+    /// Email address without the angle brackets
+    pub mail: String,
+}
+
+/// Share of the analyzed code owned by an author or team
+#[derive(Debug, Clone, PartialEq)]
+pub struct Owner {
+    pub name: String,
+    /// Cyclomatic complexity of the functions, split by the owner's share of their lines
+    pub complexity: f64,
+    /// Functions in which the owner last changed more lines than anyone else
+    pub functions: usize,
+    /// Function lines last changed by the owner
+    pub lines: usize,
+}
+
+/// Blames the files of the results and returns their owners, most complex code first
+///
+/// Files that cannot be blamed, e.g. because they are not tracked by git, are
+/// left out with a warning.
+pub fn find_owners(
+    results: &[FunctionAnalysisResult],
+    teams: &Teams,
+) -> (Vec<Owner>, Vec<Diagnostic>) {
+    let mut files: Vec<&str> = results
+        .iter()
+        .filter_map(FunctionAnalysisResult::source_file)
+        .collect();
+    files.sort_unstable();
+    files.dedup();
+
+    let blamed: Vec<(&str, AnalysisResult<Vec<Author>>)> = files
+        .par_iter()
+        .map(|file| (*file, blame(Path::new("."), file)))
+        .collect();
+
+    let mut blames = HashMap::new();
+    let mut diagnostics = Vec::new();
+    for (file, authors) in blamed {
+        match authors {
+            Ok(authors) => {
+                blames.insert(file.to_string(), authors);
+            }
+            Err(e) => diagnostics.push(Diagnostic::new(
+                file,
+                DiagnosticKind::OwnersUnknown,
+                format!("Cannot find the owners of {file}: {e}"),
+            )),
+        }
+    }
+    (attribute(results, &blames, teams), diagnostics)
+}
+
+/// Returns the author of every line of a file, in line order
+pub fn blame(dir: &Path, file: &str) -> AnalysisResult<Vec<Author>> {
+    let output = git(dir, &["blame", "--line-porcelain", "--", file])?;
+    Ok(parse_line_porcelain(&output))
+}
+
+/// Reads the line authors from `git blame --line-porcelain` output
+///
+/// Every line is preceded by a header repeating the details of its commit,
+/// and the line itself is prefixed with a tab. The header starts with the
+/// commit hash, which is all zeros for lines that are not committed yet.
+fn parse_line_porcelain(output: &str) -> Vec<Author> {
+    let mut authors = Vec::new();
+    let mut committed = true;
+    let mut name = "";
+    let mut mail = "";
+    for line in output.lines() {
+        let first_word = line.split(' ').next().unwrap_or_default();
+        if line.starts_with('\t') {
+            authors.push(if committed {
+                Author {
+                    name: name.to_string(),
+                    mail: mail.to_string(),
+                }
+            } else {
+                Author {
+                    name: UNCOMMITTED.to_string(),
+                    mail: String::new(),
+                }
+            });
+        } else if first_word.len() == 40 && first_word.bytes().all(|b| b.is_ascii_hexdigit()) {
+            committed = first_word.bytes().any(|b| b != b'0');
+        } else if let Some(value) = line.strip_prefix("author-mail ") {
+            mail = value.trim_start_matches('<').trim_end_matches('>');
+        } else if let Some(value) = line.strip_prefix("author ") {
+            name = value;
+        }
+    }
+    authors
+}
+
+/// Splits the lines and complexity of every function among the authors of its lines
+fn attribute(
+    results: &[FunctionAnalysisResult],
+    blames: &HashMap<String, Vec<Author>>,
+    teams: &Teams,
+) -> Vec<Owner> {
+    let mut owners: BTreeMap<String, Owner> = BTreeMap::new();
+    for result in results {
+        let Some(authors) = result.source_file().and_then(|file| blames.get(file)) else {
+            continue;
+        };
+        let mut lines: BTreeMap<&str, usize> = BTreeMap::new();
+        for author in authors
+            .iter()
+            .take(result.end_line)
+            .skip(result.start_line.saturating_sub(1))
+        {
+            *lines.entry(owner_name(author, teams)).or_default() += 1;
+        }
+
+        let total: usize = lines.values().sum();
+        for (name, &count) in &lines {
+            let owner = owners.entry(name.to_string()).or_insert_with(|| Owner {
+                name: name.to_string(),
+                complexity: 0.0,
+                functions: 0,
+                lines: 0,
+            });
+            owner.lines += count;
+            owner.complexity += result.cyclomatic_complexity as f64 * count as f64 / total as f64;
+        }
+        if let Some((main, _)) = lines.iter().max_by_key(|(_, count)| **count) {
+            owners.get_mut(*main).expect("owner was added").functions += 1;
+        }
+    }
+
+    let mut owners: Vec<Owner> = owners.into_values().collect();
+    owners.sort_by(|a, b| {
+        b.complexity
+            .total_cmp(&a.complexity)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    owners
+}
+
+/// Returns the team of an author, or their own name if they are in no team
+fn owner_name<'a>(author: &'a Author, teams: &'a Teams) -> &'a str {
+    if author.name == UNCOMMITTED {
+        return UNCOMMITTED;
+    }
+    teams
+        .iter()
+        .find(|(_, members)| {
+            members
+                .iter()
+                .any(|member| *member == author.name || member.eq_ignore_ascii_case(&author.mail))
+        })
+        .map_or(&author.name, |(team, _)| team)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn function(
+        name: &str,
+        complexity: usize,
+        start_line: usize,
+        end_line: usize,
+    ) -> FunctionAnalysisResult {
+        FunctionAnalysisResult {
+            file: PathBuf::from("src/lib.rs"),
+            start_line,
+            end_line,
+            ..FunctionAnalysisResult::sample(name, end_line - start_line + 1, complexity)
+        }
+    }
+
+    fn porcelain_line(name: &str, mail: &str) -> String {
+        format!(
+            "1234567890abcdef1234567890abcdef12345678 1 1 1\n\
+             author {name}\n\
+             author-mail <{mail}>\n\
+             author-time 1700000000\n\
+             summary change\n\
+             filename src/lib.rs\n\
+             \tcode\n"
+        )
+    }
+
+    #[test]
+    fn test_complexity_is_split_by_blamed_lines() {
+        let output = [
+            porcelain_line("Alice", "alice@example.com"),
+            porcelain_line("Alice", "alice@example.com"),
+            porcelain_line("Bob", "bob@example.com"),
+            porcelain_line("Carol", "carol@example.com"),
+        ]
+        .concat();
+        let authors = parse_line_porcelain(&output);
+        assert_eq!(authors.len(), 4);
+        assert_eq!(authors[2].mail, "bob@example.com");
+
+        let blames = HashMap::from([("src/lib.rs".to_string(), authors)]);
+        let results = [function("parse", 8, 1, 4), function("tail", 1, 4, 4)];
+
+        let owners = attribute(&results, &blames, &Teams::new());
+        let summary: Vec<(&str, f64, usize, usize)> = owners
+            .iter()
+            .map(|owner| {
+                (
+                    owner.name.as_str(),
+                    owner.complexity,
+                    owner.functions,
+                    owner.lines,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("Alice", 4.0, 1, 2),
+                ("Carol", 3.0, 1, 2),
+                ("Bob", 2.0, 0, 1)
+            ]
+        );
+
+        let teams = Teams::from([(
+            "parsers".to_string(),
+            vec!["Alice".to_string(), "BOB@example.com".to_string()],
+        )]);
+        let owners = attribute(&results, &blames, &teams);
+        assert_eq!(owners[0].name, "parsers");
+        assert_eq!(owners[0].complexity, 6.0);
+        assert_eq!(owners[0].lines, 3);
+    }
+
+    #[test]
+    fn test_uncommitted_lines_have_their_own_owner() {
+        let repo = std::env::temp_dir().join(format!("fnloc_owners_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&repo);
+        std::fs::create_dir_all(&repo).unwrap();
+        std::fs::write(repo.join("lib.rs"), "fn a() {\n    b();\n}\n").unwrap();
+        let git = |args: &[&str]| {
+            let status = std::process::Command::new("git")
+                .arg("-C")
+                .arg(&repo)
+                .args([
+                    "-c",
+                    "user.name=Alice",
+                    "-c",
+                    "user.email=alice@example.com",
+                ])
+                .args(args)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {args:?} failed");
+        };
+        git(&["init", "-q"]);
+        git(&["add", "."]);
+        git(&["commit", "-q", "-m", "initial"]);
+        std::fs::write(repo.join("lib.rs"), "fn a() {\n    b();\n    c();\n}\n").unwrap();
+
+        let authors = blame(&repo, "lib.rs").unwrap();
+        let names: Vec<&str> = authors.iter().map(|author| author.name.as_str()).collect();
+        assert_eq!(names, ["Alice", "Alice", UNCOMMITTED, "Alice"]);
+
+        // Uncommitted lines are not counted for a team, even one naming git's pseudo-author
+        let teams = Teams::from([(
+            "everyone".to_string(),
+            vec!["Alice".to_string(), "Not Committed Yet".to_string()],
+        )]);
+        let blames = HashMap::from([("src/lib.rs".to_string(), authors)]);
+        let owners = attribute(&[function("a", 4, 1, 4)], &blames, &teams);
+        let names: Vec<(&str, usize)> = owners
+            .iter()
+            .map(|owner| (owner.name.as_str(), owner.lines))
+            .collect();
+        assert_eq!(names, [("everyone", 3), (UNCOMMITTED, 1)]);
+
+        std::fs::remove_dir_all(&repo).unwrap();
+    }
+}
@@ -0,0 +1,347 @@
+//! Panic reachability report
+//!
+//! Classifies each function by whether it can panic directly (`panic!`-style
+//! macros, `unwrap`/`expect`, indexing), whether it can panic through a callee
+//! in the analyzed set, and — for functions returning `Result` — whether callers
+//! propagate the error, handle it, or turn it into a panic with `unwrap`.
+
+use crate::call_graph::{CallGraph, ParsedFunction};
+use std::collections::{BTreeMap, VecDeque};
+use syn::visit::{self, Visit};
+use syn::{Expr, ExprCall, ItemFn, Macro, ReturnType, Type};
+
+/// Macros that panic when reached
+const PANIC_MACROS: [&str; 7] = [
+    "panic",
+    "unreachable",
+    "todo",
+    "unimplemented",
+    "assert",
+    "assert_eq",
+    "assert_ne",
+];
+
+/// Methods that panic on `None` / `Err`
+const PANIC_METHODS: [&str; 2] = ["unwrap", "expect"];
+
+/// How a function can reach a panic
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PanicReachability {
+    /// The function contains panic sites itself
+    Direct { sites: usize },
+    /// The function calls an analyzed function that can panic
+    Transitive { via: String },
+    /// No panic site was found in the function or its analyzed callees
+    None,
+}
+
+/// How callers treat the `Result` returned by a function, counted per call site
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResultHandling {
+    /// Call sites that propagate the error with `?`
+    pub propagated: usize,
+    /// Call sites that handle the result (match, `if let`, combinators, ...)
+    pub handled: usize,
+    /// Call sites that call `unwrap`/`expect` on the result
+    pub unwrapped: usize,
+}
+
+/// Panic classification for one function
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PanicReportEntry {
+    pub function: String,
+    pub reachability: PanicReachability,
+    pub returns_result: bool,
+    pub caller_handling: ResultHandling,
+}
+
+/// Builds the panic reachability report for the given functions
+///
+/// Functions sharing a name, such as `#[cfg]`-gated twins, get a single entry.
+pub fn build_panic_report(functions: &[ParsedFunction]) -> Vec<PanicReportEntry> {
+    let graph = CallGraph::build(functions);
+
+    // Direct panic sites per function; `#[cfg]` twins share a name and keep
+    // the count of the twin with the most sites
+    let mut reachability: BTreeMap<&str, PanicReachability> = BTreeMap::new();
+    for function in functions {
+        let sites = count_panic_sites(&function.item);
+        let state = reachability
+            .entry(function.name.as_str())
+            .or_insert(PanicReachability::None);
+        let recorded = match state {
+            PanicReachability::Direct { sites } => *sites,
+            _ => 0,
+        };
+        if sites > recorded {
+            *state = PanicReachability::Direct { sites };
+        }
+    }
+
+    // Propagate reachability from panicking functions to their callers
+    let mut queue: VecDeque<&str> = reachability
+        .iter()
+        .filter(|(_, state)| **state != PanicReachability::None)
+        .map(|(name, _)| *name)
+        .collect();
+    while let Some(callee) = queue.pop_front() {
+        for caller in graph.callers(callee) {
+            if let Some(state) = reachability.get_mut(caller)
+                && *state == PanicReachability::None
+            {
+                *state = PanicReachability::Transitive {
+                    via: callee.to_string(),
+                };
+                queue.push_back(caller);
+            }
+        }
+    }
+
+    // How call sites treat the results of each called function, keyed by name
+    let mut usages: BTreeMap<String, ResultHandling> = BTreeMap::new();
+    for function in functions {
+        let mut collector = CallUsageCollector {
+            usages: &mut usages,
+        };
+        collector.visit_block(&function.item.block);
+    }
+
+    // One entry per name: the first twin's entry takes the merged reachability
+    functions
+        .iter()
+        .filter_map(|function| {
+            let reachability = reachability.remove(function.name.as_str())?;
+            let returns_result = returns_result(&function.item);
+            let caller_handling = if returns_result {
+                usages
+                    .get(&function.item.sig.ident.to_string())
+                    .cloned()
+                    .unwrap_or_default()
+            } else {
+                ResultHandling::default()
+            };
+            Some(PanicReportEntry {
+                function: function.name.clone(),
+                reachability,
+                returns_result,
+                caller_handling,
+            })
+        })
+        .collect()
+}
+
+/// Counts the places where a function can panic directly
+pub fn count_panic_sites(func: &ItemFn) -> usize {
+    let mut counter = PanicSiteCounter::default();
+    counter.visit_block(&func.block);
+    counter.sites
+}
+
+/// Returns true if the function's return type is `Result` or a `...Result` alias
+fn returns_result(func: &ItemFn) -> bool {
+    match &func.sig.output {
+        ReturnType::Type(_, ty) => match ty.as_ref() {
+            Type::Path(type_path) => type_path
+                .path
+                .segments
+                .last()
+                .is_some_and(|segment| segment.ident.to_string().ends_with("Result")),
+            _ => false,
+        },
+        ReturnType::Default => false,
+    }
+}
+
+/// AST visitor counting panic sites
+#[derive(Default)]
+struct PanicSiteCounter {
+    sites: usize,
+}
+
+impl<'ast> Visit<'ast> for PanicSiteCounter {
+    fn visit_macro(&mut self, mac: &'ast Macro) {
+        if mac
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| PANIC_MACROS.contains(&segment.ident.to_string().as_str()))
+        {
+            self.sites += 1;
+        }
+        visit::visit_macro(self, mac);
+    }
+
+    fn visit_expr(&mut self, expr: &'ast Expr) {
+        match expr {
+            Expr::MethodCall(call) if PANIC_METHODS.contains(&call.method.to_string().as_str()) => {
+                self.sites += 1;
+            }
+            Expr::Index(_) => self.sites += 1,
+            _ => {}
+        }
+        visit::visit_expr(self, expr);
+    }
+}
+
+/// AST visitor classifying how the result of each function call is used
+struct CallUsageCollector<'a> {
+    usages: &'a mut BTreeMap<String, ResultHandling>,
+}
+
+impl CallUsageCollector<'_> {
+    /// Returns the called function name of a path call expression
+    fn called_ident(call: &ExprCall) -> Option<String> {
+        match call.func.as_ref() {
+            Expr::Path(path) => path
+                .path
+                .segments
+                .last()
+                .map(|segment| segment.ident.to_string()),
+            _ => None,
+        }
+    }
+
+    /// Records a call site and visits the call's arguments
+    fn record(&mut self, call: &ExprCall, update: fn(&mut ResultHandling)) {
+        if let Some(ident) = Self::called_ident(call) {
+            update(self.usages.entry(ident).or_default());
+        }
+        for arg in &call.args {
+            self.visit_expr(arg);
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for CallUsageCollector<'_> {
+    fn visit_expr(&mut self, expr: &'ast Expr) {
+        match expr {
+            Expr::Try(expr_try) => {
+                if let Expr::Call(call) = expr_try.expr.as_ref() {
+                    self.record(call, |handling| handling.propagated += 1);
+                    return;
+                }
+            }
+            Expr::MethodCall(method)
+                if PANIC_METHODS.contains(&method.method.to_string().as_str()) =>
+            {
+                if let Expr::Call(call) = method.receiver.as_ref() {
+                    self.record(call, |handling| handling.unwrapped += 1);
+                    for arg in &method.args {
+                        self.visit_expr(arg);
+                    }
+                    return;
+                }
+            }
+            Expr::Call(call) => {
+                self.record(call, |handling| handling.handled += 1);
+                return;
+            }
+            _ => {}
+        }
+        visit::visit_expr(self, expr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::call_graph::parse_functions_from_source;
+
+    fn report(source: &str) -> BTreeMap<String, PanicReportEntry> {
+        build_panic_report(&parse_functions_from_source(source))
+            .into_iter()
+            .map(|entry| (entry.function.clone(), entry))
+            .collect()
+    }
+
+    #[test]
+    fn test_direct_panic_sites() {
+        let func: ItemFn = syn::parse_quote! {
+            fn risky(values: &[i32], text: &str) -> i32 {
+                let parsed: i32 = text.parse().unwrap();
+                if parsed < 0 {
+                    panic!("negative");
+                }
+                values[0] + parsed
+            }
+        };
+
+        assert_eq!(count_panic_sites(&func), 3);
+    }
+
+    #[test]
+    fn test_transitive_reachability() {
+        let entries = report(
+            r#"
+            fn top() { middle(); }
+            fn middle() { leaf(); }
+            fn leaf() { todo!() }
+            fn safe() -> i32 { 1 }
+            "#,
+        );
+
+        assert_eq!(
+            entries["leaf"].reachability,
+            PanicReachability::Direct { sites: 1 }
+        );
+        assert_eq!(
+            entries["middle"].reachability,
+            PanicReachability::Transitive {
+                via: "leaf".to_string()
+            }
+        );
+        assert_eq!(
+            entries["top"].reachability,
+            PanicReachability::Transitive {
+                via: "middle".to_string()
+            }
+        );
+        assert_eq!(entries["safe"].reachability, PanicReachability::None);
+    }
+
+    #[test]
+    fn test_caller_result_handling() {
+        let entries = report(
+            r#"
+            fn load() -> Result<i32, String> { Ok(1) }
+            fn propagate() -> Result<i32, String> { let v = load()?; Ok(v) }
+            fn unwrap_it() -> i32 { load().unwrap() }
+            fn handle() -> i32 { match load() { Ok(v) => v, Err(_) => 0 } }
+            "#,
+        );
+
+        let load = &entries["load"];
+        assert!(load.returns_result);
+        assert_eq!(
+            load.caller_handling,
+            ResultHandling {
+                propagated: 1,
+                handled: 1,
+                unwrapped: 1,
+            }
+        );
+        assert!(!entries["handle"].returns_result);
+        assert_eq!(
+            entries["unwrap_it"].reachability,
+            PanicReachability::Direct { sites: 1 }
+        );
+    }
+
+    #[test]
+    fn test_cfg_twins_are_reported_once() {
+        let entries = build_panic_report(&parse_functions_from_source(
+            r#"
+            #[cfg(unix)]
+            fn run() {}
+            #[cfg(not(unix))]
+            fn run() { unimplemented!() }
+            "#,
+        ));
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(
+            entries[0].reachability,
+            PanicReachability::Direct { sites: 1 }
+        );
+    }
+}
@@ -0,0 +1,193 @@
+//! Outcome of a run of the command-line tool
+//!
+//! [`run_analysis`](crate::run_analysis) returns an [`AnalysisReport`] instead
+//! of exiting, so the binary decides how to exit and embedders get the
+//! analyzed files and functions, their summary, the diagnostics raised, and any
+//! limit violations as one value. Formatters display the report as well.
+
+use crate::analyzer::FunctionAnalysisResult;
+use crate::config::Violation;
+use crate::diagnostics::{Diagnostic, DiagnosticKind};
+use crate::errors::AnalysisError;
+use crate::summary::Summary;
+use std::time::Duration;
+
+/// Everything a run found, from the analyzed files to the violated limits
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AnalysisReport {
+    /// Paths of the analyzed files; empty for `diff` and `history`, which report changes instead
+    pub files: Vec<String>,
+    /// Reported functions; rows streamed with `--format jsonl` are not kept
+    pub functions: Vec<FunctionAnalysisResult>,
+    /// Summary statistics over the reported functions
+    pub summary: Summary,
+    /// Warnings raised during the run, such as files that failed or functions below
+    /// `--min-comment-density`; the library does not print them
+    pub diagnostics: Vec<Diagnostic>,
+    /// Wall time spent finding and analyzing the files
+    pub duration: Duration,
+    /// Limit violations found with `--fail-on` or `--baseline`
+    pub violations: Vec<Violation>,
+}
+
+/// A file that could not be read or analyzed, listed in the output next to the functions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileError {
+    /// Path of the file, as given to the analysis
+    pub file: String,
+    /// Why the file could not be analyzed
+    pub error: String,
+}
+
+impl AnalysisReport {
+    /// Creates a report of the functions found in the given files, summarizing them
+    pub fn new(
+        files: Vec<String>,
+        functions: Vec<FunctionAnalysisResult>,
+        duration: Duration,
+    ) -> Self {
+        Self {
+            summary: Summary::new(&functions, files.len()),
+            files,
+            functions,
+            diagnostics: Vec::new(),
+            duration,
+            violations: Vec::new(),
+        }
+    }
+
+    /// Returns the files that could not be analyzed, in analysis order
+    ///
+    /// Their functions are missing from the report, so the measured coverage is incomplete.
+    pub fn file_errors(&self) -> Vec<FileError> {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.kind == DiagnosticKind::AnalysisFailed)
+            .map(|diagnostic| FileError {
+                file: diagnostic.file.clone(),
+                error: diagnostic
+                    .error
+                    .clone()
+                    .unwrap_or_else(|| diagnostic.message.clone()),
+            })
+            .collect()
+    }
+
+    /// Returns the files with functions missing from the report, in analysis order
+    ///
+    /// These are the files that could not be analyzed and those with regions
+    /// skipped or estimated because they do not parse.
+    pub fn incomplete_files(&self) -> Vec<String> {
+        let mut files: Vec<String> = Vec::new();
+        for diagnostic in &self.diagnostics {
+            let incomplete = matches!(
+                diagnostic.kind,
+                DiagnosticKind::AnalysisFailed | DiagnosticKind::UnparsedRegion
+            );
+            if incomplete && !files.contains(&diagnostic.file) {
+                files.push(diagnostic.file.clone());
+            }
+        }
+        files
+    }
+
+    /// Converts incomplete files, if any, into an error, as `--strict` does
+    pub fn require_complete(self) -> Result<Self, AnalysisError> {
+        let files = self.incomplete_files();
+        if files.is_empty() {
+            Ok(self)
+        } else {
+            Err(AnalysisError::IncompleteAnalysis { files })
+        }
+    }
+
+    /// Returns true if no function violates a limit
+    pub fn passed(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    /// Converts the violations, if any, into an error
+    pub fn into_result(self) -> Result<Self, AnalysisError> {
+        if self.passed() {
+            Ok(self)
+        } else {
+            Err(AnalysisError::ThresholdsExceeded {
+                violations: self.violations,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_violations_become_an_error() {
+        assert!(AnalysisReport::default().into_result().is_ok());
+
+        let report = AnalysisReport {
+            violations: vec![Violation {
+                function: "f".to_string(),
+                metric: "code",
+                value: 120,
+                limit: 80,
+            }],
+            ..AnalysisReport::default()
+        };
+        assert!(!report.passed());
+        assert!(matches!(
+            report.into_result(),
+            Err(AnalysisError::ThresholdsExceeded { violations }) if violations.len() == 1
+        ));
+    }
+
+    #[test]
+    fn test_failed_files_are_listed_as_file_errors() {
+        let error = AnalysisError::NonUtf8File {
+            file: "b.rs".to_string(),
+        };
+        let report = AnalysisReport {
+            diagnostics: vec![
+                Diagnostic::new(
+                    "a.rs",
+                    DiagnosticKind::InvalidUtf8,
+                    "a.rs is not valid UTF-8",
+                ),
+                Diagnostic::analysis_failed("b.rs", &error),
+            ],
+            ..AnalysisReport::default()
+        };
+
+        assert_eq!(
+            report.file_errors(),
+            [FileError {
+                file: "b.rs".to_string(),
+                error: error.to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_strict_requires_every_file_to_be_analyzed() {
+        let complete = AnalysisReport {
+            diagnostics: vec![Diagnostic::new(
+                "a.rs",
+                DiagnosticKind::InvalidUtf8,
+                "a.rs is not valid UTF-8",
+            )],
+            ..AnalysisReport::default()
+        };
+        assert!(complete.require_complete().is_ok());
+
+        let region = |file: &str| Diagnostic::new(file, DiagnosticKind::UnparsedRegion, "skipped");
+        let incomplete = AnalysisReport {
+            diagnostics: vec![region("b.rs"), region("b.rs"), region("c.rs")],
+            ..AnalysisReport::default()
+        };
+        assert!(matches!(
+            incomplete.require_complete(),
+            Err(AnalysisError::IncompleteAnalysis { files }) if files == ["b.rs", "c.rs"]
+        ));
+    }
+}
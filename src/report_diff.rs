@@ -0,0 +1,374 @@
+//! Per-function comparison of two reports
+//!
+//! `fnloc diff OLD.json [NEW.json]` compares two JSON reports, or a report and
+//! the current tree, and lists the functions that are new, removed, grew, or
+//! shrank, so reviewers can see how a change affected size and complexity.
+
+use crate::analyzer::FunctionAnalysisResult;
+use crate::errors::{AnalysisError, AnalysisResult};
+use crate::source::read_text;
+use serde::de::IgnoredAny;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Metrics of one function as read from a JSON report
+///
+/// Complexity and nesting are None for functions whose lines were counted
+/// without a syntax tree, which JSON reports write as null.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FunctionMetrics {
+    pub total: usize,
+    pub code: usize,
+    pub complexity: Option<usize>,
+    pub nesting: Option<usize>,
+}
+
+/// A function entry of a JSON report; other fields are ignored
+#[derive(Deserialize)]
+struct ReportFunction {
+    name: String,
+    #[serde(flatten)]
+    metrics: FunctionMetrics,
+}
+
+/// An entry of a plain array: a function, or a file that could not be analyzed
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ReportEntry {
+    Function(ReportFunction),
+    /// Only the presence of the `error` field is checked
+    FileError {
+        #[serde(rename = "error")]
+        _error: IgnoredAny,
+    },
+}
+
+/// JSON output is either a plain array or an object with a `functions` array
+///
+/// The envelope lists files that could not be analyzed under `errors`, which
+/// is ignored; the plain array lists them after the functions.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Report {
+    Entries(Vec<ReportEntry>),
+    Envelope { functions: Vec<ReportFunction> },
+}
+
+/// Kind of change of a function, in display order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Change {
+    New,
+    Removed,
+    Grew,
+    Shrank,
+}
+
+/// How one function changed between two reports
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FunctionDelta {
+    pub name: String,
+    pub change: Change,
+    pub old: Option<FunctionMetrics>,
+    pub new: Option<FunctionMetrics>,
+}
+
+/// Metrics of every function in a report, keyed by function name
+pub type ReportMetrics = BTreeMap<String, FunctionMetrics>;
+
+/// Reads the functions of a JSON report written with `--format json`
+pub fn load_report(path: &Path) -> AnalysisResult<ReportMetrics> {
+    let source = read_text(path)?;
+    let report: Report =
+        serde_json::from_str(&source).map_err(|e| AnalysisError::InvalidReport {
+            path: path.display().to_string(),
+            message: e.to_string(),
+        })?;
+    let functions = match report {
+        Report::Entries(entries) => entries
+            .into_iter()
+            .filter_map(|entry| match entry {
+                ReportEntry::Function(function) => Some(function),
+                ReportEntry::FileError { .. } => None,
+            })
+            .collect(),
+        Report::Envelope { functions } => functions,
+    };
+    Ok(functions
+        .into_iter()
+        .map(|function| (function.name, function.metrics))
+        .collect())
+}
+
+/// Collects the metrics of analysis results
+pub fn report_metrics(results: &[FunctionAnalysisResult]) -> ReportMetrics {
+    results
+        .iter()
+        .map(|result| {
+            let metrics = FunctionMetrics {
+                total: result.total,
+                code: result.code,
+                complexity: result.ast_metrics.then_some(result.cyclomatic_complexity),
+                nesting: result.ast_metrics.then_some(result.nesting_depth),
+            };
+            (result.qualified_name(), metrics)
+        })
+        .collect()
+}
+
+/// Compares two reports, leaving out unchanged functions
+///
+/// A function grew if it has more code lines, or as many with a higher
+/// complexity or nesting; it shrank in the opposite case. Complexity and
+/// nesting are only compared if both reports have them. Changes are ordered
+/// by kind, then by function name.
+pub fn diff_reports(old: &ReportMetrics, new: &ReportMetrics) -> Vec<FunctionDelta> {
+    let mut deltas: Vec<FunctionDelta> = Vec::new();
+    for (name, old_metrics) in old {
+        let change = match new.get(name) {
+            None => Some(Change::Removed),
+            Some(new_metrics) => match compare_size(new_metrics, old_metrics) {
+                Ordering::Greater => Some(Change::Grew),
+                Ordering::Less => Some(Change::Shrank),
+                Ordering::Equal => None,
+            },
+        };
+        if let Some(change) = change {
+            deltas.push(FunctionDelta {
+                name: name.clone(),
+                change,
+                old: Some(*old_metrics),
+                new: new.get(name).copied(),
+            });
+        }
+    }
+    deltas.extend(new.iter().filter(|(name, _)| !old.contains_key(*name)).map(
+        |(name, new_metrics)| FunctionDelta {
+            name: name.clone(),
+            change: Change::New,
+            old: None,
+            new: Some(*new_metrics),
+        },
+    ));
+    deltas.sort_by(|a, b| (a.change, &a.name).cmp(&(b.change, &b.name)));
+    deltas
+}
+
+/// Orders metrics by code lines, then complexity, then nesting
+///
+/// Metrics without complexity and nesting are ordered by code lines only.
+fn compare_size(a: &FunctionMetrics, b: &FunctionMetrics) -> Ordering {
+    let syntax = |metrics: &FunctionMetrics| metrics.complexity.zip(metrics.nesting);
+    a.code
+        .cmp(&b.code)
+        .then_with(|| match (syntax(a), syntax(b)) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            _ => Ordering::Equal,
+        })
+}
+
+/// Formats the changes as a human-readable list followed by counts per kind
+pub fn format_deltas(deltas: &[FunctionDelta]) -> String {
+    let mut lines: Vec<String> = deltas.iter().map(format_delta).collect();
+    let count = |change: Change| deltas.iter().filter(|delta| delta.change == change).count();
+    lines.push(format!(
+        "{} new, {} removed, {} grew, {} shrank",
+        count(Change::New),
+        count(Change::Removed),
+        count(Change::Grew),
+        count(Change::Shrank)
+    ));
+    lines.join("\n")
+}
+
+/// Formats a single change, showing old and new values of the metrics that differ
+fn format_delta(delta: &FunctionDelta) -> String {
+    match (delta.old, delta.new) {
+        (Some(old), Some(new)) => {
+            let label = if delta.change == Change::Grew {
+                "grew"
+            } else {
+                "shrank"
+            };
+            let changes: Vec<String> = [
+                ("code", Some(old.code), Some(new.code)),
+                ("complexity", old.complexity, new.complexity),
+                ("nesting", old.nesting, new.nesting),
+            ]
+            .into_iter()
+            .filter(|(_, old, new)| old != new)
+            .map(|(metric, old, new)| match old.zip(new) {
+                Some((old, new)) => {
+                    format!("{metric} {old} -> {new} ({:+})", new as i64 - old as i64)
+                }
+                None => format!("{metric} {} -> {}", value(old), value(new)),
+            })
+            .collect();
+            format!("  ~ {label:<7} fn {}: {}", delta.name, changes.join(", "))
+        }
+        (None, Some(new)) => format!(
+            "  + new     fn {}: code={}, complexity={}, nesting={}",
+            delta.name,
+            new.code,
+            value(new.complexity),
+            value(new.nesting)
+        ),
+        (Some(old), None) => format!(
+            "  - removed fn {}: code={}, complexity={}, nesting={}",
+            delta.name,
+            old.code,
+            value(old.complexity),
+            value(old.nesting)
+        ),
+        (None, None) => String::new(),
+    }
+}
+
+/// Formats a metric, or `n/a` if the report does not have it
+fn value(metric: Option<usize>) -> String {
+    metric.map_or_else(|| "n/a".to_string(), |metric| metric.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::{Format, FormatOptions, JsonFormat};
+    use crate::report::FileError;
+    use std::fs;
+
+    fn metrics(code: usize, complexity: usize) -> FunctionMetrics {
+        FunctionMetrics {
+            total: code,
+            code,
+            complexity: Some(complexity),
+            nesting: Some(1),
+        }
+    }
+
+    #[test]
+    fn test_diff_classifies_changes() {
+        let old = ReportMetrics::from([
+            ("kept".to_string(), metrics(10, 2)),
+            ("grown".to_string(), metrics(10, 2)),
+            ("branchier".to_string(), metrics(10, 2)),
+            ("gone".to_string(), metrics(5, 1)),
+        ]);
+        let new = ReportMetrics::from([
+            ("kept".to_string(), metrics(10, 2)),
+            ("grown".to_string(), metrics(25, 2)),
+            ("branchier".to_string(), metrics(10, 1)),
+            ("added".to_string(), metrics(7, 1)),
+        ]);
+
+        let deltas = diff_reports(&old, &new);
+        let changes: Vec<(&str, Change)> = deltas
+            .iter()
+            .map(|delta| (delta.name.as_str(), delta.change))
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                ("added", Change::New),
+                ("gone", Change::Removed),
+                ("grown", Change::Grew),
+                ("branchier", Change::Shrank),
+            ]
+        );
+
+        let text = format_deltas(&deltas);
+        assert!(text.contains("  ~ grew    fn grown: code 10 -> 25 (+15)"));
+        assert!(text.ends_with("1 new, 1 removed, 1 grew, 1 shrank"));
+    }
+
+    #[test]
+    fn test_load_report_accepts_array_and_envelope() {
+        let dir = std::env::temp_dir().join("fnloc_report_diff_load");
+        fs::create_dir_all(&dir).unwrap();
+        let array = dir.join("array.json");
+        let envelope = dir.join("envelope.json");
+        let function = r#"{"name":"f","total":5,"code":3,"comment":1,"empty":1,"complexity":2,"nesting":1,"comment_density":25.0}"#;
+        fs::write(&array, format!("[{function}]")).unwrap();
+        fs::write(
+            &envelope,
+            format!(r#"{{"functions":[{function}],"summary":{{}}}}"#),
+        )
+        .unwrap();
+
+        assert_eq!(load_report(&array).unwrap()["f"].complexity, Some(2));
+        assert_eq!(load_report(&envelope).unwrap()["f"].code, 3);
+
+        let malformed = dir.join("malformed.json");
+        fs::write(&malformed, r#"[{"name":"f"}]"#).unwrap();
+        let error = load_report(&malformed).unwrap_err();
+        assert_eq!(error.kind(), "invalid_report");
+        assert!(error.to_string().starts_with("Invalid report "));
+        assert_eq!(error.exit_code(), crate::exit_code::ANALYSIS_ERROR);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_diff_of_a_report_listing_a_file_error() {
+        let path = std::env::temp_dir().join(format!(
+            "fnloc_report_diff_errors_{}.json",
+            std::process::id()
+        ));
+        let results = [FunctionAnalysisResult {
+            file: "src/lib.rs".into(),
+            ..FunctionAnalysisResult::sample("run", 12, 3)
+        }];
+        let errors = [FileError {
+            file: "src/broken.rs".to_string(),
+            error: "Failed to parse src/broken.rs:3:1: expected `;`".to_string(),
+        }];
+        let report = JsonFormat.render_with_errors(&results, &errors, &FormatOptions::default());
+        fs::write(&path, report).unwrap();
+
+        let old = load_report(&path).unwrap();
+
+        assert_eq!(old.keys().collect::<Vec<_>>(), ["src/lib.rs::run"]);
+        assert!(diff_reports(&old, &report_metrics(&results)).is_empty());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_heuristically_counted_functions_round_trip() {
+        let path = std::env::temp_dir().join(format!(
+            "fnloc_report_diff_heuristic_{}.json",
+            std::process::id()
+        ));
+        let parsed = FunctionAnalysisResult {
+            file: "src/lib.rs".into(),
+            nesting_depth: 2,
+            ..FunctionAnalysisResult::sample("parsed", 10, 4)
+        };
+        let counted = FunctionAnalysisResult {
+            file: "src/lib.rs".into(),
+            ast_metrics: false,
+            ..FunctionAnalysisResult::sample("counted", 8, 1)
+        };
+        let results = [parsed, counted.clone()];
+        fs::write(
+            &path,
+            JsonFormat.render(&results, &FormatOptions::default()),
+        )
+        .unwrap();
+
+        let old = load_report(&path).unwrap();
+
+        assert_eq!(old, report_metrics(&results));
+        assert_eq!(old["src/lib.rs::counted"].complexity, None);
+        assert_eq!(old["src/lib.rs::parsed"].nesting, Some(2));
+
+        // Once parsed, only the code lines of the counted function are compared
+        let reparsed = FunctionAnalysisResult {
+            ast_metrics: true,
+            cyclomatic_complexity: 5,
+            ..counted
+        };
+        let new = report_metrics(&[results[0].clone(), reparsed]);
+        assert!(diff_reports(&old, &new).is_empty());
+        fs::remove_file(path).unwrap();
+    }
+}
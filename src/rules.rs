@@ -0,0 +1,287 @@
+//! Composite rules over function metrics
+//!
+//! Rules are defined in `fnloc.toml` and combine metric comparisons with `and`
+//! and `or`, where `and` binds tighter:
+//!
+//! ```toml
+//! [[rules]]
+//! name = "god function"
+//! when = "code > 80 and complexity > 15 and fan_out > 20"
+//! ```
+//!
+//! Besides the metrics of the analysis (see [`METRIC_KEYS`]), conditions can
+//! use `fan_out` and `fan_in`: the number of analyzed functions a function
+//! calls and is called by.
+
+use crate::analyzer::{FunctionAnalysisResult, METRIC_KEYS};
+use crate::call_graph::CallGraph;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Call-graph metrics usable in conditions besides [`METRIC_KEYS`]
+pub const CALL_METRICS: [&str; 2] = ["fan_out", "fan_in"];
+
+/// A named condition that functions should not meet
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    pub name: String,
+    pub when: Condition,
+}
+
+/// Comparison operator of a condition
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+    Equal,
+    NotEqual,
+}
+
+/// A single `metric <operator> number` comparison
+#[derive(Debug, Clone, PartialEq)]
+struct Comparison {
+    metric: String,
+    operator: Operator,
+    value: f64,
+}
+
+/// Parsed rule condition: any of several groups in which all comparisons hold
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Condition {
+    source: String,
+    any_of: Vec<Vec<Comparison>>,
+}
+
+/// A function meeting a rule
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleMatch {
+    pub rule: String,
+    pub function: String,
+    /// The rule's condition as written
+    pub condition: String,
+}
+
+/// Call-graph metrics of a function
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CallCounts {
+    pub fan_out: usize,
+    pub fan_in: usize,
+}
+
+impl Condition {
+    /// Returns true if any comparison needs call-graph metrics
+    pub fn uses_call_graph(&self) -> bool {
+        self.comparisons()
+            .any(|comparison| CALL_METRICS.contains(&comparison.metric.as_str()))
+    }
+
+    /// Evaluates the condition for a function
+    pub fn matches(&self, result: &FunctionAnalysisResult, calls: CallCounts) -> bool {
+        self.any_of.iter().any(|group| {
+            group.iter().all(|comparison| {
+                let value = match comparison.metric.as_str() {
+                    "fan_out" => calls.fan_out as f64,
+                    "fan_in" => calls.fan_in as f64,
+                    metric => result.metric(metric).unwrap_or(0.0),
+                };
+                comparison.operator.compare(value, comparison.value)
+            })
+        })
+    }
+
+    fn comparisons(&self) -> impl Iterator<Item = &Comparison> {
+        self.any_of.iter().flatten()
+    }
+}
+
+impl TryFrom<String> for Condition {
+    type Error = String;
+
+    fn try_from(source: String) -> Result<Self, Self::Error> {
+        let any_of = split_keyword(&source, "or")
+            .into_iter()
+            .map(|group| {
+                split_keyword(&group, "and")
+                    .iter()
+                    .map(|comparison| parse_comparison(comparison))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { source, any_of })
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl Operator {
+    /// Operators in the order they are matched, longer ones first
+    const ALL: [(&'static str, Operator); 6] = [
+        (">=", Operator::GreaterOrEqual),
+        ("<=", Operator::LessOrEqual),
+        ("==", Operator::Equal),
+        ("!=", Operator::NotEqual),
+        (">", Operator::Greater),
+        ("<", Operator::Less),
+    ];
+
+    fn compare(self, left: f64, right: f64) -> bool {
+        match self {
+            Operator::Greater => left > right,
+            Operator::GreaterOrEqual => left >= right,
+            Operator::Less => left < right,
+            Operator::LessOrEqual => left <= right,
+            Operator::Equal => left == right,
+            Operator::NotEqual => left != right,
+        }
+    }
+}
+
+/// Splits text at a keyword surrounded by whitespace, in any letter case
+fn split_keyword(text: &str, keyword: &str) -> Vec<String> {
+    let mut parts = vec![Vec::new()];
+    for word in text.split_whitespace() {
+        if word.eq_ignore_ascii_case(keyword) {
+            parts.push(Vec::new());
+        } else if let Some(part) = parts.last_mut() {
+            part.push(word);
+        }
+    }
+    parts.into_iter().map(|words| words.join(" ")).collect()
+}
+
+/// Parses `metric <operator> number`
+fn parse_comparison(text: &str) -> Result<Comparison, String> {
+    let text = text.trim();
+    let (index, symbol, operator) = Operator::ALL
+        .iter()
+        .filter_map(|&(symbol, operator)| text.find(symbol).map(|index| (index, symbol, operator)))
+        .min_by_key(|&(index, symbol, _)| (index, std::cmp::Reverse(symbol.len())))
+        .ok_or_else(|| format!("expected METRIC OPERATOR NUMBER, got '{text}'"))?;
+
+    let metric = text[..index].trim();
+    if !METRIC_KEYS.contains(&metric) && !CALL_METRICS.contains(&metric) {
+        return Err(format!(
+            "unknown metric '{metric}', expected one of {}, {}",
+            METRIC_KEYS.join(", "),
+            CALL_METRICS.join(", ")
+        ));
+    }
+    let value = text[index + symbol.len()..].trim();
+    let value = value
+        .parse()
+        .map_err(|_| format!("expected a number after '{metric} {symbol}', got '{value}'"))?;
+
+    Ok(Comparison {
+        metric: metric.to_string(),
+        operator,
+        value,
+    })
+}
+
+/// Evaluates every rule for every function, in rule order
+///
+/// Call counts are looked up by function name; functions without an entry count
+/// as calling and being called by nothing.
+pub fn evaluate(
+    rules: &[Rule],
+    results: &[FunctionAnalysisResult],
+    calls: &HashMap<String, CallCounts>,
+) -> Vec<RuleMatch> {
+    rules
+        .iter()
+        .flat_map(|rule| {
+            results
+                .iter()
+                .filter(|result| {
+                    let counts = calls
+                        .get(&result.qualified_name())
+                        .copied()
+                        .unwrap_or_default();
+                    rule.when.matches(result, counts)
+                })
+                .map(|result| RuleMatch {
+                    rule: rule.name.clone(),
+                    function: result.qualified_name(),
+                    condition: rule.when.to_string(),
+                })
+        })
+        .collect()
+}
+
+/// Counts the callees and callers of every function in a call graph
+pub fn call_counts(graph: &CallGraph) -> HashMap<String, CallCounts> {
+    let mut counts: HashMap<String, CallCounts> = HashMap::new();
+    for function in graph.functions() {
+        for callee in graph.callees(function) {
+            counts.entry(function.to_string()).or_default().fan_out += 1;
+            counts.entry(callee.to_string()).or_default().fan_in += 1;
+        }
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_result(code: usize, complexity: usize) -> FunctionAnalysisResult {
+        FunctionAnalysisResult {
+            nesting_depth: 1,
+            ..FunctionAnalysisResult::sample("f", code, complexity)
+        }
+    }
+
+    fn condition(source: &str) -> Condition {
+        Condition::try_from(source.to_string()).unwrap()
+    }
+
+    #[test]
+    fn test_and_binds_tighter_than_or() {
+        let when = condition("code > 80 AND complexity >= 15 or fan_out > 20");
+        let no_calls = CallCounts::default();
+
+        assert!(when.matches(&sample_result(100, 15), no_calls));
+        assert!(!when.matches(&sample_result(100, 14), no_calls));
+        let busy = CallCounts {
+            fan_out: 21,
+            fan_in: 0,
+        };
+        assert!(when.matches(&sample_result(10, 1), busy));
+        assert!(when.uses_call_graph());
+    }
+
+    #[test]
+    fn test_invalid_conditions_are_rejected() {
+        assert!(Condition::try_from("lines > 3".to_string()).is_err());
+        assert!(Condition::try_from("code > many".to_string()).is_err());
+        assert!(Condition::try_from("code".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_evaluate_names_rule_and_function() {
+        let rules = [Rule {
+            name: "long".to_string(),
+            when: condition("code > 50"),
+        }];
+        let results = [sample_result(60, 1), sample_result(10, 1)];
+
+        let matches = evaluate(&rules, &results, &HashMap::new());
+        assert_eq!(
+            matches,
+            [RuleMatch {
+                rule: "long".to_string(),
+                function: "f".to_string(),
+                condition: "code > 50".to_string(),
+            }]
+        );
+    }
+}
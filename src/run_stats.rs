@@ -0,0 +1,95 @@
+//! Resource usage of an analysis run
+//!
+//! Collected with `--stats` so the effect of performance-related options can
+//! be measured: wall time, peak resident set size, and throughput.
+
+use std::fs;
+use std::time::{Duration, Instant};
+
+/// Resource usage and throughput of a single analysis run
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunStats {
+    pub wall_time: Duration,
+    /// Peak resident set size in bytes, if the platform reports it
+    pub peak_rss_bytes: Option<u64>,
+    pub files: usize,
+    pub functions: usize,
+}
+
+impl RunStats {
+    /// Measures a run that started at `start` and processed the given counts
+    pub fn measure(start: Instant, files: usize, functions: usize) -> Self {
+        Self {
+            wall_time: start.elapsed(),
+            peak_rss_bytes: peak_rss_bytes(),
+            files,
+            functions,
+        }
+    }
+
+    /// Returns the number of files analyzed per second
+    pub fn files_per_sec(&self) -> f64 {
+        per_second(self.files, self.wall_time)
+    }
+
+    /// Returns the number of functions analyzed per second
+    pub fn functions_per_sec(&self) -> f64 {
+        per_second(self.functions, self.wall_time)
+    }
+}
+
+/// Returns `count / duration`, or 0 for an empty duration
+fn per_second(count: usize, duration: Duration) -> f64 {
+    let seconds = duration.as_secs_f64();
+    if seconds > 0.0 {
+        count as f64 / seconds
+    } else {
+        0.0
+    }
+}
+
+/// Reads the peak resident set size of the current process
+///
+/// Uses the `VmHWM` entry of `/proc/self/status`, so it is only available on
+/// Linux; other platforms report `None`.
+fn peak_rss_bytes() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    parse_vm_hwm(&status)
+}
+
+/// Parses the `VmHWM:  1234 kB` line of a proc status file into bytes
+fn parse_vm_hwm(status: &str) -> Option<u64> {
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    let kilobytes: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kilobytes * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_throughput() {
+        let stats = RunStats {
+            wall_time: Duration::from_millis(500),
+            peak_rss_bytes: None,
+            files: 10,
+            functions: 100,
+        };
+
+        assert_eq!(stats.files_per_sec(), 20.0);
+        assert_eq!(stats.functions_per_sec(), 200.0);
+    }
+
+    #[test]
+    fn test_zero_duration_has_zero_throughput() {
+        assert_eq!(per_second(5, Duration::ZERO), 0.0);
+    }
+
+    #[test]
+    fn test_parse_vm_hwm() {
+        let status = "Name:\tfnloc\nVmPeak:\t  9000 kB\nVmHWM:\t  2048 kB\n";
+        assert_eq!(parse_vm_hwm(status), Some(2048 * 1024));
+        assert_eq!(parse_vm_hwm("Name:\tfnloc\n"), None);
+    }
+}
@@ -0,0 +1,120 @@
+//! Reading Rust source files
+//!
+//! Source files are decoded leniently: a file containing invalid UTF-8 (e.g. a
+//! stray Latin-1 byte in a comment) is still analyzed, with the invalid bytes
+//! replaced by U+FFFD, instead of losing all of its results.
+//!
+//! Decoded text is also normalized: a leading byte order mark is removed and
+//! CRLF line endings become LF, so Windows-authored files produce the same
+//! metrics as their Unix counterparts.
+
+use crate::errors::{AnalysisError, AnalysisResult};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Decoded contents of a source file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceText {
+    pub text: String,
+    /// True if invalid UTF-8 sequences were replaced during decoding
+    pub lossy: bool,
+}
+
+/// Reads and normalizes a source file, replacing invalid UTF-8 sequences instead of failing
+pub fn read_source(path: impl AsRef<Path>) -> io::Result<SourceText> {
+    let bytes = fs::read(path)?;
+    Ok(decode_source(bytes))
+}
+
+/// Fails with [`AnalysisError::FileTooLarge`] if a file of `size` bytes exceeds the limit
+pub fn check_size(path: &str, size: u64, limit: Option<u64>) -> AnalysisResult<()> {
+    match limit {
+        Some(limit) if size > limit => Err(AnalysisError::FileTooLarge {
+            file: path.to_string(),
+            size,
+            limit,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Reads a text file such as a configuration or report, which must be valid UTF-8
+pub fn read_text(path: &Path) -> AnalysisResult<String> {
+    fs::read_to_string(path).map_err(|e| match e.kind() {
+        io::ErrorKind::InvalidData => AnalysisError::NonUtf8File {
+            file: path.display().to_string(),
+        },
+        _ => AnalysisError::Io(e),
+    })
+}
+
+/// Decodes source bytes as UTF-8, replacing invalid sequences, and normalizes the text
+pub fn decode_source(bytes: Vec<u8>) -> SourceText {
+    let (text, lossy) = match String::from_utf8(bytes) {
+        Ok(text) => (text, false),
+        Err(e) => (String::from_utf8_lossy(e.as_bytes()).into_owned(), true),
+    };
+    SourceText {
+        text: normalize_source(&text),
+        lossy,
+    }
+}
+
+/// Removes a leading byte order mark and converts CRLF line endings to LF
+pub fn normalize_source(text: &str) -> String {
+    let text = text.strip_prefix('\u{FEFF}').unwrap_or(text);
+    text.replace("\r\n", "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_utf8_is_unchanged() {
+        let source = decode_source("fn café() {}\n".as_bytes().to_vec());
+        assert_eq!(source.text, "fn café() {}\n");
+        assert!(!source.lossy);
+    }
+
+    #[test]
+    fn test_bom_and_crlf_are_normalized() {
+        let source = decode_source(b"\xef\xbb\xbffn f() {\r\n    1\r\n}\r\n".to_vec());
+        assert_eq!(source.text, "fn f() {\n    1\n}\n");
+        assert!(!source.lossy);
+    }
+
+    #[test]
+    fn test_invalid_utf8_is_replaced() {
+        let source = decode_source(b"// caf\xe9\nfn f() {}\n".to_vec());
+        assert_eq!(source.text, "// caf\u{FFFD}\nfn f() {}\n");
+        assert!(source.lossy);
+    }
+
+    #[test]
+    fn test_read_text_rejects_invalid_utf8() {
+        let path = std::env::temp_dir().join(format!("fnloc_text_{}.toml", std::process::id()));
+        fs::write(&path, b"name = \"caf\xe9\"\n").unwrap();
+        let read = read_text(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert!(
+            matches!(read, Err(AnalysisError::NonUtf8File { file }) if file.ends_with(".toml"))
+        );
+    }
+
+    #[test]
+    fn test_size_limit() {
+        assert!(check_size("big.rs", 2048, None).is_ok());
+        assert!(check_size("big.rs", 2048, Some(2048)).is_ok());
+        assert!(matches!(
+            check_size("big.rs", 2049, Some(2048)),
+            Err(AnalysisError::FileTooLarge {
+                size: 2049,
+                limit: 2048,
+                ..
+            })
+        ));
+    }
+}
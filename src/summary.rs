@@ -0,0 +1,263 @@
+//! Summary statistics over all reported functions
+//!
+//! Collected with `--summary`: file and function counts, the overall comment
+//! density, and the total, mean, median, 90th percentile, and maximum of every
+//! per-function metric, so a codebase can be characterized at a glance.
+//!
+//! When the results mix production and test code, the same statistics are
+//! also given for each population, with the ratio of test to production code
+//! lines, as the two are usually judged by different standards.
+
+use crate::analyzer::FunctionAnalysisResult;
+use serde::{Serialize, Serializer};
+use std::collections::HashSet;
+
+/// Distribution of one metric over all functions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct MetricSummary {
+    pub total: f64,
+    #[serde(serialize_with = "round_to_tenth")]
+    pub mean: f64,
+    #[serde(serialize_with = "round_to_tenth")]
+    pub median: f64,
+    /// 90th percentile, using the nearest-rank method
+    pub p90: f64,
+    pub max: f64,
+}
+
+/// Summary statistics of an analysis
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Summary {
+    pub files: usize,
+    pub functions: usize,
+    /// Comment lines as a percentage of all code and comment lines
+    #[serde(serialize_with = "round_to_tenth")]
+    pub comment_density: f64,
+    /// Distribution of every metric, keyed like [`FunctionAnalysisResult::metrics`]
+    #[serde(serialize_with = "serialize_metrics")]
+    pub metrics: Vec<(&'static str, MetricSummary)>,
+    /// Production and test code summarized apart; None unless the results contain both
+    #[serde(flatten)]
+    pub split: Option<Box<TestSplit>>,
+}
+
+/// Summaries of production and test code
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TestSplit {
+    pub production: Summary,
+    pub test: Summary,
+    /// Code lines of tests per code line of production code
+    #[serde(serialize_with = "round_to_hundredth")]
+    pub test_to_code_ratio: f64,
+}
+
+impl TestSplit {
+    /// Splits the results into production and test code, if they contain both
+    pub fn new(results: &[FunctionAnalysisResult]) -> Option<Self> {
+        let (test, production): (Vec<_>, Vec<_>) =
+            results.iter().cloned().partition(|result| result.is_test);
+        if test.is_empty() || production.is_empty() {
+            return None;
+        }
+        let production = Summary::without_split(&production, file_count(&production));
+        let test = Summary::without_split(&test, file_count(&test));
+        let test_code = test.metric("code").map_or(0.0, |code| code.total);
+        let production_code = production.metric("code").map_or(0.0, |code| code.total);
+        Some(Self {
+            test_to_code_ratio: if production_code > 0.0 {
+                test_code / production_code
+            } else {
+                0.0
+            },
+            production,
+            test,
+        })
+    }
+}
+
+/// Counts the files containing the functions
+fn file_count(results: &[FunctionAnalysisResult]) -> usize {
+    results
+        .iter()
+        .map(|result| &result.file)
+        .collect::<HashSet<_>>()
+        .len()
+}
+
+impl Summary {
+    /// Summarizes the given functions, found in `files` files
+    ///
+    /// The per-function comment density is left out of the metric distributions;
+    /// the overall density is reported instead. Without functions there are no
+    /// metric distributions.
+    pub fn new(results: &[FunctionAnalysisResult], files: usize) -> Self {
+        Self {
+            split: TestSplit::new(results).map(Box::new),
+            ..Self::without_split(results, files)
+        }
+    }
+
+    /// Summarizes the functions like [`Summary::new`], as a single population
+    fn without_split(results: &[FunctionAnalysisResult], files: usize) -> Self {
+        let code: usize = results.iter().map(|result| result.code).sum();
+        let comment: usize = results.iter().map(|result| result.comment).sum();
+        let comment_density = if code + comment == 0 {
+            0.0
+        } else {
+            comment as f64 * 100.0 / (code + comment) as f64
+        };
+
+        // One list of values per metric, in report order
+        let mut values: Vec<(&'static str, Vec<f64>)> = Vec::new();
+        for result in results {
+            let metrics = result
+                .metrics()
+                .into_iter()
+                .filter(|&(key, _)| key != "comment_density");
+            for (index, (key, value)) in metrics.enumerate() {
+                match values.get_mut(index) {
+                    Some((_, metric_values)) => metric_values.push(value),
+                    None => values.push((key, vec![value])),
+                }
+            }
+        }
+        let metrics = values
+            .into_iter()
+            .map(|(key, metric_values)| (key, MetricSummary::new(metric_values)))
+            .collect();
+
+        Self {
+            files,
+            functions: results.len(),
+            comment_density,
+            metrics,
+            split: None,
+        }
+    }
+
+    /// Returns the distribution of a metric by its key
+    pub fn metric(&self, key: &str) -> Option<&MetricSummary> {
+        self.metrics
+            .iter()
+            .find(|(metric, _)| *metric == key)
+            .map(|(_, summary)| summary)
+    }
+}
+
+impl MetricSummary {
+    /// Summarizes a list of values; an empty list yields all zeros
+    pub fn new(mut values: Vec<f64>) -> Self {
+        if values.is_empty() {
+            return Self::default();
+        }
+        values.sort_by(f64::total_cmp);
+
+        let count = values.len();
+        let total: f64 = values.iter().sum();
+        let median = if count.is_multiple_of(2) {
+            (values[count / 2 - 1] + values[count / 2]) / 2.0
+        } else {
+            values[count / 2]
+        };
+        let p90_rank = (count * 9).div_ceil(10);
+
+        Self {
+            total,
+            mean: total / count as f64,
+            median,
+            p90: values[p90_rank - 1],
+            max: values[count - 1],
+        }
+    }
+}
+
+/// Serializes metric distributions as an object keyed by metric, in report order
+fn serialize_metrics<S: Serializer>(
+    metrics: &[(&'static str, MetricSummary)],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(metrics.iter().map(|(key, summary)| (key, summary)))
+}
+
+/// Serializes a float rounded to one decimal place
+fn round_to_tenth<S: Serializer>(value: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64((value * 10.0).round() / 10.0)
+}
+
+/// Serializes a float rounded to two decimal places
+fn round_to_hundredth<S: Serializer>(value: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64((value * 100.0).round() / 100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_metric_summary() {
+        let summary = MetricSummary::new((1..=10).rev().map(f64::from).collect());
+
+        assert_eq!(summary.total, 55.0);
+        assert_eq!(summary.mean, 5.5);
+        assert_eq!(summary.median, 5.5);
+        assert_eq!(summary.p90, 9.0);
+        assert_eq!(summary.max, 10.0);
+        assert_eq!(MetricSummary::new(Vec::new()), MetricSummary::default());
+    }
+
+    #[test]
+    fn test_summary_over_functions() {
+        let result = |code: usize, complexity: usize| FunctionAnalysisResult {
+            total: code + 1,
+            comment: 1,
+            end_line: code + 1,
+            ..FunctionAnalysisResult::sample("f", code, complexity)
+        };
+        let summary = Summary::new(&[result(3, 1), result(5, 4), result(10, 2)], 2);
+
+        assert_eq!(summary.files, 2);
+        assert_eq!(summary.functions, 3);
+        assert!((summary.comment_density - 14.29).abs() < 0.01);
+        assert_eq!(summary.metric("code").unwrap().median, 5.0);
+        assert_eq!(summary.metric("complexity").unwrap().max, 4.0);
+        assert!(summary.metric("comment_density").is_none());
+
+        let json = serde_json::to_string(&summary).unwrap();
+        assert!(json.starts_with(
+            r#"{"files":2,"functions":3,"comment_density":14.3,"metrics":{"total":{"total":21.0,"#
+        ));
+        assert!(summary.split.is_none());
+    }
+
+    #[test]
+    fn test_production_and_test_code_are_summarized_apart() {
+        let result = |file: &str, code: usize, is_test: bool| FunctionAnalysisResult {
+            file: PathBuf::from(file),
+            is_test,
+            ..FunctionAnalysisResult::sample("f", code, 1)
+        };
+        let results = [
+            result("src/lib.rs", 30, false),
+            result("src/lib.rs", 10, true),
+            result("tests/cli.rs", 5, true),
+            result("src/main.rs", 10, false),
+        ];
+
+        let summary = Summary::new(&results, 3);
+
+        let split = summary.split.as_deref().unwrap();
+        assert_eq!((split.production.files, split.production.functions), (2, 2));
+        assert_eq!((split.test.files, split.test.functions), (2, 2));
+        assert_eq!(split.test.metric("code").unwrap().total, 15.0);
+        assert_eq!(split.test_to_code_ratio, 0.375);
+        assert!(split.production.split.is_none());
+
+        let json: serde_json::Value = serde_json::to_value(&summary).unwrap();
+        assert_eq!(json["functions"], 4);
+        assert_eq!(json["production"]["functions"], 2);
+        assert_eq!(json["test"]["metrics"]["code"]["max"], 10.0);
+        assert_eq!(json["test_to_code_ratio"], 0.38);
+        assert!(json["production"].get("test").is_none());
+    }
+}
@@ -0,0 +1,123 @@
+//! Per-file timings of an analysis run
+//!
+//! Collected with `--timings` to find out why a run is slow: the time spent
+//! reading and parsing each file and computing its metrics, and the files that
+//! took longest, which are often generated code worth excluding.
+
+use crate::analyzer::PhaseTimings;
+use std::time::Duration;
+
+/// Number of slowest files listed in the report
+pub const SLOWEST_FILES: usize = 10;
+
+/// Time spent on one file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileTiming {
+    pub path: String,
+    pub timings: PhaseTimings,
+}
+
+impl FileTiming {
+    /// Returns the time spent on the file in both phases
+    pub fn total(&self) -> Duration {
+        self.timings.parse + self.timings.analysis
+    }
+}
+
+/// Timings of all analyzed files
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Timings {
+    pub files: Vec<FileTiming>,
+}
+
+impl Timings {
+    /// Records the time spent on a file
+    pub fn record(&mut self, path: &str, timings: PhaseTimings) {
+        self.files.push(FileTiming {
+            path: path.to_string(),
+            timings,
+        });
+    }
+
+    /// Returns the summed parse and analysis time over all files
+    ///
+    /// With parallel analysis the sum exceeds the wall time of the run.
+    pub fn total(&self) -> PhaseTimings {
+        self.files
+            .iter()
+            .fold(PhaseTimings::default(), |total, file| PhaseTimings {
+                parse: total.parse + file.timings.parse,
+                analysis: total.analysis + file.timings.analysis,
+            })
+    }
+
+    /// Returns up to `count` files, slowest first
+    pub fn slowest(&self, count: usize) -> Vec<&FileTiming> {
+        let mut files: Vec<&FileTiming> = self.files.iter().collect();
+        files.sort_by_key(|file| std::cmp::Reverse(file.total()));
+        files.truncate(count);
+        files
+    }
+
+    /// Formats the summed phases and the slowest files as text
+    pub fn to_text(&self) -> String {
+        let total = self.total();
+        let mut lines = vec![format!(
+            "Timings: {} files, parse {}, analysis {}",
+            self.files.len(),
+            millis(total.parse),
+            millis(total.analysis)
+        )];
+        let slowest = self.slowest(SLOWEST_FILES);
+        if !slowest.is_empty() {
+            lines.push("Slowest files:".to_string());
+        }
+        for file in slowest {
+            lines.push(format!(
+                "  {:>10}  {} (parse {}, analysis {})",
+                millis(file.total()),
+                file.path,
+                millis(file.timings.parse),
+                millis(file.timings.analysis)
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Formats a duration in milliseconds with one decimal place
+fn millis(duration: Duration) -> String {
+    format!("{:.1} ms", duration.as_secs_f64() * 1000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn phases(parse: u64, analysis: u64) -> PhaseTimings {
+        PhaseTimings {
+            parse: Duration::from_millis(parse),
+            analysis: Duration::from_millis(analysis),
+        }
+    }
+
+    #[test]
+    fn test_slowest_files_and_report() {
+        let mut timings = Timings::default();
+        timings.record("src/small.rs", phases(1, 1));
+        timings.record("src/generated.rs", phases(40, 10));
+        timings.record("src/lib.rs", phases(5, 3));
+
+        let slowest: Vec<&str> = timings
+            .slowest(2)
+            .iter()
+            .map(|file| file.path.as_str())
+            .collect();
+        assert_eq!(slowest, ["src/generated.rs", "src/lib.rs"]);
+        assert_eq!(timings.total(), phases(46, 14));
+        assert_eq!(
+            timings.to_text().lines().nth(2),
+            Some("     50.0 ms  src/generated.rs (parse 40.0 ms, analysis 10.0 ms)")
+        );
+    }
+}
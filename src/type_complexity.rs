@@ -0,0 +1,165 @@
+//! Weighted Methods per Class (WMC) aggregation
+//!
+//! WMC sums the cyclomatic complexity of all methods implemented for a type.
+//! Inherent and trait `impl` blocks are combined, including blocks spread over
+//! several files, so the heaviest types stand out rather than only the heaviest
+//! individual functions.
+
+use crate::analyzer::{ComplexityRules, calculate_cyclomatic_complexity_with_rules};
+use crate::source::read_source;
+use std::collections::BTreeMap;
+use syn::{ImplItem, Item, ItemFn, Type};
+
+/// Aggregated method complexity for one type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeComplexity {
+    pub type_name: String,
+    pub method_count: usize,
+    /// Sum of the cyclomatic complexity of all methods
+    pub weighted_methods: usize,
+    pub max_method_complexity: usize,
+}
+
+/// Computes WMC for every type implemented in the given files, heaviest first
+///
+/// Files that cannot be read or parsed are skipped, matching `analyze_all_files`.
+pub fn weighted_methods_per_class(
+    file_paths: &[String],
+    rules: ComplexityRules,
+) -> Vec<TypeComplexity> {
+    let mut types = BTreeMap::new();
+
+    for path in file_paths {
+        if let Ok(source) = read_source(path)
+            && let Ok(parsed) = syn::parse_file(&source.text)
+        {
+            collect_type_complexity(&parsed.items, rules, &mut types);
+        }
+    }
+
+    sort_heaviest_first(types)
+}
+
+/// Computes WMC for every type implemented in a single source
+pub fn weighted_methods_per_class_from_source(
+    source: &str,
+    rules: ComplexityRules,
+) -> Vec<TypeComplexity> {
+    let mut types = BTreeMap::new();
+    if let Ok(parsed) = syn::parse_file(source) {
+        collect_type_complexity(&parsed.items, rules, &mut types);
+    }
+    sort_heaviest_first(types)
+}
+
+/// Adds the methods of every `impl` block in the items to the per-type totals
+fn collect_type_complexity(
+    items: &[Item],
+    rules: ComplexityRules,
+    types: &mut BTreeMap<String, TypeComplexity>,
+) {
+    for item in items {
+        let Item::Impl(item_impl) = item else {
+            continue;
+        };
+        let Some(type_name) = type_name(&item_impl.self_ty) else {
+            continue;
+        };
+
+        let entry = types
+            .entry(type_name.clone())
+            .or_insert_with(|| TypeComplexity {
+                type_name,
+                method_count: 0,
+                weighted_methods: 0,
+                max_method_complexity: 0,
+            });
+
+        for impl_item in &item_impl.items {
+            if let ImplItem::Fn(method) = impl_item {
+                let func = ItemFn {
+                    attrs: method.attrs.clone(),
+                    vis: method.vis.clone(),
+                    sig: method.sig.clone(),
+                    block: Box::new(method.block.clone()),
+                };
+                let complexity = calculate_cyclomatic_complexity_with_rules(&func, rules);
+                entry.method_count += 1;
+                entry.weighted_methods += complexity;
+                entry.max_method_complexity = entry.max_method_complexity.max(complexity);
+            }
+        }
+    }
+}
+
+/// Returns the name of an implemented type without generic arguments
+pub(crate) fn type_name(ty: &Type) -> Option<String> {
+    match ty {
+        Type::Path(type_path) => type_path
+            .path
+            .segments
+            .last()
+            .map(|segment| segment.ident.to_string()),
+        Type::Reference(reference) => type_name(&reference.elem),
+        _ => None,
+    }
+}
+
+/// Orders types by WMC descending, then by name
+fn sort_heaviest_first(types: BTreeMap<String, TypeComplexity>) -> Vec<TypeComplexity> {
+    let mut types: Vec<TypeComplexity> = types
+        .into_values()
+        .filter(|entry| entry.method_count > 0)
+        .collect();
+    types.sort_by(|a, b| {
+        b.weighted_methods
+            .cmp(&a.weighted_methods)
+            .then_with(|| a.type_name.cmp(&b.type_name))
+    });
+    types
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wmc_combines_impl_blocks() {
+        let source = r#"
+            struct Parser;
+            impl Parser {
+                fn new() -> Self { Parser }
+                fn parse(&self, x: i32) -> i32 { if x > 0 { 1 } else { 0 } }
+            }
+            impl Default for Parser {
+                fn default() -> Self { Self::new() }
+            }
+            struct Token;
+            impl<T> From<T> for Token {
+                fn from(_: T) -> Self { Token }
+            }
+        "#;
+
+        let types = weighted_methods_per_class_from_source(source, ComplexityRules::default());
+
+        assert_eq!(types.len(), 2);
+        assert_eq!(
+            types[0],
+            TypeComplexity {
+                type_name: "Parser".to_string(),
+                method_count: 3,
+                weighted_methods: 4,
+                max_method_complexity: 2,
+            }
+        );
+        assert_eq!(types[1].type_name, "Token");
+        assert_eq!(types[1].weighted_methods, 1);
+    }
+
+    #[test]
+    fn test_impl_without_methods_is_skipped() {
+        let source = "struct Marker;\nimpl Copy for Marker {}\n";
+        let types = weighted_methods_per_class_from_source(source, ComplexityRules::default());
+        assert!(types.is_empty());
+    }
+}
@@ -0,0 +1,217 @@
+//! Private functions that nothing in the analyzed set refers to
+//!
+//! Used by `--unreferenced` to find dead code that can be deleted instead of
+//! refactored. This is a heuristic: a private function counts as referenced if
+//! another function calls it in the call graph, or if its name appears as a
+//! path anywhere else in its file, including macro and attribute arguments
+//! such as `#[serde(serialize_with = "name")]`. Calls through
+//! other modules' macros, `#[cfg]`-disabled code, or code outside the analyzed
+//! paths are not seen, so every function listed should be checked before it
+//! is removed.
+//!
+//! Functions that are entry points of their own, such as `main`, tests,
+//! benchmarks, and functions with `#[no_mangle]`, are never listed.
+
+use crate::analyzer::FunctionAnalysisResult;
+use crate::call_graph::{CallGraph, parse_functions};
+use crate::normalize_path;
+use crate::source::read_source;
+use proc_macro2::{TokenStream, TokenTree};
+use quote::ToTokens;
+use std::collections::HashMap;
+use syn::visit::{self, Visit};
+use syn::{Item, ItemFn, Visibility};
+
+/// Attributes marking a function that is called from outside the code
+const ENTRY_POINT_ATTRIBUTES: [&str; 8] = [
+    "test",
+    "bench",
+    "main",
+    "no_mangle",
+    "export_name",
+    "proc_macro",
+    "proc_macro_derive",
+    "proc_macro_attribute",
+];
+
+/// A private function that appears to be unused
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnreferencedFunction {
+    pub function: String,
+    pub start_line: usize,
+    pub code: usize,
+    pub complexity: usize,
+}
+
+/// Finds the analyzed private functions without references, most complex first
+///
+/// Only functions with a result in `results` are listed, so functions filtered
+/// out by `--min-*` options are not reported either.
+pub fn find_unreferenced(
+    files: &[String],
+    results: &[FunctionAnalysisResult],
+) -> Vec<UnreferencedFunction> {
+    let graph = CallGraph::build(&parse_functions(files));
+    let results: HashMap<String, &FunctionAnalysisResult> = results
+        .iter()
+        .map(|result| (result.qualified_name(), result))
+        .collect();
+
+    let mut unreferenced = Vec::new();
+    for path in files {
+        let Ok(source) = read_source(path) else {
+            continue;
+        };
+        let Ok(parsed) = syn::parse_file(&source.text) else {
+            continue;
+        };
+        let normalized_path = normalize_path(path);
+        let in_file = references(|counter| counter.visit_file(&parsed));
+
+        for func in parsed.items.iter().filter_map(private_function) {
+            let name = format!("{normalized_path}::{}", func.sig.ident);
+            let Some(result) = results.get(&name) else {
+                continue;
+            };
+            let ident = func.sig.ident.to_string();
+            let in_body = references(|counter| counter.visit_block(&func.block));
+            let referenced_in_file = in_file.get(&ident) > in_body.get(&ident);
+            let called = graph.callers(&name).any(|caller| caller != name);
+            if !referenced_in_file && !called {
+                unreferenced.push(UnreferencedFunction {
+                    function: name,
+                    start_line: result.start_line,
+                    code: result.code,
+                    complexity: result.cyclomatic_complexity,
+                });
+            }
+        }
+    }
+
+    unreferenced.sort_by(|a, b| {
+        b.complexity
+            .cmp(&a.complexity)
+            .then_with(|| b.code.cmp(&a.code))
+            .then_with(|| a.function.cmp(&b.function))
+    });
+    unreferenced
+}
+
+/// Returns the function of an item if it is private and not an entry point
+fn private_function(item: &Item) -> Option<&ItemFn> {
+    let Item::Fn(func) = item else {
+        return None;
+    };
+    let entry_point = func.sig.ident == "main"
+        || func.attrs.iter().any(|attr| {
+            // `#[unsafe(no_mangle)]` and `#[unsafe(export_name = ..)]`
+            attr.path().is_ident("unsafe")
+                || attr.path().segments.last().is_some_and(|segment| {
+                    ENTRY_POINT_ATTRIBUTES.contains(&segment.ident.to_string().as_str())
+                })
+        });
+    (matches!(func.vis, Visibility::Inherited) && !entry_point).then_some(func)
+}
+
+/// Counts the identifiers referred to by paths in the visited code
+fn references(visit: impl FnOnce(&mut ReferenceCounter)) -> HashMap<String, usize> {
+    let mut counter = ReferenceCounter::default();
+    visit(&mut counter);
+    counter.counts
+}
+
+/// AST visitor counting the final segment of every path, and every identifier
+/// or word of a string literal in macro and attribute input
+#[derive(Default)]
+struct ReferenceCounter {
+    counts: HashMap<String, usize>,
+}
+
+impl ReferenceCounter {
+    fn count_tokens(&mut self, tokens: &TokenStream) {
+        for token in tokens.clone() {
+            match token {
+                TokenTree::Ident(ident) => *self.counts.entry(ident.to_string()).or_default() += 1,
+                TokenTree::Group(group) => self.count_tokens(&group.stream()),
+                TokenTree::Literal(literal) => {
+                    let text = literal.to_string();
+                    for word in text
+                        .split(|c: char| !c.is_alphanumeric() && c != '_')
+                        .filter(|word| !word.is_empty())
+                    {
+                        *self.counts.entry(word.to_string()).or_default() += 1;
+                    }
+                }
+                TokenTree::Punct(_) => {}
+            }
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for ReferenceCounter {
+    fn visit_path(&mut self, path: &'ast syn::Path) {
+        if let Some(segment) = path.segments.last() {
+            *self.counts.entry(segment.ident.to_string()).or_default() += 1;
+        }
+        visit::visit_path(self, path);
+    }
+
+    fn visit_attribute(&mut self, attr: &'ast syn::Attribute) {
+        if let syn::Meta::List(list) = &attr.meta {
+            self.count_tokens(&list.tokens);
+        } else if let syn::Meta::NameValue(name_value) = &attr.meta {
+            self.count_tokens(&name_value.value.to_token_stream());
+        }
+        visit::visit_attribute(self, attr);
+    }
+
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        self.count_tokens(&mac.tokens);
+        visit::visit_macro(self, mac);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_only_private_functions_without_references_are_listed() {
+        let dir = std::env::temp_dir().join(format!("fnloc_unreferenced_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let lib = dir.join("lib.rs");
+        fs::write(
+            &lib,
+            r#"
+pub fn api() { called(); }
+fn called() {}
+fn dead(n: u32) -> u32 { if n > 0 { dead(n - 1) } else { 0 } }
+fn passed() {}
+fn skip_empty(value: &u8) -> bool { *value == 0 }
+#[derive(serde::Serialize)]
+struct Row { #[serde(skip_serializing_if = "skip_empty")] value: u8 }
+fn in_macro() -> u8 { 1 }
+fn uses() { let f = passed; println!("{}", in_macro()); f(); }
+pub(crate) fn crate_visible() {}
+#[test]
+fn a_test() {}
+fn main() {}
+"#,
+        )
+        .unwrap();
+        let files = vec![lib.to_string_lossy().to_string()];
+        let results = crate::analyze_all_files(&files);
+
+        let unreferenced = find_unreferenced(&files, &results);
+
+        let names: Vec<&str> = unreferenced
+            .iter()
+            .map(|function| function.function.rsplit("::").next().unwrap())
+            .collect();
+        assert_eq!(names, ["dead", "uses"]);
+        assert_eq!(unreferenced[0].complexity, 2);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
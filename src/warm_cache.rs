@@ -0,0 +1,173 @@
+//! Analysis results kept in memory by long-running processes
+//!
+//! `fnloc serve` and `fnloc daemon` analyze the same tree on every request.
+//! The cache remembers the results of each file together with its
+//! modification time and size, so only files that changed since the last
+//! request are parsed again.
+
+use crate::analyzer::{AnalysisOptions, FunctionAnalysisResult};
+use crate::diagnostics::Diagnostic;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
+
+/// Modification time and size of a file when it was analyzed
+type Stamp = (SystemTime, u64);
+
+/// Results and warnings of one file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileResults {
+    /// Path of the file as it was given to the cache
+    pub path: String,
+    pub functions: Vec<FunctionAnalysisResult>,
+    /// Warnings about the file, including why it could not be analyzed
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Results of a file with its stamp when it was analyzed
+#[derive(Debug)]
+struct CachedFile {
+    /// None if the file could not be stamped, so it is analyzed every time
+    stamp: Option<Stamp>,
+    results: FileResults,
+}
+
+/// Per-file analysis results, reused while the files are unchanged
+#[derive(Debug, Default)]
+pub struct WarmCache {
+    files: HashMap<String, CachedFile>,
+}
+
+impl WarmCache {
+    /// Analyzes the files that changed since they were last analyzed, and
+    /// returns the results and warnings of all files in input order
+    ///
+    /// Files that no longer exist are dropped from the cache; other files not
+    /// in `files` are kept, so a query for a subset of the tree, such as the
+    /// changed files, does not cool the cache for the next full query. The
+    /// options must be the same on every call, as cached results are not
+    /// invalidated when they change.
+    pub fn analyze(
+        &mut self,
+        files: &[String],
+        options: &AnalysisOptions,
+    ) -> (Vec<FunctionAnalysisResult>, Vec<Diagnostic>) {
+        let mut functions = Vec::new();
+        let mut diagnostics = Vec::new();
+        for file in self.analyze_files(files, options) {
+            functions.extend(file.functions);
+            diagnostics.extend(file.diagnostics);
+        }
+        (functions, diagnostics)
+    }
+
+    /// Like [`WarmCache::analyze`], keeping the results of each file apart
+    pub fn analyze_files(
+        &mut self,
+        files: &[String],
+        options: &AnalysisOptions,
+    ) -> Vec<FileResults> {
+        let listed: HashSet<&String> = files.iter().collect();
+        self.files
+            .retain(|path, _| listed.contains(path) || Path::new(path).exists());
+
+        let stamps: HashMap<&String, Option<Stamp>> =
+            files.iter().map(|path| (path, stamp(path))).collect();
+        let stale: Vec<String> = files
+            .iter()
+            .filter(|path| {
+                let stamp = stamps[path];
+                stamp.is_none() || self.files.get(*path).is_none_or(|file| file.stamp != stamp)
+            })
+            .cloned()
+            .collect();
+
+        if !stale.is_empty() {
+            let mut fresh: HashMap<String, CachedFile> = stale
+                .iter()
+                .map(|path| {
+                    let file = CachedFile {
+                        stamp: stamps[path],
+                        results: FileResults {
+                            path: path.clone(),
+                            ..FileResults::default()
+                        },
+                    };
+                    (path.clone(), file)
+                })
+                .collect();
+            let (_, diagnostics) =
+                crate::analyze_all_files_timed(&stale, None, options, |path, _, functions| {
+                    if let Some(file) = fresh.get_mut(path) {
+                        file.results.functions = functions.clone();
+                    }
+                });
+            for diagnostic in diagnostics {
+                if let Some(file) = fresh.get_mut(&diagnostic.file) {
+                    file.results.diagnostics.push(diagnostic);
+                }
+            }
+            self.files.extend(fresh);
+        }
+
+        files
+            .iter()
+            .filter_map(|path| self.files.get(path))
+            .map(|file| file.results.clone())
+            .collect()
+    }
+}
+
+/// Returns the modification time and size of a file, if available
+fn stamp(path: &str) -> Option<Stamp> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+
+    #[test]
+    fn test_unchanged_files_are_not_analyzed_again() {
+        let dir = std::env::temp_dir().join(format!("fnloc_warm_cache_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("lib.rs");
+        let file = path.to_string_lossy().to_string();
+        let options = AnalysisOptions::default();
+        let mut cache = WarmCache::default();
+
+        fs::write(&path, "fn one() {}\n").unwrap();
+        let (functions, _) = cache.analyze(std::slice::from_ref(&file), &options);
+        assert_eq!(functions[0].name, "one");
+
+        // Same size and modification time: the cached results are returned
+        let modified = fs::metadata(&path).unwrap().modified().unwrap();
+        fs::write(&path, "fn two() {}\n").unwrap();
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        let (functions, _) = cache.analyze(std::slice::from_ref(&file), &options);
+        assert_eq!(functions[0].name, "one");
+
+        fs::write(&path, "fn three() {}\n").unwrap();
+        let (functions, _) = cache.analyze(std::slice::from_ref(&file), &options);
+        assert_eq!(functions[0].name, "three");
+
+        // Files left out of a query stay cached until they are deleted
+        let (functions, _) = cache.analyze(&[], &options);
+        assert!(functions.is_empty());
+        assert!(cache.files.contains_key(&file));
+        fs::remove_file(&path).unwrap();
+        cache.analyze(&[], &options);
+        assert!(cache.files.is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
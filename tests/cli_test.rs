@@ -20,6 +20,21 @@ fn run_fnloc(args: &[&str]) -> Result<(String, String, bool), Box<dyn std::error
     Ok((stdout, stderr, success))
 }
 
+/// Runs the built fnloc binary in `dir`, where it reads that directory's `fnloc.toml`
+fn run_fnloc_in(
+    dir: &std::path::Path,
+    args: &[&str],
+) -> Result<(String, String, bool), Box<dyn std::error::Error>> {
+    let output = Command::new(env!("CARGO_BIN_EXE_cargo-fnloc"))
+        .current_dir(dir)
+        .args(args)
+        .output()?;
+
+    let stdout = str::from_utf8(&output.stdout)?.to_string();
+    let stderr = str::from_utf8(&output.stderr)?.to_string();
+    Ok((stdout, stderr, output.status.success()))
+}
+
 #[test]
 fn test_cli_version() {
     let (stdout, _stderr, success) =
@@ -91,3 +106,456 @@ fn test_cli_with_test_sample() {
     );
     assert!(stdout.contains("nesting="), "Should show nesting depth");
 }
+
+#[test]
+fn test_cli_max_params_suggestions() {
+    let (stdout, _stderr, success) = run_fnloc(&["tests/test_sample", "--max-params", "0"])
+        .expect("Failed to run fnloc with --max-params");
+
+    assert!(success, "Should succeed with --max-params");
+    assert!(stdout.contains("fn"), "Should still show function results");
+}
+
+#[test]
+fn test_cli_min_lines_filter() {
+    let (stdout, _stderr, success) = run_fnloc(&["tests/test_sample", "--min-lines", "10"])
+        .expect("Failed to run fnloc with --min-lines");
+
+    assert!(success, "Should succeed with --min-lines");
+    assert!(
+        stdout.contains("large_function"),
+        "Should keep functions with enough code lines"
+    );
+    assert!(
+        !stdout.contains("simple_function"),
+        "Should drop functions below the minimum"
+    );
+}
+
+#[test]
+fn test_cli_metric_minimums_compose() {
+    let (stdout, _stderr, success) = run_fnloc(&[
+        "tests/test_sample",
+        "--format",
+        "csv",
+        "--min-complexity",
+        "5",
+        "--min-nesting",
+        "3",
+    ])
+    .expect("Failed to run fnloc with metric minimums");
+
+    assert!(success, "Should succeed with metric minimums");
+    assert!(
+        stdout.contains("large_function"),
+        "Should keep functions meeting every minimum"
+    );
+    assert!(
+        !stdout.contains("complex_function"),
+        "Should drop functions failing any minimum"
+    );
+}
+
+#[test]
+fn test_cli_test_code_filters() {
+    let dir = std::env::temp_dir().join("fnloc_cli_test_code_filters");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("lib.rs"),
+        "pub fn production() {}\n\n#[cfg(test)]\nmod tests {\n    #[test]\n    fn checks_production() {}\n}\n",
+    )
+    .unwrap();
+    let path = dir.to_str().unwrap();
+
+    let (stdout, _stderr, success) =
+        run_fnloc(&[path, "--format", "json"]).expect("Failed to run fnloc on test code");
+    assert!(success, "Should succeed with test code");
+    assert!(stdout.contains("tests::checks_production"));
+    assert!(stdout.contains("\"is_test\":true"));
+
+    let (stdout, _stderr, _) =
+        run_fnloc(&[path, "--exclude-tests"]).expect("Failed to run fnloc with --exclude-tests");
+    assert!(stdout.contains("production"));
+    assert!(!stdout.contains("checks_production"));
+
+    let (stdout, _stderr, _) =
+        run_fnloc(&[path, "--only-tests"]).expect("Failed to run fnloc with --only-tests");
+    assert!(stdout.contains("checks_production"));
+    assert!(!stdout.contains("lib.rs::production"));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_cli_multiple_paths() {
+    let (stdout, _stderr, success) =
+        run_fnloc(&["tests/test_sample/sample.rs", "tests/test_sample/module.rs"])
+            .expect("Failed to run fnloc with multiple paths");
+
+    assert!(success, "Should succeed with multiple paths");
+    assert!(
+        stdout.contains("Analyzing 2 Rust files"),
+        "Should merge files from every path"
+    );
+    assert!(
+        stdout.contains("large_function"),
+        "Should analyze sample.rs"
+    );
+    assert!(
+        stdout.contains("divide_numbers"),
+        "Should analyze module.rs"
+    );
+}
+
+#[test]
+fn test_cli_single_file() {
+    let (stdout, _stderr, success) = run_fnloc(&["tests/test_sample/module.rs"])
+        .expect("Failed to run fnloc with a single file");
+
+    assert!(success, "Should succeed with a single file");
+    assert!(
+        stdout.contains("Analyzing 1 Rust files"),
+        "Should analyze exactly the given file"
+    );
+    assert!(
+        stdout.contains("divide_numbers"),
+        "Should analyze module.rs"
+    );
+}
+
+#[test]
+fn test_cli_output_file() {
+    let dir = std::env::temp_dir().join("fnloc_cli_output_file");
+    let _ = std::fs::remove_dir_all(&dir);
+    let path = dir.join("reports/fnloc.csv");
+
+    let (stdout, _stderr, success) = run_fnloc(&[
+        "tests/test_sample",
+        "--format",
+        "csv",
+        "--output",
+        path.to_str().unwrap(),
+    ])
+    .expect("Failed to run fnloc with --output");
+
+    assert!(success, "Should succeed writing to a file");
+    assert!(stdout.is_empty(), "Nothing should be written to stdout");
+    let report = std::fs::read_to_string(&path).expect("Report file should exist");
+    assert!(report.starts_with("Function,Total Lines"));
+    assert!(report.contains("divide_numbers"));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_cli_quiet_and_no_header() {
+    let (stdout, _stderr, success) =
+        run_fnloc(&["tests/test_sample", "--quiet"]).expect("Failed to run fnloc with --quiet");
+    assert!(success, "Should succeed with --quiet");
+    assert!(!stdout.contains("Analyzing"), "Banner should be suppressed");
+    assert!(stdout.contains("fn "), "Rows should still be shown");
+
+    let (stdout, _stderr, success) =
+        run_fnloc(&["tests/test_sample", "--format", "csv", "--no-header"])
+            .expect("Failed to run fnloc with --no-header");
+    assert!(success, "Should succeed with --no-header");
+    assert!(!stdout.contains("Function,Total Lines"));
+    assert!(stdout.contains("divide_numbers"));
+}
+
+#[test]
+fn test_cli_fail_on_limits() {
+    let (stdout, stderr, success) = run_fnloc(&["tests/test_sample", "--fail-on", "code=1"])
+        .expect("Failed to run fnloc with --fail-on");
+    assert!(!success, "Should fail when a limit is exceeded");
+    assert!(stdout.is_empty(), "Only the violations should be printed");
+    assert!(stderr.contains("threshold violation(s)"));
+    assert!(stderr.contains("exceeds limit 1"));
+
+    let (_stdout, stderr, success) = run_fnloc(&[
+        "tests/test_sample",
+        "--fail-on",
+        "code=1",
+        "--format",
+        "json",
+    ])
+    .expect("Failed to run fnloc with --fail-on and --format json");
+    assert!(!success, "Should fail when a limit is exceeded");
+    let error: serde_json::Value = serde_json::from_str(stderr.lines().last().unwrap())
+        .expect("The error should be a JSON object");
+    assert_eq!(error["error"]["kind"], "thresholds_exceeded");
+
+    let (_stdout, _stderr, success) = run_fnloc(&[
+        "tests/test_sample",
+        "--fail-on",
+        "code=10000,complexity=1000",
+    ])
+    .expect("Failed to run fnloc with --fail-on");
+    assert!(
+        success,
+        "Should succeed when every function is within the limits"
+    );
+}
+
+#[test]
+fn test_cli_baseline_ratchet() {
+    let dir = std::env::temp_dir().join("fnloc_cli_baseline");
+    let _ = std::fs::remove_dir_all(&dir);
+    let path = dir.join("baseline.json");
+    let path = path.to_str().unwrap();
+
+    let (_stdout, _stderr, success) =
+        run_fnloc(&["baseline", "write", "tests/test_sample", "--file", path])
+            .expect("Failed to run fnloc baseline write");
+    assert!(success, "Should write the baseline");
+
+    let (_stdout, stderr, success) = run_fnloc(&[
+        "tests/test_sample",
+        "--fail-on",
+        "code=1",
+        "--baseline",
+        path,
+    ])
+    .expect("Failed to run fnloc with --baseline");
+    assert!(
+        success,
+        "Functions unchanged since the baseline should pass: {stderr}"
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_cli_jobs_keep_output_order() {
+    let args = ["src", "--format", "csv"];
+    let (sequential, _stderr, success) = run_fnloc(&[&args[..], &["--jobs", "1"]].concat())
+        .expect("Failed to run fnloc with --jobs 1");
+    assert!(success, "Should succeed with --jobs 1");
+
+    let (parallel, _stderr, success) = run_fnloc(&[&args[..], &["--jobs", "4"]].concat())
+        .expect("Failed to run fnloc with --jobs 4");
+    assert!(success, "Should succeed with --jobs 4");
+    assert_eq!(sequential, parallel, "Output should not depend on --jobs");
+}
+
+#[test]
+fn test_cli_failed_files_are_listed_in_json() {
+    let dir = std::env::temp_dir().join("fnloc_cli_failed_files");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("ok.rs"), "fn ok() {}\n").unwrap();
+    std::fs::write(dir.join("broken.rs"), "fn broken( {\n").unwrap();
+
+    let (stdout, stderr, success) = run_fnloc(&[dir.to_str().unwrap(), "--format", "json"])
+        .expect("Failed to run fnloc on a broken file");
+    assert!(success, "A broken file should not fail the run: {stderr}");
+    let entries: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    let entries = entries.as_array().unwrap();
+    assert_eq!(entries.len(), 2);
+    assert!(entries[1]["file"].as_str().unwrap().ends_with("broken.rs"));
+    assert!(entries[1]["error"].is_string());
+
+    let (_stdout, stderr, success) =
+        run_fnloc(&[dir.to_str().unwrap(), "--strict"]).expect("Failed to run fnloc with --strict");
+    assert!(!success, "--strict should fail on a broken file");
+    assert!(stderr.contains("could not be fully analyzed"));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_cli_exit_codes() {
+    let exit_code = |args: &[&str]| {
+        Command::new("cargo")
+            .args(["run", "--"])
+            .args(args)
+            .output()
+            .expect("Failed to run fnloc")
+            .status
+            .code()
+    };
+
+    assert_eq!(exit_code(&["tests/test_sample"]), Some(0));
+    assert_eq!(
+        exit_code(&["tests/test_sample", "--fail-on", "code=1"]),
+        Some(1)
+    );
+    assert_eq!(exit_code(&["--no-such-flag"]), Some(2));
+    assert_eq!(exit_code(&["non_existent_directory"]), Some(2));
+    assert_eq!(
+        exit_code(&["tests/test_sample", "--at", "no-such-revision"]),
+        Some(3)
+    );
+}
+
+#[test]
+fn test_cli_max_file_size_skips_large_files() {
+    let (stdout, stderr, success) = run_fnloc(&[
+        "tests/test_sample",
+        "--max-file-size",
+        "1",
+        "--format",
+        "json",
+    ])
+    .expect("Failed to run fnloc with --max-file-size");
+    assert!(success, "Skipped files should not fail the run: {stderr}");
+    assert!(stderr.contains("File too large"));
+    let entries: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert!(
+        entries
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|entry| entry["error"].is_string())
+    );
+}
+
+#[test]
+fn test_cli_cargo_subcommand_argument_is_ignored() {
+    let (direct, _stderr, success) =
+        run_fnloc(&["tests/test_sample", "-q"]).expect("Failed to run fnloc");
+    assert!(success, "Should succeed when run directly");
+
+    let (as_subcommand, stderr, success) = run_fnloc(&["fnloc", "tests/test_sample", "-q"])
+        .expect("Failed to run fnloc as a cargo subcommand");
+    assert!(
+        success,
+        "Should succeed when run as `cargo fnloc`: {stderr}"
+    );
+    assert_eq!(direct, as_subcommand);
+}
+
+#[test]
+fn test_cli_complexity_preset_from_config() {
+    let dir = std::env::temp_dir().join("fnloc_cli_complexity_preset");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("lib.rs"),
+        "fn gate(a: bool, b: bool) -> u8 {\n    if a && b {\n        return 1;\n    }\n    0\n}\n",
+    )
+    .unwrap();
+    std::fs::write(dir.join("fnloc.toml"), "complexity_preset = \"mccabe\"\n").unwrap();
+    let complexity = |args: &[&str]| {
+        let (stdout, stderr, success) = run_fnloc_in(&dir, args).expect("Failed to run fnloc");
+        assert!(success, "Should succeed: {stderr}");
+        let rows: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+        rows[0]["complexity"].as_u64().unwrap()
+    };
+
+    assert_eq!(complexity(&["lib.rs", "-q", "--format", "json"]), 2);
+    assert_eq!(
+        complexity(&[
+            "lib.rs",
+            "-q",
+            "--format",
+            "json",
+            "--complexity-preset",
+            "strict"
+        ]),
+        4
+    );
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_cli_name_normalization_from_config() {
+    let dir = std::env::temp_dir().join("fnloc_cli_names_config");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("lib.rs"),
+        "#[cfg(test)]\nmod tests {\n    fn helper() {}\n}\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("fnloc.toml"),
+        "[names]\nstrip_crate_prefixes = [\"tests\"]\n",
+    )
+    .unwrap();
+
+    let (stdout, stderr, success) =
+        run_fnloc_in(&dir, &["lib.rs", "-q", "--format", "json"]).expect("Failed to run fnloc");
+    assert!(success, "Should succeed: {stderr}");
+    let rows: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(rows[0]["name"], "lib.rs::helper");
+
+    let (_stdout, stderr, success) =
+        run_fnloc_in(&dir, &["baseline", "write", "lib.rs"]).expect("Failed to run fnloc");
+    assert!(success, "Should write the baseline: {stderr}");
+    let baseline: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.join("fnloc-baseline.json")).unwrap())
+            .unwrap();
+    let keys: Vec<&String> = baseline["functions"].as_object().unwrap().keys().collect();
+    assert_eq!(keys, ["lib.rs::helper"]);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_cli_call_graph_follows_format() {
+    let (stdout, stderr, success) =
+        run_fnloc(&["--format", "json", "call-graph", "tests/test_sample"])
+            .expect("Failed to run fnloc call-graph");
+    assert!(success, "Should succeed: {stderr}");
+    let graph: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert!(graph["nodes"].is_array() && graph["edges"].is_array());
+
+    let (stdout, _stderr, success) =
+        run_fnloc(&["call-graph", "tests/test_sample"]).expect("Failed to run fnloc call-graph");
+    assert!(success, "Should succeed without --format");
+    assert!(stdout.starts_with("digraph calls {"));
+}
+
+#[test]
+fn test_cli_history_accepts_format_after_subcommand() {
+    let dir = std::env::temp_dir().join(format!("fnloc_cli_history_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    std::fs::write(dir.join("src/lib.rs"), "fn answer() -> u8 {\n    42\n}\n").unwrap();
+    for args in [
+        &["init", "-q"][..],
+        &["add", "."],
+        &[
+            "-c",
+            "user.name=fnloc",
+            "-c",
+            "user.email=fnloc@example.com",
+            "commit",
+            "-q",
+            "-m",
+            "initial",
+        ],
+    ] {
+        let status = Command::new("git")
+            .current_dir(&dir)
+            .args(args)
+            .status()
+            .unwrap();
+        assert!(status.success(), "git {args:?} failed");
+    }
+
+    let (stdout, stderr, success) =
+        run_fnloc_in(&dir, &["history", "-f", "json"]).expect("Failed to run fnloc history");
+    assert!(success, "Should accept -f after the subcommand: {stderr}");
+    let points: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(points[0]["functions"], 1);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_cli_subcommand_output_to_closed_pipe() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_cargo-fnloc"))
+        .args(["call-graph", "tests/test_sample"])
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .expect("Failed to run fnloc call-graph");
+    // Close the reading end before the graph is written, like `| head` exiting early
+    drop(child.stdout.take());
+
+    let output = child.wait_with_output().unwrap();
+    let stderr = str::from_utf8(&output.stderr).unwrap();
+    assert!(output.status.success(), "Should exit cleanly: {stderr}");
+    assert!(!stderr.contains("panicked"));
+}
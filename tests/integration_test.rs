@@ -3,72 +3,15 @@
 //! These tests use sample Rust files to verify that the analysis
 //! produces expected results for various function patterns.
 
-use std::collections::HashMap;
-use std::fs;
 use std::path::Path;
+use std::time::Duration;
 
 // Import the modules we need to test
-use fnloc::analyze_all_files;
-use fnloc::analyzer::FunctionAnalysisResult;
 use fnloc::file_scanner::find_rust_files;
-
-/// Expected result for a function analysis
-#[derive(Debug, PartialEq)]
-struct ExpectedResult {
-    name: String,
-    total: usize,
-    code: usize,
-    comment: usize,
-    empty: usize,
-    cyclomatic_complexity: usize,
-    nesting_depth: usize,
-}
-
-/// Parse expected results from the configuration file
-fn parse_expected_results(
-    file_path: &str,
-) -> Result<Vec<ExpectedResult>, Box<dyn std::error::Error>> {
-    let content = fs::read_to_string(file_path)?;
-    let mut results = Vec::new();
-
-    for line in content.lines() {
-        let line = line.trim();
-        // Skip comments and empty lines
-        if line.starts_with('#') || line.is_empty() {
-            continue;
-        }
-
-        let parts: Vec<&str> = line.split(',').map(|s| s.trim()).collect();
-        if parts.len() == 7 {
-            results.push(ExpectedResult {
-                name: parts[0].to_string(),
-                total: parts[1].parse()?,
-                code: parts[2].parse()?,
-                comment: parts[3].parse()?,
-                empty: parts[4].parse()?,
-                cyclomatic_complexity: parts[5].parse()?,
-                nesting_depth: parts[6].parse()?,
-            });
-        }
-    }
-
-    Ok(results)
-}
-
-/// Convert analysis results to a map for easy lookup
-fn results_to_map(results: &[FunctionAnalysisResult]) -> HashMap<String, &FunctionAnalysisResult> {
-    let mut map = HashMap::new();
-    for result in results {
-        // Extract just the function name without the file path
-        let function_name = if let Some(pos) = result.name.rfind("::") {
-            &result.name[pos + 2..]
-        } else {
-            &result.name
-        };
-        map.insert(function_name.to_string(), result);
-    }
-    map
-}
+use fnloc::fixtures::Fixture;
+use fnloc::{
+    AnalysisOptions, analyze_all_files, analyze_all_files_with_callback, analyze_file_functions,
+};
 
 #[test]
 fn test_sample_files_analysis() {
@@ -98,58 +41,24 @@ fn test_sample_files_analysis() {
     assert!(!results.is_empty(), "No analysis results produced");
 
     // Load expected results
-    let expected_results = parse_expected_results(&format!("{test_dir}/expected_results.txt"))
-        .expect("Failed to parse expected results");
-
-    // Convert results to map for easier comparison
-    let result_map = results_to_map(&results);
+    let fixture = Fixture::load(Path::new(&format!("{test_dir}/expected_results.toml")))
+        .expect("Failed to load expected results");
 
     // Verify each expected result
-    for expected in &expected_results {
-        let actual = result_map.get(&expected.name).unwrap_or_else(|| {
-            panic!("Function '{}' not found in analysis results", expected.name)
-        });
-
-        assert_eq!(
-            actual.total, expected.total,
-            "Total lines mismatch for function '{}': expected {}, got {}",
-            expected.name, expected.total, actual.total
-        );
-
-        assert_eq!(
-            actual.code, expected.code,
-            "Code lines mismatch for function '{}': expected {}, got {}",
-            expected.name, expected.code, actual.code
-        );
-
-        assert_eq!(
-            actual.comment, expected.comment,
-            "Comment lines mismatch for function '{}': expected {}, got {}",
-            expected.name, expected.comment, actual.comment
-        );
-
-        assert_eq!(
-            actual.empty, expected.empty,
-            "Empty lines mismatch for function '{}': expected {}, got {}",
-            expected.name, expected.empty, actual.empty
-        );
-
-        assert_eq!(
-            actual.cyclomatic_complexity, expected.cyclomatic_complexity,
-            "Cyclomatic complexity mismatch for function '{}': expected {}, got {}",
-            expected.name, expected.cyclomatic_complexity, actual.cyclomatic_complexity
-        );
-
-        assert_eq!(
-            actual.nesting_depth, expected.nesting_depth,
-            "Nesting depth mismatch for function '{}': expected {}, got {}",
-            expected.name, expected.nesting_depth, actual.nesting_depth
-        );
-    }
+    let mismatches = fixture.compare(&results);
+    assert!(
+        mismatches.is_empty(),
+        "Analysis results differ from fixture:\n{}",
+        mismatches
+            .iter()
+            .map(|mismatch| mismatch.to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    );
 
     println!(
         "✅ All {} function analyses match expected results!",
-        expected_results.len()
+        fixture.functions.len()
     );
 }
 
@@ -182,7 +91,7 @@ fn test_function_filtering_and_sorting() {
     let mut results = analyze_all_files(&files);
 
     // Test sorting by total lines (descending)
-    results.sort_by(|a, b| b.total.cmp(&a.total));
+    results.sort_by_key(|result| std::cmp::Reverse(result.total));
 
     // Verify sorting order
     for i in 1..results.len() {
@@ -211,3 +120,153 @@ fn test_function_filtering_and_sorting() {
 
     println!("✅ Filtering and sorting work correctly!");
 }
+
+#[test]
+fn test_windows_line_endings_and_bom_match_unix_results() {
+    let unix =
+        std::fs::read_to_string("tests/test_sample/sample.rs").expect("Failed to read sample.rs");
+    let windows = format!("\u{FEFF}{}", unix.replace('\n', "\r\n"));
+
+    let temp_dir = std::env::temp_dir().join(format!("fnloc_crlf_{}", std::process::id()));
+    std::fs::create_dir_all(&temp_dir).expect("Failed to create temp directory");
+    let unix_path = temp_dir.join("unix.rs");
+    let windows_path = temp_dir.join("windows.rs");
+    std::fs::write(&unix_path, &unix).expect("Failed to write unix.rs");
+    std::fs::write(&windows_path, &windows).expect("Failed to write windows.rs");
+
+    let unix_results =
+        analyze_file_functions(&unix_path.to_string_lossy()).expect("Failed to analyze LF file");
+    let windows_results = analyze_file_functions(&windows_path.to_string_lossy())
+        .expect("Failed to analyze CRLF file with BOM");
+
+    assert!(!unix_results.is_empty());
+    assert_eq!(unix_results, windows_results);
+
+    std::fs::remove_dir_all(&temp_dir).expect("Failed to remove temp directory");
+}
+
+#[test]
+fn test_cancelled_analysis_keeps_finished_files() {
+    let files = find_rust_files("tests/test_sample").expect("Should find sample files");
+
+    let options = AnalysisOptions::default();
+    let results = analyze_all_files_with_callback(&files, &options, |_| {
+        options.cancellation.cancel();
+    });
+    assert!(options.cancellation.is_cancelled());
+    assert!(
+        !results.is_empty(),
+        "Files finished before the cancellation should still be returned"
+    );
+
+    let cancelled = AnalysisOptions::default();
+    cancelled.cancellation.cancel();
+    assert!(analyze_all_files_with_callback(&files, &cancelled, |_| {}).is_empty());
+}
+
+#[test]
+fn test_file_timeout_keeps_files_finished_in_time() {
+    let files = find_rust_files("tests/test_sample").expect("Should find sample files");
+
+    let options = AnalysisOptions {
+        file_timeout: Some(Duration::from_secs(60)),
+        ..AnalysisOptions::default()
+    };
+    assert_eq!(
+        analyze_all_files_with_callback(&files, &options, |_| {}),
+        analyze_all_files(&files)
+    );
+}
+
+#[test]
+fn test_run_analysis_returns_report() {
+    use clap::Parser;
+
+    let output = std::env::temp_dir().join(format!("fnloc_report_{}.txt", std::process::id()));
+    let cli = fnloc::Client::parse_from([
+        "cargo-fnloc",
+        "tests/test_sample",
+        "--min-comment-density",
+        "100",
+        "--output",
+        output.to_str().unwrap(),
+    ]);
+    let report = fnloc::run_analysis(&cli).expect("Analysis should succeed");
+    std::fs::remove_file(&output).unwrap();
+
+    assert_eq!(report.files.len(), 2);
+    assert_eq!(report.summary.functions, report.functions.len());
+    assert!(!report.diagnostics.is_empty());
+    assert!(report.passed());
+}
+
+#[test]
+fn test_analyzer_streams_files_in_order() {
+    let mut files = find_rust_files("tests/test_sample").expect("Should find sample files");
+    files.push("tests/test_sample/missing.rs".to_string());
+
+    let analyzed: Vec<_> = fnloc::Analyzer::new(files.clone()).iter_files().collect();
+
+    let paths: Vec<_> = analyzed.iter().map(|file| file.path.clone()).collect();
+    assert_eq!(paths, files);
+    assert!(analyzed[..2].iter().all(|file| file.error.is_none()));
+    assert!(analyzed[2].error.is_some());
+    assert_eq!(
+        analyzed
+            .into_iter()
+            .flat_map(|file| file.functions)
+            .collect::<Vec<_>>(),
+        analyze_all_files(&files)
+    );
+}
+
+#[test]
+fn test_analyzer_hooks_report_progress_and_warnings() {
+    use std::sync::{Arc, Mutex};
+
+    let mut files = find_rust_files("tests/test_sample").expect("Should find sample files");
+    files.push("tests/test_sample/missing.rs".to_string());
+    let events = Arc::new(Mutex::new(Vec::new()));
+
+    let (started, done, warned) = (events.clone(), events.clone(), events.clone());
+    let results = fnloc::Analyzer::new(files.clone())
+        .on_file_start(move |path| started.lock().unwrap().push(format!("start {path}")))
+        .on_file_done(move |file| done.lock().unwrap().push(format!("done {}", file.path)))
+        .on_warning(move |diagnostic| {
+            warned
+                .lock()
+                .unwrap()
+                .push(format!("warn {}", diagnostic.file))
+        })
+        .analyze();
+
+    assert_eq!(results, analyze_all_files(&files));
+    let events = events.lock().unwrap();
+    for path in &files {
+        let position = |event: String| events.iter().position(|e| *e == event).unwrap();
+        assert!(position(format!("start {path}")) < position(format!("done {path}")));
+    }
+    assert_eq!(
+        events
+            .iter()
+            .filter(|e| e.starts_with("warn"))
+            .collect::<Vec<_>>(),
+        ["warn tests/test_sample/missing.rs"]
+    );
+}
+
+#[test]
+fn test_failed_files_are_returned_as_diagnostics() {
+    let files = vec![
+        "tests/test_sample/sample.rs".to_string(),
+        "tests/test_sample/missing.rs".to_string(),
+    ];
+
+    let (results, diagnostics) =
+        fnloc::analyze_all_files_timed(&files, None, &AnalysisOptions::default(), |_, _, _| {});
+
+    assert!(!results.is_empty());
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].file, "tests/test_sample/missing.rs");
+    assert_eq!(diagnostics[0].kind, fnloc::DiagnosticKind::AnalysisFailed);
+}